@@ -0,0 +1,314 @@
+use crate::{
+    ext::{SetSortedError, SliceExt, SliceMove},
+    real::{R32, R64},
+    time::{Beats, MSecs, Secs},
+};
+use std::{cmp::Ordering, fmt::Debug, iter::from_fn, marker::PhantomData, mem::size_of};
+
+/// A value whose changes can be stored as deltas instead of copies of the value before & after.
+pub trait Diff {
+    /// the default value must be the delta of an unchanged value
+    type Delta: Debug + Clone + Copy + PartialEq + Eq + Default + Pack;
+    /// The delta that turns `self` into `to`.
+    fn delta(&self, to: &Self) -> Self::Delta;
+    /// Applies `delta` to `self`. Applying the same delta twice must restore the value exactly.
+    fn patch(&mut self, delta: Self::Delta);
+}
+
+macro_rules! int_diff_impl {
+    ($($t:ty),+) => {$(
+        impl Diff for $t {
+            type Delta = $t;
+            fn delta(&self, to: &Self) -> Self::Delta {
+                self ^ to
+            }
+            fn patch(&mut self, delta: Self::Delta) {
+                *self ^= delta
+            }
+        }
+    )+};
+}
+
+int_diff_impl!(bool, u8, u16, u32, u64, usize);
+
+/// A delta that can be stored in a byte buffer, as compactly as the delta allows.
+pub trait Pack: Sized {
+    /// Appends `self` to `dst`.
+    fn encode(self, dst: &mut Vec<u8>);
+    /// Reads back a value written by `encode` from the start of `src`, advancing `src` past it.
+    fn decode(src: &mut &[u8]) -> Option<Self>;
+}
+
+impl Pack for bool {
+    fn encode(self, dst: &mut Vec<u8>) {
+        dst.push(self.into())
+    }
+
+    fn decode(src: &mut &[u8]) -> Option<Self> {
+        u8::decode(src).map(|x| x != 0)
+    }
+}
+
+impl Pack for u8 {
+    fn encode(self, dst: &mut Vec<u8>) {
+        dst.push(self)
+    }
+
+    fn decode(src: &mut &[u8]) -> Option<Self> {
+        let (&res, rest) = src.split_first()?;
+        *src = rest;
+        Some(res)
+    }
+}
+
+/// Wider integers leave out their leading & trailing zero bytes, preceded by a byte with the
+/// number of the trailing zero bytes in the high nibble & the number of the bytes kept in the low
+/// one. XORs of close values, such as the bits of floats that share their exponents, or of values
+/// on a grid, are mostly zero bytes, so a zero delta takes up 1 byte & a typical one 2 to 4.
+macro_rules! int_pack_impl {
+    ($($t:ty),+) => {$(
+        impl Pack for $t {
+            fn encode(self, dst: &mut Vec<u8>) {
+                let bytes = self.to_le_bytes();
+                let trailing = bytes.iter().take_while(|x| **x == 0).count();
+                let kept = bytes.iter().rposition(|x| *x != 0).map_or(0, |x| x + 1 - trailing);
+                dst.push((trailing << 4 | kept) as u8);
+                dst.extend(bytes.iter().skip(trailing).take(kept))
+            }
+
+            fn decode(src: &mut &[u8]) -> Option<Self> {
+                let header = usize::from(u8::decode(src)?);
+                let (trailing, kept) = (header >> 4, header & 0xF);
+                let (value, rest) = src.try_split_at(kept)?;
+                let mut bytes = [0; size_of::<$t>()];
+                bytes.get_mut(trailing..trailing + kept)?.copy_from_slice(value);
+                *src = rest;
+                Some(Self::from_le_bytes(bytes))
+            }
+        }
+    )+};
+}
+
+int_pack_impl!(u16, u32, u64, usize);
+
+macro_rules! tuple_pack_impl {
+    ($($t:ident),+) => {
+        impl<$($t: Pack),+> Pack for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn encode(self, dst: &mut Vec<u8>) {
+                let ($($t,)+) = self;
+                $($t.encode(dst);)+
+            }
+
+            fn decode(src: &mut &[u8]) -> Option<Self> {
+                Some(($($t::decode(src)?,)+))
+            }
+        }
+    };
+}
+
+tuple_pack_impl!(A, B);
+tuple_pack_impl!(A, B, C);
+tuple_pack_impl!(A, B, C, D);
+
+/// LEB128: 7 bits per byte, the high bit marking that more bytes follow.
+fn encode_varint(mut x: usize, dst: &mut Vec<u8>) {
+    while x >= 0x80 {
+        dst.push(x as u8 | 0x80);
+        x >>= 7;
+    }
+    dst.push(x as u8)
+}
+
+fn decode_varint(src: &mut &[u8]) -> Option<usize> {
+    let mut res = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let byte = u8::decode(src)?;
+        res |= usize::from(byte & 0x7F).checked_shl(shift)?;
+        if byte < 0x80 {
+            return Some(res);
+        }
+    }
+    None
+}
+
+/// The deltas of the floats are the XORs of their bits rather than their arithmetic differences,
+/// since subtracting a float's difference from the float doesn't always give back the original.
+macro_rules! real_diff_impl {
+    ($($t:ty: $bits:ty, $float:ty),+) => {$(
+        impl Diff for $t {
+            type Delta = $bits;
+            fn delta(&self, to: &Self) -> Self::Delta {
+                self.get().to_bits() ^ to.get().to_bits()
+            }
+            fn patch(&mut self, delta: Self::Delta) {
+                *self = Self::new_or(*self, <$float>::from_bits(self.get().to_bits() ^ delta))
+            }
+        }
+    )+};
+}
+
+real_diff_impl!(R64: u64, f64, R32: u32, f32);
+
+macro_rules! time_diff_impl {
+    ($($t:ty),+) => {$(
+        impl Diff for $t {
+            type Delta = <R64 as Diff>::Delta;
+            fn delta(&self, to: &Self) -> Self::Delta {
+                self.0.delta(&to.0)
+            }
+            fn patch(&mut self, delta: Self::Delta) {
+                self.0.patch(delta)
+            }
+        }
+    )+};
+}
+
+time_diff_impl!(Beats, Secs, MSecs);
+
+/// A compact record of an edit of a sorted pattern: every step of it is the delta of the changed
+/// element, with the move it took afterwards to keep the pattern sorted.
+/// Unchanged elements take up no space, unlike with copies of the whole pattern, & the steps are
+/// packed into 1 buffer: the index the element was at & the distance it moved as varints, followed
+/// by the delta as packed by [`Pack`], so that a step typically takes up a few bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternDiff<T: Diff> {
+    steps: Vec<u8>,
+    _data: PhantomData<T>,
+}
+
+impl<T: Diff> Default for PatternDiff<T> {
+    fn default() -> Self {
+        Self { steps: vec![], _data: PhantomData }
+    }
+}
+
+impl<T: Diff> PatternDiff<T> {
+    /// Replaces the element of `data`, sorted by `f`, at `index` with `value`, moving it to keep
+    /// `data` sorted, & records the change as the last step of the diff.
+    /// Returns the move of the element, with which other indices into `data` must be adjusted.
+    pub fn set(
+        &mut self,
+        data: &mut [T],
+        index: usize,
+        value: T,
+        f: impl FnMut(&T, &T) -> Ordering,
+    ) -> Result<SliceMove, SetSortedError> {
+        let len = data.len();
+        let delta = data.get(index).ok_or(SetSortedError { index, len })?.delta(&value);
+        let res = data.set_sorted_by(index, value, f)?;
+        if res.from != res.to || delta != T::Delta::default() {
+            // the lowest bit of the distance is the direction of the move
+            let dist = res.to.abs_diff(res.from) << 1 | usize::from(res.to < res.from);
+            encode_varint(res.from, &mut self.steps);
+            encode_varint(dist, &mut self.steps);
+            delta.encode(&mut self.steps);
+        }
+        Ok(res)
+    }
+
+    /// Whether nothing was changed.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The recorded steps, in the order they were taken.
+    fn steps(&self) -> impl Iterator<Item = (SliceMove, T::Delta)> + '_ {
+        let mut src = self.steps.as_slice();
+        from_fn(move || {
+            let from = decode_varint(&mut src)?;
+            let dist = decode_varint(&mut src)?;
+            let to = if dist & 1 == 0 {
+                from.checked_add(dist >> 1)
+            } else {
+                from.checked_sub(dist >> 1)
+            }?;
+            Some((SliceMove { from, to }, T::Delta::decode(&mut src)?))
+        })
+    }
+
+    /// Repeats the recorded change on `data`, which must be the same as it was before the change.
+    pub fn apply(&self, data: &mut [T]) {
+        for (m, delta) in self.steps() {
+            if let Some(x) = data.get_mut(m.from) {
+                x.patch(delta)
+            }
+            m.perform(data)
+        }
+    }
+
+    /// Reverts the recorded change on `data`, which must be the same as it was after the change.
+    pub fn unapply(&self, data: &mut [T]) {
+        let steps: Vec<_> = self.steps().collect();
+        for (m, delta) in steps.into_iter().rev() {
+            m.rev().perform(data);
+            if let Some(x) = data.get_mut(m.from) {
+                x.patch(delta)
+            }
+        }
+    }
+
+    /// Adjusts indices into the pattern as it was before the change to point to the same elements
+    /// after it.
+    pub fn apply_to_indices(&self, indices: &mut [usize]) {
+        for (m, _) in self.steps() {
+            m.apply(indices)
+        }
+    }
+
+    /// Adjusts indices into the pattern as it was after the change to point to the same elements
+    /// before it.
+    pub fn unapply_to_indices(&self, indices: &mut [usize]) {
+        let steps: Vec<_> = self.steps().collect();
+        for (m, _) in steps.into_iter().rev() {
+            m.rev().apply(indices)
+        }
+    }
+}
+
+#[test]
+fn pattern_diff_roundtrip() {
+    use crate::rng::Rng;
+    let mut rng = Rng::new(0);
+    let mut random = |max: f64| R64::new_or(R64::ZERO, (rng.next_signed() + 1.0) * max / 2.0);
+    for len in [0, 1, 2, 10, 100] {
+        let mut data: Vec<R64> = (0..len).map(|_| random(10.0)).collect();
+        data.sort_unstable();
+        let original = data.clone();
+        let mut diff = PatternDiff::default();
+        let mut ids: Vec<usize> = (0..len).filter(|_| *random(1.0) < 0.5).collect();
+        for i in 0..ids.len() {
+            let Some(&id) = ids.get(i) else { continue };
+            let value = data.get(id).map_or(R64::ZERO, |&x| x + random(2.0) - 1.0);
+            let Ok(m) = diff.set(&mut data, id, value, Ord::cmp) else {
+                panic!("index {id} out of bounds")
+            };
+            m.apply(&mut ids);
+        }
+        assert!(data.windows(2).all(|x| x[0] <= x[1]));
+        let changed = data.clone();
+        diff.unapply(&mut data);
+        assert_eq!(data, original);
+        diff.apply(&mut data);
+        assert_eq!(data, changed);
+    }
+}
+
+#[test]
+fn delta_packing() {
+    let half = R64::new_or(R64::ZERO, 1.5).delta(&R64::ONE);
+    let deltas = [(0u64, 1), (0x100, 2), (half, 2), (1 << 63 | 1, 9), (u64::MAX, 9)];
+    let mut buf = vec![];
+    for (delta, size) in deltas {
+        let start = buf.len();
+        delta.encode(&mut buf);
+        assert_eq!(buf.len() - start, size, "{delta:#x}");
+    }
+    let mut src = buf.as_slice();
+    for (delta, _) in deltas {
+        assert_eq!(u64::decode(&mut src), Some(delta));
+    }
+    assert!(src.is_empty());
+    // truncated input isn't read past its end
+    assert_eq!(u64::decode(&mut &[0x13][..]), None);
+}
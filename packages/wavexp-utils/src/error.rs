@@ -4,6 +4,7 @@ use std::{
     hint::unreachable_unchecked,
     num::TryFromIntError,
     ops::{ControlFlow, FromResidual, Try},
+    panic::Location,
     str::Utf8Error,
 };
 
@@ -13,7 +14,10 @@ use web_sys::{
     HtmlElement,
 };
 
-use crate::{ext::HtmlDocumentExt, js::document};
+use crate::{
+    ext::{HtmlDocumentExt, ReorderError, SetSortedError},
+    js::document,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 // TODO: optimise by using an enum to delay conversion to a JsValue
@@ -54,6 +58,8 @@ macro_rules! impl_into_app_error {
 
 impl_into_app_error! {
     hound::Error,
+    ReorderError,
+    SetSortedError,
     TryReserveError,
     Utf8Error,
     TryFromIntError,
@@ -108,6 +114,23 @@ pub fn report_err(err: js_sys::Error) {
     }
 }
 
+/// `report_err` with `loc` added to the error's message, for when the backtrace is unavailable
+pub fn report_err_at(err: js_sys::Error, loc: &Location) {
+    err.set_message(&located_message(&String::from(err.message()), loc));
+    report_err(err)
+}
+
+fn located_message(msg: &str, loc: &Location) -> String {
+    format!("{msg} (at {loc})")
+}
+
+#[test]
+fn error_location() {
+    let msg = located_message("`Option` contained the `None` value", Location::caller());
+    assert!(msg.starts_with("`Option` contained the `None` value (at "));
+    assert!(msg.contains("error.rs:"));
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ResultV2<T, E> {
     Ok(T),
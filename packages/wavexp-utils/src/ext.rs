@@ -1,8 +1,11 @@
 use std::{
     array::{from_fn, try_from_fn},
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
     iter::{successors, Sum},
-    mem::ManuallyDrop,
+    mem::{replace, ManuallyDrop},
     ops::{Add, Div, Mul, Neg, RangeBounds, Rem, Residual, Sub, Try},
+    panic::Location,
     ptr,
 };
 
@@ -11,9 +14,10 @@ use wasm_bindgen::JsCast;
 use web_sys::{CanvasRenderingContext2d, Document, Element, HtmlCanvasElement};
 
 use crate::{
-    bail,
-    error::{report_err, Result},
+    bail, ensure,
+    error::{report_err, report_err_at, Result},
     fallible,
+    js::window,
     range::RangeBoundsExt,
     AppError, Point, Rect, RoundTo, SliceRef,
 };
@@ -28,10 +32,43 @@ impl<T, const OUTER: usize, const INNER: usize> TransposedArray<T, OUTER, INNER>
     // [[1, 2], [4, 5], [7, 8]] => [[1, 4, 7], [2, 5, 8]]
     fn transposed(self) -> [[T; OUTER]; INNER] {
         let original = ManuallyDrop::new(self);
+        // SAFETY: every `(j, i)` pair is visited exactly once, so every element is moved out of
+        // `original` exactly once, & `original` itself is never dropped
         from_fn(|i| from_fn(|j| unsafe { ptr::read(&original[j][i]) }))
     }
 }
 
+#[test]
+fn array_transposed() {
+    use std::rc::Rc;
+    fn check<const OUTER: usize, const INNER: usize>() {
+        let src: [[usize; INNER]; OUTER] = from_fn(|i| from_fn(|j| i * INNER + j));
+        let mut naive = [[0; OUTER]; INNER];
+        for (i, row) in src.iter().enumerate() {
+            for (j, &x) in row.iter().enumerate() {
+                naive[j][i] = x;
+            }
+        }
+        assert_eq!(src.transposed(), naive, "{OUTER}x{INNER}");
+        assert_eq!(src.transposed().transposed(), src, "{OUTER}x{INNER}");
+    }
+    check::<2, 2>();
+    check::<3, 3>();
+    check::<4, 4>();
+    check::<2, 3>();
+    check::<3, 2>();
+    check::<1, 4>();
+    check::<0, 3>();
+    assert_eq!([[1, 2], [4, 5], [7, 8]].transposed(), [[1, 4, 7], [2, 5, 8]]);
+    // every element is moved exactly once: nothing's leaked or dropped twice
+    let counter = Rc::new(());
+    let src: [[_; 3]; 2] = from_fn(|_| from_fn(|_| counter.clone()));
+    let res = src.transposed();
+    assert_eq!(Rc::strong_count(&counter), 7);
+    drop(res);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
 pub trait ArrayExt<T, const N: usize>: Sized {
     fn zip<U, R>(self, other: [U; N], f: impl FnMut(T, U) -> R) -> [R; N];
 
@@ -46,6 +83,13 @@ pub trait ArrayExt<T, const N: usize>: Sized {
 
     fn zip_fold<U, R>(self, init: R, other: [U; N], f: impl FnMut(R, T, U) -> R) -> R;
 
+    /// Like `map`, but also passes the index of every element to `f`.
+    fn enumerate_map<R>(self, f: impl FnMut(usize, T) -> R) -> [R; N];
+
+    /// Like `map`, but stops at the 1st error returned by `f`, dropping all the elements
+    /// produced before it.
+    fn map_res<R, E>(self, f: impl FnMut(T) -> Result<R, E>) -> Result<[R; N], E>;
+
     fn sum<R: Sum<T>>(self) -> R;
 
     fn fits<Range, Bound>(self, ranges: &[Range; N]) -> bool
@@ -174,6 +218,19 @@ impl<T, const N: usize> ArrayExt<T, N> for [T; N] {
         self.into_iter().zip(other).fold(init, |r, (x, y)| f(r, x, y))
     }
 
+    fn enumerate_map<R>(self, mut f: impl FnMut(usize, T) -> R) -> [R; N] {
+        let mut iter = self.into_iter();
+        // Safety: `iter` is an iterator from `self`, which is of length N, as is the return type
+        from_fn(|i| f(i, unsafe { iter.next().unwrap_unchecked() }))
+    }
+
+    fn map_res<R, E>(self, mut f: impl FnMut(T) -> Result<R, E>) -> Result<[R; N], E> {
+        let mut iter = self.into_iter();
+        // Safety: same as in `enumerate_map`; on an error, `try_from_fn` drops the elements
+        // produced so far, & the rest of `self` is dropped along with `iter`
+        try_from_fn(|_| f(unsafe { iter.next().unwrap_unchecked() }))
+    }
+
     fn sum<R: Sum<T>>(self) -> R {
         self.into_iter().sum()
     }
@@ -200,6 +257,25 @@ impl<T, const N: usize> ArrayExt<T, N> for [T; N] {
     }*/
 }
 
+#[test]
+fn array_enumerate_map() {
+    assert_eq!([5, 6, 7].enumerate_map(|i, x| i * x), [0, 6, 14]);
+    assert_eq!(["a", "b"].enumerate_map(|i, x| format!("{x}{i}")), ["a0", "b1"]);
+}
+
+#[test]
+fn array_map_res() {
+    use std::rc::Rc;
+    assert_eq!(["1", "2", "3"].map_res(str::parse::<u8>), Ok([1, 2, 3]));
+    assert!(["1", "x", "3"].map_res(str::parse::<u8>).is_err());
+    // the elements produced before the error, as well as the unconsumed ones, are dropped
+    let counter = Rc::new(());
+    let src = [0, 1, 2, 3].map(|x| (x, counter.clone()));
+    let res = src.map_res(|(x, rc)| if x == 2 { Err(x) } else { Ok(rc) });
+    assert_eq!(res.err(), Some(2));
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
 pub trait BoolExt {
     fn choose<T>(self, on_true: T, on_false: T) -> T;
     fn then_or<T>(self, default: T, f: impl FnOnce() -> T) -> T;
@@ -277,6 +353,11 @@ pub trait ResultExt<T, E> {
     fn report(self) -> Option<T>
     where
         E: Into<AppError>;
+    /// like `report`, but also puts the location of the call into the reported error's message
+    #[track_caller]
+    fn report_loc(self) -> Option<T>
+    where
+        E: Into<AppError>;
 }
 
 impl<T, E> ResultExt<T, E> for Result<T, E> {
@@ -303,6 +384,14 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
             }
         }
     }
+
+    #[track_caller]
+    fn report_loc(self) -> Option<T>
+    where
+        E: Into<AppError>,
+    {
+        ok_or_report_at(self, |e, loc| report_err_at(e.into().into(), loc))
+    }
 }
 
 pub trait OptionExt<T> {
@@ -311,6 +400,10 @@ pub trait OptionExt<T> {
     fn drop(self) -> Option<()>;
     fn get_or_try_insert<E>(&mut self, f: impl FnOnce() -> Result<T, E>) -> Result<&mut T, E>;
     fn map_or_default<U: Default>(self, f: impl FnOnce(T) -> U) -> U;
+    /// `Option::zip` followed by `Option::map`
+    fn zip_map<U, R>(self, other: Option<U>, f: impl FnOnce(T, U) -> R) -> Option<R>;
+    /// `Option::and_then` followed by `Option::unwrap_or_default`
+    fn filter_map_or<U: Default>(self, f: impl FnOnce(T) -> Option<U>) -> U;
     fn try_map<U, R>(self, f: impl FnOnce(T) -> R) -> <R::Residual as Residual<Option<U>>>::TryType
     where
         R: Try<Output = U>,
@@ -352,6 +445,14 @@ impl<T> OptionExt<T> for Option<T> {
         self.map_or_else(default, f)
     }
 
+    fn zip_map<U, R>(self, other: Option<U>, f: impl FnOnce(T, U) -> R) -> Option<R> {
+        Some(f(self?, other?))
+    }
+
+    fn filter_map_or<U: Default>(self, f: impl FnOnce(T) -> Option<U>) -> U {
+        self.and_then(f).unwrap_or_default()
+    }
+
     fn try_map<U, R>(self, f: impl FnOnce(T) -> R) -> <R::Residual as Residual<Option<U>>>::TryType
     where
         R: Try<Output = U>,
@@ -364,11 +465,121 @@ impl<T> OptionExt<T> for Option<T> {
     }
 }
 
+/// `Result::ok`, handing the error to `report` along with the location of the caller.
+#[track_caller]
+fn ok_or_report_at<T, E>(res: Result<T, E>, report: impl FnOnce(E, &Location)) -> Option<T> {
+    match res {
+        Ok(x) => Some(x),
+        Err(e) => {
+            report(e, Location::caller());
+            None
+        }
+    }
+}
+
+#[test]
+fn result_report_loc() {
+    /// the same as `report_loc`, but recording the error & where it was reported from
+    #[track_caller]
+    fn report_loc<T>(res: Result<T, &str>, log: &mut Vec<String>) -> Option<T> {
+        ok_or_report_at(res, |e, loc| log.push(format!("{e} at {}:{}", loc.file(), loc.line())))
+    }
+
+    let mut log = vec![];
+    assert_eq!(report_loc(Ok(1), &mut log), Some(1));
+    assert!(log.is_empty());
+    let line = line!() + 1;
+    assert_eq!(report_loc(Err::<i32, _>("oops"), &mut log), None);
+    assert_eq!(log, [format!("oops at {}:{line}", file!())]);
+}
+
+#[test]
+fn option_zip_map() {
+    let mul = |x: i32, y: f64| x as f64 * y;
+    assert_eq!(Some(2).zip_map(Some(3.5), mul), Some(7.0));
+    assert_eq!(Some(2).zip_map(None, mul), None);
+    assert_eq!(None.zip_map(Some(3.5), mul), None);
+}
+
+#[test]
+fn option_filter_map_or() {
+    assert_eq!(Some(4).filter_map_or(|x: u32| x.checked_sub(1)), 3);
+    assert_eq!(Some(0).filter_map_or(|x: u32| x.checked_sub(1)), 0);
+    assert_eq!(None.filter_map_or(|x: u32| x.checked_sub(1)), 0);
+}
+
+/// Attributes of the 2D rendering context of a canvas, taking effect only when the context is
+/// requested for the 1st time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanvasContextOptions {
+    /// `false` makes the canvas opaque, speeding up its compositing with the rest of the page
+    pub alpha: bool,
+    /// `true` decouples the canvas from the event loop, lowering the latency of drawing
+    pub desynchronized: bool,
+}
+
+impl Default for CanvasContextOptions {
+    fn default() -> Self {
+        Self { alpha: true, desynchronized: false }
+    }
+}
+
+impl CanvasContextOptions {
+    /// the fields of the JS object passed to `getContext`
+    pub const fn fields(self) -> [(&'static str, bool); 2] {
+        [("alpha", self.alpha), ("desynchronized", self.desynchronized)]
+    }
+
+    #[apply(fallible!)]
+    pub fn to_js(self) -> js_sys::Object {
+        let res = js_sys::Object::new();
+        for (name, value) in self.fields() {
+            js_sys::Reflect::set(&res, &name.into(), &value.into())?;
+        }
+        res
+    }
+}
+
+#[test]
+fn canvas_context_options() {
+    let opts = CanvasContextOptions { alpha: false, ..Default::default() };
+    assert_eq!(opts.fields(), [("alpha", false), ("desynchronized", false)]);
+    let opts = CanvasContextOptions { desynchronized: true, ..opts };
+    assert_eq!(opts.fields(), [("alpha", false), ("desynchronized", true)]);
+    assert_eq!(
+        CanvasContextOptions::default().fields(),
+        [("alpha", true), ("desynchronized", false)]
+    );
+}
+
+/// Size in device pixels of the backing store of a canvas with `client_size` in CSS pixels,
+/// displayed with `device_pixel_ratio` device pixels per CSS pixel.
+pub fn hidpi_size(client_size: [i32; 2], device_pixel_ratio: f64) -> [u32; 2] {
+    client_size.map(|x| (x.max(0) as f64 * device_pixel_ratio).round() as u32)
+}
+
+#[test]
+fn hidpi_canvas_size() {
+    assert_eq!(hidpi_size([300, 150], 2.0), [600, 300]);
+    assert_eq!(hidpi_size([300, 150], 1.0), [300, 150]);
+    assert_eq!(hidpi_size([101, 33], 1.5), [152, 50]);
+    assert_eq!(hidpi_size([-1, 0], 2.0), [0, 0]);
+}
+
 pub trait HtmlCanvasExt {
+    /// Returns the 2D rendering context of the canvas, created with the default attributes if
+    /// requested for the 1st time.
     fn get_2d_context(&self) -> Result<CanvasRenderingContext2d>;
+    fn get_2d_context_with(&self, opts: CanvasContextOptions) -> Result<CanvasRenderingContext2d>;
     fn rect(&self) -> Rect;
     fn size(&self) -> [u32; 2];
     fn sync(&self);
+    /// Makes the backing store of the canvas as large as the canvas is on the screen in device
+    /// pixels & scales its 2D context for it to still be drawn on in CSS pixels.
+    /// Returns the size of the canvas in CSS pixels.
+    fn sync_hidpi(&self) -> Result<[u32; 2]>;
+    /// Like `sync_hidpi`, but for a canvas drawn on in units of `size` instead of its client size.
+    fn sync_hidpi_as(&self, size: [u32; 2]) -> Result;
 }
 
 impl HtmlCanvasExt for HtmlCanvasElement {
@@ -377,8 +588,14 @@ impl HtmlCanvasExt for HtmlCanvasElement {
         self.get_context("2d")??.unchecked_into()
     }
 
+    #[apply(fallible!)]
+    fn get_2d_context_with(&self, opts: CanvasContextOptions) -> CanvasRenderingContext2d {
+        let opts = opts.to_js()?;
+        self.get_context_with_context_options("2d", &opts)??.unchecked_into()
+    }
+
     fn rect(&self) -> Rect {
-        Rect(Point::ZERO, Point { x: self.width() as i32, y: self.height() as i32 })
+        Rect::new(Point::ZERO, Point { x: self.width() as i32, y: self.height() as i32 })
     }
 
     fn size(&self) -> [u32; 2] {
@@ -390,6 +607,23 @@ impl HtmlCanvasExt for HtmlCanvasElement {
             (self.client_height() as f64 / self.client_width() as f64 * self.width() as f64) as u32,
         );
     }
+
+    #[apply(fallible!)]
+    fn sync_hidpi(&self) -> [u32; 2] {
+        let size = self.client_size().map(|x| x.max(0) as u32);
+        self.sync_hidpi_as(size)?;
+        size
+    }
+
+    #[apply(fallible!)]
+    fn sync_hidpi_as(&self, size: [u32; 2]) {
+        let ratio = window().device_pixel_ratio();
+        let [w, h] = hidpi_size(size.map(|x| x as i32), ratio);
+        // resizing the canvas resets its context, so the scale is set afterwards
+        self.set_width(w);
+        self.set_height(h);
+        self.get_2d_context()?.set_transform(ratio, 0.0, 0.0, ratio, 0.0, 0.0)?;
+    }
 }
 
 pub trait HtmlDocumentExt {
@@ -409,7 +643,7 @@ pub trait HtmlElementExt {
 
 impl HtmlElementExt for Element {
     fn client_rect(&self) -> Rect {
-        Rect(Point::ZERO, Point { x: self.client_width(), y: self.client_height() })
+        Rect::new(Point::ZERO, Point { x: self.client_width(), y: self.client_height() })
     }
 
     fn client_size(&self) -> [i32; 2] {
@@ -417,6 +651,81 @@ impl HtmlElementExt for Element {
     }
 }
 
+/// Describes an element of a slice having been moved from one index to another, with the elements
+/// in between shifted to fill the gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceMove {
+    pub from: usize,
+    pub to: usize,
+}
+
+impl SliceMove {
+    /// Adjusts indices into the slice the move happened in so that they point to the same
+    /// elements as before it.
+    pub fn apply(self, indices: &mut [usize]) {
+        for i in indices {
+            if *i == self.from {
+                *i = self.to
+            } else if (self.from + 1..=self.to).contains(i) {
+                *i -= 1
+            } else if (self.to..self.from).contains(i) {
+                *i += 1
+            }
+        }
+    }
+
+    /// The move that brings the moved element back to where it was.
+    pub const fn rev(self) -> Self {
+        Self { from: self.to, to: self.from }
+    }
+
+    /// Moves the element of `slice` at `self.from` to `self.to`, shifting the elements in
+    /// between. Does nothing if either index is out of bounds.
+    pub fn perform<T>(self, slice: &mut [T]) {
+        if self.from <= self.to {
+            if let Some(x) = slice.get_mut(self.from..=self.to) {
+                x.rotate_left(1)
+            }
+        } else if let Some(x) = slice.get_mut(self.to..=self.from) {
+            x.rotate_right(1)
+        }
+    }
+}
+
+/// Returned by `SliceExt::reorder_by` & `SliceExt::reorder_by_key`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReorderError {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl Display for ReorderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "index of the element to reorder (is {}) should be < len (is {})",
+            self.index, self.len
+        )
+    }
+}
+
+/// Returned by `SliceExt::set_sorted_by` & `SliceExt::set_sorted_by_key`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetSortedError {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl Display for SetSortedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "index of the element to set (is {}) should be < len (is {})",
+            self.index, self.len
+        )
+    }
+}
+
 pub trait SliceExt<T> {
     fn not_empty(&self) -> bool;
     fn any(&self, f: impl FnMut(&T) -> bool) -> bool;
@@ -435,6 +744,67 @@ pub trait SliceExt<T> {
     /// `index` must be a valid index into `self`
     unsafe fn get_unchecked_aware(&self, index: usize) -> SliceRef<'_, T>;
     fn try_split_at(&self, mid: usize) -> Option<(&[T], &[T])>;
+    /// Like `windows`, but also yields the index of the 1st element of every window.
+    /// A `size` of 0 yields nothing instead of panicking.
+    fn windows_indexed<'this>(
+        &'this self,
+        size: usize,
+    ) -> impl Iterator<Item = (usize, &'this [T])>
+    where
+        T: 'this;
+    /// Like `chunks_exact_mut`, but also yields the index of the 1st element of every chunk.
+    /// A `size` of 0 yields nothing instead of panicking.
+    fn chunks_indexed_mut<'this>(
+        &'this mut self,
+        size: usize,
+    ) -> impl Iterator<Item = (usize, &'this mut [T])>
+    where
+        T: 'this;
+    /// Moves the element at `index` to where it belongs in the otherwise sorted `self`.
+    /// # Safety
+    /// `index` must be a valid index into `self`
+    unsafe fn reorder_unchecked_by(
+        &mut self,
+        index: usize,
+        f: impl FnMut(&T, &T) -> Ordering,
+    ) -> SliceMove;
+    /// # Safety
+    /// `index` must be a valid index into `self`
+    unsafe fn reorder_unchecked_by_key<K: Ord>(
+        &mut self,
+        index: usize,
+        f: impl FnMut(&T) -> K,
+    ) -> SliceMove;
+    fn reorder_by(
+        &mut self,
+        index: usize,
+        f: impl FnMut(&T, &T) -> Ordering,
+    ) -> Result<SliceMove, ReorderError>;
+    fn reorder_by_key<K: Ord>(
+        &mut self,
+        index: usize,
+        f: impl FnMut(&T) -> K,
+    ) -> Result<SliceMove, ReorderError>;
+    /// Replaces the element at `index` with `value` & moves it to where it belongs in the
+    /// otherwise sorted `self`.
+    fn set_sorted_by(
+        &mut self,
+        index: usize,
+        value: T,
+        f: impl FnMut(&T, &T) -> Ordering,
+    ) -> Result<SliceMove, SetSortedError>;
+    fn set_sorted_by_key<K: Ord>(
+        &mut self,
+        index: usize,
+        value: T,
+        f: impl FnMut(&T) -> K,
+    ) -> Result<SliceMove, SetSortedError>;
+    /// Returns the index of the 1st element of `self`, sorted by `f`, whose key isn't less than
+    /// `key`, i.e. the 1st index at which `key` can be inserted keeping `self` sorted.
+    fn lower_bound_by_key<K: Ord>(&self, key: &K, f: impl FnMut(&T) -> K) -> usize;
+    /// Returns the index of the 1st element of `self`, sorted by `f`, whose key is greater than
+    /// `key`, i.e. the last index at which `key` can be inserted keeping `self` sorted.
+    fn upper_bound_by_key<K: Ord>(&self, key: &K, f: impl FnMut(&T) -> K) -> usize;
 }
 
 impl<T> SliceExt<T> for [T] {
@@ -521,6 +891,143 @@ impl<T> SliceExt<T> for [T] {
         // fulfills the requirements of `split_at_unchecked`.
         Some(unsafe { self.split_at_unchecked(mid) })
     }
+
+    fn windows_indexed<'this>(&'this self, size: usize) -> impl Iterator<Item = (usize, &'this [T])>
+    where
+        T: 'this,
+    {
+        (size != 0).then(|| self.windows(size)).into_iter().flatten().enumerate()
+    }
+
+    fn chunks_indexed_mut<'this>(
+        &'this mut self,
+        size: usize,
+    ) -> impl Iterator<Item = (usize, &'this mut [T])>
+    where
+        T: 'this,
+    {
+        let chunks = (size != 0).then(|| self.chunks_exact_mut(size));
+        chunks.into_iter().flatten().enumerate().map(move |(i, x)| (i * size, x))
+    }
+
+    unsafe fn reorder_unchecked_by(
+        &mut self,
+        index: usize,
+        mut f: impl FnMut(&T, &T) -> Ordering,
+    ) -> SliceMove {
+        let (before, rest) = self.split_at_mut_unchecked(index);
+        let (elem, after) = rest.split_first_mut().unwrap_unchecked();
+        if before.last().is_some_and(|prev| f(prev, elem).is_gt()) {
+            let to = before.partition_point(|x| f(x, elem).is_le());
+            self.get_unchecked_mut(to..=index).rotate_right(1);
+            SliceMove { from: index, to }
+        } else {
+            let to = index + after.partition_point(|x| f(x, elem).is_lt());
+            self.get_unchecked_mut(index..=to).rotate_left(1);
+            SliceMove { from: index, to }
+        }
+    }
+
+    unsafe fn reorder_unchecked_by_key<K: Ord>(
+        &mut self,
+        index: usize,
+        mut f: impl FnMut(&T) -> K,
+    ) -> SliceMove {
+        self.reorder_unchecked_by(index, |a, b| f(a).cmp(&f(b)))
+    }
+
+    fn reorder_by(
+        &mut self,
+        index: usize,
+        f: impl FnMut(&T, &T) -> Ordering,
+    ) -> Result<SliceMove, ReorderError> {
+        let len = self.len();
+        if index >= len {
+            return Err(ReorderError { index, len });
+        }
+        Ok(unsafe { self.reorder_unchecked_by(index, f) })
+    }
+
+    fn reorder_by_key<K: Ord>(
+        &mut self,
+        index: usize,
+        f: impl FnMut(&T) -> K,
+    ) -> Result<SliceMove, ReorderError> {
+        let len = self.len();
+        if index >= len {
+            return Err(ReorderError { index, len });
+        }
+        Ok(unsafe { self.reorder_unchecked_by_key(index, f) })
+    }
+
+    fn set_sorted_by(
+        &mut self,
+        index: usize,
+        value: T,
+        f: impl FnMut(&T, &T) -> Ordering,
+    ) -> Result<SliceMove, SetSortedError> {
+        let len = self.len();
+        let Some(dst) = self.get_mut(index) else {
+            return Err(SetSortedError { index, len });
+        };
+        *dst = value;
+        Ok(unsafe { self.reorder_unchecked_by(index, f) })
+    }
+
+    fn set_sorted_by_key<K: Ord>(
+        &mut self,
+        index: usize,
+        value: T,
+        f: impl FnMut(&T) -> K,
+    ) -> Result<SliceMove, SetSortedError> {
+        let len = self.len();
+        let Some(dst) = self.get_mut(index) else {
+            return Err(SetSortedError { index, len });
+        };
+        *dst = value;
+        Ok(unsafe { self.reorder_unchecked_by_key(index, f) })
+    }
+
+    fn lower_bound_by_key<K: Ord>(&self, key: &K, mut f: impl FnMut(&T) -> K) -> usize {
+        self.partition_point(|x| f(x) < *key)
+    }
+
+    fn upper_bound_by_key<K: Ord>(&self, key: &K, mut f: impl FnMut(&T) -> K) -> usize {
+        self.partition_point(|x| f(x) <= *key)
+    }
+}
+
+#[test]
+fn slice_bounds_by_key() {
+    // seeded, to get the same data every time
+    let mut rng = crate::rng::Rng::new(0);
+    for len in [0, 1, 2, 10, 100] {
+        let mut data: Vec<(u64, usize)> = (0..len).map(|i| (rng.next_u64() % 20, i)).collect();
+        data.sort_unstable();
+        for key in 0..21 {
+            let lower = data.iter().position(|x| x.0 >= key).unwrap_or(len);
+            let upper = data.iter().position(|x| x.0 > key).unwrap_or(len);
+            assert_eq!(data.lower_bound_by_key(&key, |x| x.0), lower);
+            assert_eq!(data.upper_bound_by_key(&key, |x| x.0), upper);
+        }
+    }
+}
+
+#[test]
+fn slice_indexed_windows_and_chunks() {
+    let data = [1, 2, 3, 4, 5];
+    let windows: Vec<_> = data.windows_indexed(3).collect();
+    assert_eq!(windows, [(0, &[1, 2, 3][..]), (1, &[2, 3, 4]), (2, &[3, 4, 5])]);
+    assert_eq!(data.windows_indexed(0).count(), 0);
+    assert_eq!(data.windows_indexed(6).count(), 0);
+
+    let mut data = [0; 7];
+    for (i, chunk) in data.chunks_indexed_mut(3) {
+        chunk.fill(i + 1)
+    }
+    // the remainder that doesn't make up a whole chunk is left untouched
+    assert_eq!(data, [1, 1, 1, 4, 4, 4, 0]);
+    assert_eq!(data.chunks_indexed_mut(0).count(), 0);
 }
 
 #[test]
@@ -539,6 +1046,34 @@ fn slice_get_var_mut() {
     assert_eq!(x.get_var_mut(&[1, 4, 5, 1]), None);
 }
 
+#[test]
+fn slice_reorder_by_key() {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Block {
+        offset: f64,
+        id: u8,
+    }
+    let key = |x: &Block| (x.offset * 100.0) as i64;
+    let mut x = [0.0, 1.0, 2.5, 3.0, 4.0].map(|offset| Block { offset, id: offset as u8 });
+    let mut ids = [0, 2, 4];
+
+    x[1].offset = 3.5;
+    let moved = x.reorder_by_key(1, key);
+    assert_eq!(moved, Ok(SliceMove { from: 1, to: 3 }));
+    assert_eq!(x.map(|x| x.id), [0, 2, 3, 1, 4]);
+    moved.unwrap_or_else(|_| unreachable!()).apply(&mut ids);
+    assert_eq!(ids.map(|i| x[i].id), [0, 2, 4]);
+
+    let moved = x.set_sorted_by_key(4, Block { offset: -1.0, id: 4 }, key);
+    assert_eq!(moved, Ok(SliceMove { from: 4, to: 0 }));
+    assert_eq!(x.map(|x| x.id), [4, 0, 2, 3, 1]);
+
+    let moved =
+        x.set_sorted_by(2, Block { offset: 2.0, id: 2 }, |a, b| a.offset.total_cmp(&b.offset));
+    assert_eq!(moved, Ok(SliceMove { from: 2, to: 2 }));
+    assert_eq!(x.reorder_by_key(5, key), Err(ReorderError { index: 5, len: 5 }));
+}
+
 pub trait VecExt<T> {
     fn try_remove(&mut self, index: usize) -> Result<T>;
     /// # Safety
@@ -547,6 +1082,9 @@ pub trait VecExt<T> {
     fn try_swap_remove(&mut self, index: usize) -> Result<T>;
     fn try_insert(&mut self, index: usize, element: T) -> Result<&mut T>;
     fn push_unique(&mut self, value: T, f: impl Fn(&T, &T) -> bool) -> bool;
+    /// Removes the elements at `sorted_ids`, which must be strictly increasing & in bounds,
+    /// in 1 pass, returning the removed elements in order.
+    fn remove_indices(&mut self, sorted_ids: &[usize]) -> Result<Vec<T>>;
 }
 
 impl<T> VecExt<T> for Vec<T> {
@@ -623,6 +1161,37 @@ impl<T> VecExt<T> for Vec<T> {
         self.push(value);
         true
     }
+
+    #[apply(fallible!)]
+    fn remove_indices(&mut self, sorted_ids: &[usize]) -> Vec<T> {
+        let len = self.len();
+        ensure!(
+            sorted_ids.windows(2).all(|x| x[0] < x[1]),
+            "indices for removal should be strictly increasing"
+        );
+        if let Some(&last) = sorted_ids.last() {
+            ensure!(last < len, "removal index (is {last}) should be < len (is {len})");
+        }
+        let mut ids = sorted_ids.iter().peekable();
+        let mut removed = Vec::with_capacity(sorted_ids.len());
+        let src = replace(self, Vec::with_capacity(len - sorted_ids.len()));
+        for (i, x) in src.into_iter().enumerate() {
+            if ids.next_if(|&&id| id == i).is_some() {
+                removed.push(x)
+            } else {
+                self.push(x)
+            }
+        }
+        removed
+    }
+}
+
+#[test]
+fn vec_remove_indices() {
+    let mut x = vec!['a', 'b', 'c', 'd', 'e', 'f'];
+    assert_eq!(x.remove_indices(&[1, 3, 4]).ok(), Some(vec!['b', 'd', 'e']));
+    assert_eq!(x, ['a', 'c', 'f']);
+    assert_eq!(x.remove_indices(&[]).ok(), Some(vec![]));
 }
 
 // Shortcut functions for common traits
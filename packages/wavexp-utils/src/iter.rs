@@ -3,11 +3,22 @@ pub struct EveryNth<'slice, T> {
     n: usize,
     state: usize,
     off: usize,
+    back_state: usize,
+    back_off: usize,
+    /// number of elements yet to be yielded from either end
+    remaining: usize,
+}
+
+/// index of the last element in column `col` of a slice of length `len` split into `n` columns;
+/// `col` must be less than both `len` & `n`
+const fn last_in_column(len: usize, n: usize, col: usize) -> usize {
+    col + (len - 1 - col) / n * n
 }
 
 impl<'slice, T> Iterator for EveryNth<'slice, T> {
     type Item = &'slice T;
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
         if let res @ Some(_) = self.iter.get(self.state) {
             self.state += self.n;
             res
@@ -21,6 +32,24 @@ impl<'slice, T> Iterator for EveryNth<'slice, T> {
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'slice, T> DoubleEndedIterator for EveryNth<'slice, T> {
+    fn next_back(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        let res = self.iter.get(self.back_state);
+        if self.back_state >= self.n {
+            self.back_state -= self.n
+        } else if self.back_off > 0 {
+            self.back_off -= 1;
+            self.back_state = last_in_column(self.iter.len(), self.n, self.back_off);
+        }
+        res
+    }
 }
 
 pub struct EveryNthMut<'slice, T> {
@@ -28,11 +57,16 @@ pub struct EveryNthMut<'slice, T> {
     n: usize,
     state: usize,
     off: usize,
+    back_state: usize,
+    back_off: usize,
+    /// number of elements yet to be yielded from either end
+    remaining: usize,
 }
 
 impl<'slice, T> Iterator for EveryNthMut<'slice, T> {
     type Item = &'slice mut T;
     fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
         if let Some(res) = self.iter.get_mut(self.state) {
             self.state += self.n;
             Some(unsafe { (res as *mut T).as_mut().unwrap_unchecked() })
@@ -48,6 +82,27 @@ impl<'slice, T> Iterator for EveryNthMut<'slice, T> {
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'slice, T> DoubleEndedIterator for EveryNthMut<'slice, T> {
+    fn next_back(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        let res = self
+            .iter
+            .get_mut(self.back_state)
+            .map(|x| unsafe { (x as *mut T).as_mut().unwrap_unchecked() });
+        if self.back_state >= self.n {
+            self.back_state -= self.n
+        } else if self.back_off > 0 {
+            self.back_off -= 1;
+            self.back_state = last_in_column(self.iter.len(), self.n, self.back_off);
+        }
+        res
+    }
 }
 
 pub trait ToEveryNth<T> {
@@ -55,12 +110,24 @@ pub trait ToEveryNth<T> {
     fn every_nth_mut(&mut self, n: usize) -> EveryNthMut<'_, T>;
 }
 
+/// returns the column & the index of the element an `EveryNth` iterator over a slice of length
+/// `len` yields last, and the number of elements it yields in total
+fn every_nth_back(len: usize, n: usize) -> (usize, usize, usize) {
+    if len == 0 || n == 0 {
+        return (0, 0, 0);
+    }
+    let back_off = len.min(n) - 1;
+    (back_off, last_in_column(len, n, back_off), len)
+}
+
 impl<T> ToEveryNth<T> for [T] {
     fn every_nth(&self, n: usize) -> EveryNth<'_, T> {
-        EveryNth { iter: self, n, state: 0, off: 0 }
+        let (back_off, back_state, remaining) = every_nth_back(self.len(), n);
+        EveryNth { iter: self, n, state: 0, off: 0, back_state, back_off, remaining }
     }
     fn every_nth_mut(&mut self, n: usize) -> EveryNthMut<'_, T> {
-        EveryNthMut { iter: self, n, state: 0, off: 0 }
+        let (back_off, back_state, remaining) = every_nth_back(self.len(), n);
+        EveryNthMut { iter: self, n, state: 0, off: 0, back_state, back_off, remaining }
     }
 }
 
@@ -73,6 +140,33 @@ fn test_every_nth_mut() {
     assert_eq!(transposed_mut, [0, 3, 6, 9, 1, 4, 7, 10, 2, 5, 8]);
 }
 
+#[test]
+fn test_every_nth_rev() {
+    for len in [12, 11] {
+        let mut data: Vec<usize> = (0..len).collect();
+        let mut forward: Vec<usize> = data.every_nth(3).copied().collect();
+        forward.reverse();
+        let backward: Vec<usize> = data.every_nth(3).rev().copied().collect();
+        let backward_mut: Vec<usize> = data.every_nth_mut(3).rev().map(|x| *x).collect();
+        assert_eq!(backward, forward);
+        assert_eq!(backward_mut, forward);
+    }
+}
+
+#[test]
+fn test_every_nth_meet_in_middle() {
+    let mut data = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    let mut iter = data.every_nth_mut(3);
+    let mut visited = vec![];
+    while let Some(x) = iter.next() {
+        visited.push(*x);
+        if let Some(x) = iter.next_back() {
+            visited.push(*x)
+        }
+    }
+    assert_eq!(visited, [0, 8, 3, 5, 6, 2, 9, 10, 1, 7, 4]);
+}
+
 pub struct IterIndicesMut<'data, 'ids, T> {
     data: &'data mut [T],
     /// all indices are valid, trust me bro
@@ -90,8 +184,26 @@ impl<'data, 'ids, T> Iterator for IterIndicesMut<'data, 'ids, T> {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.ids.len() - self.state;
+        (len, Some(len))
+    }
 }
 
+impl<'data, 'ids, T> DoubleEndedIterator for IterIndicesMut<'data, 'ids, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.state >= self.ids.len() {
+            return None;
+        }
+        let (&id, rest) = self.ids.split_last()?;
+        self.ids = rest;
+        unsafe { (self.data.get_unchecked_mut(id) as *mut T).as_mut() }
+    }
+}
+
+impl<'data, 'ids, T> ExactSizeIterator for IterIndicesMut<'data, 'ids, T> {}
+
 pub trait ToIterIndicesMut<'data, 'ids, T> {
     /// # Safety
     /// all `ids` must be valid indices into `self`
@@ -124,3 +236,21 @@ impl<'data, 'ids, T> ToIterIndicesMut<'data, 'ids, T> for [T] {
         Some(IterIndicesMut { data: self, ids, state: 0 })
     }
 }
+
+#[test]
+fn iter_indices_mut_len() {
+    let mut data = [10, 11, 12, 13, 14];
+    let mut iter = unsafe { data.iter_indices_unchecked_mut(&[4, 0, 2, 3]) };
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next().copied(), Some(14));
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next_back().copied(), Some(13));
+    assert_eq!(iter.size_hint(), (2, Some(2)));
+    assert_eq!(iter.next().copied(), Some(10));
+    assert_eq!(iter.next_back().copied(), Some(12));
+    assert_eq!(iter.len(), 0);
+    assert!(iter.next().is_none() && iter.next_back().is_none());
+    assert!(data.iter_indices_mut(&[5]).is_none());
+    let rev = data.iter_indices_mut(&[1, 3]).map(|x| x.rev().map(|x| *x).collect::<Vec<_>>());
+    assert_eq!(rev, Some(vec![13, 11]));
+}
@@ -1,4 +1,4 @@
-use crate::{error::Result, real::R64};
+use crate::{error::Result, real::R64, time::Secs};
 use js_sys::Uint8Array;
 use wasm_bindgen::JsCast;
 use web_sys::{Blob, Document, HtmlAnchorElement, Url, Window};
@@ -86,6 +86,6 @@ pub fn document() -> Document {
 }
 
 /// returns precise current time in seconds.
-pub fn now() -> Option<R64> {
-    Some(R64::new(window().performance()?.now())? / 1000)
+pub fn now() -> Option<Secs> {
+    Some(Secs(R64::new(window().performance()?.now())? / 1000))
 }
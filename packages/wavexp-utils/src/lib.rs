@@ -18,12 +18,15 @@
 extern crate self as wavexp_utils;
 
 pub mod cell;
+pub mod diff;
 pub mod error;
 pub mod ext;
 pub mod iter;
 pub mod js;
 pub mod range;
 pub mod real;
+pub mod rng;
+pub mod time;
 
 use error::{AppError, Result};
 pub use js_sys;
@@ -247,24 +250,120 @@ impl Point {
     pub fn map<T>(self, mut f: impl FnMut(i32) -> T) -> [T; 2] {
         [f(self.x), f(self.y)]
     }
+
+    /// Rotates the point by `radians` around `about`.
+    /// The rotation is clockwise in screen coordinates, i.e. when Y grows downwards.
+    /// The result is rounded to the nearest integer coordinates, saturating at `i32`'s bounds.
+    pub fn rotate(self, radians: R64, about: Point) -> Point {
+        let dx = f64::from(self.x) - f64::from(about.x);
+        let dy = f64::from(self.y) - f64::from(about.y);
+        let (sin, cos) = radians.sin_cos();
+        Point {
+            x: (f64::from(about.x) + dx * cos - dy * sin).round() as i32,
+            y: (f64::from(about.y) + dx * sin + dy * cos).round() as i32,
+        }
+    }
+
+    /// Moves the point `factor` times farther from `about`.
+    /// The result is rounded to the nearest integer coordinates, saturating at `i32`'s bounds.
+    pub fn scale(self, factor: R64, about: Point) -> Point {
+        let dx = f64::from(self.x) - f64::from(about.x);
+        let dy = f64::from(self.y) - f64::from(about.y);
+        Point {
+            x: (f64::from(about.x) + dx * *factor).round() as i32,
+            y: (f64::from(about.y) + dy * *factor).round() as i32,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[test]
+fn point_rotate() {
+    use std::f64::consts::{FRAC_PI_2, PI};
+    let [quarter, half] = [FRAC_PI_2, PI].map(|x| R64::new_or(R64::ZERO, x));
+    let p = Point { x: 1, y: 0 };
+    assert_eq!(p.rotate(quarter, Point::ZERO), Point { x: 0, y: 1 });
+    assert_eq!(p.rotate(half, Point::ZERO), Point { x: -1, y: 0 });
+    let pivot = Point { x: 10, y: 10 };
+    assert_eq!(Point { x: 13, y: 10 }.rotate(-quarter, pivot), Point { x: 10, y: 7 });
+}
+
+#[test]
+fn point_scale() {
+    let pivot = Point { x: 5, y: -5 };
+    assert_eq!(Point { x: 8, y: -1 }.scale(r64!(2), pivot), Point { x: 11, y: 3 });
+    assert_eq!(Point { x: 8, y: -1 }.scale(r64!(0.5), pivot), Point { x: 7, y: -3 });
+    assert_eq!(Point { x: i32::MAX, y: 0 }.scale(r64!(2), Point::ZERO).x, i32::MAX);
+}
+
+/// an axis-aligned rectangle in screen coordinates, i.e. with Y growing downwards,
+/// including its boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Rect(Point, Point);
 
 impl Rect {
-    const fn left(&self) -> i32 {
+    /// Creates a rectangle with the 2 points as its opposite corners, in any order.
+    pub fn new(a: Point, b: Point) -> Self {
+        Self(Point { x: a.x.min(b.x), y: a.y.min(b.y) }, Point { x: a.x.max(b.x), y: a.y.max(b.y) })
+    }
+    pub const fn left(&self) -> i32 {
         self.0.x
     }
-    const fn bottom(&self) -> i32 {
+    pub const fn right(&self) -> i32 {
+        self.1.x
+    }
+    pub const fn top(&self) -> i32 {
+        self.0.y
+    }
+    pub const fn bottom(&self) -> i32 {
         self.1.y
     }
-    const fn width(&self) -> Option<i32> {
+    pub const fn width(&self) -> Option<i32> {
         self.1.x.checked_sub(self.0.x)
     }
-    const fn height(&self) -> Option<i32> {
+    pub const fn height(&self) -> Option<i32> {
         self.1.y.checked_sub(self.0.y)
     }
+
+    pub const fn center(&self) -> Point {
+        Point {
+            x: ((self.0.x as i64 + self.1.x as i64) / 2) as i32,
+            y: ((self.0.y as i64 + self.1.y as i64) / 2) as i32,
+        }
+    }
+
+    pub const fn contains(&self, p: Point) -> bool {
+        self.0.x <= p.x && p.x <= self.1.x && self.0.y <= p.y && p.y <= self.1.y
+    }
+
+    /// Returns the area covered by both rectangles, or `None` if they don't overlap.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let res = Self(
+            Point { x: self.0.x.max(other.0.x), y: self.0.y.max(other.0.y) },
+            Point { x: self.1.x.min(other.1.x), y: self.1.y.min(other.1.y) },
+        );
+        (res.0.x <= res.1.x && res.0.y <= res.1.y).then_some(res)
+    }
+}
+
+#[test]
+fn rect_contains() {
+    let rect = Rect::new(Point { x: 10, y: 20 }, Point { x: 0, y: 5 });
+    assert_eq!((rect.left(), rect.top(), rect.right(), rect.bottom()), (0, 5, 10, 20));
+    assert!(rect.contains(Point { x: 0, y: 5 }));
+    assert!(rect.contains(Point { x: 10, y: 12 }));
+    assert!(rect.contains(rect.center()));
+    assert!(!rect.contains(Point { x: 11, y: 12 }));
+    assert!(!rect.contains(Point { x: 5, y: 4 }));
+}
+
+#[test]
+fn rect_intersect() {
+    let a = Rect::new(Point::ZERO, Point { x: 10, y: 10 });
+    let b = Rect::new(Point { x: 5, y: -5 }, Point { x: 15, y: 5 });
+    assert_eq!(a.intersect(&b), Some(Rect::new(Point { x: 5, y: 0 }, Point { x: 10, y: 5 })));
+    assert_eq!(a.intersect(&b), b.intersect(&a));
+    let c = Rect::new(Point { x: 11, y: 0 }, Point { x: 20, y: 10 });
+    assert_eq!(a.intersect(&c), None);
 }
 
 pub trait RoundTo: Sized {
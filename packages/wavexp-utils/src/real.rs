@@ -541,10 +541,51 @@ macro_rules! real_impl {
                 self.0.is_finite()
             }
 
+            /// Follows IEEE 754: 1 for +0 & -1 for -0.
+            pub fn signum(self) -> Self {
+                Self(self.0.signum())
+            }
+
+            /// `true` for +0 too.
+            pub const fn is_sign_positive(&self) -> bool {
+                self.0.is_sign_positive()
+            }
+
+            /// `true` for -0 too.
+            pub const fn is_sign_negative(&self) -> bool {
+                self.0.is_sign_negative()
+            }
+
             pub fn abs(self) -> Self {
                 Self(self.0.abs())
             }
 
+            pub fn min(self, other: Self) -> Self {
+                Self(self.0.min(other.0))
+            }
+
+            pub fn max(self, other: Self) -> Self {
+                Self(self.0.max(other.0))
+            }
+
+            /// Restricts the value to `lo ..= hi`; unlike `f64::clamp`, only panics on `lo > hi` in
+            /// debug builds.
+            pub fn clamp(self, lo: Self, hi: Self) -> Self {
+                debug_assert!(lo <= hi, "`clamp`'s lower bound is greater than its upper bound");
+                Self(self.0.max(lo.0).min(hi.0))
+            }
+
+            /// Whether `self` & `value` differ by at most `rel` times the greater of their
+            /// magnitudes.
+            pub fn loose_eq_rel(&self, value: Self, rel: Self) -> bool {
+                let max = self.0.abs().max(value.0.abs());
+                self.0 == value.0 || (self.0 - value.0).abs() <= rel.0 * max
+            }
+
+            pub fn loose_ne_rel(&self, value: Self, rel: Self) -> bool {
+                !self.loose_eq_rel(value, rel)
+            }
+
             pub fn sin(self) -> Option<Self> {
                 Self::new(self.0.sin())
             }
@@ -572,6 +613,84 @@ macro_rules! real_impl {
             pub fn cos_or(self, default: Self) -> Self {
                 Self::new(self.0.cos()).unwrap_or(default)
             }
+
+            pub fn powi(self, n: i32) -> Self {
+                Self(self.0.powi(n))
+            }
+
+            pub fn powf(self, other: Self) -> Option<Self> {
+                Self::new(self.0.powf(other.0))
+            }
+
+            /// # Safety
+            /// `self` must not be negative or `other` must be an integer
+            pub unsafe fn powf_unchecked(self, other: Self) -> Self {
+                Self(self.0.powf(other.0))
+            }
+
+            pub fn powf_or(self, other: Self, default: Self) -> Self {
+                Self::new(self.0.powf(other.0)).unwrap_or(default)
+            }
+
+            pub fn cbrt(self) -> Self {
+                Self(self.0.cbrt())
+            }
+
+            pub fn sqrt(self) -> Option<Self> {
+                Self::new(self.0.sqrt())
+            }
+
+            /// # Safety
+            /// `self` must not be negative
+            pub unsafe fn sqrt_unchecked(self) -> Self {
+                Self(self.0.sqrt())
+            }
+
+            pub fn sqrt_or(self, default: Self) -> Self {
+                Self::new(self.0.sqrt()).unwrap_or(default)
+            }
+
+            pub fn ln(self) -> Option<Self> {
+                Self::new(self.0.ln())
+            }
+
+            /// # Safety
+            /// `self` must not be negative
+            pub unsafe fn ln_unchecked(self) -> Self {
+                Self(self.0.ln())
+            }
+
+            pub fn ln_or(self, default: Self) -> Self {
+                Self::new(self.0.ln()).unwrap_or(default)
+            }
+
+            pub fn log2(self) -> Option<Self> {
+                Self::new(self.0.log2())
+            }
+
+            /// # Safety
+            /// `self` must not be negative
+            pub unsafe fn log2_unchecked(self) -> Self {
+                Self(self.0.log2())
+            }
+
+            pub fn log2_or(self, default: Self) -> Self {
+                Self::new(self.0.log2()).unwrap_or(default)
+            }
+
+            pub fn log10(self) -> Option<Self> {
+                Self::new(self.0.log10())
+            }
+
+            /// # Safety
+            /// `self` must not be negative
+            pub unsafe fn log10_unchecked(self) -> Self {
+                Self(self.0.log10())
+            }
+
+            pub fn log10_or(self, default: Self) -> Self {
+                Self::new(self.0.log10()).unwrap_or(default)
+            }
         }
     };
 }
@@ -579,6 +698,54 @@ macro_rules! real_impl {
 real_impl!(R32 { f32 }, R64 { f64 });
 real_impl!(R64 { f64 }, R32 { f32 });
 
+#[test]
+fn real_clamp() {
+    let (lo, hi) = (R64(-1.0), R64(2.5));
+    assert_eq!(R64(-3.0).clamp(lo, hi), lo);
+    assert_eq!(R64(1.0).clamp(lo, hi), R64(1.0));
+    assert_eq!(R64(4.0).clamp(lo, hi), hi);
+    assert_eq!(R64::NEG_INFINITY.clamp(lo, hi), lo);
+    assert_eq!(R32(0.5).clamp(R32(1.0), R32(1.0)), R32(1.0));
+    assert_eq!(R64(1.0).min(R64(-2.0)), R64(-2.0));
+    assert_eq!(R32(1.0).max(R32(-2.0)), R32(1.0));
+}
+
+#[test]
+fn real_loose_eq_rel() {
+    assert!(R64(1000.0).loose_eq_rel(R64(1000.5), R64(0.001)));
+    assert!(R64(1000.0).loose_ne_rel(R64(1000.5), R64(0.0001)));
+    assert!(R32(1000.5).loose_eq_rel(R32(1000.0), R32(0.001)));
+    assert!(R32(1000.5).loose_ne_rel(R32(1000.0), R32(0.0001)));
+    assert!(R64(0.0).loose_eq_rel(R64(0.0), R64(0.0)));
+    assert!(R64::INFINITY.loose_eq_rel(R64::INFINITY, R64(0.001)));
+    assert!(R64(-1.0).loose_ne_rel(R64(1.0), R64(0.5)));
+}
+
+#[test]
+fn real_sign() {
+    assert_eq!(R64(2.5).signum(), R64(1.0));
+    assert_eq!(R64(-0.1).signum(), R64(-1.0));
+    assert_eq!(R64(0.0).signum(), R64(1.0));
+    assert_eq!(R64(-0.0).signum(), R64(-1.0));
+    assert_eq!(R32(-3.0).signum(), R32(-1.0));
+    assert!(R64(1.0).is_sign_positive() && !R64(1.0).is_sign_negative());
+    assert!(R32(-1.0).is_sign_negative() && !R32(-1.0).is_sign_positive());
+    assert!(R64(0.0).is_sign_positive() && R64(-0.0).is_sign_negative());
+}
+
+#[test]
+fn real_pow_log() {
+    assert_eq!(R64(8.0).log2(), Some(R64(3.0)));
+    assert_eq!(R64(1000.0).log10(), Some(R64(3.0)));
+    assert_eq!(R64(9.0).sqrt(), Some(R64(3.0)));
+    assert_eq!(R64(-1.0).sqrt(), None);
+    assert_eq!(R64(-1.0).sqrt_or(R64::ZERO), R64::ZERO);
+    assert_eq!(R64(-8.0).cbrt(), R64(-2.0));
+    assert_eq!(R64(-2.0).powi(3), R64(-8.0));
+    assert_eq!(R64(-2.0).powf(R64(0.5)), None);
+    assert_eq!(R32(4.0).powf(R32(0.5)), Some(R32(2.0)));
+}
+
 #[macro_export]
 macro_rules! r32 {
     ($x:literal) => {{
@@ -0,0 +1,39 @@
+/// Xorshift pseudo-random number generator, unlike `Math.random` giving the same sequence for the
+/// same seed on every run & being available outside of the browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// also substitutes a seed of 0, as xorshift never leaves the all-zeros state
+    pub const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+    pub const fn new(seed: u64) -> Self {
+        Self(if seed == 0 { Self::DEFAULT_SEED } else { seed })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// uniformly distributed in [-1, 1)
+    pub fn next_signed(&mut self) -> f64 {
+        // the top 53 bits mapped onto [-1, 1)
+        (self.next_u64() >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+#[test]
+fn rng_determinism() {
+    let seq = |seed| {
+        let mut rng = Rng::new(seed);
+        (0..64).map(|_| rng.next_u64()).collect::<Vec<_>>()
+    };
+    assert_eq!(seq(42), seq(42));
+    assert_ne!(seq(42), seq(43));
+    assert_ne!(seq(0), seq(1));
+    let mut rng = Rng::new(7);
+    assert!((0..1000).map(|_| rng.next_signed()).all(|x| (-1.0..1.0).contains(&x)));
+}
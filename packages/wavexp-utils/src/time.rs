@@ -0,0 +1,237 @@
+//! Units of time, kept as distinct types so that seconds can't be passed where beats are
+//! expected, or vice versa:
+//! ```compile_fail
+//! use wavexp_utils::time::{Beats, Secs};
+//! fn wait(_: Beats) {}
+//! wait(Secs::ZERO);
+//! ```
+//! ```compile_fail
+//! use wavexp_utils::time::{Beats, Secs};
+//! let _ = Beats::ZERO + Secs::ZERO;
+//! ```
+use crate::{
+    real::{R32, R64},
+    AppError, RoundTo,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter::Sum,
+    ops::{Add, AddAssign, Deref, Div, DivAssign, Mul, MulAssign, Neg, Rem, Sub, SubAssign},
+};
+use yew::html::IntoPropValue;
+
+macro_rules! time_unit_impl {
+    ($($(#[$attr:meta])* $unit:ident),+) => {$(
+        $(#[$attr])*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+        pub struct $unit(pub R64);
+
+        impl $unit {
+            pub const ZERO: Self = Self(R64::ZERO);
+            pub const ONE: Self = Self(R64::ONE);
+            pub const INFINITY: Self = Self(R64::INFINITY);
+
+            pub fn floor(self) -> Self {
+                Self(self.0.floor())
+            }
+
+            pub fn ceil(self) -> Self {
+                Self(self.0.ceil())
+            }
+
+            pub fn round(self) -> Self {
+                Self(self.0.round())
+            }
+
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+        }
+
+        impl Deref for $unit {
+            type Target = f64;
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl From<R64> for $unit {
+            fn from(x: R64) -> Self {
+                Self(x)
+            }
+        }
+
+        impl TryFrom<f64> for $unit {
+            type Error = AppError;
+            fn try_from(x: f64) -> Result<Self, Self::Error> {
+                R64::try_from(x).map(Self)
+            }
+        }
+
+        impl From<$unit> for R64 {
+            fn from(x: $unit) -> Self {
+                x.0
+            }
+        }
+
+        impl IntoPropValue<R64> for $unit {
+            fn into_prop_value(self) -> R64 {
+                self.0
+            }
+        }
+
+        impl Display for $unit {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Display::fmt(&self.0, f)
+            }
+        }
+
+        impl Neg for $unit {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Self(-self.0)
+            }
+        }
+
+        impl Add for $unit {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl AddAssign for $unit {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl SubAssign for $unit {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0
+            }
+        }
+
+        impl Rem for $unit {
+            type Output = Self;
+            fn rem(self, rhs: Self) -> Self::Output {
+                Self(self.0 % rhs.0)
+            }
+        }
+
+        /// the ratio of 2 durations is unitless
+        impl Div for $unit {
+            type Output = R64;
+            fn div(self, rhs: Self) -> Self::Output {
+                self.0 / rhs.0
+            }
+        }
+
+        impl<T> Mul<T> for $unit
+        where
+            R64: Mul<T, Output = R64>,
+        {
+            type Output = Self;
+            fn mul(self, rhs: T) -> Self::Output {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl<T> MulAssign<T> for $unit
+        where
+            R64: MulAssign<T>,
+        {
+            fn mul_assign(&mut self, rhs: T) {
+                self.0 *= rhs
+            }
+        }
+
+        impl<T: Scalar> Div<T> for $unit
+        where
+            R64: Div<T, Output = R64>,
+        {
+            type Output = Self;
+            fn div(self, rhs: T) -> Self::Output {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl<T: Scalar> DivAssign<T> for $unit
+        where
+            R64: DivAssign<T>,
+        {
+            fn div_assign(&mut self, rhs: T) {
+                self.0 /= rhs
+            }
+        }
+
+        impl RoundTo for $unit {
+            fn floor_to(self, step: Self) -> Self {
+                Self(self.0.floor_to(step.0))
+            }
+
+            fn ceil_to(self, step: Self) -> Self {
+                Self(self.0.ceil_to(step.0))
+            }
+        }
+
+        impl Sum for $unit {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|x| x.0).sum())
+            }
+        }
+
+        impl<'item> Sum<&'item $unit> for $unit {
+            fn sum<I: Iterator<Item = &'item Self>>(iter: I) -> Self {
+                Self(iter.map(|x| x.0).sum())
+            }
+        }
+    )+};
+}
+
+/// A unitless number a duration can be multiplied or divided by.
+/// Exists to keep the division of a duration by a number apart from the division of
+/// 2 durations, which yields a unitless ratio instead.
+pub trait Scalar {}
+
+macro_rules! scalar_impl {
+    ($($t:ty),+) => {$(impl Scalar for $t {} impl Scalar for &$t {})+};
+}
+
+scalar_impl!(R64, R32, f64, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+scalar_impl!(
+    std::num::NonZeroU8,
+    std::num::NonZeroU16,
+    std::num::NonZeroU32,
+    std::num::NonZeroUsize
+);
+
+time_unit_impl! {
+    /// Time measured in beats, i.e. relative to the tempo of the composition.
+    Beats,
+    /// Time measured in seconds.
+    Secs,
+    /// Time measured in milliseconds.
+    MSecs
+}
+
+#[test]
+fn time_unit_arithmetic() {
+    use crate::r64;
+    let x = Beats(r64!(3));
+    assert_eq!(x + Beats(r64!(1)), Beats(r64!(4)));
+    assert_eq!(x * 2u8, Beats(r64!(6)));
+    assert_eq!(x / 2u8, Beats(r64!(1.5)));
+    assert_eq!(x / Beats(r64!(2)), r64!(1.5));
+    assert_eq!(x % Beats(r64!(2)), Beats(r64!(1)));
+    assert_eq!([x, x, -x].into_iter().sum::<Beats>(), x);
+    assert_eq!(Beats(r64!(2.5)).floor_to(Beats(R64::ONE)), Beats(r64!(2)));
+    assert!(Secs::ZERO < Secs(r64!(0.1)));
+}
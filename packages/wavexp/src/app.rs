@@ -17,7 +17,7 @@ use crate::{
     editor::Editor,
     img,
     popup::Popup,
-    sound::Secs,
+    sound::{MSecs, Secs},
 };
 
 /// carries all the app-wide settings that are passed to all the event receivers
@@ -36,6 +36,16 @@ impl AppContext {
     pub fn force_rerender(&mut self) {
         self.rerender_needed = true
     }
+
+    pub const fn frame(&self) -> Secs {
+        self.frame
+    }
+
+    /// an app context that's usable outside of a browser
+    #[cfg(test)]
+    pub fn test() -> Self {
+        Self { frame: Secs::ZERO, event_emitter: Callback::noop(), rerender_needed: false }
+    }
 }
 
 impl ContextMut<'_, '_> {
@@ -78,7 +88,7 @@ impl Component for App {
             projects: vec![Editor::new().unwrap()],
             selected_proj: 0,
             ctx: AppContext::new(ctx.link().callback(|x| x)).unwrap(),
-            frame_emitter: js_function!(|x| cb.emit(R64::new_or(r64!(0), x))),
+            frame_emitter: js_function!(|x| cb.emit(MSecs(R64::new_or(r64!(0), x)))),
             popups: vec![],
         };
         window().request_animation_frame(&res.frame_emitter).unwrap();
@@ -91,7 +101,7 @@ impl Component for App {
             match msg {
                 AppEvent::Frame(frame) => {
                     window().request_animation_frame(&self.frame_emitter)?;
-                    self.ctx.frame = frame / 1000
+                    self.ctx.frame = Secs(frame.0 / 1000)
                 }
 
                 AppEvent::StartPlay(_) | AppEvent::StopPlay => self.ctx.rerender_needed = true,
@@ -99,24 +109,34 @@ impl Component for App {
                 AppEvent::KeyPress(_, ref e) if !e.repeat() && e.code() == "Escape" => {
                     if let Some(closed) = self.popups.pop() {
                         e.prevent_default();
-                        selected_proj
-                            .ctx
-                            .register_action(&mut self.ctx, EditorAction::ClosePopup(closed))?;
+                        if closed.undoable() {
+                            selected_proj
+                                .ctx
+                                .register_action(&mut self.ctx, EditorAction::ClosePopup(closed))?;
+                        }
+                        self.ctx.rerender_needed = true;
                     }
                 }
 
                 AppEvent::OpenPopup(ref opened) => {
-                    selected_proj
-                        .ctx
-                        .register_action(&mut self.ctx, EditorAction::OpenPopup(opened.clone()))?;
+                    if opened.undoable() {
+                        selected_proj.ctx.register_action(
+                            &mut self.ctx,
+                            EditorAction::OpenPopup(opened.clone()),
+                        )?;
+                    }
                     self.popups.push(opened.clone());
+                    self.ctx.rerender_needed = true;
                 }
 
                 AppEvent::ClosePopup => {
                     let closed = self.popups.pop()?;
-                    selected_proj
-                        .ctx
-                        .register_action(&mut self.ctx, EditorAction::ClosePopup(closed))?;
+                    if closed.undoable() {
+                        selected_proj
+                            .ctx
+                            .register_action(&mut self.ctx, EditorAction::ClosePopup(closed))?;
+                    }
+                    self.ctx.rerender_needed = true;
                 }
 
                 AppEvent::Undo(ref actions) => {
@@ -149,10 +169,8 @@ impl Component for App {
             if let Some(popup) = self.popups.last_mut() {
                 popup.handle_event(
                     &msg,
-                    ContextMut {
-                        app: &mut self.ctx,
-                        editor: &mut selected_proj.ctx,
-                    },
+                    ContextMut { app: &mut self.ctx, editor: &mut selected_proj.ctx },
+                    &selected_proj.sequencer,
                 )?;
             }
             return take(&mut self.ctx.rerender_needed)
@@ -1,18 +1,31 @@
 //! This module contains the types used for communication between components of the app
 
-use std::{any::Any, borrow::Cow, mem::transmute, num::NonZeroU32, ops::Deref, rc::Rc};
+use std::{
+    any::Any,
+    borrow::Cow,
+    mem::{discriminant, transmute},
+    num::NonZeroU32,
+    ops::Deref,
+    rc::Rc,
+    slice::from_ref,
+};
 
 use crate::{
     app::AppContext,
     editor::EditorContext,
     popup::Popup,
-    sound::{AudioInput, Beats, MSecs, SoundType},
-    visual::SpecialAction,
+    sequencer::{CompressorSetting, LayerMix},
+    sound::{
+        ArpMode, AudioInput, Beats, Chord, FilterType, LfoShape, MSecs, NoiseColor, Note,
+        NoteBlock, Scale, SoundType, TempoMap,
+    },
+    visual::{Axis, GradientPreset, SpecialAction},
 };
 use wavexp_utils::{
     cell::Shared,
+    diff::PatternDiff,
     error::{AppError, Result},
-    ext::ArrayExt,
+    ext::{ArrayExt, SliceExt},
     real::R32,
     real::R64,
     Point,
@@ -46,6 +59,8 @@ pub enum AppEvent {
     Remove,
     /// emitted when a `Noise` sound block's volume has been changed
     Volume(R32),
+    /// emitted when a sound block's stereo position has been changed
+    Pan(R32),
     /// emitted when a sound block's attack time has been changed
     Attack(Beats),
     /// emitted when a sound block's decay time has been changed
@@ -55,11 +70,45 @@ pub enum AppEvent {
     /// emitted when a sound block's release time has been changed
     Release(Beats),
     /// emitted when the global BPM has been changed
-    Bpm(R64),
+    SetBpm(R64),
+    /// add a change of the tempo of the composition at the playhead
+    AddTempoChange,
+    /// set the tempo in BPM from the given beat on, or remove the change of tempo at it if `None`
+    SetTempoChange(Beats, Option<R64>),
     /// emitted when the global volume has been changed
     MasterVolume(R32),
+    /// emitted when the swing of the playback has been changed
+    Swing(R32),
+    /// set the volume of the blocks on a layer in the mixer
+    LayerVolume(u32, R32),
+    /// toggle whether the blocks on a layer are muted in the mixer
+    ToggleLayerMute(u32),
+    /// emitted when the delay time of the master bus' echo has been changed
+    DelayTime(Beats),
+    /// emitted when the feedback of the master bus' echo has been changed
+    DelayFeedback(R32),
+    /// emitted when the volume of the master bus' echo has been changed
+    DelayWet(R32),
+    /// emitted when a parameter of the limiter on the master bus has been changed
+    SetCompressor(CompressorSetting),
+    /// emitted when the metronome needs to be turned on or off
+    ToggleMetronome,
+    /// emitted when the number of beats the metronome counts in before the playback has been
+    /// changed
+    SetCountIn(Beats),
+    /// emitted when the user starts playing a note of the selected `Note` sound block
+    /// with the computer keyboard
+    PianoKeyDown(Note),
+    /// emitted when the user releases a key of the computer keyboard that was playing a note
+    PianoKeyUp(Note),
     /// emitted when the global editor snap step has been changed
     SnapStep(R64),
+    /// emitted when snapping dragged points to the edges of other points is toggled
+    ToggleMagneticSnap,
+    /// emitted when drawing the original positions of a dragged selection is toggled
+    ToggleDragGhost,
+    /// emitted when playing the selected `Note` sound block with the computer keyboard is toggled
+    TogglePiano,
     /// emitted when the user selects the type of sound block for the selected sound block
     SetBlockType(SoundType),
     /// emitted when the user focuses an editor plane i.e. by holding left click
@@ -74,6 +123,35 @@ pub enum AppEvent {
     /// emitted when the user releases any key on the keyboard
     /// the 1st field is the `GraphEditor::id` of the recipient
     KeyRelease(usize, KeyboardEvent),
+    /// emitted when the user copies the selected points of an editor plane
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    Copy(usize),
+    /// emitted when the user pastes the copied points into an editor plane at the cursor
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    Paste(usize),
+    /// emitted when the user duplicates the selected points of an editor plane
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    DuplicateSelection(usize),
+    /// emitted when the user removes the selected points of an editor plane
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    RemoveSelection(usize),
+    /// emitted when the user right-clicks an editor plane
+    /// the 1st field is the `GraphEditor::id` of the recipient
+    OpenContextMenu(usize, MouseEvent),
+    /// emitted when the user selects all the points of an editor plane
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    SelectAll(usize),
+    /// emitted when the user selects all the unselected points of an editor plane and unselects
+    /// the selected ones
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    InvertSelection(usize),
+    /// emitted when the user moves the selected points of an editor plane with the arrow keys
+    /// `dx` & `dy` are in the user space of the editor plane
+    NudgeSelection { editor_id: usize, dx: R64, dy: R64 },
+    /// emitted when the user scrolls the mouse wheel over an editor plane
+    /// `at` is the position of the cursor relative to the canvas element, in CSS pixels;
+    /// the scale along `axis` is multiplied by `2 ^ -delta`
+    Zoom { editor_id: usize, delta: R64, at: Point, axis: Axis },
     /// emitted when the user drags the cursor out of an editor plane
     /// the inner `usize` is the `GraphEditor::id` of the recipient
     Enter(usize, MouseEvent),
@@ -99,6 +177,49 @@ pub enum AppEvent {
     Rewind(usize),
     /// set the repetition count of a sound block
     RepCount(NonZeroU32),
+    /// set the gap between the repetitions of a sound block's pattern
+    RepGap(Beats),
+    /// toggle whether a sound block is muted, the payload is the index of the block
+    ToggleMute(usize),
+    /// toggle whether a sound block is soloed, the payload is the index of the block
+    ToggleSolo(usize),
+    /// set the scale to which the notes of a `Note` sound block are snapped
+    Scale(Scale),
+    /// set the root of the scale of a `Note` sound block, in semitones from C
+    ScaleRoot(u8),
+    /// set the chord placed by a single click in the note editor of a `Note` sound block
+    Chord(Chord),
+    /// move the selected notes of a `Note` sound block towards the snap grid by `strength`,
+    /// 0 meaning no change & 1 meaning full snap; `lengths` enables quantizing the lengths too
+    Quantize { strength: R32, lengths: bool },
+    /// extend every selected note of a `Note` sound block to the start of the next selected note
+    /// of the same pitch
+    Legato,
+    /// move the pitches of the selected notes of a `Note` sound block by a number of semitones
+    Transpose(isize),
+    /// pseudo-randomly move the offsets & velocities of the selected notes of a `Note` sound block
+    /// by up to `time_amt` beats & `vel_amt` respectively
+    Humanize { time_amt: Beats, vel_amt: R32 },
+    /// set the color of the noise generated by a `Noise` sound block
+    NoiseColor(NoiseColor),
+    /// set the type of the filter of a sound block
+    FilterType(FilterType),
+    /// set the cutoff frequency of the filter of a sound block, in Hz
+    FilterFreq(R64),
+    /// set the resonance of the filter of a sound block
+    FilterQ(R64),
+    /// set the waveform of the LFO modulating the filter cutoff of a sound block
+    LfoShape(LfoShape),
+    /// set the length of a cycle of the LFO modulating the filter cutoff of a sound block
+    LfoRate(Beats),
+    /// set how far the LFO moves the filter cutoff of a sound block, in Hz
+    LfoDepth(R64),
+    /// toggle the arpeggiator of a sound block
+    ToggleArp,
+    /// set the order in which the arpeggiator of a sound block plays the notes of chords
+    ArpMode(ArpMode),
+    /// set the length of a note played by the arpeggiator of a sound block
+    ArpRate(Beats),
     /// file was selected to be a new audio input to be added
     AudioUploaded(Event),
     /// audio source was decoded and is ready to be used
@@ -107,12 +228,29 @@ pub enum AppEvent {
     Speed(R32),
     /// emitted when the user clicks a button to add an audio input
     StartInputAdd,
+    /// emitted when the user clicks a button to import a MIDI file
+    StartMidiImport,
+    /// emitted when the user selects a MIDI file to import
+    MidiUploaded(Event),
+    /// emitted when a MIDI file has been read & needs to be added to the composition as a new
+    /// sound block
+    ImportMidi(Rc<[u8]>),
+    /// emitted when the user clicks a button to export the selected note pattern as a MIDI file
+    ExportMidi,
+    /// add a copy of the active pattern of a sound block & make it the active one
+    AddPattern,
+    /// make the pattern of a sound block at the given index the active one
+    SelectPattern(usize),
+    /// emitted when the user changes the name of the active pattern of a sound block
+    RenamePattern(Event),
     /// emitted when a pop-up window needs to be opened.
     OpenPopup(Popup),
     /// emitted when the current pop-up window needs to be closed
     ClosePopup,
     /// emitted when an audio input is selected, e.g. clicked
     SelectInput(Shared<AudioInput>),
+    /// remove a sample of a Drum Pads sound block by its index, i.e. its row in the pattern
+    RemoveDrumSample(usize),
     /// emitted when the edited audio input's name is changed.
     SetInputName(Event),
     /// emitted when the edited audio input needs to be reversed.
@@ -121,19 +259,65 @@ pub enum AppEvent {
     SetStartCutOff(Beats),
     /// set the ending cut-off of the edited audio input.
     SetEndCutOff(Beats),
+    /// set both the starting & the ending cut-offs of the edited audio input at once.
+    TrimInput([Beats; 2]),
+    /// emitted when the edited audio input's fade-in length needs to be changed.
+    SetFadeIn(Beats),
+    /// emitted when the edited audio input's fade-out length needs to be changed.
+    SetFadeOut(Beats),
+    /// emitted when the normalization of the edited audio input needs to be toggled.
+    ToggleNormalizeInput,
+    /// set the gain of the edited audio input.
+    SetInputGain(R32),
+    /// set the factor by which the duration of the edited audio input is stretched.
+    SetInputStretch(R32),
     /// set the special action for editor spaces.
     SetSpecialAction(SpecialAction),
     /// export the composition to a `.wav` file under the provided name
     Export(Rc<str>),
     /// save the composition as a `.wavexp` file under the provided name
     Save(Rc<str>),
+    /// emitted when the user clicks a button to load a project from a `.wavexp` file
+    StartProjectLoad,
+    /// emitted when the user selects a `.wavexp` file to load
+    ProjectUploaded(Event),
+    /// emitted when a `.wavexp` file has been read & needs to replace the current composition
+    LoadProject(Rc<[u8]>),
     /// set the filename under which the project will be saved
     SetOutputFileName(Event),
     /// display an explanation for why the export file name is invalid.
     ExplainInvalidExportFileName(Event),
+    /// emitted when the user clicks on the sound visualiser to switch what it displays
+    ToggleVisualiserMode,
+    /// emitted when the user picks the colour scheme of the spectrogram
+    SetVisualiserGradient(GradientPreset),
+    /// emitted when the user switches between linear & logarithmic scaling of the spectrogram
+    ToggleSpectrogramLogScale,
+    /// emitted when the user presses or moves the cursor across the minimap
+    MinimapPointer(PointerEvent),
+    /// emitted to move the viewport of a graph editor so that the point, in user coordinates,
+    /// is at its centre
+    /// the 1st field is the `GraphEditor::id` of the recipient
+    ScrollTo(usize, [R64; 2]),
+    /// emitted when the user releases the cursor after scrolling a graph editor through the
+    /// minimap, to record the whole scrolling as a single action
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    FinishScroll(usize),
+    /// emitted when the user requests to zoom & scroll an editor plane so that all of its points
+    /// are visible
+    /// the inner `usize` is the `GraphEditor::id` of the recipient
+    FitView(usize),
+    /// emitted when the user presses or moves the cursor across the ruler above the editor plane
+    RulerPointer(PointerEvent),
+    /// move the playhead to the specified beat, seeking the playback there if it's in progress
+    Seek(Beats),
+    /// set the region of the composition that's played repeatedly as `[start, end]`, an empty one
+    /// disabling it
+    SetLoop([Beats; 2]),
 }
 
-/// For `AppAction::RemovePoint`
+/// For `EditorAction::RemovePoint`, `EditorAction::InsertPoint` &
+/// `EditorAction::DuplicateSelection`
 #[derive(Debug, Clone)]
 pub struct RemovedPoint {
     pub point: Rc<dyn Any>,
@@ -148,10 +332,13 @@ pub enum EditorAction {
     Start,
     /// drag the plane of a graph editor
     DragPlane { editor_id: usize, offset_delta: Point, scale_delta: [R64; 2] },
-    /// drag a point of a graph editor
-    DragPoint { editor_id: usize, point_id: usize, delta: [R64; 2] },
-    /// drag selection in a graph editor
-    DragSelection { editor_id: usize, delta: [R64; 2] },
+    /// drag a point of a graph editor; `changes` is the `PatternDiff` of the editor's points
+    DragPoint { editor_id: usize, changes: Rc<dyn Any> },
+    /// drag selection in a graph editor; `changes` is the `PatternDiff` of the editor's points,
+    /// `delta` is how far the selection area was moved
+    DragSelection { editor_id: usize, delta: [R64; 2], changes: Rc<dyn Any> },
+    /// move selection in a graph editor with the arrow keys; consecutive nudges are merged
+    NudgeSelection { editor_id: usize, delta: [R64; 2] },
     /// change selection in a graph editor
     SetSelection {
         editor_id: usize,
@@ -170,24 +357,80 @@ pub enum EditorAction {
     SwitchTab { from: usize, to: usize },
     /// change sound's volume
     SetVolume { from: R32, to: R32 },
+    /// change sound's stereo position
+    SetPan { from: R32, to: R32 },
     /// change sound's attack time
-    SetAttack { from: R64, to: R64 },
+    SetAttack { from: Beats, to: Beats },
     /// change sound'ss decay time
-    SetDecay { from: R64, to: R64 },
+    SetDecay { from: Beats, to: Beats },
     /// change sound's sustain level
     SetSustain { from: R32, to: R32 },
     /// change sound's release time
-    SetRelease { from: R64, to: R64 },
+    SetRelease { from: Beats, to: Beats },
     /// change global tempo
     SetTempo { from: R64, to: R64 },
+    /// change the changes of the global tempo
+    SetTempoMap { from: TempoMap, to: TempoMap },
     /// set global snap step for all graph editors
     SetSnapStep { from: R64, to: R64 },
     /// set master gain level for the composition
     SetMasterVolume { from: R32, to: R32 },
+    /// change the swing of the playback
+    SetSwing { from: R32, to: R32 },
+    /// change the volume or the mute state of a layer in the mixer
+    SetLayerMix { layer: u32, from: LayerMix, to: LayerMix },
+    /// set the delay time of the echo on the master bus
+    SetDelayTime { from: Beats, to: Beats },
+    /// set the feedback of the echo on the master bus
+    SetDelayFeedback { from: R32, to: R32 },
+    /// set the volume of the echo on the master bus
+    SetDelayWet { from: R32, to: R32 },
+    /// set a parameter of the limiter on the master bus
+    SetCompressor { from: CompressorSetting, to: CompressorSetting },
     /// set repetition count of a sound block
     SetRepCount { from: NonZeroU32, to: NonZeroU32 },
+    /// set the gap between the repetitions of a sound block's pattern
+    SetRepGap { from: Beats, to: Beats },
     /// set playback speed of the audio source of a Custom Audio sound block
     SetSpeed { from: R32, to: R32 },
+    /// toggle whether a sound block is muted
+    ToggleMute { block_id: usize },
+    /// toggle whether a sound block is soloed
+    ToggleSolo { block_id: usize },
+    /// set the scale of a Note sound block
+    SetScale { from: Scale, to: Scale },
+    /// set the root of the scale of a Note sound block
+    SetScaleRoot { from: u8, to: u8 },
+    /// set the chord placed by a single click in the note editor of a Note sound block
+    SetChord { from: Chord, to: Chord },
+    /// quantize the selected notes of a `Note` sound block
+    QuantizeNotes { changes: PatternDiff<NoteBlock> },
+    /// join the selected notes of a `Note` sound block
+    Legato { changes: PatternDiff<NoteBlock> },
+    /// move the pitches of the notes of a `Note` sound block by `by` semitones
+    Transpose { ids: Box<[usize]>, by: isize },
+    /// humanize the selected notes of a `Note` sound block
+    Humanize { changes: PatternDiff<NoteBlock> },
+    /// set the noise color of a Noise sound block
+    SetNoiseColor { from: NoiseColor, to: NoiseColor },
+    /// set the type of the filter of a sound block
+    SetFilterType { from: FilterType, to: FilterType },
+    /// set the cutoff frequency of the filter of a sound block
+    SetFilterFreq { from: R64, to: R64 },
+    /// set the resonance of the filter of a sound block
+    SetFilterQ { from: R64, to: R64 },
+    /// set the waveform of the LFO of a sound block
+    SetLfoShape { from: LfoShape, to: LfoShape },
+    /// set the length of a cycle of the LFO of a sound block
+    SetLfoRate { from: Beats, to: Beats },
+    /// set the depth of the LFO of a sound block
+    SetLfoDepth { from: R64, to: R64 },
+    /// toggle the arpeggiator of a sound block
+    ToggleArp,
+    /// set the order in which the arpeggiator of a sound block plays the notes of chords
+    SetArpMode { from: ArpMode, to: ArpMode },
+    /// set the length of a note played by the arpeggiator of a sound block
+    SetArpRate { from: Beats, to: Beats },
     /// register a new audio input
     AddInput(Shared<AudioInput>),
     /// Open a pop-up window.
@@ -196,20 +439,55 @@ pub enum EditorAction {
     ClosePopup(Popup),
     /// change the selected audio input of the sound block.
     SelectInput { from: Option<Shared<AudioInput>>, to: Option<Shared<AudioInput>> },
+    /// append a sample to the samples of a Drum Pads sound block.
+    AddDrumSample(Shared<AudioInput>),
+    /// remove a sample from the samples of a Drum Pads sound block.
+    RemoveDrumSample { index: usize, sample: Shared<AudioInput> },
     /// change the name of the currently edited audio input.
     SetInputName { from: Rc<str>, to: Rc<str> },
+    /// add a pattern to a sound block, holding the name & the editor of the added pattern, and
+    /// make it the active one instead of `prev_active`.
+    AddPattern { pattern: Rc<dyn Any>, prev_active: usize },
+    /// change the active pattern of a sound block.
+    SelectPattern { from: usize, to: usize },
+    /// change the name of a pattern of a sound block.
+    RenamePattern { index: usize, from: Rc<str>, to: Rc<str> },
     /// add a point onto a graph editor.
     AddPoint { editor_id: usize, point_id: usize, point_loc: [R64; 2] },
     /// remove a point from a graph editor.
     RemovePoint(usize, Box<[RemovedPoint]>),
+    /// insert points into a graph editor, in the order they're listed in
+    InsertPoint(usize, Box<[RemovedPoint]>),
+    /// insert copies of the selected points of a graph editor, moved by `delta`, in the order
+    /// they're listed in, & select them instead of the points selected before, i.e. `prev_ids`
+    DuplicateSelection {
+        editor_id: usize,
+        inserted: Box<[RemovedPoint]>,
+        prev_ids: Box<[usize]>,
+        delta: [R64; 2],
+    },
     /// reverse the currently edited audio input.
     ReverseInput,
     /// set the currently edited audio input's starting cut off.
     SetStartCutOff { from: Beats, to: Beats },
     /// set the currently edited audio input's ending cut off.
     SetEndCutOff { from: Beats, to: Beats },
+    /// set the currently edited audio input's starting & ending cut-offs at once.
+    TrimInput { from: [Beats; 2], to: [Beats; 2] },
+    /// set the currently edited audio input's fade-in length.
+    SetFadeIn { from: Beats, to: Beats },
+    /// set the currently edited audio input's fade-out length.
+    SetFadeOut { from: Beats, to: Beats },
+    /// toggle the normalization of the currently edited audio input.
+    ToggleNormalizeInput,
+    /// set the currently edited audio input's gain.
+    SetInputGain { from: R32, to: R32 },
+    /// set the currently edited audio input's time stretch factor.
+    SetInputStretch { from: R32, to: R32 },
     /// change the filename under which to save the project.
     SetOutputFileName { from: Rc<str>, to: Rc<str> },
+    /// actions registered during a transaction, undone & redone together; never nested
+    Group(Vec<EditorAction>),
 }
 
 impl EditorAction {
@@ -224,36 +502,152 @@ impl EditorAction {
             Self::DragPlane { .. } => None, // "Drag Plane"
             Self::DragPoint { .. } => Some("Drag Block"),
             Self::DragSelection { .. } => Some("Drag Selection"),
+            Self::NudgeSelection { .. } => Some("Nudge Selection"),
             Self::SetSelection { .. } => None, // "Set Selection"
             Self::Select { .. } => None, // "Open Sound Block Editor" | "Close Sound Block Editor"
             Self::SetBlockType(..) => Some("Set Sound Block Type"),
             Self::SwitchTab { .. } => None, // "Switch Tabs",
             Self::SetVolume { .. } => Some("Set Volume"),
+            Self::SetPan { .. } => Some("Set Pan"),
             Self::SetAttack { .. } => Some("Set Attack Time"),
             Self::SetDecay { .. } => Some("Set Decay Time"),
             Self::SetSustain { .. } => Some("Set Sustain Level"),
             Self::SetRelease { .. } => Some("Set Release Time"),
             Self::SetTempo { .. } => Some("Set Tempo"),
+            Self::SetTempoMap { .. } => Some("Set Tempo Map"),
             Self::SetSnapStep { .. } => Some("Set Snap Step"),
             Self::SetMasterVolume { .. } => Some("Set Master Volume"),
+            Self::SetSwing { .. } => Some("Set Swing"),
+            Self::SetLayerMix { from, to, .. } => {
+                Some(if from.muted == to.muted { "Set Layer Volume" } else { "Toggle Layer Mute" })
+            }
+            Self::SetDelayTime { .. } => Some("Set Delay Time"),
+            Self::SetDelayFeedback { .. } => Some("Set Delay Feedback"),
+            Self::SetDelayWet { .. } => Some("Set Delay Level"),
+            Self::SetCompressor { .. } => Some("Set Limiter Parameter"),
             Self::SetRepCount { .. } => Some("Set Sound Block Repetition Count"),
+            Self::SetRepGap { .. } => Some("Set Sound Block Repetition Gap"),
             Self::SetSpeed { .. } => Some("Set Custom Audio's Playback Speed"),
+            Self::ToggleMute { .. } => Some("Toggle Mute"),
+            Self::ToggleSolo { .. } => Some("Toggle Solo"),
+            Self::SetScale { .. } => Some("Set Scale"),
+            Self::SetChord { .. } => Some("Set Chord"),
+            Self::SetScaleRoot { .. } => Some("Set Scale Root"),
+            Self::QuantizeNotes { .. } => Some("Quantize Notes"),
+            Self::Legato { .. } => Some("Join Notes"),
+            Self::Transpose { .. } => Some("Transpose Notes"),
+            Self::Humanize { .. } => Some("Humanize Notes"),
+            Self::SetNoiseColor { .. } => Some("Set Noise Color"),
+            Self::SetFilterType { .. } => Some("Set Filter Type"),
+            Self::SetFilterFreq { .. } => Some("Set Filter Cutoff Frequency"),
+            Self::SetFilterQ { .. } => Some("Set Filter Resonance"),
+            Self::SetLfoShape { .. } => Some("Set LFO Shape"),
+            Self::SetLfoRate { .. } => Some("Set LFO Rate"),
+            Self::SetLfoDepth { .. } => Some("Set LFO Depth"),
+            Self::ToggleArp => Some("Toggle Arpeggiator"),
+            Self::SetArpMode { .. } => Some("Set Arpeggiator Mode"),
+            Self::SetArpRate { .. } => Some("Set Arpeggiator Rate"),
             Self::AddInput(..) => Some("Add Audio Input"),
             Self::OpenPopup(_) => None,
             Self::ClosePopup(_) => None,
             Self::SelectInput { .. } => Some("Select Audio Input"),
+            Self::AddDrumSample(_) => Some("Add Drum Sample"),
+            Self::RemoveDrumSample { .. } => Some("Remove Drum Sample"),
             Self::SetInputName { .. } => Some("Rename Audio Input"),
+            Self::AddPattern { .. } => Some("Add Pattern"),
+            Self::SelectPattern { .. } => Some("Select Pattern"),
+            Self::RenamePattern { .. } => Some("Rename Pattern"),
             Self::AddPoint { .. } => Some("Add a point to an editor plane"),
             Self::RemovePoint(_, points) => Some(if points.len() == 1 {
                 "Remove a point from an editor plane"
             } else {
                 "Remove points from an editor plane"
             }),
+            Self::InsertPoint(_, points) => Some(if points.len() == 1 {
+                "Insert a point into an editor plane"
+            } else {
+                "Insert points into an editor plane"
+            }),
+            Self::DuplicateSelection { .. } => Some("Duplicate Selection"),
             Self::ReverseInput => Some("Reverse Audio Input"),
             Self::SetStartCutOff { .. } => Some("Set Starting Cut-Off"),
             Self::SetEndCutOff { .. } => Some("Set Ending Cut-Off"),
+            Self::TrimInput { .. } => Some("Trim Audio Input"),
+            Self::SetFadeIn { .. } => Some("Set Fade-In"),
+            Self::SetFadeOut { .. } => Some("Set Fade-Out"),
+            Self::ToggleNormalizeInput => Some("Toggle Audio Input Normalization"),
+            Self::SetInputGain { .. } => Some("Set Audio Input Gain"),
+            Self::SetInputStretch { .. } => Some("Stretch Audio Input"),
             Self::SetOutputFileName { .. } => None,
+            Self::Group(actions) => actions.iter().rev().find_map(Self::name),
+        }
+    }
+
+    /// Returns the actions that `self` consists of, in the order in which they were registered:
+    /// the members of a group, or just `self` for any other action.
+    pub fn members(&self) -> &[Self] {
+        match self {
+            Self::Group(actions) => actions,
+            _ => from_ref(self),
+        }
+    }
+
+    /// If `other` changes the same parameter as `self`, sets the result of `self` to that of
+    /// `other`, keeping the original value, & returns `true`.
+    pub fn coalesce(&mut self, other: &Self) -> bool {
+        macro_rules! coalesce {
+            ($($variant:ident),+) => {
+                match (self, other) {
+                    $((Self::$variant { to, .. }, Self::$variant { to: new, .. }) => *to = *new,)+
+                    (Self::SetCompressor { to, .. }, Self::SetCompressor { to: new, .. })
+                        if discriminant(to) == discriminant(new) =>
+                    {
+                        *to = *new
+                    }
+                    (Self::SetTempoMap { to, .. }, Self::SetTempoMap { from: new_from, to: new })
+                        if to == new_from
+                            && to.changes().len() == new.changes().len() =>
+                    {
+                        *to = new.clone()
+                    }
+                    (
+                        Self::SetLayerMix { layer, from, to },
+                        Self::SetLayerMix { layer: new_layer, from: new_from, to: new },
+                    ) if layer == new_layer
+                        && from.muted == to.muted
+                        && new_from.muted == new.muted =>
+                    {
+                        *to = *new
+                    }
+                    _ => return false,
+                }
+            };
         }
+        coalesce!(
+            SetVolume,
+            SetPan,
+            SetAttack,
+            SetDecay,
+            SetSustain,
+            SetRelease,
+            SetTempo,
+            SetMasterVolume,
+            SetSwing,
+            SetDelayTime,
+            SetDelayFeedback,
+            SetDelayWet,
+            SetRepCount,
+            SetRepGap,
+            SetSpeed,
+            SetFilterFreq,
+            SetFilterQ,
+            SetLfoRate,
+            SetLfoDepth,
+            SetArpRate,
+            SetInputGain,
+            SetInputStretch
+        );
+        true
     }
 
     /// Try to incorporate `other` into `self`, returning either both of them,
@@ -266,6 +660,12 @@ impl EditorAction {
     ) -> Result<Option<(Self, Option<Self>)>, (Self, Self, AppError)> {
         Ok(match (self, other) {
             (Self::OpenPopup(_), Self::ClosePopup(_)) => None,
+            (Self::ToggleMute { block_id: id_1 }, Self::ToggleMute { block_id: id_2 })
+            | (Self::ToggleSolo { block_id: id_1 }, Self::ToggleSolo { block_id: id_2 })
+                if id_1 == id_2 =>
+            {
+                None
+            }
             (Self::SwitchTab { from, .. }, Self::SwitchTab { to, .. }) if from == to => None,
             (Self::SwitchTab { from, .. }, Self::SwitchTab { to, .. }) => {
                 Some((Self::SwitchTab { from, to }, None))
@@ -299,6 +699,15 @@ impl EditorAction {
                 },
                 None,
             )),
+            (
+                Self::NudgeSelection { editor_id: eid_1, delta: d_1 },
+                Self::NudgeSelection { editor_id: eid_2, delta: d_2 },
+            ) if eid_1 == eid_2 => {
+                let delta = d_1.add(d_2);
+                delta
+                    .any(|x| *x != 0)
+                    .then_some((Self::NudgeSelection { editor_id: eid_1, delta }, None))
+            }
             (a, b) => Some((a, Some(b))),
         })
     }
@@ -332,6 +741,15 @@ impl<'app, 'editor> ContextMut<'app, 'editor> {
         self.editor.register_action(self.app, action)
     }
 
+    /// Runs `f`, making all the actions it registers be undone & redone as 1 action.
+    /// The transaction is ended even if `f` fails, hence its result is returned as is.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        self.editor.begin_transaction();
+        let res = f(self);
+        self.editor.end_transaction(self.app)?;
+        Ok(res)
+    }
+
     pub fn as_ref(&self) -> ContextRef<'_, '_> {
         ContextRef { editor: self.editor, app: self.app }
     }
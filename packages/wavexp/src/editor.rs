@@ -3,61 +3,308 @@ use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
     img,
     input::{Button, GraphEditorCanvas, Switch},
-    sequencer::{Sequencer, SoundBlock},
-    visual::{HintHandler, SoundVisualiser, SpecialAction},
+    sequencer::{Composition, Sequencer, SoundBlock},
+    sound::{Beats, Note, Secs},
+    visual::{GradientPreset, HintHandler, Minimap, Ruler, SoundVisualiser, SpecialAction},
 };
 use macro_rules_attribute::apply;
-use std::{cmp::Ordering, iter::once, mem::take, slice::from_ref};
+use std::{
+    any::Any,
+    cmp::Ordering,
+    iter::once,
+    mem::{replace, take},
+    rc::Rc,
+};
 use wasm_bindgen::JsCast;
 use wavexp_utils::{
+    ensure,
     error::Result,
     ext::{default, BoolExt, OptionExt, SliceExt},
     fallible,
     js::window,
-    js_function, r64,
-    real::R64,
+    js_function, r32, r64,
+    real::{R32, R64},
     ToAttrValue,
 };
 use yew::{AttrValue, Callback, Html};
 use yew_html_ext::html;
 
+/// Collapses repeated requests to perform an action into at most 1 action per `interval`.
+#[derive(Debug, Clone, Copy)]
+pub struct Throttle {
+    interval: Secs,
+    last: Secs,
+    pending: bool,
+}
+
+impl Throttle {
+    pub const fn new(interval: Secs) -> Self {
+        Self { interval, last: Secs(R64::NEG_INFINITY), pending: false }
+    }
+
+    /// Requests the action to be performed.
+    pub fn request(&mut self) {
+        self.pending = true
+    }
+
+    /// Returns whether the requested action is to be performed at the point in time `now`.
+    pub fn poll(&mut self, now: Secs) -> bool {
+        if !self.pending || now - self.last < self.interval {
+            return false;
+        }
+        self.pending = false;
+        self.last = now;
+        true
+    }
+}
+
+#[test]
+fn throttle_collapses_requests() {
+    let mut throttle = Throttle::new(Secs(r64!(2)));
+    assert!(!throttle.poll(Secs(r64!(0))));
+    let writes = (0..100)
+        .filter(|&i| {
+            throttle.request();
+            throttle.poll(Secs(r64!(0.01) * i))
+        })
+        .count();
+    assert_eq!(writes, 1);
+    assert!(!throttle.poll(Secs(r64!(1.5))));
+    throttle.request();
+    assert!(!throttle.poll(Secs(r64!(1.5))));
+    assert!(throttle.poll(Secs(r64!(2))));
+    assert!(!throttle.poll(Secs(r64!(5))));
+}
+
 pub struct EditorContext {
     actions: Vec<EditorAction>,
     undid_actions: usize,
+    /// maximum number of actions kept in `actions`, including `EditorAction::Start`
+    max_actions: usize,
+    /// time at which the last action was registered
+    last_action_at: Secs,
+    /// number of transactions begun but not yet ended
+    transaction_depth: usize,
+    /// actions registered during the current transaction
+    transaction: Vec<EditorAction>,
     selected_tab: usize,
     snap_step: R64,
+    /// whether dragged points snap to the edges of other points
+    magnetic_snap: bool,
+    /// whether the original positions of a dragged selection are drawn until it's released
+    drag_ghost: bool,
+    /// whether the computer keyboard plays the selected Note sound block like a piano instead of
+    /// triggering the shortcuts bound to the same keys
+    piano: bool,
     special_action: SpecialAction,
     selected_block: Option<usize>,
+    autosave: Throttle,
+    /// points copied from an editor plane, stored as `Vec<T>` where `T` is the type of the points
+    clipboard: Option<Rc<dyn Any>>,
 }
 
 impl EditorContext {
+    /// Minimum time between 2 autosaves of the composition.
+    const AUTOSAVE_INTERVAL: Secs = Secs(r64!(2));
+    /// Default value of `max_actions`.
+    const DEFAULT_MAX_ACTIONS: usize = 1000;
+    /// Maximum time between 2 changes of the same parameter for them to be undone as 1 action.
+    const COALESCE_WINDOW: Secs = Secs(r64!(0.5));
+
     pub fn new() -> Self {
         Self {
             actions: vec![EditorAction::Start],
             undid_actions: 0,
+            max_actions: Self::DEFAULT_MAX_ACTIONS,
+            last_action_at: Secs(R64::NEG_INFINITY),
+            transaction_depth: 0,
+            transaction: vec![],
             selected_tab: 0,
             snap_step: r64!(1),
+            magnetic_snap: false,
+            drag_ghost: true,
+            piano: false,
             special_action: default(),
             selected_block: None,
+            autosave: Throttle::new(Self::AUTOSAVE_INTERVAL),
+            clipboard: None,
         }
     }
 
     pub fn register_action(&mut self, app: &mut AppContext, action: EditorAction) -> Result {
         app.force_rerender();
+        self.autosave.request();
+        self.push_action(action, app.frame())
+    }
+
+    /// Makes all the actions registered until the matching `end_transaction` call be undone &
+    /// redone as 1 action. Transactions begun inside another one are merged into the outer one.
+    pub fn begin_transaction(&mut self) {
+        self.transaction_depth += 1
+    }
+
+    /// Ends the transaction begun by the last `begin_transaction` call; if it's the outermost
+    /// one, adds its actions to the history as a single `EditorAction::Group`.
+    pub fn end_transaction(&mut self, app: &mut AppContext) -> Result {
+        self.close_transaction(app.frame())
+    }
+
+    fn close_transaction(&mut self, now: Secs) -> Result {
+        ensure!(self.transaction_depth > 0, "no transaction to end");
+        self.transaction_depth -= 1;
+        if self.transaction_depth > 0 {
+            return Ok(());
+        }
+        let mut actions = take(&mut self.transaction);
+        let action = if actions.len() > 1 {
+            EditorAction::Group(actions)
+        } else if let Some(action) = actions.pop() {
+            action
+        } else {
+            return Ok(());
+        };
+        self.push_action(action, now)
+    }
+
+    /// Adds `action` to the history as if it was registered at `now`, dropping the undone
+    /// actions. Changes of the same parameter made within `Self::COALESCE_WINDOW` of each other
+    /// are combined into 1 action.
+    /// If a transaction is in progress, `action` is added to it instead.
+    fn push_action(&mut self, action: EditorAction, now: Secs) -> Result {
+        if self.transaction_depth > 0 {
+            return Self::append_action(&mut self.transaction, action);
+        }
         self.actions.drain(self.actions.len() - take(&mut self.undid_actions)..);
-        if let Some(last) = self.actions.pop() {
-            match last.merge(action) {
-                Ok(None) => (),
-                Ok(Some((first, None))) => self.actions.push(first),
-                Ok(Some((first, Some(second)))) => self.actions.extend([first, second]),
-                Err((first, second, err)) => {
-                    self.actions.extend([first, second]);
-                    return Err(err);
-                }
-            };
+        let recent = now - replace(&mut self.last_action_at, now) < Self::COALESCE_WINDOW;
+        if recent && self.actions.last_mut().is_some_and(|last| last.coalesce(&action)) {
+            return Ok(());
         }
+        Self::append_action(&mut self.actions, action)?;
+        self.trim_actions();
         Ok(())
     }
+
+    /// Adds `action` to the end of `actions`, merging it with the last one if possible.
+    fn append_action(actions: &mut Vec<EditorAction>, action: EditorAction) -> Result {
+        let Some(last) = actions.pop() else {
+            actions.push(action);
+            return Ok(());
+        };
+        match last.merge(action) {
+            Ok(None) => (),
+            Ok(Some((first, None))) => actions.push(first),
+            Ok(Some((first, Some(second)))) => actions.extend([first, second]),
+            Err((first, second, err)) => {
+                actions.extend([first, second]);
+                return Err(err);
+            }
+        };
+        Ok(())
+    }
+
+    /// Marks the last `n` performed actions as undone, returning the actions to undo in the order
+    /// in which they're to be undone, with groups expanded.
+    /// Returns `None` if there are less than `n` actions that can be undone.
+    fn unwind(&mut self, n: usize) -> Option<Box<[EditorAction]>> {
+        let end = self.actions.len() - self.undid_actions;
+        let start = end.checked_sub(n).filter(|&x| x > 0)?;
+        self.undid_actions += n;
+        Some(
+            self.actions[start..end]
+                .iter()
+                .rev()
+                .flat_map(|x| x.members().iter().rev())
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Marks the first `n` undone actions as performed again, returning the actions to redo in
+    /// the order in which they're to be redone, with groups expanded.
+    /// Returns `None` if there are less than `n` undone actions.
+    fn rewind(&mut self, n: usize) -> Option<Box<[EditorAction]>> {
+        let start = self.actions.len() - self.undid_actions;
+        let rewound = self.actions.get(start..start + n)?;
+        let rewound = rewound.iter().flat_map(EditorAction::members).cloned().collect();
+        self.undid_actions -= n;
+        Some(rewound)
+    }
+
+    /// Drops the oldest actions that exceed `max_actions`, keeping the undone ones to be able to
+    /// redo them, as well as `EditorAction::Start`.
+    fn trim_actions(&mut self) {
+        let len = self.actions.len();
+        let excess = len.saturating_sub(self.max_actions).min(len - self.undid_actions - 1);
+        self.actions.drain(1..=excess);
+    }
+}
+
+#[test]
+fn action_history() {
+    let mut ctx = EditorContext::new();
+    for i in 0..100 {
+        let action = EditorAction::SetVolume { from: R32::from(i), to: R32::from(i + 1) };
+        assert!(ctx.push_action(action, Secs(r64!(0.01) * i)).is_ok());
+    }
+    assert!(matches!(ctx.actions[..], [
+        EditorAction::Start,
+        EditorAction::SetVolume { from, to }
+    ] if from == R32::from(0) && to == R32::from(100)));
+    // a change long after the previous one is a separate action
+    let action = EditorAction::SetVolume { from: R32::from(100), to: R32::ONE };
+    assert!(ctx.push_action(action, Secs(r64!(5))).is_ok());
+    assert_eq!(ctx.actions.len(), 3);
+
+    ctx.max_actions = 2;
+    ctx.trim_actions();
+    assert!(matches!(ctx.actions[..], [
+        EditorAction::Start,
+        EditorAction::SetVolume { to, .. }
+    ] if to == R32::ONE));
+}
+
+#[test]
+fn transaction() {
+    let mut ctx = EditorContext::new();
+    ctx.begin_transaction();
+    let action = EditorAction::SetVolume { from: R32::ZERO, to: R32::ONE };
+    assert!(ctx.push_action(action, Secs(r64!(0))).is_ok());
+    ctx.begin_transaction();
+    let action = EditorAction::SetTempo { from: r64!(120), to: r64!(60) };
+    assert!(ctx.push_action(action, Secs(r64!(0))).is_ok());
+    assert!(ctx.close_transaction(Secs(r64!(0))).is_ok());
+    assert!(ctx.close_transaction(Secs(r64!(0))).is_ok());
+    assert_eq!(ctx.actions.len(), 2);
+
+    assert!(matches!(
+        ctx.unwind(1).as_deref(),
+        Some([EditorAction::SetTempo { .. }, EditorAction::SetVolume { .. }])
+    ));
+    assert!(ctx.unwind(1).is_none());
+    assert!(matches!(
+        ctx.rewind(1).as_deref(),
+        Some([EditorAction::SetVolume { .. }, EditorAction::SetTempo { .. }])
+    ));
+    assert!(ctx.rewind(1).is_none());
+}
+
+#[test]
+fn failed_transaction() {
+    let (mut editor, mut app) = (EditorContext::new(), AppContext::test());
+    let n_actions = editor.actions.len();
+    let mut ctx = ContextMut { editor: &mut editor, app: &mut app };
+    let res = ctx.transaction(|ctx| {
+        let action = EditorAction::SetVolume { from: R32::ZERO, to: R32::ONE };
+        assert!(ctx.register_action(action).is_ok());
+        Err::<(), _>("failed halfway")
+    });
+    assert_eq!(res.ok(), Some(Err("failed halfway")));
+    // the transaction is closed, so the next action goes straight into the history
+    assert_eq!(editor.transaction_depth, 0);
+    assert_eq!(editor.actions.len(), n_actions + 1);
+    let mut ctx = ContextMut { editor: &mut editor, app: &mut app };
+    assert!(ctx.register_action(EditorAction::SetTempo { from: r64!(2), to: r64!(1) }).is_ok());
+    assert_eq!(editor.actions.len(), n_actions + 2);
 }
 
 impl ContextMut<'_, '_> {
@@ -69,6 +316,14 @@ impl ContextMut<'_, '_> {
         self.editor.snap_step
     }
 
+    pub fn magnetic_snap(&self) -> bool {
+        self.editor.magnetic_snap
+    }
+
+    pub fn drag_ghost(&self) -> bool {
+        self.editor.drag_ghost
+    }
+
     pub fn special_action(&self) -> SpecialAction {
         self.editor.special_action
     }
@@ -77,24 +332,61 @@ impl ContextMut<'_, '_> {
         self.editor.selected_block
     }
 
+    pub fn clipboard(&self) -> Option<&Rc<dyn Any>> {
+        self.editor.clipboard.as_ref()
+    }
+
+    pub fn set_clipboard(&mut self, points: Rc<dyn Any>) {
+        self.editor.clipboard = Some(points)
+    }
+
     pub fn actions(&self) -> &[EditorAction] {
         &self.editor.actions
     }
 }
 
+#[test]
+fn piano_keeps_shortcuts() {
+    let key = |code, piano| Editor::key_event(code, [false; 3], piano, 0);
+    // the keys bound to shortcuts that the piano also uses only play notes when it's enabled
+    for code in ["KeyA", "KeyS", "KeyF", "KeyH", "KeyL"] {
+        assert!(Note::from_key_code(code).is_some());
+        assert!(matches!(key(code, true), Some(AppEvent::PianoKeyDown(_))));
+        assert!(!matches!(key(code, false), Some(AppEvent::PianoKeyDown(_)) | None));
+    }
+    assert!(matches!(key("KeyA", false), Some(AppEvent::SetSpecialAction(SpecialAction::Add))));
+    assert!(matches!(key("KeyS", false), Some(AppEvent::SetSpecialAction(SpecialAction::Select))));
+    assert!(matches!(key("KeyF", false), Some(AppEvent::FitView(0))));
+    assert!(matches!(key("KeyH", false), Some(AppEvent::Humanize { .. })));
+    assert!(matches!(key("KeyL", false), Some(AppEvent::Legato)));
+    // a shortcut bound to a key the piano doesn't use works either way
+    assert!(matches!(key("KeyR", true), Some(AppEvent::SetSpecialAction(SpecialAction::Remove))));
+    // holding Ctrl leaves the key to the shortcuts even with the piano enabled
+    let ctrl_a = Editor::key_event("KeyA", [false, false, true], true, 0);
+    assert!(matches!(ctrl_a, Some(AppEvent::SetSpecialAction(SpecialAction::Add))));
+}
+
 pub struct Editor {
     sound_visualiser: SoundVisualiser,
+    minimap: Minimap,
+    ruler: Ruler,
     pub sequencer: Sequencer,
     pub ctx: EditorContext,
     hint_handler: HintHandler,
 }
 
 impl Editor {
+    /// distance along the X axis by which the arrow keys move the selected points of a graph
+    /// editor, unless Shift is held, in which case they're moved by the snap step
+    const NUDGE_STEP: R64 = r64!(0.0625);
+
     pub fn new() -> Result<Self> {
         Ok(Self {
             hint_handler: default(),
-            sequencer: Sequencer::new()?,
+            sequencer: Sequencer::new(Composition::autosaved().unwrap_or_default())?,
             sound_visualiser: SoundVisualiser::new(),
+            minimap: default(),
+            ruler: default(),
             ctx: EditorContext::new(),
         })
     }
@@ -108,6 +400,12 @@ impl Editor {
                 ctx.editor.snap_step = to;
             }
 
+            AppEvent::ToggleMagneticSnap => ctx.editor.magnetic_snap.flip(),
+
+            AppEvent::ToggleDragGhost => ctx.editor.drag_ghost.flip(),
+
+            AppEvent::TogglePiano => ctx.editor.piano.flip(),
+
             AppEvent::SetTab(to) => {
                 ctx.register_action(EditorAction::SwitchTab { from: ctx.editor.selected_tab, to })?;
                 ctx.editor.selected_tab = to;
@@ -118,63 +416,81 @@ impl Editor {
                 ctx.force_rerender();
             }
 
-            AppEvent::KeyPress(_, ref e) if !e.repeat() => match e.code().as_str() {
+            AppEvent::KeyPress(editor_id, ref e) if e.code().starts_with("Arrow") => {
+                // don't let the browser scroll the page
+                e.prevent_default();
+                let dx = match ctx.editor.snap_step {
+                    step if e.shift_key() && step > R64::ZERO => step,
+                    _ => Self::NUDGE_STEP,
+                };
+                let (dx, dy) = match e.code().as_str() {
+                    "ArrowLeft" => (-dx, R64::ZERO),
+                    "ArrowRight" => (dx, R64::ZERO),
+                    "ArrowUp" => (R64::ZERO, r64!(-1)),
+                    "ArrowDown" => (R64::ZERO, r64!(1)),
+                    _ => return Ok(()),
+                };
+                ctx.emit_event(AppEvent::NudgeSelection { editor_id, dx, dy })
+            }
+
+            AppEvent::KeyPress(id, ref e) if !e.repeat() => match e.code().as_str() {
                 "KeyZ" if e.meta_key() => {
                     if e.shift_key() {
-                        if ctx.editor.undid_actions > 0 {
+                        if let Some(rewound) = ctx.editor.rewind(1) {
                             ctx.force_rerender();
-                            let a = unsafe {
-                                ctx.editor.actions.get_unchecked(
-                                    ctx.editor.actions.len() - ctx.editor.undid_actions,
-                                )
-                            };
-                            ctx.emit_event(AppEvent::Redo(from_ref(a).to_box()));
-                            ctx.editor.undid_actions -= 1;
+                            ctx.emit_event(AppEvent::Redo(rewound));
                         }
-                    } else if ctx.editor.undid_actions < ctx.editor.actions.len() - 1 {
+                    } else if let Some(unwound) = ctx.editor.unwind(1) {
                         ctx.force_rerender();
-                        ctx.editor.undid_actions += 1;
-                        let a = unsafe {
-                            ctx.editor
-                                .actions
-                                .get_unchecked(ctx.editor.actions.len() - ctx.editor.undid_actions)
-                        };
-                        ctx.emit_event(AppEvent::Undo(from_ref(a).to_box()));
+                        ctx.emit_event(AppEvent::Undo(unwound));
                     }
                 }
 
-                "KeyA" => ctx.emit_event(AppEvent::SetSpecialAction(SpecialAction::Add)),
+                "KeyC" if e.meta_key() || e.ctrl_key() => ctx.emit_event(AppEvent::Copy(id)),
+
+                "KeyV" if e.meta_key() || e.ctrl_key() => ctx.emit_event(AppEvent::Paste(id)),
 
-                "KeyS" => ctx.emit_event(AppEvent::SetSpecialAction(SpecialAction::Select)),
+                "KeyD" if e.meta_key() || e.ctrl_key() => {
+                    // don't let the browser bookmark the page
+                    e.prevent_default();
+                    ctx.emit_event(AppEvent::DuplicateSelection(id))
+                }
+
+                "KeyA" if e.meta_key() || e.ctrl_key() => {
+                    // don't let the browser select the whole page
+                    e.prevent_default();
+                    ctx.emit_event(AppEvent::SelectAll(id))
+                }
 
-                "KeyR" => ctx.emit_event(AppEvent::SetSpecialAction(SpecialAction::Remove)),
+                "KeyI" if e.meta_key() || e.ctrl_key() => {
+                    e.prevent_default();
+                    ctx.emit_event(AppEvent::InvertSelection(id))
+                }
 
-                _ => (),
+                code => {
+                    let piano = ctx.editor.piano && self.sequencer.piano_enabled()?;
+                    let mods = [e.shift_key(), e.meta_key(), e.ctrl_key()];
+                    if let Some(event) = Self::key_event(code, mods, piano, id) {
+                        ctx.emit_event(event)
+                    }
+                }
             },
 
+            AppEvent::KeyRelease(_, ref e) => {
+                if let Some(note) = Note::from_key_code(&e.code()) {
+                    ctx.emit_event(AppEvent::PianoKeyUp(note))
+                }
+            }
+
             AppEvent::Unwind(n) => {
                 ctx.force_rerender();
-                let unwound = ctx
-                    .editor
-                    .actions
-                    .get(ctx.editor.actions.len() - n - ctx.editor.undid_actions..)?
-                    .iter()
-                    .rev()
-                    .cloned()
-                    .collect();
-                ctx.editor.undid_actions += n;
+                let unwound = ctx.editor.unwind(n)?;
                 ctx.emit_event(AppEvent::Undo(unwound))
             }
 
             AppEvent::Rewind(n) => {
                 ctx.force_rerender();
-                let rewound = ctx
-                    .editor
-                    .actions
-                    .get(ctx.editor.actions.len() - ctx.editor.undid_actions..)?
-                    .get(..n)?
-                    .to_box();
-                ctx.editor.undid_actions -= n;
+                let rewound = ctx.editor.rewind(n)?;
                 ctx.emit_event(AppEvent::Redo(rewound))
             }
 
@@ -190,14 +506,29 @@ impl Editor {
                 }
             }
 
+            AppEvent::Frame(_) => {
+                if ctx.editor.autosave.poll(ctx.frame()) {
+                    self.sequencer.composition().autosave()?
+                }
+            }
+
+            AppEvent::LoadProject(ref bytes) => {
+                let comp = Composition::decode(&mut &**bytes)?;
+                self.sequencer.set_composition(comp, ctx.as_mut());
+                *ctx.editor = EditorContext::new();
+                ctx.force_rerender();
+            }
+
             AppEvent::Remove => {
                 let mut pattern = self.sequencer.pattern().get_mut()?;
                 let id = ctx.selected_block()?;
                 let block_id = *pattern.selection().get(id)?;
                 let action = pattern.remove_points(once(block_id))?;
-                ctx.register_action(action)?;
-                let from = take(&mut ctx.editor.selected_tab);
-                ctx.register_action(EditorAction::SwitchTab { from, to: 0 })?;
+                ctx.transaction(|ctx| {
+                    ctx.register_action(action)?;
+                    let from = take(&mut ctx.editor.selected_tab);
+                    ctx.register_action(EditorAction::SwitchTab { from, to: 0 })
+                })??
             }
 
             AppEvent::Enter(id, _) => {
@@ -217,6 +548,7 @@ impl Editor {
             }
 
             AppEvent::Undo(ref actions) => {
+                ctx.editor.autosave.request();
                 for action in actions.iter() {
                     match *action {
                         EditorAction::Select { from, prev_selected_tab, .. } => {
@@ -234,6 +566,7 @@ impl Editor {
             }
 
             AppEvent::Redo(ref actions) => {
+                ctx.editor.autosave.request();
                 for action in actions.iter() {
                     match *action {
                         EditorAction::Select { to, .. } => {
@@ -260,14 +593,12 @@ impl Editor {
     pub fn render(&self, app: &AppContext) -> Html {
         // TODO: add switching between selected blocks
         let pattern = self.sequencer.pattern().get()?;
-        let block = self
-            .ctx
-            .selected_block
-            .try_map(|i| pattern.selection().get(i))?
-            .try_map(|i| pattern.data().get(*i))?;
+        let block_id = self.ctx.selected_block.try_map(|i| pattern.selection().get(i).copied())?;
+        let block = block_id.try_map(|i| pattern.points().get(i))?;
         let ctx = ContextRef { editor: &self.ctx, app };
         let emitter = ctx.event_emitter();
         let special_action = self.ctx.special_action;
+        let pattern_id = pattern.id();
 
         html! {
             <>
@@ -305,16 +636,45 @@ impl Editor {
                                 >
                                     <img::Cross />
                                 </Button>
+                                if let Some(block_id) = block_id {
+                                    <Button
+                                        name="Mute sound block"
+                                        class={block.muted.choose("selected", "")}
+                                        help="Click to toggle whether the block is played"
+                                        onclick={emitter.reform(move |_| AppEvent::ToggleMute(block_id))}
+                                    >
+                                        <span>{ "M" }</span>
+                                    </Button>
+                                    <Button
+                                        name="Solo sound block"
+                                        class={block.soloed.choose("selected", "")}
+                                        help="Click to toggle whether only this & other soloed blocks are played"
+                                        onclick={emitter.reform(move |_| AppEvent::ToggleSolo(block_id))}
+                                    >
+                                        <span>{ "S" }</span>
+                                    </Button>
+                                }
                             </div>
                         } else {
                             <div id="tab-list">{ self.sequencer.tabs(ctx) }</div>
                             { self.sequencer.params(ctx) }
                         }
                     </div>
-                    <GraphEditorCanvas<SoundBlock>
-                        editor={self.sequencer.pattern()}
-                        emitter={emitter.clone()}
-                    />
+                    <div id="plane">
+                        <canvas
+                            id="ruler"
+                            ref={self.ruler.canvas()}
+                            data-main-hint="Ruler"
+                            data-aux-hint="Click to move the playhead, Shift + drag to set a loop"
+                            onpointerdown={emitter.reform(AppEvent::RulerPointer)}
+                            onpointermove={emitter.reform(AppEvent::RulerPointer)}
+                            onpointerup={emitter.reform(AppEvent::RulerPointer)}
+                        />
+                        <GraphEditorCanvas<SoundBlock>
+                            editor={self.sequencer.pattern()}
+                            emitter={emitter.clone()}
+                        />
+                    </div>
                 </div>
                 <div id="io-panel" data-main-hint="Editor plane settings">
                     <div class="horizontal-menu" id="actions">
@@ -370,6 +730,54 @@ impl Editor {
                                 _ => 0,
                             }}
                         />
+                        <Switch
+                            key="magnetic-snap"
+                            name="Snapping to the edges of other blocks"
+                            setter={emitter.reform(|_| AppEvent::ToggleMagneticSnap)}
+                            options={vec!["Off", "On"]}
+                            initial={self.ctx.magnetic_snap as usize}
+                        />
+                        <Switch
+                            key="drag-ghost"
+                            name="Showing where the dragged blocks were"
+                            setter={emitter.reform(|_| AppEvent::ToggleDragGhost)}
+                            options={vec!["Off", "On"]}
+                            initial={self.ctx.drag_ghost as usize}
+                        />
+                        <Switch
+                            key="piano"
+                            name="Playing the selected Note block with the keyboard"
+                            setter={emitter.reform(|_| AppEvent::TogglePiano)}
+                            options={vec!["Off", "On"]}
+                            initial={self.ctx.piano as usize}
+                        />
+                        <Switch
+                            key="visualiser-gradient"
+                            name="Colours of the spectrogram"
+                            setter={emitter.reform(|x: usize| {
+                                let preset = *GradientPreset::ALL.get_wrapping(x);
+                                AppEvent::SetVisualiserGradient(preset)
+                            })}
+                            options={GradientPreset::ALL.map(GradientPreset::name).to_vec()}
+                            initial={self.sound_visualiser
+                                .gradient_preset()
+                                .map_or(0, |x| x as usize)}
+                        />
+                        <Switch
+                            key="spectrogram-scale"
+                            name="Scale of the spectrogram"
+                            setter={emitter.reform(|_| AppEvent::ToggleSpectrogramLogScale)}
+                            options={vec!["Linear", "Logarithmic"]}
+                            initial={self.sound_visualiser.log_scale() as usize}
+                        />
+                        <Button
+                            name="Fit the view to the sound blocks"
+                            class="small"
+                            help="Click to show all the sound blocks, or press F over any editor plane"
+                            onclick={emitter.reform(move |_| AppEvent::FitView(pattern_id))}
+                        >
+                            <span>{ "Fit" }</span>
+                        </Button>
                     </div>
                     if self.sequencer.playback_ctx().all_playing() {
                         <Button name="Stop" onclick={emitter.reform(|_| AppEvent::StopPlay)}>
@@ -383,11 +791,23 @@ impl Editor {
                             <img::Play />
                         </Button>
                     }
+                    <canvas
+                        id="minimap"
+                        ref={self.minimap.canvas()}
+                        class="blue-border"
+                        data-main-hint="Minimap"
+                        data-aux-hint="Click or drag to scroll the editor plane"
+                        onpointerdown={emitter.reform(AppEvent::MinimapPointer)}
+                        onpointermove={emitter.reform(AppEvent::MinimapPointer)}
+                        onpointerup={emitter.reform(AppEvent::MinimapPointer)}
+                    />
                     <canvas
                         id="sound-visualiser"
                         ref={self.sound_visualiser.canvas()}
                         class="blue-border"
                         data-main-hint="Sound visualiser"
+                        data-aux-hint="Click to switch between spectrogram & waveform"
+                        onclick={emitter.reform(|_| AppEvent::ToggleVisualiserMode)}
                     />
                 </div>
             </>
@@ -396,15 +816,44 @@ impl Editor {
 }
 
 impl Editor {
+    /// Maps a key, pressed with the Shift, Meta & Ctrl keys in the state described by `mods`,
+    /// onto the event it triggers, unless it's a combination handled elsewhere. The key plays a
+    /// note if `piano` is set & neither Meta nor Ctrl is held, & triggers its shortcut otherwise.
+    fn key_event(
+        code: &str,
+        [shift, meta, ctrl]: [bool; 3],
+        piano: bool,
+        editor_id: usize,
+    ) -> Option<AppEvent> {
+        if piano && !meta && !ctrl {
+            if let Some(note) = Note::from_key_code(code) {
+                return Some(AppEvent::PianoKeyDown(note));
+            }
+        }
+        Some(match code {
+            "KeyA" => AppEvent::SetSpecialAction(SpecialAction::Add),
+            "KeyS" => AppEvent::SetSpecialAction(SpecialAction::Select),
+            "KeyQ" if !meta => AppEvent::Quantize { strength: R32::ONE, lengths: shift },
+            "KeyL" => AppEvent::Legato,
+            "KeyH" => AppEvent::Humanize { time_amt: Beats(r64!(0.05)), vel_amt: r32!(0.2) },
+            "KeyF" => AppEvent::FitView(editor_id),
+            "KeyR" => AppEvent::SetSpecialAction(SpecialAction::Remove),
+            _ => return None,
+        })
+    }
+
     #[apply(fallible!)]
     fn forward_event(&mut self, event: &mut AppEvent, app: &mut AppContext) {
         let mut ctx = ContextMut { editor: &mut self.ctx, app };
         self.hint_handler.handle_event(event)?;
         self.sound_visualiser.handle_event(event, &self.sequencer)?;
+        self.minimap.handle_event(event, ctx.as_mut(), &self.sequencer)?;
+        self.ruler.handle_event(event, ctx.as_mut(), &self.sequencer)?;
         self.sequencer.handle_event(event, ctx.as_mut())?;
         let mut pattern = self.sequencer.pattern().get_mut()?;
         if let Some(&id) = pattern.selection().first() {
-            let mut block = pattern.get_mut(id)?;
+            let mut blocks = pattern.points_mut();
+            let mut block = blocks.get_mut(id)?;
             let offset = block.offset;
             block.inner().handle_event(event, ctx.as_mut(), &self.sequencer, offset)?;
         }
@@ -3,7 +3,7 @@ use crate::{
     img,
     popup::Popup,
     sound::{AudioInput, Beats},
-    visual::{GraphEditor, GraphPoint},
+    visual::{AnyGraphEditor, Axis, GraphEditor, GraphPoint},
 };
 use macro_rules_attribute::apply;
 use std::{
@@ -14,12 +14,14 @@ use wavexp_utils::{
     cell::Shared,
     error::AppError,
     ext::default,
-    ext::{BoolExt, HtmlCanvasExt, HtmlElementExt, ResultExt},
+    ext::{ArrayExt, BoolExt, HtmlCanvasExt, HtmlElementExt, ResultExt},
     fallible, r64,
-    real::R64,
-    Pipe, Point,
+    real::{R32, R64},
+    ArrayFrom, Pipe, Point,
+};
+use web_sys::{
+    Element, HtmlCanvasElement, KeyboardEvent, MouseEvent, Path2d, PointerEvent, WheelEvent,
 };
-use web_sys::{Element, HtmlCanvasElement, KeyboardEvent, MouseEvent, PointerEvent};
 use yew::{
     classes, function_component, html, html::Children, AttrValue, Callback, Classes, Component,
     Context, Html, NodeRef, Properties, TargetCast,
@@ -105,9 +107,28 @@ pub enum Cmd {
     Unfocus(PointerEvent),
 }
 
+/// max time in milliseconds between 2 clicks for them to be considered a double click
+pub const DOUBLE_CLICK_TIME: f64 = 300.0;
+
+/// Checks if a click at time `now` makes a double click with the one at time `prev`,
+/// both being timestamps in milliseconds; `prev` being NaN means there was no previous click.
+pub fn is_double_click(prev: f64, now: f64) -> bool {
+    now - prev <= DOUBLE_CLICK_TIME
+}
+
+#[test]
+fn double_click_detection() {
+    assert!(is_double_click(1000.0, 1200.0));
+    assert!(is_double_click(1000.0, 1000.0 + DOUBLE_CLICK_TIME));
+    assert!(!is_double_click(1000.0, 1400.0));
+    assert!(!is_double_click(f64::NAN, 1000.0));
+}
+
 pub struct Slider {
     old_value: f64,
     value: R64,
+    /// timestamp of the last click that didn't change the value, NaN if there's none
+    last_click: f64,
     target: NodeRef,
 }
 
@@ -116,6 +137,10 @@ pub struct SliderProps {
     pub name: AttrValue,
     #[prop_or(false)]
     pub signed: bool,
+    /// if true, the slider is centered at 0, ranging from `-max` to `max` & snapping to 0 when
+    /// close enough to it; `min` is ignored then
+    #[prop_or(false)]
+    pub bipolar: bool,
     #[prop_or(R64::ONE)]
     pub max: R64,
     #[prop_or(R64::ZERO)]
@@ -124,21 +149,53 @@ pub struct SliderProps {
     pub fmt: Callback<R64, String>,
     #[prop_or("")]
     pub postfix: &'static str,
+    /// the value to reset the slider to when it's double-clicked, if any
+    #[prop_or_default]
+    pub default: Option<R64>,
     pub setter: Callback<R64>,
     pub initial: R64,
 }
 
+impl Slider {
+    /// max distance from 0, relative to the range of a bipolar slider, at which it snaps to 0
+    pub const DEAD_ZONE: R64 = r64!(0.025);
+
+    /// Snaps `value` of a bipolar slider with the maximum value `max` to 0 if it's close enough.
+    pub fn snap_bipolar(value: R64, max: R64) -> R64 {
+        if value.abs() <= max * Self::DEAD_ZONE * 2u8 {
+            R64::ZERO
+        } else {
+            value
+        }
+    }
+
+    /// The value to show & emit, which may differ from the one being dragged by the user.
+    fn output(&self, props: &SliderProps) -> R64 {
+        if props.bipolar {
+            Self::snap_bipolar(self.value, props.max)
+        } else {
+            self.value
+        }
+    }
+}
+
 impl Component for Slider {
     type Message = Cmd;
     type Properties = SliderProps;
 
     fn create(ctx: &Context<Self>) -> Self {
-        Self { value: ctx.props().initial, old_value: f64::NAN, target: default() }
+        Self {
+            value: ctx.props().initial,
+            old_value: f64::NAN,
+            last_click: f64::NAN,
+            target: default(),
+        }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         fallible! {
-            let SliderProps { setter, min, max, signed, .. } = ctx.props();
+            let SliderProps { setter, min, max, signed, bipolar, default, .. } = ctx.props();
+            let min = if *bipolar { -*max } else { *min };
             match &msg {
                 Cmd::Drag(e) => {
                     self.value = R64::from(e.movement_y())
@@ -146,7 +203,7 @@ impl Component for Slider {
                         .div(e.shift_key().choose(400u16, 2))
                         .mul(max - min)
                         .add(self.value)
-                        .clamp(signed.choose(-*max, *min), *max)
+                        .clamp(signed.choose(-*max, min), *max)
                 }
 
                 Cmd::Focus(e) => {
@@ -154,14 +211,23 @@ impl Component for Slider {
                         .cast::<Element>()?
                         .set_pointer_capture(e.pointer_id())?;
                     self.old_value = *self.value;
+                    if let Some(default) = default
+                        && is_double_click(self.last_click, e.time_stamp())
+                    {
+                        self.value = *default;
+                    }
                 }
 
                 Cmd::Unfocus(e) => {
                     self.target
                         .cast::<Element>()?
                         .release_pointer_capture(e.pointer_id())?;
+                    self.value = self.output(ctx.props());
                     if self.old_value != *self.value {
-                        setter.emit(self.value)
+                        setter.emit(self.value);
+                        self.last_click = f64::NAN;
+                    } else {
+                        self.last_click = e.time_stamp();
                     }
                     self.old_value = f64::NAN;
                 }
@@ -183,9 +249,20 @@ impl Component for Slider {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let SliderProps { name, postfix, max, min, fmt, .. } = ctx.props();
+        let SliderProps { name, postfix, max, min, fmt, bipolar, .. } = ctx.props();
         let scope = ctx.link();
-        let selected: AttrValue = if self.value.abs() == *max {
+        let value = self.output(ctx.props());
+        let selected: AttrValue = if *bipolar {
+            // the angle clockwise from the top
+            let p = value / max * R64::PI;
+            format!(
+                "M 50 12 A 38 38 0 0 {} {} {}",
+                (p > 0) as u8,
+                p.sin_or(r64!(0)) * 38u8 + 50,
+                p.cos_or(r64!(0)) * -38i8 + 50
+            )
+            .into()
+        } else if self.value.abs() == *max {
             "M 50 12 A 38 38 0 0 0 50 88 A 38 38 0 1 0 50 12".into()
         } else {
             let p = ((self.value - min) / (max - min) - 0.5f32) * R64::TAU;
@@ -208,15 +285,28 @@ impl Component for Slider {
                 onpointermove={(!self.old_value.is_nan()).then(|| scope.callback(Cmd::Drag))}
             >
                 <circle class="outer" cx="50" cy="50" r="40" />
+                if *bipolar {
+                    <line class="detent" x1="50" y1="0" x2="50" y2="12" />
+                }
                 <path d={selected} />
                 <circle class="inner" cx="50" cy="50" r="38" />
-                <text x="50" y="50">{ fmt.emit(self.value) }</text>
+                <text x="50" y="50">{ fmt.emit(value) }</text>
                 <text x="50" y="65">{ postfix }</text>
             </svg>
         }
     }
 }
 
+#[test]
+fn slider_bipolar_snapping() {
+    let max = r64!(1);
+    assert_eq!(Slider::snap_bipolar(r64!(0.03), max), R64::ZERO);
+    assert_eq!(Slider::snap_bipolar(r64!(-0.05), max), R64::ZERO);
+    assert_eq!(Slider::snap_bipolar(r64!(0.06), max), r64!(0.06));
+    assert_eq!(Slider::snap_bipolar(r64!(-0.5), max), r64!(-0.5));
+    assert_eq!(Slider::snap_bipolar(r64!(4), r64!(100)), R64::ZERO);
+}
+
 pub struct Switch {
     value: R64,
     old_value: usize,
@@ -393,6 +483,33 @@ pub struct GraphEditorCanvasProps<T: GraphPoint> {
     pub id: Option<&'static str>,
 }
 
+impl<T: GraphPoint> GraphEditorCanvas<T> {
+    /// number of pixels the mouse wheel has to be scrolled by to zoom an editor plane 2 times
+    const WHEEL_ZOOM_STEP: f64 = 500.0;
+
+    fn zoom_event(editor_id: usize, e: WheelEvent) -> AppEvent {
+        // don't let the browser scroll or zoom the page
+        e.prevent_default();
+        // most browsers turn vertical scrolling with Shift held into horizontal scrolling
+        let delta = if e.delta_y() == 0.0 { e.delta_x() } else { e.delta_y() };
+        let px_per_unit = match e.delta_mode() {
+            WheelEvent::DOM_DELTA_LINE => 16.0,
+            WheelEvent::DOM_DELTA_PAGE => 800.0,
+            _ => 1.0,
+        };
+        AppEvent::Zoom {
+            editor_id,
+            delta: R64::new_or(R64::ZERO, -delta * px_per_unit / Self::WHEEL_ZOOM_STEP),
+            at: Point { x: e.offset_x(), y: e.offset_y() },
+            axis: match (e.ctrl_key(), e.shift_key()) {
+                (true, _) => Axis::X,
+                (false, true) => Axis::Y,
+                (false, false) => Axis::Both,
+            },
+        }
+    }
+}
+
 impl<T: GraphPoint> Component for GraphEditorCanvas<T> {
     type Message = ();
     type Properties = GraphEditorCanvasProps<T>;
@@ -418,6 +535,8 @@ impl<T: GraphPoint> Component for GraphEditorCanvas<T> {
                         onpointermove={emitter.reform(move  |e| AppEvent::Hover(id, MouseEvent::from(e)))}
                         onpointerenter={emitter.reform(move |e| AppEvent::Enter(id, MouseEvent::from(e)))}
                         onpointerout={emitter.reform(move   |_| AppEvent::Leave(id))}
+                        onwheel={emitter.reform(move        |e| Self::zoom_event(id, e))}
+                        oncontextmenu={emitter.reform(move  |e| AppEvent::OpenContextMenu(id, e))}
                     />
                 }
             }
@@ -435,9 +554,451 @@ impl<T: GraphPoint> Component for GraphEditorCanvas<T> {
     }
 }
 
+/// a control point of an `EnvelopeGraph` that can be dragged to change one of the parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeHandle {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// graph of an ADSR envelope, the parameters of which can be changed by dragging its handles
+#[derive(Default)]
+pub struct EnvelopeGraph {
+    attack: Beats,
+    decay: Beats,
+    sustain: R32,
+    release: Beats,
+    /// the handle being dragged & how many beats a pixel on the X axis represents
+    drag: Option<(EnvelopeHandle, R64)>,
+    target: NodeRef,
+}
+
+#[derive(PartialEq, Properties)]
+pub struct EnvelopeGraphProps {
+    pub attack: Beats,
+    pub decay: Beats,
+    pub sustain: R32,
+    pub release: Beats,
+    pub emitter: Callback<AppEvent>,
+}
+
+impl EnvelopeGraph {
+    /// share of the graph's width that the sustain level is held for
+    const HOLD: R64 = r64!(0.25);
+    /// max distance in pixels from a handle at which it can be grabbed
+    const HANDLE_RADIUS: R64 = r64!(10);
+
+    /// Maps the envelope's parameters to the points of its graph, in the order they're connected.
+    /// The time axis is scaled to fit `attack + decay + release` into the space left after the
+    /// sustain hold; both axes are in the range `0 ..= 1`, the Y axis being the level.
+    pub fn points(attack: Beats, decay: Beats, sustain: R32, release: Beats) -> [[R64; 2]; 5] {
+        let [attack, decay, release] = [attack, decay, release].map(R64::from);
+        let total = attack + decay + release;
+        let (hold, step) = if total > 0 {
+            (Self::HOLD, (R64::ONE - Self::HOLD) / total)
+        } else {
+            (R64::ONE, R64::ZERO)
+        };
+        let sustain = R64::from(sustain);
+        let attack_end = attack * step;
+        let decay_end = attack_end + decay * step;
+        let hold_end = decay_end + hold;
+        [
+            [R64::ZERO, R64::ZERO],
+            [attack_end, R64::ONE],
+            [decay_end, sustain],
+            [hold_end, sustain],
+            [hold_end + release * step, R64::ZERO],
+        ]
+    }
+
+    /// Maps a point of the graph, as returned by `points`, to a point on a canvas of size `size`.
+    fn to_canvas([x, y]: [R64; 2], size: [R64; 2]) -> [R64; 2] {
+        let pad = AnyGraphEditor::LINE_WIDTH;
+        [x * (size[0] - pad * 2.0) + pad, (R64::ONE - y) * (size[1] - pad * 2.0) + pad]
+    }
+
+    /// Maps the points of the graph, as returned by `points`, to the locations of its handles.
+    fn handles(points: &[[R64; 2]; 5]) -> [(EnvelopeHandle, [R64; 2]); 4] {
+        [
+            (EnvelopeHandle::Attack, points[1]),
+            (EnvelopeHandle::Decay, points[2]),
+            (EnvelopeHandle::Sustain, [(points[2][0] + points[3][0]) / 2.0, points[2][1]]),
+            (EnvelopeHandle::Release, points[4]),
+        ]
+    }
+
+    /// Returns the handle nearest to `cursor` on a canvas of size `size`,
+    /// if it's no further than `HANDLE_RADIUS` pixels away.
+    pub fn handle_at(
+        points: &[[R64; 2]; 5],
+        size: [R64; 2],
+        cursor: [R64; 2],
+    ) -> Option<EnvelopeHandle> {
+        Self::handles(points)
+            .into_iter()
+            .map(|(handle, point)| {
+                let [dx, dy] = Self::to_canvas(point, size).sub(cursor);
+                (handle, dx * dx + dy * dy)
+            })
+            .filter(|(_, dist)| *dist <= Self::HANDLE_RADIUS * Self::HANDLE_RADIUS)
+            .min_by_key(|(_, dist)| *dist)
+            .map(|(handle, _)| handle)
+    }
+
+    #[apply(fallible!)]
+    fn redraw(&self) {
+        let canvas: HtmlCanvasElement = self.target.cast()?;
+        let size = canvas.sync_hidpi()?.map(R64::from);
+        let ctx = canvas.get_2d_context()?;
+        ctx.set_fill_style(&AnyGraphEditor::BG_STYLE.into());
+        ctx.fill_rect(0.0, 0.0, *size[0], *size[1]);
+
+        let points = Self::points(self.attack, self.decay, self.sustain, self.release);
+        let path = Path2d::new()?;
+        for point in points {
+            let [x, y] = Self::to_canvas(point, size);
+            path.line_to(*x, *y);
+        }
+        let r = *Self::HANDLE_RADIUS / 2.0;
+        for (_, point) in Self::handles(&points) {
+            let [x, y] = Self::to_canvas(point, size);
+            path.rect(*x - r, *y - r, r * 2.0, r * 2.0);
+        }
+        ctx.set_line_width(AnyGraphEditor::LINE_WIDTH);
+        ctx.set_stroke_style(&AnyGraphEditor::FG_STYLE.into());
+        ctx.stroke_with_path(&path);
+    }
+}
+
+impl Component for EnvelopeGraph {
+    type Message = Cmd;
+    type Properties = EnvelopeGraphProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let EnvelopeGraphProps { attack, decay, sustain, release, .. } = *ctx.props();
+        Self { attack, decay, sustain, release, ..default() }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        fallible! {
+            let EnvelopeGraphProps { emitter, .. } = ctx.props();
+            match msg {
+                Cmd::Drag(e) => {
+                    let Some((handle, beats_per_px)) = self.drag else { return false };
+                    let dx = Beats(beats_per_px * R64::from(e.movement_x()));
+                    match handle {
+                        EnvelopeHandle::Attack => self.attack = (self.attack + dx).max(Beats::ZERO),
+                        EnvelopeHandle::Decay => self.decay = (self.decay + dx).max(Beats::ZERO),
+                        EnvelopeHandle::Release => {
+                            self.release = (self.release + dx).max(Beats::ZERO)
+                        }
+                        EnvelopeHandle::Sustain => {
+                            let canvas = self.target.cast::<Element>()?;
+                            let height = R64::from(canvas.client_height())
+                                - AnyGraphEditor::LINE_WIDTH * 2.0;
+                            self.sustain = R32::from(R64::from(e.movement_y()) / -height)
+                                .add(self.sustain)
+                                .clamp(R32::ZERO, R32::ONE)
+                        }
+                    }
+                }
+
+                Cmd::Focus(e) => {
+                    let canvas = self.target.cast::<HtmlCanvasElement>()?;
+                    let size = canvas.size().map(R64::from);
+                    let points = Self::points(self.attack, self.decay, self.sustain, self.release);
+                    let cursor = R64::array_from(Cursor::try_from(&e)?.point);
+                    let Some(handle) = Self::handle_at(&points, size, cursor) else {
+                        return false;
+                    };
+                    canvas.set_pointer_capture(e.pointer_id())?;
+                    let total = self.attack + self.decay + self.release;
+                    let width = size[0] - AnyGraphEditor::LINE_WIDTH * 2.0;
+                    let beats_per_px = if *total > 0.0 {
+                        total.0 / (width * (R64::ONE - Self::HOLD))
+                    } else {
+                        R64::ONE / width
+                    };
+                    self.drag = Some((handle, beats_per_px));
+                }
+
+                Cmd::Unfocus(e) => {
+                    let Some((handle, _)) = self.drag.take() else { return false };
+                    self.target
+                        .cast::<Element>()?
+                        .release_pointer_capture(e.pointer_id())?;
+                    let props = ctx.props();
+                    match handle {
+                        EnvelopeHandle::Attack if self.attack != props.attack => {
+                            emitter.emit(AppEvent::Attack(self.attack))
+                        }
+                        EnvelopeHandle::Decay if self.decay != props.decay => {
+                            emitter.emit(AppEvent::Decay(self.decay))
+                        }
+                        EnvelopeHandle::Sustain if self.sustain != props.sustain => {
+                            emitter.emit(AppEvent::Sustain(self.sustain))
+                        }
+                        EnvelopeHandle::Release if self.release != props.release => {
+                            emitter.emit(AppEvent::Release(self.release))
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            return true
+        }
+        .report();
+        false
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _: &Self::Properties) -> bool {
+        let EnvelopeGraphProps { attack, decay, sustain, release, .. } = *ctx.props();
+        self.attack = attack;
+        self.decay = decay;
+        self.sustain = sustain;
+        self.release = release;
+        self.drag = None;
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let scope = ctx.link();
+        html! {
+            <canvas
+                ref={self.target.clone()}
+                class="envelope-graph"
+                data-main-hint="Envelope"
+                data-aux-hint="Drag the squares to change the envelope's parameters"
+                onpointerdown={scope.callback(Cmd::Focus)}
+                onpointerup={scope.callback(Cmd::Unfocus)}
+                onpointermove={self.drag.is_some().then(|| scope.callback(Cmd::Drag))}
+            />
+        }
+    }
+
+    fn rendered(&mut self, _: &Context<Self>, _: bool) {
+        self.redraw().report();
+    }
+}
+
+#[test]
+fn envelope_graph_points() {
+    let [start, attack, decay, hold, release] =
+        EnvelopeGraph::points(Beats::ONE, Beats(r64!(0.5)), r64!(0.5).into(), Beats(r64!(1.5)));
+    assert_eq!(start, [r64!(0), r64!(0)]);
+    assert_eq!(attack, [r64!(0.25), r64!(1)]);
+    assert_eq!(decay, [r64!(0.375), r64!(0.5)]);
+    assert_eq!(hold, [r64!(0.625), r64!(0.5)]);
+    assert_eq!(release, [r64!(1), r64!(0)]);
+
+    let [.., hold, release] =
+        EnvelopeGraph::points(Beats::ZERO, Beats::ZERO, R32::ONE, Beats::ZERO);
+    assert_eq!(hold, [r64!(1), r64!(1)]);
+    assert_eq!(release, [r64!(1), r64!(0)]);
+}
+
+#[test]
+fn envelope_graph_hit_testing() {
+    let points =
+        EnvelopeGraph::points(Beats::ONE, Beats(r64!(0.5)), r64!(0.5).into(), Beats(r64!(1.5)));
+    let size = [r64!(206), r64!(106)];
+    let near = |point| EnvelopeGraph::to_canvas(point, size).add([r64!(4), r64!(-3)]);
+    let handle_at = |cursor| EnvelopeGraph::handle_at(&points, size, cursor);
+    assert_eq!(handle_at(near(points[1])), Some(EnvelopeHandle::Attack));
+    assert_eq!(handle_at(near(points[2])), Some(EnvelopeHandle::Decay));
+    assert_eq!(handle_at(near([r64!(0.5), r64!(0.5)])), Some(EnvelopeHandle::Sustain));
+    assert_eq!(handle_at(near(points[4])), Some(EnvelopeHandle::Release));
+    assert_eq!(handle_at([r64!(100), r64!(100)]), None);
+}
+
+/// waveform of a raw audio input with 2 handles that can be dragged to cut off its start & end
+#[derive(Default)]
+pub struct TrimHandles {
+    /// the starting & the ending cut-offs
+    cuts: [Beats; 2],
+    /// index of the handle being dragged in `cuts`
+    dragged: Option<usize>,
+    target: NodeRef,
+}
+
+#[derive(PartialEq, Properties)]
+pub struct TrimHandlesProps {
+    /// SVG path of the raw audio input's waveform, see `AudioInput::raw_thumbnail`
+    pub thumbnail: AttrValue,
+    /// duration of the raw audio input
+    pub duration: Beats,
+    pub initial: [Beats; 2],
+    pub setter: Callback<[Beats; 2]>,
+}
+
+impl TrimHandles {
+    /// the shortest the trimmed audio input is allowed to get
+    pub const MIN_LEN: Beats = Beats(r64!(0.0625));
+
+    /// Maps an X coordinate on the waveform of width `width` to a point in the audio input.
+    pub fn x_to_beats(x: R64, width: R64, duration: Beats) -> Beats {
+        if width <= 0 {
+            return Beats::ZERO;
+        }
+        duration * (x / width).clamp(R64::ZERO, R64::ONE)
+    }
+
+    /// Clamps the cut-off at index `handle` in `cuts` so that the handles don't cross each other
+    /// & what's left of the audio input is no shorter than `MIN_LEN`.
+    pub fn clamp_cuts(mut cuts: [Beats; 2], handle: usize, duration: Beats) -> [Beats; 2] {
+        let max = (duration - Self::MIN_LEN.min(duration) - cuts[1 - handle]).max(Beats::ZERO);
+        cuts[handle] = cuts[handle].clamp(Beats::ZERO, max);
+        cuts
+    }
+
+    /// Returns the index of the handle in `cuts` nearest to the point `at` in the audio input.
+    pub fn handle_at(cuts: [Beats; 2], duration: Beats, at: Beats) -> usize {
+        ((at - cuts[0]).abs() > (duration - cuts[1] - at).abs()) as usize
+    }
+
+    /// Moves the dragged handle to the cursor.
+    #[apply(fallible!)]
+    fn drag_to(&mut self, e: &PointerEvent, duration: Beats) {
+        let Some(handle) = self.dragged else { return Ok(()) };
+        let width = self.target.cast::<Element>()?.client_width();
+        let at = Self::x_to_beats(e.offset_x().into(), width.into(), duration);
+        self.cuts[handle] = if handle == 0 { at } else { duration - at };
+        self.cuts = Self::clamp_cuts(self.cuts, handle, duration);
+    }
+}
+
+impl Component for TrimHandles {
+    type Message = Cmd;
+    type Properties = TrimHandlesProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self { cuts: ctx.props().initial, ..default() }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        fallible! {
+            let TrimHandlesProps { duration, initial, setter, .. } = ctx.props();
+            match msg {
+                Cmd::Drag(e) => self.drag_to(&e, *duration)?,
+
+                Cmd::Focus(e) => {
+                    let width = self.target.cast::<Element>()?.client_width();
+                    let at = Self::x_to_beats(e.offset_x().into(), width.into(), *duration);
+                    self.dragged = Some(Self::handle_at(self.cuts, *duration, at));
+                    self.target
+                        .cast::<Element>()?
+                        .set_pointer_capture(e.pointer_id())?;
+                    self.drag_to(&e, *duration)?;
+                }
+
+                Cmd::Unfocus(e) => {
+                    if self.dragged.take().is_none() {
+                        return false;
+                    }
+                    self.target
+                        .cast::<Element>()?
+                        .release_pointer_capture(e.pointer_id())?;
+                    if self.cuts != *initial {
+                        setter.emit(self.cuts)
+                    }
+                }
+            }
+            return true
+        }
+        .report();
+        false
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _: &Self::Properties) -> bool {
+        self.cuts = ctx.props().initial;
+        self.dragged = None;
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let TrimHandlesProps { thumbnail, duration, .. } = ctx.props();
+        let scope = ctx.link();
+        let [w, h] = AudioInput::THUMBNAIL_SIZE;
+        let to_x = |x: Beats| {
+            if **duration > 0.0 {
+                *(x / *duration * w)
+            } else {
+                0.0
+            }
+        };
+        let [start, end] = [to_x(self.cuts[0]), w as f64 - to_x(self.cuts[1])];
+        html! {
+            <svg
+                ref={self.target.clone()}
+                class="trim-handles"
+                viewBox={format!("0 0 {w} {h}")}
+                preserveAspectRatio="none"
+                data-main-hint="Trim"
+                data-aux-hint="Drag the handles to cut off the start or the end of the audio input"
+                onpointerdown={scope.callback(Cmd::Focus)}
+                onpointerup={scope.callback(Cmd::Unfocus)}
+                onpointermove={self.dragged.is_some().then(|| scope.callback(Cmd::Drag))}
+            >
+                <path class="waveform" d={thumbnail} />
+                <rect class="cut" x="0" y="0" width={start.to_string()} height={h.to_string()} />
+                <rect
+                    class="cut"
+                    x={end.to_string()}
+                    y="0"
+                    width={(w as f64 - end).to_string()}
+                    height={h.to_string()}
+                />
+                <rect
+                    class="handle"
+                    x={(start - 1.0).to_string()}
+                    y="0"
+                    width="2"
+                    height={h.to_string()}
+                />
+                <rect
+                    class="handle"
+                    x={(end - 1.0).to_string()}
+                    y="0"
+                    width="2"
+                    height={h.to_string()}
+                />
+            </svg>
+        }
+    }
+}
+
+#[test]
+fn trim_handles() {
+    let beats = |x| Beats(R64::new_or(R64::ZERO, x));
+    let duration = beats(4.0);
+    assert_eq!(TrimHandles::x_to_beats(r64!(50), r64!(200), duration), beats(1.0));
+    assert_eq!(TrimHandles::x_to_beats(r64!(-10), r64!(200), duration), beats(0.0));
+    assert_eq!(TrimHandles::x_to_beats(r64!(250), r64!(200), duration), duration);
+    assert_eq!(TrimHandles::x_to_beats(r64!(50), r64!(0), duration), beats(0.0));
+
+    assert_eq!(TrimHandles::handle_at([beats(1.0); 2], duration, beats(1.5)), 0);
+    assert_eq!(TrimHandles::handle_at([beats(1.0); 2], duration, beats(2.5)), 1);
+
+    let clamp_cuts = |cuts: [f64; 2], handle, duration| {
+        TrimHandles::clamp_cuts(cuts.map(beats), handle, duration).map(|x| *x)
+    };
+    let max = *(duration - TrimHandles::MIN_LEN - beats(1.0));
+    assert_eq!(clamp_cuts([1.0, 1.0], 0, duration), [1.0, 1.0]);
+    assert_eq!(clamp_cuts([3.5, 1.0], 0, duration), [max, 1.0]);
+    assert_eq!(clamp_cuts([1.0, 3.5], 1, duration), [1.0, max]);
+    assert_eq!(clamp_cuts([-1.0, 1.0], 0, duration), [0.0, 1.0]);
+    let short = TrimHandles::MIN_LEN / 2u8;
+    assert_eq!(clamp_cuts([1.0, 0.0], 0, short), [0.0, 0.0]);
+}
+
 pub struct Counter {
     value: R64,
     old_value: f64,
+    /// timestamp of the last click that didn't change the value, NaN if there's none
+    last_click: f64,
     target: NodeRef,
 }
 
@@ -448,35 +1009,62 @@ pub struct CounterProps {
     pub coef: R64,
     #[prop_or(R64::ZERO)]
     pub min: R64,
+    /// if true, dragging multiplies/divides the value instead of adding to/subtracting from it,
+    /// in which case `min` is raised to `Counter::LOG_MIN` if it's lower
+    #[prop_or(false)]
+    pub logarithmic: bool,
     #[prop_or(Callback::from(|x| format!("{x:.2}")))]
     pub fmt: Callback<R64, String>,
     #[prop_or("")]
     pub postfix: &'static str,
+    /// the value to reset the counter to when it's double-clicked, if any
+    #[prop_or_default]
+    pub default: Option<R64>,
     pub setter: Callback<R64>,
     pub initial: R64,
 }
 
+impl Counter {
+    /// the lowest value a logarithmic counter can reach
+    pub const LOG_MIN: R64 = r64!(0.001);
+
+    /// Changes `value` by `delta`, not letting it go below `min`.
+    /// If `logarithmic` is true, `value` is instead multiplied by `2 ^ delta`.
+    pub fn step(value: R64, delta: R64, min: R64, logarithmic: bool) -> R64 {
+        if logarithmic {
+            let min = min.max(Self::LOG_MIN);
+            (value.max(min) * delta.exp2()).max(min)
+        } else {
+            (value + delta).max(min)
+        }
+    }
+}
+
 impl Component for Counter {
     type Message = Cmd;
     type Properties = CounterProps;
 
     fn create(ctx: &Context<Self>) -> Self {
-        Self { value: ctx.props().initial, old_value: f64::NAN, target: default() }
+        Self {
+            value: ctx.props().initial,
+            old_value: f64::NAN,
+            last_click: f64::NAN,
+            target: default(),
+        }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         fallible! {
             let CounterProps {
-                setter, coef, min, ..
+                setter, coef, min, logarithmic, default, ..
             } = ctx.props();
             match msg {
                 Cmd::Drag(e) => {
-                    self.value = R64::from(e.movement_y())
+                    let delta = R64::from(e.movement_y())
                         .div(-self.target.cast::<Element>()?.client_height())
                         .mul(coef)
-                        .pipe_if(e.shift_key(), |x| x / 200)
-                        .add(self.value)
-                        .max(*min)
+                        .pipe_if(e.shift_key(), |x| x / 200);
+                    self.value = Self::step(self.value, delta, *min, *logarithmic)
                 }
 
                 Cmd::Focus(e) => {
@@ -484,6 +1072,11 @@ impl Component for Counter {
                         .cast::<Element>()?
                         .set_pointer_capture(e.pointer_id())?;
                     self.old_value = *self.value;
+                    if let Some(default) = default
+                        && is_double_click(self.last_click, e.time_stamp())
+                    {
+                        self.value = *default;
+                    }
                 }
 
                 Cmd::Unfocus(e) => {
@@ -491,7 +1084,10 @@ impl Component for Counter {
                         .cast::<Element>()?
                         .release_pointer_capture(e.pointer_id())?;
                     if self.old_value != *self.value {
-                        setter.emit(self.value)
+                        setter.emit(self.value);
+                        self.last_click = f64::NAN;
+                    } else {
+                        self.last_click = e.time_stamp();
                     }
                     self.old_value = f64::NAN;
                 }
@@ -503,7 +1099,8 @@ impl Component for Counter {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let CounterProps { name, fmt, postfix, min, .. } = ctx.props();
+        let CounterProps { name, fmt, postfix, min, logarithmic, .. } = ctx.props();
+        let min = if *logarithmic { Self::LOG_MIN.max(*min) } else { *min };
         let scope = ctx.link();
         html! {
             <svg
@@ -520,7 +1117,7 @@ impl Component for Counter {
                 if !postfix.is_empty() {
                     <text x="50" y="70">{ postfix }</text>
                 }
-                if self.value != *min {
+                if self.value != min {
                     <polygon class="lower" points="6,84 40,84 50,94 60,84 94,84" />
                 }
             </svg>
@@ -528,6 +1125,23 @@ impl Component for Counter {
     }
 }
 
+#[test]
+fn counter_log_step() {
+    let mut value = r64!(1);
+    let values: Vec<R64> = (0..4)
+        .map(|_| {
+            value = Counter::step(value, R64::ONE, R64::ZERO, true);
+            value
+        })
+        .collect();
+    assert_eq!(values, [r64!(2), r64!(4), r64!(8), r64!(16)]);
+    assert_eq!(Counter::step(r64!(16), r64!(-2), R64::ZERO, true), r64!(4));
+    assert_eq!(Counter::step(r64!(1), r64!(-2), r64!(0.5), true), r64!(0.5));
+    assert_eq!(Counter::step(r64!(0), r64!(-1), r64!(-5), true), Counter::LOG_MIN);
+    assert_eq!(Counter::step(r64!(0), r64!(1), R64::ZERO, true), Counter::LOG_MIN * 2u8);
+    assert_eq!(Counter::step(r64!(1), r64!(-2), R64::ZERO, false), R64::ZERO);
+}
+
 #[derive(PartialEq, Properties)]
 pub struct TabProps {
     pub name: AttrValue,
@@ -554,7 +1168,7 @@ pub fn Tab(props: &TabProps) -> Html {
 
 #[derive(PartialEq, Properties)]
 pub struct AudioInputButtonProps {
-    pub bps: Beats,
+    pub bps: R64,
     pub name: AttrValue,
     pub input: Option<Shared<AudioInput>>,
     #[prop_or_default]
@@ -603,6 +1217,16 @@ pub fn AudioInputButton(props: &AudioInputButtonProps) -> Html {
                         </Button>
                     }
                     <p>{ input.desc(*bps) }</p>
+                    <svg
+                        class="waveform"
+                        viewBox={{
+                            let [w, h] = AudioInput::THUMBNAIL_SIZE;
+                            format!("0 0 {w} {h}")
+                        }}
+                        preserveAspectRatio="none"
+                    >
+                        <path d={input.thumbnail().clone()} />
+                    </svg>
                     <Button
                         name="Edit audio input"
                         help="Click to edit the audio input"
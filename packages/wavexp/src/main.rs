@@ -20,6 +20,7 @@ mod ctx;
 mod editor;
 mod img;
 mod input;
+mod midi;
 mod persistence;
 mod popup;
 mod sequencer;
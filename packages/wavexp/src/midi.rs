@@ -0,0 +1,221 @@
+//! defines decoding & encoding of Standard MIDI Files
+
+use crate::sound::Secs;
+use wavexp_utils::{bail, ensure, error::Result, real::R64};
+
+/// a note decoded from a MIDI file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiNote {
+    /// MIDI key number, 60 being C4
+    pub key: u8,
+    pub start: Secs,
+    pub len: Secs,
+}
+
+/// tempo that a MIDI file has until its 1st tempo event, in microseconds per quarter note
+const DEFAULT_TEMPO: u32 = 500_000;
+/// number of ticks per quarter note in the encoded MIDI files
+const TICKS_PER_QUARTER: u16 = 480;
+/// velocity of all the encoded notes
+const VELOCITY: u8 = 64;
+
+fn decode_bytes<'src>(src: &mut &'src [u8], n: usize) -> Result<&'src [u8]> {
+    ensure!(src.len() >= n, "unexpected end of the MIDI file");
+    let (res, rest) = src.split_at(n);
+    *src = rest;
+    Ok(res)
+}
+
+fn decode_u8(src: &mut &[u8]) -> Result<u8> {
+    Ok(decode_bytes(src, 1)?[0])
+}
+
+fn decode_u16(src: &mut &[u8]) -> Result<u16> {
+    let bytes = decode_bytes(src, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn decode_u32(src: &mut &[u8]) -> Result<u32> {
+    let bytes = decode_bytes(src, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// decode a variable-length quantity, which is at most 4 bytes long
+fn decode_vlq(src: &mut &[u8]) -> Result<u32> {
+    let mut res = 0;
+    for _ in 0..4 {
+        let byte = decode_u8(src)?;
+        res = res << 7 | (byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            return Ok(res);
+        }
+    }
+    bail!("variable-length quantity in the MIDI file is longer than 4 bytes")
+}
+
+/// Decodes the notes of a format 0 or format 1 Standard MIDI File, sorted by their start.
+/// The notes of all the tracks & channels are merged.
+pub fn decode(mut src: &[u8]) -> Result<Vec<MidiNote>> {
+    let src = &mut src;
+    ensure!(decode_bytes(src, 4)? == b"MThd", "not a MIDI file");
+    let header_len = decode_u32(src)? as usize;
+    ensure!(header_len >= 6, "invalid MIDI header length: {header_len}");
+    let format = decode_u16(src)?;
+    ensure!(format <= 1, "unsupported MIDI file format: {format}");
+    let n_tracks = decode_u16(src)?;
+    let ticks_per_quarter = decode_u16(src)?;
+    ensure!(ticks_per_quarter & 0x8000 == 0, "SMPTE-based MIDI timing isn't supported");
+    ensure!(ticks_per_quarter != 0, "invalid MIDI time division: 0");
+    decode_bytes(src, header_len - 6)?;
+
+    // (tick, microseconds per quarter note)
+    let mut tempos = vec![(0, DEFAULT_TEMPO)];
+    // (key, start tick, end tick)
+    let mut notes = vec![];
+    let mut n_decoded = 0;
+    while n_decoded < n_tracks {
+        let id = decode_bytes(src, 4)?;
+        let len = decode_u32(src)? as usize;
+        let mut track = decode_bytes(src, len)?;
+        if id != b"MTrk" {
+            continue;
+        }
+        n_decoded += 1;
+        let track = &mut track;
+        let mut tick = 0u64;
+        let mut running_status = None;
+        // (channel, key, start tick)
+        let mut pending: Vec<(u8, u8, u64)> = vec![];
+        while !track.is_empty() {
+            tick += decode_vlq(track)? as u64;
+            let status = match track.first() {
+                Some(&byte) if byte & 0x80 != 0 => {
+                    *track = &track[1..];
+                    byte
+                }
+                _ => {
+                    ensure!(
+                        let Some(status) = running_status,
+                        "MIDI data byte without a preceding status byte"
+                    );
+                    status
+                }
+            };
+            match status {
+                0xFF => {
+                    running_status = None;
+                    let meta_type = decode_u8(track)?;
+                    let len = decode_vlq(track)? as usize;
+                    let data = decode_bytes(track, len)?;
+                    match (meta_type, data) {
+                        (0x2F, _) => break,
+                        (0x51, &[a, b, c]) => tempos.push((tick, u32::from_be_bytes([0, a, b, c]))),
+                        _ => (),
+                    }
+                }
+                0xF0 | 0xF7 => {
+                    running_status = None;
+                    let len = decode_vlq(track)? as usize;
+                    decode_bytes(track, len)?;
+                }
+                0x80..=0xEF => {
+                    running_status = Some(status);
+                    // program change & channel pressure messages have 1 data byte, others have 2
+                    if matches!(status & 0xF0, 0xC0 | 0xD0) {
+                        decode_u8(track)?;
+                        continue;
+                    }
+                    let channel = status & 0xF;
+                    let data = decode_bytes(track, 2)?;
+                    let (key, velocity) = (data[0], data[1]);
+                    match status & 0xF0 {
+                        0x90 if velocity > 0 => pending.push((channel, key, tick)),
+                        // note-on with 0 velocity is a note-off
+                        0x80 | 0x90 => {
+                            let i = pending.iter().position(|x| x.0 == channel && x.1 == key);
+                            if let Some(i) = i {
+                                let (.., start) = pending.remove(i);
+                                notes.push((key, start, tick));
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                _ => bail!("invalid MIDI status byte: {status:#X}"),
+            }
+        }
+    }
+
+    tempos.sort_by_key(|x| x.0);
+    let ticks_per_quarter = ticks_per_quarter as f64;
+    let tick_to_secs = |tick: u64| {
+        let mut secs = 0.0;
+        let mut prev = (0, DEFAULT_TEMPO);
+        for &(at, tempo) in tempos.iter().take_while(|x| x.0 < tick) {
+            secs += (at - prev.0) as f64 / ticks_per_quarter * prev.1 as f64 / 1e6;
+            prev = (at, tempo);
+        }
+        secs += (tick - prev.0) as f64 / ticks_per_quarter * prev.1 as f64 / 1e6;
+        Secs(R64::new_or(R64::ZERO, secs))
+    };
+    let mut res: Vec<MidiNote> = notes
+        .into_iter()
+        .map(|(key, start, end)| {
+            let start_secs = tick_to_secs(start);
+            MidiNote { key, start: start_secs, len: tick_to_secs(end) - start_secs }
+        })
+        .collect();
+    res.sort_by_key(|x| x.start);
+    Ok(res)
+}
+
+fn encode_vlq(dst: &mut Vec<u8>, mut x: u32) {
+    let mut bytes = [(x & 0x7F) as u8, 0, 0, 0];
+    let mut n = 1;
+    x >>= 7;
+    while x > 0 && n < bytes.len() {
+        bytes[n] = (x & 0x7F) as u8 | 0x80;
+        x >>= 7;
+        n += 1;
+    }
+    dst.extend(bytes[..n].iter().rev());
+}
+
+/// Encodes the notes into a format 0 Standard MIDI File with a single track, in tempo `bps`.
+/// Notes that are shorter than a tick are omitted.
+pub fn encode(notes: &[MidiNote], bps: R64) -> Box<[u8]> {
+    let to_ticks = |x: Secs| (*x * *bps * TICKS_PER_QUARTER as f64).round() as u32;
+    // (tick, is note-on, key); at the same tick, note-offs come first
+    let mut events: Vec<(u32, bool, u8)> = notes
+        .iter()
+        .map(|note| (to_ticks(note.start), to_ticks(note.start + note.len), note.key))
+        .filter(|(start, end, _)| end > start)
+        .flat_map(|(start, end, key)| [(start, true, key), (end, false, key)])
+        .collect();
+    events.sort_unstable();
+
+    let mut track = vec![];
+    let tempo = (1e6 / *bps).round() as u32;
+    encode_vlq(&mut track, 0);
+    track.extend([0xFF, 0x51, 3]);
+    track.extend(&tempo.to_be_bytes()[1..]);
+    let mut prev_tick = 0;
+    for (tick, on, key) in events {
+        encode_vlq(&mut track, tick - prev_tick);
+        track.extend([if on { 0x90 } else { 0x80 }, key, VELOCITY]);
+        prev_tick = tick;
+    }
+    encode_vlq(&mut track, 0);
+    track.extend([0xFF, 0x2F, 0]);
+
+    let mut res = Vec::with_capacity(track.len() + 22);
+    res.extend(b"MThd");
+    res.extend(6u32.to_be_bytes());
+    res.extend(0u16.to_be_bytes());
+    res.extend(1u16.to_be_bytes());
+    res.extend(TICKS_PER_QUARTER.to_be_bytes());
+    res.extend(b"MTrk");
+    res.extend((track.len() as u32).to_be_bytes());
+    res.extend(track);
+    res.into_boxed_slice()
+}
@@ -1,21 +1,22 @@
 //! defines decoding/encoding of a composition
 
-use crate::sequencer::{Composition, Sequencer};
-use crate::sound::FromBeats;
+use crate::sequencer::{Composition, DelayParams, Mixer, Sequencer};
 use crate::{
     sequencer::SoundBlock,
     sound::{
-        AudioInput, CustomBlock, CustomSound, NoiseBlock, NoiseSound, Note, NoteBlock, NoteSound,
-        Sound,
+        encoded_sample_rate, ArpMode, AudioInput, AutomationPoint, Beats, Chord, CustomBlock,
+        CustomSound, DrumBlock, DrumSound, FilterType, LfoShape, NoiseBlock, NoiseColor,
+        NoiseSound, Note, NoteBlock, NoteSound, Patterns, SampleParams, Scale, Secs, Sound,
+        TempoMap,
     },
     visual::{GraphEditor, GraphPoint},
 };
 use hound::{SampleFormat, WavSpec, WavWriter};
 use js_sys::ArrayBuffer;
+use macro_rules_attribute::apply;
 use std::future::Future;
 use std::io::Cursor;
-use std::iter::zip;
-use std::ops::{Add, Mul};
+use std::ops::Mul;
 use std::{
     num::{
         NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64,
@@ -34,20 +35,98 @@ use wavexp_utils::{
     ensure,
     error::Result,
     ext::{BoolExt, SliceExt},
+    fallible,
+    js::window,
+    r64,
     real::{R32, R64},
     TryÍnto,
 };
-use wavexp_utils::{const_assert, r64};
 use web_sys::{AudioBuffer, AudioBufferOptions, BaseAudioContext, OfflineAudioContext};
 
 impl Composition {
     const WAVEXP_HEADER: [u8; 8] = *b"3XPL0RE!";
+    /// version of the `.wavexp` file format, stored right after the header, to be incremented on
+    /// every change to the encoding of any of the composition's parts
+    const FORMAT_VERSION: u16 = 4;
+
+    /// whether a `.wavexp` file of the given format version can be decoded; older versions are
+    /// to be listed here once a migration for them is in place
+    const fn is_version_supported(version: u16) -> bool {
+        version == Self::FORMAT_VERSION
+    }
 
     /// decodes the contents of a `.wavexp` file
     pub fn decode(src: &mut &[u8]) -> Result<Self> {
         let header: [u8; 8] = decode(src)?;
         ensure!(header == Self::WAVEXP_HEADER, "invalid header");
-        Ok(Self { pattern: decode(src)?, inputs: decode_short(src)?, bps: decode(src)? })
+        let version: u16 = decode(src)?;
+        ensure!(
+            Self::is_version_supported(version),
+            "unsupported `.wavexp` format version: {version}, expected {}",
+            Self::FORMAT_VERSION
+        );
+        Ok(Self {
+            pattern: decode(src)?,
+            inputs: decode_short(src)?,
+            tempo: decode(src)?,
+            delay: decode(src)?,
+        })
+    }
+
+    /// key under which the composition is autosaved in `localStorage`, changes along with the
+    /// `.wavexp` file format version
+    fn autosave_key() -> String {
+        format!("wavexp-autosave-v{}", Self::FORMAT_VERSION)
+    }
+
+    /// the composition encoded as Base64, since `localStorage` only stores strings
+    fn encode_base64(&self) -> Result<String> {
+        let encoded: String = self.encode()?.into_iter().map(char::from).collect();
+        Ok(window().btoa(&encoded)?)
+    }
+
+    /// a copy of the composition with all the audio inputs left out
+    fn without_audio(&self) -> Result<Self> {
+        let pattern = self.pattern.get()?;
+        let blocks = pattern.points().iter().map(|block| {
+            let mut block = block.clone();
+            match &mut block.sound {
+                Sound::Custom(sound) => sound.src = None,
+                Sound::Drum(sound) => sound.samples.clear(),
+                Sound::None | Sound::Note(_) | Sound::Noise(_) => (),
+            }
+            block
+        });
+        Ok(Self {
+            pattern: Shared::from(GraphEditor::new(blocks.collect())),
+            inputs: vec![],
+            tempo: self.tempo.clone(),
+            delay: self.delay,
+        })
+    }
+
+    /// save the composition in `localStorage`, to be restored when the app is reopened
+    #[apply(fallible!)]
+    pub fn autosave(&self) {
+        let storage = window().local_storage()??;
+        let key = Self::autosave_key();
+        if storage.set_item(&key, &self.encode_base64()?).is_err() {
+            // the storage quota is easily exceeded by the audio inputs, so at least the rest of
+            // the composition is saved
+            storage.set_item(&key, &self.without_audio()?.encode_base64()?)?
+        }
+    }
+
+    /// the last autosaved composition, if there is one & it's valid
+    pub fn autosaved() -> Option<Self> {
+        let saved = window().local_storage().ok()??.get_item(&Self::autosave_key()).ok()??;
+        let saved: Vec<u8> = window()
+            .atob(&saved)
+            .ok()?
+            .chars()
+            .map(|c| u8::try_from(c).ok())
+            .collect::<Option<_>>()?;
+        Self::decode(&mut saved.as_slice()).ok()
     }
 
     /// imports extenal audio and creates a composition of 1 custom audio block
@@ -56,21 +135,26 @@ impl Composition {
         src: &ArrayBuffer,
         ctx: &BaseAudioContext,
     ) -> Result<Self> {
+        let encoded_rate = encoded_sample_rate(src);
         let src =
             JsFuture::from(ctx.decode_audio_data(src)?).await?.unchecked_into::<AudioBuffer>();
-        let src = Shared::from(AudioInput::new(src_name, src)?);
+        let original_rate = encoded_rate.unwrap_or(src.sample_rate() as u32);
+        let src = Shared::from(AudioInput::new(src_name, src)?.with_original_rate(original_rate));
         Ok(Self {
             pattern: Shared::from(GraphEditor::new(vec![SoundBlock {
                 sound: Sound::Custom(CustomSound {
-                    pattern: Shared::from(GraphEditor::new(vec![CustomBlock {
-                        offset: r64!(0),
+                    patterns: GraphEditor::new(vec![CustomBlock {
+                        offset: Beats::ZERO,
                         pitch: Note::MID,
-                    }])),
+                    }])
+                    .into(),
                     src: Some(src.clone()),
                     ..default()
                 }),
                 layer: 0,
-                offset: r64!(0),
+                offset: Beats::ZERO,
+                muted: false,
+                soloed: false,
             }])),
             inputs: vec![src],
             ..default()
@@ -81,61 +165,125 @@ impl Composition {
     pub fn encode(&self) -> Result<Vec<u8>> {
         let mut dst = vec![];
         dst.extend(Self::WAVEXP_HEADER);
+        Self::FORMAT_VERSION.encode(&mut dst)?;
         self.pattern.encode(&mut dst)?;
         self.inputs.encode_short(&mut dst)?;
-        self.bps.encode(&mut dst)?;
+        self.tempo.encode(&mut dst)?;
+        self.delay.encode(&mut dst)?;
         Ok(dst)
     }
 
-    /// export the composition into the `.wav` audio file format with the provided volume
-    pub fn export(&self, volume: R32) -> Result<impl Future<Output = Result<Vec<u8>>>> {
+    /// render the whole composition, with all the repetitions of all the sound blocks, into an
+    /// audio buffer with the provided volume & sample rate
+    pub fn render_to_buffer(
+        &self,
+        volume: R32,
+        mut mixer: Mixer,
+        swing: R32,
+        sample_rate: u32,
+    ) -> Result<impl Future<Output = Result<AudioBuffer>>> {
         let mut pat = self.pattern.get_mut()?;
+        let mut len = Beats::ZERO;
+        for block in pat.points() {
+            len = len.max(block.offset + block.total_len(self.tempo.bps())?);
+        }
         let renderer = OfflineAudioContext::new_with_number_of_channels_and_length_and_sample_rate(
             Sequencer::CHANNEL_COUNT,
-            'len: {
-                let Some(last) = pat.data().last() else {
-                    break 'len 1;
-                };
-                last.len(self.bps)?.add(last.offset).mul(Sequencer::SAMPLE_RATE).max(r64!(1)).into()
-            },
-            Sequencer::SAMPLE_RATE as f32,
+            self.tempo.to_secs(len).mul(sample_rate).0.max(r64!(1)).into(),
+            sample_rate as f32,
         )?;
         let gain = renderer.create_gain()?;
         gain.gain().set_value(*volume);
         gain.connect_with_audio_node(&renderer.destination())?;
-        for mut block in pat.iter_data_mut() {
-            block.inner().prepare(self.bps)?;
+        for mut block in pat.points_mut().iter_mut() {
+            block.inner().prepare(self.tempo.bps())?;
         }
-        for mut block in pat.iter_data_mut() {
-            let offset = block.offset.to_secs(self.bps);
-            block.inner().play(&gain, R64::ZERO, offset, self.bps)?;
+        for block in SoundBlock::audible_blocks(pat.points()) {
+            let offset = block.offset;
+            let plug = mixer.plug(block.layer, &gain)?;
+            let span = Beats::ZERO..Beats::INFINITY;
+            let automated = block.sound.automate_volume(
+                &plug,
+                Secs::ZERO,
+                offset,
+                span.clone(),
+                &self.tempo,
+            )?;
+            let plug = automated.unwrap_or(plug);
+            block.sound.play(&plug, Secs::ZERO, offset, span, &self.tempo, swing)?;
         }
 
         Ok(async move {
-            let rendered =
-                JsFuture::from(renderer.start_rendering()?).await?.unchecked_into::<AudioBuffer>();
-            let mut wav: Cursor<Vec<u8>> = default();
-            let mut wav_writer = WavWriter::new(
-                &mut wav,
-                WavSpec {
-                    channels: Sequencer::CHANNEL_COUNT as u16,
-                    sample_rate: Sequencer::SAMPLE_RATE,
-                    bits_per_sample: 32,
-                    sample_format: SampleFormat::Float,
-                },
-            )?;
-
-            const_assert!(Sequencer::CHANNEL_COUNT == 2);
-            let ch1 = rendered.get_channel_data(0)?;
-            let ch2 = rendered.get_channel_data(1)?;
-            for (s1, s2) in zip(ch1, ch2) {
-                wav_writer.write_sample(s1)?;
-                wav_writer.write_sample(s2)?;
-            }
-            wav_writer.finalize()?;
-            Ok(wav.into_inner())
+            Ok(JsFuture::from(renderer.start_rendering()?).await?.unchecked_into::<AudioBuffer>())
         })
     }
+
+    /// export the composition into the `.wav` audio file format with the provided volume, layer
+    /// volumes, swing & sample rate
+    pub fn export(
+        &self,
+        volume: R32,
+        mixer: Mixer,
+        swing: R32,
+        sample_rate: u32,
+    ) -> Result<impl Future<Output = Result<Box<[u8]>>>> {
+        let rendered = self.render_to_buffer(volume, mixer, swing, sample_rate)?;
+        Ok(async move { encode_wav(&rendered.await?) })
+    }
+}
+
+/// encode an audio buffer into the `.wav` audio file format as 16-bit PCM
+pub fn encode_wav(buffer: &AudioBuffer) -> Result<Box<[u8]>> {
+    let channels = (0..buffer.number_of_channels())
+        .map(|ch_id| buffer.get_channel_data(ch_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    encode_wav_channels(&channels, buffer.sample_rate() as u32)
+}
+
+/// encode channels of samples into the `.wav` audio file format as 16-bit PCM, interleaving the
+/// channels & clamping the samples to [-1, 1]
+fn encode_wav_channels(channels: &[Vec<f32>], sample_rate: u32) -> Result<Box<[u8]>> {
+    let mut wav: Cursor<Vec<u8>> = default();
+    let mut wav_writer = WavWriter::new(
+        &mut wav,
+        WavSpec {
+            channels: channels.len() as u16,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        },
+    )?;
+
+    let n_samples = channels.iter().map(Vec::len).min().unwrap_or(0);
+    for i in 0..n_samples {
+        for ch in channels {
+            wav_writer.write_sample((ch[i].clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+        }
+    }
+    wav_writer.finalize()?;
+    Ok(wav.into_inner().into_boxed_slice())
+}
+
+#[test]
+fn wav_encoding() {
+    let wav = encode_wav_channels(&[vec![0.0, 1.0, 2.0], vec![-0.5, -2.0, 0.25]], 44100)
+        .unwrap_or_default();
+    let u16_at = |i: usize| u16::from_le_bytes([wav[i], wav[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([wav[i], wav[i + 1], wav[i + 2], wav[i + 3]]);
+    let i16_at = |i: usize| i16::from_le_bytes([wav[i], wav[i + 1]]);
+    assert_eq!(wav.len(), 44 + 3 * 2 * 2);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(4) as usize, wav.len() - 8);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    // PCM format, 2 channels, 44.1KHz, 16 bits per sample
+    assert_eq!(u16_at(20), 1);
+    assert_eq!(u16_at(22), 2);
+    assert_eq!(u32_at(24), 44100);
+    assert_eq!(u16_at(34), 16);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(40), 12);
+    let samples: Vec<i16> = (44..wav.len()).step_by(2).map(i16_at).collect();
+    assert_eq!(samples, [0, -16383, i16::MAX, -i16::MAX, i16::MAX, 8191]);
 }
 
 // TODO: make propagated errors more informative by adding an API to `AppError` for nesting error
@@ -209,6 +357,20 @@ macro_rules! impl_persist_for_ints {
 // not isize/uzize as its layout is platform-dependent
 impl_persist_for_ints!(i8, u8, i16, u16, i32, u32, i64, u64);
 
+impl Persist for bool {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            x => bail!("invalid boolean: {x}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(*self as u8))
+    }
+}
+
 macro_rules! impl_persist_for_nonzeros {
     ($($nonzero:ty : $int:ty ),+ $(,)?) => {
         $(
@@ -252,6 +414,25 @@ macro_rules! impl_persist_for_reals {
 
 impl_persist_for_reals!(R32: f32, R64: f64);
 
+macro_rules! impl_persist_for_time_units {
+    ($($unit:ty),+) => {
+        $(
+            impl Persist for $unit {
+                fn decode(src: &mut &[u8]) -> Result<Self> {
+                    decode(src).map(Self)
+                }
+
+                #[inline]
+                fn encode(&self, dst: &mut Vec<u8>) -> Result {
+                    self.0.encode(dst)
+                }
+            }
+        )+
+    }
+}
+
+impl_persist_for_time_units!(Beats, Secs);
+
 macro_rules! impl_persist_for_short_ints {
     ($($int:ty),*) => {
         $(
@@ -407,15 +588,39 @@ impl<T: GraphPoint + Persist> Persist for GraphEditor<T> {
     }
 
     fn encode(&self, dst: &mut Vec<u8>) -> Result {
-        self.data().encode_short(dst)
+        let points = self.points();
+        points.len().try_ínto::<u32>()?.encode_short(dst)?;
+        Ok(for point in points {
+            point.encode(dst)?
+        })
+    }
+}
+
+impl<T: GraphPoint + Persist> Persist for Patterns<T> {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        let mut patterns = Vec::with_capacity(u32::decode_short(src)? as usize);
+        for _ in 0..patterns.capacity() {
+            patterns.push((decode_short(src)?, decode(src)?))
+        }
+        Self::new(patterns, u32::decode_short(src)? as usize)
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        self.all().len().try_ínto::<u32>()?.encode_short(dst)?;
+        for (name, pattern) in self.all() {
+            name.encode_short(dst)?;
+            pattern.encode(dst)?;
+        }
+        self.active_index().try_ínto::<u32>()?.encode_short(dst)
     }
 }
 
 impl Persist for AudioBuffer {
     fn decode(src: &mut &[u8]) -> Result<Self> {
         let length = decode(src)?;
+        let sample_rate: u32 = decode(src)?;
         let res = AudioBuffer::new(
-            AudioBufferOptions::new(length, Sequencer::SAMPLE_RATE as f32)
+            AudioBufferOptions::new(length, sample_rate as f32)
                 .number_of_channels(Sequencer::CHANNEL_COUNT),
         )?;
         for ch_id in 0..Sequencer::CHANNEL_COUNT as i32 {
@@ -427,6 +632,7 @@ impl Persist for AudioBuffer {
 
     fn encode(&self, dst: &mut Vec<u8>) -> Result {
         self.length().encode(dst)?;
+        (self.sample_rate() as u32).encode(dst)?;
         for ch_id in 0..Sequencer::CHANNEL_COUNT {
             let ch = self.get_channel_data(ch_id)?;
             encode_f32_seq(&ch, dst);
@@ -438,28 +644,32 @@ impl Persist for AudioBuffer {
 impl Persist for AudioInput {
     fn decode(src: &mut &[u8]) -> Result<Self> {
         let name = decode_short(src)?;
-        let length = decode(src)?;
-        let buffer = AudioBuffer::new(
-            AudioBufferOptions::new(length, Sequencer::SAMPLE_RATE as f32)
-                .number_of_channels(Sequencer::CHANNEL_COUNT),
-        )?;
-        for ch_id in 0..Sequencer::CHANNEL_COUNT as i32 {
-            let ch = decode_f32_seq(src, length as usize)?;
-            buffer.copy_to_channel(ch, ch_id)?;
-        }
-        AudioInput::new(name, buffer)
+        let original_rate = u32::decode(src)?;
+        Ok(AudioInput::new(name, AudioBuffer::decode(src)?)?.with_original_rate(original_rate))
     }
 
     fn encode(&self, dst: &mut Vec<u8>) -> Result {
-        let raw_buf = self.raw();
-
         self.name().encode_short(dst)?;
-        raw_buf.length().encode(dst)?;
-        for ch_id in 0..Sequencer::CHANNEL_COUNT {
-            let ch = raw_buf.get_channel_data(ch_id)?;
-            encode_f32_seq(&ch, dst);
-        }
-        Ok(())
+        self.original_rate().encode(dst)?;
+        self.raw().encode(dst)
+    }
+}
+
+impl Persist for TempoMap {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        let bps = decode(src)?;
+        let n_changes = u32::decode_short(src)?;
+        let changes = (0..n_changes).map(|_| Ok((decode(src)?, decode(src)?)));
+        Ok(TempoMap::with_changes(bps, changes.collect::<Result<Vec<_>>>()?))
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        self.bps().encode(dst)?;
+        self.changes().len().try_ínto::<u32>()?.encode_short(dst)?;
+        Ok(for (at, bps) in self.changes() {
+            at.encode(dst)?;
+            bps.encode(dst)?
+        })
     }
 }
 
@@ -473,6 +683,84 @@ impl Persist for Note {
     }
 }
 
+impl Persist for Scale {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            tag @ 0..=4 => Ok(Scale::ALL[tag as usize]),
+            tag => bail!("invalid scale tag: {tag}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(self.index() as u8))
+    }
+}
+
+impl Persist for NoiseColor {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            tag @ 0..=2 => Ok(NoiseColor::ALL[tag as usize]),
+            tag => bail!("invalid noise color tag: {tag}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(self.index() as u8))
+    }
+}
+
+impl Persist for FilterType {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            tag @ 0..=2 => Ok(FilterType::ALL[tag as usize]),
+            tag => bail!("invalid filter type tag: {tag}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(self.index() as u8))
+    }
+}
+
+impl Persist for Chord {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            tag @ 0..=9 => Ok(Chord::ALL[tag as usize]),
+            tag => bail!("invalid chord tag: {tag}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(self.index() as u8))
+    }
+}
+
+impl Persist for ArpMode {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            tag @ 0..=3 => Ok(ArpMode::ALL[tag as usize]),
+            tag => bail!("invalid arpeggiator mode tag: {tag}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(self.index() as u8))
+    }
+}
+
+impl Persist for LfoShape {
+    fn decode(src: &mut &[u8]) -> Result<Self> {
+        match u8::decode(src)? {
+            tag @ 0..=3 => Ok(LfoShape::ALL[tag as usize]),
+            tag => bail!("invalid LFO shape tag: {tag}"),
+        }
+    }
+
+    fn encode(&self, dst: &mut Vec<u8>) -> Result {
+        Ok(dst.push(self.index() as u8))
+    }
+}
+
 macro_rules! decoder {
     () => {
         decode
@@ -515,13 +803,28 @@ macro_rules! impl_persist_for_structs {
 }
 
 impl_persist_for_structs!(
-    NoteBlock { offset, value, len },
-    NoteSound { pattern, volume, attack, decay, sustain, release, rep_count },
+    NoteBlock { offset, value, len, velocity },
+    NoteSound {
+        patterns, volume, attack, decay, sustain, release, rep_count, scale, scale_root,
+        filter_type, filter_freq, filter_q, pan, lfo_shape, lfo_rate, lfo_depth, arp_enabled,
+        arp_rate, arp_mode, chord, rep_gap, volume_automation
+    },
     NoiseBlock { offset, pitch, len },
-    NoiseSound { pattern, volume, attack, decay, sustain, release, rep_count },
+    NoiseSound {
+        pattern, volume, attack, decay, sustain, release, rep_count, color,
+        filter_type, filter_freq, filter_q, pan, seed, rep_gap, volume_automation
+    },
     CustomBlock { offset, pitch },
-    CustomSound { pattern, volume, attack, decay, sustain, release, rep_count, speed, src },
-    SoundBlock { sound, layer[short], offset },
+    SampleParams {
+        volume, pan, attack, decay, sustain, release, rep_count, rep_gap,
+        filter_type, filter_freq, filter_q
+    },
+    CustomSound { patterns, src, params, volume_automation, speed },
+    DrumBlock { offset, row },
+    AutomationPoint { time, value },
+    DrumSound { pattern, samples[short], params, volume_automation },
+    SoundBlock { sound, layer[short], offset, muted, soloed },
+    DelayParams { time, feedback, wet },
 );
 
 impl Persist for Sound {
@@ -531,6 +834,7 @@ impl Persist for Sound {
             1 => decode(src).map(Sound::Note),
             2 => decode(src).map(Sound::Noise),
             3 => decode(src).map(Sound::Custom),
+            4 => decode(src).map(Sound::Drum),
             tag => bail!("invalid sound type tag: {tag}"),
         }
     }
@@ -550,6 +854,91 @@ impl Persist for Sound {
                 dst.push(3);
                 custom.encode(dst)
             }
+            Sound::Drum(drum) => {
+                dst.push(4);
+                drum.encode(dst)
+            }
         }
     }
 }
+
+#[test]
+fn composition_round_trip() {
+    let note = NoteSound { attack: Beats(r64!(0.5)), release: Beats(r64!(0.25)), ..default() };
+    let noise = NoiseSound { decay: Beats(r64!(1.5)), release: Beats(r64!(2)), ..default() };
+    let comp = Composition {
+        pattern: Shared::from(GraphEditor::new(vec![
+            SoundBlock {
+                sound: Sound::Note(note),
+                layer: 0,
+                offset: Beats::ZERO,
+                muted: false,
+                soloed: false,
+            },
+            SoundBlock {
+                sound: Sound::Noise(noise),
+                layer: 3,
+                offset: Beats(r64!(4.5)),
+                muted: true,
+                soloed: false,
+            },
+        ])),
+        tempo: TempoMap::new(r64!(2)).set(Beats(r64!(8)), Some(r64!(3))),
+        delay: DelayParams {
+            time: Beats(r64!(0.75)),
+            feedback: wavexp_utils::r32!(0.25),
+            wet: wavexp_utils::r32!(0.125),
+        },
+        ..default()
+    };
+    let decoded =
+        comp.encode().and_then(|x| Composition::decode(&mut x.as_slice())).unwrap_or_default();
+    let pattern = decoded.pattern.get().ok();
+    let blocks = pattern.as_ref().map_or(&[][..], |x| x.points());
+    assert_eq!(decoded.tempo, comp.tempo);
+    assert_eq!(decoded.delay, comp.delay);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!((blocks[0].layer, blocks[0].offset, blocks[0].muted), (0, Beats::ZERO, false));
+    assert_eq!((blocks[1].layer, blocks[1].offset, blocks[1].muted), (3, Beats(r64!(4.5)), true));
+    let Sound::Note(ref note) = blocks[0].sound else { panic!("expected a note sound") };
+    assert_eq!((note.attack, note.release), (Beats(r64!(0.5)), Beats(r64!(0.25))));
+    let Sound::Noise(ref noise) = blocks[1].sound else { panic!("expected a noise sound") };
+    assert_eq!((noise.decay, noise.release), (Beats(r64!(1.5)), Beats(r64!(2))));
+}
+
+#[test]
+fn format_version() {
+    let encoded = Composition::default().encode().unwrap_or_default();
+    assert_eq!(encoded[..8], Composition::WAVEXP_HEADER);
+    assert_eq!(encoded[8..10], Composition::FORMAT_VERSION.to_le_bytes());
+    assert!(Composition::decode(&mut encoded.as_slice()).is_ok());
+    // creating the error needs a JS engine, hence only the check itself is tested for rejection
+    assert!(!Composition::is_version_supported(0));
+    assert!(!Composition::is_version_supported(Composition::FORMAT_VERSION + 1));
+    assert!(Composition::autosave_key().ends_with(&Composition::FORMAT_VERSION.to_string()));
+}
+
+#[test]
+fn autosave_without_audio() {
+    let custom = CustomSound { src: None, speed: wavexp_utils::r32!(2), ..default() };
+    let comp = Composition {
+        pattern: Shared::from(GraphEditor::new(vec![SoundBlock {
+            sound: Sound::Custom(custom),
+            layer: 1,
+            offset: Beats(r64!(2)),
+            muted: false,
+            soloed: true,
+        }])),
+        tempo: TempoMap::new(r64!(3)),
+        ..default()
+    };
+    let stripped = comp.without_audio().unwrap_or_default();
+    let pattern = stripped.pattern.get().ok();
+    let blocks = pattern.as_ref().map_or(&[][..], |x| x.points());
+    assert!(stripped.inputs.is_empty());
+    assert_eq!(stripped.tempo, comp.tempo);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!((blocks[0].layer, blocks[0].offset, blocks[0].soloed), (1, Beats(r64!(2)), true));
+    let Sound::Custom(ref custom) = blocks[0].sound else { panic!("expected a custom sound") };
+    assert_eq!((custom.src.is_none(), custom.speed), (true, wavexp_utils::r32!(2)));
+}
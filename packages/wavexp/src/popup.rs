@@ -4,18 +4,20 @@ use macro_rules_attribute::apply;
 use wavexp_utils::{
     cell::Shared,
     ext::{BoolExt, ResultExt},
-    fallible,
+    fallible, r64,
+    real::R32,
+    Point,
 };
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, MouseEvent, PointerEvent};
 use yew::{AttrValue, Callback, Html, TargetCast};
 use yew_html_ext::html;
 
 use crate::{
     ctx::{AppEvent, ContextMut, EditorAction},
     img,
-    input::{AudioInputButton, Button, Slider},
+    input::{AudioInputButton, Button, Counter, Slider, Switch, TrimHandles},
     sequencer::Sequencer,
-    sound::{AudioInput, FromBeats},
+    sound::{AudioInput, Beats, FromSecs},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,11 +38,31 @@ pub enum Popup {
     EditInput(Shared<AudioInput>),
     /// Export the sequence as a file.
     Export { format: ExportFormat, filename: Rc<str>, err_msg: AttrValue },
+    /// Act on the selected points of a graph editor, shown at `at` in client coordinates.
+    /// `pitched` enables actions specific to notes.
+    ContextMenu { editor_id: usize, at: Point, pitched: bool },
 }
 
 impl Popup {
+    /// Whether opening & closing the pop-up is registered in the action history.
+    pub const fn undoable(&self) -> bool {
+        !matches!(self, Self::ContextMenu { .. })
+    }
+
+    /// Bakes the changes into the edited audio input right away, updating its thumbnail & the
+    /// lengths of the sound blocks playing it.
+    #[apply(fallible!)]
+    fn rebake(&self, mut ctx: ContextMut, sequencer: &Sequencer) {
+        if let Self::EditInput(input) = self {
+            if input.get_mut()?.bake(sequencer.bps())? {
+                ctx.emit_event(AppEvent::RedrawEditorPlane);
+            }
+            ctx.force_rerender();
+        }
+    }
+
     #[apply(fallible!)]
-    pub fn handle_event(&mut self, event: &AppEvent, mut ctx: ContextMut) {
+    pub fn handle_event(&mut self, event: &AppEvent, mut ctx: ContextMut, sequencer: &Sequencer) {
         match *event {
             AppEvent::SetOutputFileName(ref e) => {
                 if let Self::Export { filename, err_msg, .. } = self {
@@ -69,6 +91,15 @@ impl Popup {
                 }
             }
 
+            AppEvent::RemoveSelection(_)
+            | AppEvent::DuplicateSelection(_)
+            | AppEvent::Transpose(_)
+            | AppEvent::Quantize { .. } => {
+                if let Self::ContextMenu { .. } = self {
+                    ctx.emit_event(AppEvent::ClosePopup)
+                }
+            }
+
             AppEvent::SetInputName(ref e) => {
                 if let Self::EditInput(input) = self {
                     let to: Rc<str> = e.target_dyn_into::<HtmlInputElement>()?.value().into();
@@ -83,6 +114,7 @@ impl Popup {
                 if let Self::EditInput(input) = self {
                     input.get_mut()?.changes_mut().reversed.flip();
                     ctx.register_action(EditorAction::ReverseInput)?;
+                    self.rebake(ctx, sequencer)?;
                 }
             }
 
@@ -100,6 +132,61 @@ impl Popup {
                 }
             }
 
+            AppEvent::TrimInput(to) => {
+                if let Self::EditInput(input) = self {
+                    let from = {
+                        let mut input = input.get_mut()?;
+                        let changes = input.changes_mut();
+                        [
+                            replace(&mut changes.cut_start, to[0]),
+                            replace(&mut changes.cut_end, to[1]),
+                        ]
+                    };
+                    ctx.register_action(EditorAction::TrimInput { from, to })?;
+                    self.rebake(ctx, sequencer)?;
+                }
+            }
+
+            AppEvent::SetFadeIn(to) => {
+                if let Self::EditInput(input) = self {
+                    let from = replace(&mut input.get_mut()?.changes_mut().fade_in, to);
+                    ctx.register_action(EditorAction::SetFadeIn { from, to })?;
+                    self.rebake(ctx, sequencer)?;
+                }
+            }
+
+            AppEvent::SetFadeOut(to) => {
+                if let Self::EditInput(input) = self {
+                    let from = replace(&mut input.get_mut()?.changes_mut().fade_out, to);
+                    ctx.register_action(EditorAction::SetFadeOut { from, to })?;
+                    self.rebake(ctx, sequencer)?;
+                }
+            }
+
+            AppEvent::ToggleNormalizeInput => {
+                if let Self::EditInput(input) = self {
+                    input.get_mut()?.changes_mut().normalize.flip();
+                    ctx.register_action(EditorAction::ToggleNormalizeInput)?;
+                    self.rebake(ctx, sequencer)?;
+                }
+            }
+
+            AppEvent::SetInputGain(to) => {
+                if let Self::EditInput(input) = self {
+                    let from = replace(&mut input.get_mut()?.changes_mut().gain, to);
+                    ctx.register_action(EditorAction::SetInputGain { from, to })?;
+                    self.rebake(ctx, sequencer)?;
+                }
+            }
+
+            AppEvent::SetInputStretch(to) => {
+                if let Self::EditInput(input) = self {
+                    let from = replace(&mut input.get_mut()?.changes_mut().stretch, to);
+                    ctx.register_action(EditorAction::SetInputStretch { from, to })?;
+                    self.rebake(ctx, sequencer)?;
+                }
+            }
+
             AppEvent::Undo(ref actions) => {
                 for action in actions.iter() {
                     match action {
@@ -120,7 +207,7 @@ impl Popup {
                         EditorAction::ReverseInput => {
                             if let Self::EditInput(input) = self {
                                 input.get_mut()?.changes_mut().reversed.flip();
-                                ctx.force_rerender();
+                                self.rebake(ctx.as_mut(), sequencer)?;
                             }
                         }
 
@@ -138,6 +225,52 @@ impl Popup {
                             }
                         }
 
+                        EditorAction::TrimInput { from, .. } => {
+                            if let Self::EditInput(input) = self {
+                                {
+                                    let mut input = input.get_mut()?;
+                                    let changes = input.changes_mut();
+                                    [changes.cut_start, changes.cut_end] = *from;
+                                }
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetFadeIn { from, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().fade_in = *from;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetFadeOut { from, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().fade_out = *from;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::ToggleNormalizeInput => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().normalize.flip();
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetInputGain { from, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().gain = *from;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetInputStretch { from, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().stretch = *from;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
                         _ => (),
                     }
                 }
@@ -163,7 +296,7 @@ impl Popup {
                         EditorAction::ReverseInput => {
                             if let Self::EditInput(input) = self {
                                 input.get_mut()?.changes_mut().reversed.flip();
-                                ctx.force_rerender();
+                                self.rebake(ctx.as_mut(), sequencer)?;
                             }
                         }
 
@@ -181,6 +314,52 @@ impl Popup {
                             }
                         }
 
+                        EditorAction::TrimInput { to, .. } => {
+                            if let Self::EditInput(input) = self {
+                                {
+                                    let mut input = input.get_mut()?;
+                                    let changes = input.changes_mut();
+                                    [changes.cut_start, changes.cut_end] = *to;
+                                }
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetFadeIn { to, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().fade_in = *to;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetFadeOut { to, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().fade_out = *to;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::ToggleNormalizeInput => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().normalize.flip();
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetInputGain { to, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().gain = *to;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
+                        EditorAction::SetInputStretch { to, .. } => {
+                            if let Self::EditInput(input) = self {
+                                input.get_mut()?.changes_mut().stretch = *to;
+                                self.rebake(ctx.as_mut(), sequencer)?;
+                            }
+                        }
+
                         _ => (),
                     }
                 }
@@ -275,18 +454,65 @@ impl Popup {
                                         onchange={emitter.reform(AppEvent::SetInputName)}
                                     />
                                 </div>
+                                <TrimHandles
+                                    thumbnail={input.raw_thumbnail().clone()}
+                                    duration={input.raw_duration().to_beats(sequencer.bps())}
+                                    initial={[input.changes().cut_start, input.changes().cut_end]}
+                                    setter={emitter.reform(AppEvent::TrimInput)}
+                                />
                                 <div style="display: grid; grid-template-columns: repeat(2, 1fr)">
                                     <Slider
                                         name="Start cut-off"
-                                        max={input.raw_duration().secs_to_beats(sequencer.bps())}
+                                        max={input.raw_duration().to_beats(sequencer.bps())}
                                         initial={input.changes().cut_start}
-                                        setter={emitter.reform(AppEvent::SetStartCutOff)}
+                                        setter={emitter.reform(|x| {
+                                            AppEvent::SetStartCutOff(Beats(x))
+                                        })}
                                     />
                                     <Slider
                                         name="End cut-off"
-                                        max={input.raw_duration().secs_to_beats(sequencer.bps())}
+                                        max={input.raw_duration().to_beats(sequencer.bps())}
                                         initial={input.changes().cut_end}
-                                        setter={emitter.reform(AppEvent::SetEndCutOff)}
+                                        setter={emitter.reform(|x| {
+                                            AppEvent::SetEndCutOff(Beats(x))
+                                        })}
+                                    />
+                                    <Slider
+                                        name="Fade-in"
+                                        max={input.raw_duration().to_beats(sequencer.bps())}
+                                        initial={input.changes().fade_in}
+                                        setter={emitter.reform(|x| AppEvent::SetFadeIn(Beats(x)))}
+                                    />
+                                    <Slider
+                                        name="Fade-out"
+                                        max={input.raw_duration().to_beats(sequencer.bps())}
+                                        initial={input.changes().fade_out}
+                                        setter={emitter.reform(|x| AppEvent::SetFadeOut(Beats(x)))}
+                                    />
+                                    <Switch
+                                        key="normalize"
+                                        name="Normalization"
+                                        options={vec!["Off", "On"]}
+                                        initial={input.changes().normalize as usize}
+                                        setter={emitter.reform(|_| AppEvent::ToggleNormalizeInput)}
+                                    />
+                                    <Slider
+                                        name="Gain"
+                                        default={r64!(1)}
+                                        max={r64!(4)}
+                                        initial={input.changes().gain}
+                                        setter={emitter
+                                            .reform(|x| AppEvent::SetInputGain(R32::from(x)))}
+                                    />
+                                    <Counter
+                                        name="Time stretch (keeps the pitch)"
+                                        default={r64!(1)}
+                                        fmt={|x| format!("{x:.2}x")}
+                                        min={r64!(0.25)}
+                                        logarithmic=true
+                                        initial={input.changes().stretch}
+                                        setter={emitter
+                                            .reform(|x| AppEvent::SetInputStretch(R32::from(x)))}
                                     />
                                 </div>
                             } else {
@@ -297,6 +523,61 @@ impl Popup {
                 </form>
             },
 
+            &Self::ContextMenu { editor_id, at, pitched } => html! {
+                <div
+                    id="context-menu-bg"
+                    onpointerdown={emitter.reform(|_| AppEvent::ClosePopup)}
+                    oncontextmenu={|e: MouseEvent| e.prevent_default()}
+                >
+                    <div
+                        class="context-menu dark-bg blue-border"
+                        style={format!("left: {}px; top: {}px", at.x, at.y)}
+                        onpointerdown={|e: PointerEvent| e.stop_propagation()}
+                    >
+                        <button
+                            class="input button"
+                            data-main-hint="Duplicate"
+                            onclick={emitter
+                                .reform(move |_| AppEvent::DuplicateSelection(editor_id))}
+                        >
+                            { "Duplicate" }
+                        </button>
+                        <button
+                            class="input button"
+                            data-main-hint="Delete"
+                            onclick={emitter.reform(move |_| AppEvent::RemoveSelection(editor_id))}
+                        >
+                            { "Delete" }
+                        </button>
+                        if pitched {
+                            <button
+                                class="input button"
+                                data-main-hint="Transpose up a semitone"
+                                onclick={emitter.reform(|_| AppEvent::Transpose(1))}
+                            >
+                                { "Transpose up" }
+                            </button>
+                            <button
+                                class="input button"
+                                data-main-hint="Transpose down a semitone"
+                                onclick={emitter.reform(|_| AppEvent::Transpose(-1))}
+                            >
+                                { "Transpose down" }
+                            </button>
+                            <button
+                                class="input button"
+                                data-main-hint="Quantize"
+                                onclick={emitter.reform(|_| {
+                                    AppEvent::Quantize { strength: R32::ONE, lengths: false }
+                                })}
+                            >
+                                { "Quantize" }
+                            </button>
+                        }
+                    </div>
+                </div>
+            },
+
             &Self::Export { format, ref filename, ref err_msg } => {
                 let (title, pattern, event): (_, _, fn(_) -> _) = match format {
                     ExportFormat::Wav => ("Export the project", ".*\\.wav", AppEvent::Export),
@@ -1,45 +1,92 @@
 use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
     img,
-    input::{AudioInputButton, Button, Slider, Tab},
+    input::{AudioInputButton, Button, Counter, Slider, Switch, Tab},
     popup::{ExportFormat, Popup},
-    sound::{AudioInput, Beats, FromBeats, Secs, Sound},
+    sound::{
+        AudioInput, Audition, Beats, FromBeats, FromSecs, NodeChain, Note, NoteSound, Secs, Sound,
+        TempoMap,
+    },
     visual::{GraphEditor, GraphPoint},
 };
 use macro_rules_attribute::apply;
 use std::{
     cmp::Ordering,
+    collections::{BTreeSet, HashMap},
     fmt::{self, Display, Formatter},
-    ops::{Deref, DerefMut, RangeBounds},
+    mem::replace,
+    ops::{Deref, DerefMut, Range, RangeBounds},
 };
 use wasm_bindgen::JsCast;
-use wasm_bindgen_futures::spawn_local;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
 use wavexp_utils::{
     cell::Shared,
-    error::Result,
+    diff::Diff,
+    error::{AppError, Result},
     ext::default,
-    ext::{ArrayExt, ResultExt, SliceExt},
+    ext::{ArrayExt, BoolExt, ResultExt, SliceExt},
     fallible,
     js::save_file,
     js::{document, now},
-    js_function, r64,
+    js_function, r32, r64,
     range::{RangeBoundsExt, RangeInclusiveV2, RangeV2},
     real::R32,
     real::R64,
     ArrayFrom,
 };
+use js_sys::Uint8Array;
 use web_sys::{
-    AnalyserNode, AudioContext, BaseAudioContext, GainNode, HtmlCanvasElement, HtmlInputElement,
-    OfflineAudioContext, Path2d,
+    AnalyserNode, AudioContext, AudioContextState, AudioNode, BaseAudioContext, DelayNode,
+    DynamicsCompressorNode, GainNode, HtmlCanvasElement, HtmlInputElement, OfflineAudioContext,
+    Path2d,
 };
 use yew::{AttrValue, Html, TargetCast};
 use yew_html_ext::html;
 
+/// What `AppEvent::StartPlay` does with the audio context in the given state.
+/// Browsers create audio contexts suspended until the user interacts with the page, & a suspended
+/// context's clock doesn't advance, so nothing can be scheduled on it until it's resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlaybackStart {
+    /// The context is running, the playback can be scheduled right away.
+    Schedule,
+    /// The playback is deferred until the context is resumed.
+    Resume,
+    /// The context couldn't be resumed, the user has to enable audio first.
+    Abort,
+}
+
+impl PlaybackStart {
+    const fn new(state: AudioContextState, resume_attempted: bool) -> Self {
+        match state {
+            AudioContextState::Running => Self::Schedule,
+            AudioContextState::Suspended if !resume_attempted => Self::Resume,
+            _ => Self::Abort,
+        }
+    }
+}
+
+#[test]
+fn playback_start_deferral() {
+    use AudioContextState::*;
+    assert_eq!(PlaybackStart::new(Running, false), PlaybackStart::Schedule);
+    assert_eq!(PlaybackStart::new(Running, true), PlaybackStart::Schedule);
+    // nothing is scheduled until the context reports `running`
+    assert_eq!(PlaybackStart::new(Suspended, false), PlaybackStart::Resume);
+    // resuming didn't help, no point in trying again
+    assert_eq!(PlaybackStart::new(Suspended, true), PlaybackStart::Abort);
+    assert_eq!(PlaybackStart::new(Closed, false), PlaybackStart::Abort);
+}
+
 #[derive(Debug, Clone)]
 pub struct SoundBlock {
     pub sound: Sound,
     pub layer: u32,
     pub offset: Beats,
+    /// the block is skipped during playback
+    pub muted: bool,
+    /// if any block in the composition is soloed, only the soloed blocks are played
+    pub soloed: bool,
 }
 
 impl Deref for SoundBlock {
@@ -75,6 +122,28 @@ impl Ord for SoundBlock {
     }
 }
 
+/// The sound isn't part of the delta: it's edited in place through its own actions, while the
+/// deltas only record the block being moved or flagged.
+impl Diff for SoundBlock {
+    type Delta = (u32, <Beats as Diff>::Delta, bool, bool);
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        (
+            self.layer.delta(&to.layer),
+            self.offset.delta(&to.offset),
+            self.muted.delta(&to.muted),
+            self.soloed.delta(&to.soloed),
+        )
+    }
+
+    fn patch(&mut self, (layer, offset, muted, soloed): Self::Delta) {
+        self.layer.patch(layer);
+        self.offset.patch(offset);
+        self.muted.patch(muted);
+        self.soloed.patch(soloed);
+    }
+}
+
 impl GraphPoint for SoundBlock {
     const EDITOR_NAME: &'static str = "Editor plane";
     const Y_BOUND: RangeV2<R64> = RangeV2 { start: r64!(0), end: R64::INFINITY };
@@ -86,7 +155,13 @@ impl GraphPoint for SoundBlock {
     type VisualContext = ();
 
     fn create(_: &GraphEditor<Self>, [offset, y]: [R64; 2]) -> Self {
-        Self { sound: default(), layer: y.into(), offset }
+        Self {
+            sound: default(),
+            layer: y.into(),
+            offset: Beats(offset),
+            muted: false,
+            soloed: false,
+        }
     }
 
     fn inner(&self) -> &Self::Inner {
@@ -104,11 +179,16 @@ impl GraphPoint for SoundBlock {
     }
 
     fn loc(&self) -> [R64; 2] {
-        [self.offset, self.layer.into()]
+        [self.offset.0, self.layer.into()]
+    }
+
+    fn span(&self, bps: R64) -> Result<[R64; 2]> {
+        let len = self.sound.total_len(bps)?;
+        Ok([self.offset.0, (self.offset + len).0])
     }
 
     fn móve(&mut self, delta: [R64; 2], _: bool) -> Result {
-        self.offset = r64!(0).max(self.offset + delta[0]);
+        self.offset = Beats::ZERO.max(self.offset + Beats(delta[0]));
         self.layer += u32::from(delta[1]);
         Ok(())
     }
@@ -127,7 +207,8 @@ impl GraphPoint for SoundBlock {
         _: Self::VisualContext,
     ) -> bool {
         area[1].map_bounds(u32::from).contains(&self.layer)
-            && (self.offset..=self.sound.len(sequencer.bps())?.max(r64!(0.1)) + self.offset)
+            && (self.offset.0
+                ..=(self.sound.len(sequencer.bps())?.max(Beats(r64!(0.1))) + self.offset).0)
                 .overlap(&area[0])
     }
 
@@ -141,7 +222,7 @@ impl GraphPoint for SoundBlock {
 
     fn on_redraw(
         editor: &mut GraphEditor<Self>,
-        ctx: ContextRef,
+        _: ContextRef,
         sequencer: &Sequencer,
         canvas_size: &[R64; 2],
         solid: &Path2d,
@@ -151,22 +232,40 @@ impl GraphPoint for SoundBlock {
         let step = canvas_size.div(editor.scale());
         let offset = R64::array_from(editor.offset());
         let bps = sequencer.bps();
-        for block in editor.data() {
+        for block in editor.points() {
             let [mut x, y] = block.loc().mul(step).sub(offset).map(|x| *x);
             let n_reps = block.rep_count().get();
             let w = *block.len(bps)? * *step[0];
+            let gap = *block.rep_gap() * *step[0];
             solid.rect(x, y, w, *step[1]);
             for _ in 1..n_reps {
-                x += w;
+                x += w + gap;
                 dotted.rect(x, y, w, *step[1])
             }
         }
+        Ok(())
+    }
 
-        if let PlaybackContext::All(start) = sequencer.playback_ctx() && start.is_finite() {
-            editor.force_redraw();
-            let x = (ctx.frame() - start).secs_to_beats(bps) * step[0] - offset[0];
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        dotted: &Path2d,
+        _: Self::VisualContext,
+    ) -> Result {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        if let Some(position) = sequencer.playback_position(ctx.frame()) {
+            editor.force_overlay_redraw();
+            let x = position.0 * step[0] - offset[0];
             solid.move_to(*x, 0.0);
             solid.line_to(*x, *canvas_size[1]);
+        } else if !sequencer.playback_ctx().all_playing() {
+            let x = sequencer.playhead().0 * step[0] - offset[0];
+            dotted.move_to(*x, 0.0);
+            dotted.line_to(*x, *canvas_size[1]);
         }
         Ok(())
     }
@@ -187,11 +286,26 @@ impl Display for SoundBlock {
 }
 
 impl SoundBlock {
+    /// Whether the block is to be played, given whether any block in the composition is soloed.
+    pub const fn audible(&self, soloing: bool) -> bool {
+        !self.muted && (!soloing || self.soloed)
+    }
+
+    /// The blocks of `blocks` that are to be played: the soloed ones if any block is soloed,
+    /// otherwise all of them, leaving out the muted ones either way.
+    pub fn audible_blocks(blocks: &[Self]) -> impl Iterator<Item = &Self> {
+        let soloing = blocks.iter().any(|x| x.soloed);
+        blocks.iter().filter(move |x| x.audible(soloing))
+    }
+
     pub fn tabs(&self, ctx: ContextRef) -> Html {
         let desc = &AttrValue::from(self.to_string() + ": Settings");
         match self.sound {
             Sound::None => html! { <Tab name="Choose Sound Type" {desc} selected=true /> },
-            Sound::Note { .. } | Sound::Noise { .. } | Sound::Custom { .. } => {
+            Sound::Note { .. }
+            | Sound::Noise { .. }
+            | Sound::Custom { .. }
+            | Sound::Drum { .. } => {
                 let setter = ctx.event_emitter().reform(AppEvent::SetTab);
                 let id = ctx.selected_tab();
                 html! {
@@ -214,6 +328,18 @@ impl SoundBlock {
                             setter={setter.reform(|_| 2)}
                             selected={id == 2}
                         />
+                        <Tab
+                            name="Filter"
+                            {desc}
+                            setter={setter.reform(|_| 3)}
+                            selected={id == 3}
+                        />
+                        <Tab
+                            name="Automation"
+                            {desc}
+                            setter={setter.reform(|_| 4)}
+                            selected={id == 4}
+                        />
                     </>
                 }
             }
@@ -221,6 +347,90 @@ impl SoundBlock {
     }
 }
 
+#[test]
+fn sound_block_mute_solo() {
+    let mut block = SoundBlock {
+        sound: Sound::None,
+        layer: 0,
+        offset: Beats::ZERO,
+        muted: false,
+        soloed: false,
+    };
+    assert!(block.audible(false));
+    assert!(!block.audible(true));
+    block.muted = true;
+    assert!(!block.audible(false));
+    block.soloed = true;
+    assert!(!block.audible(true));
+    block.muted = false;
+    assert!(block.audible(true));
+}
+
+#[test]
+fn metronome_clicks() {
+    let beats = |x| Beats(R64::new_or(R64::ZERO, x));
+    let clicks = |start, end| {
+        Sequencer::metronome_clicks(beats(start)..beats(end)).map(|x| *x).collect::<Vec<_>>()
+    };
+    assert_eq!(clicks(0.0, 4.0), [0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(clicks(0.5, 4.5).len(), 4);
+    assert!(clicks(1.2, 1.8).is_empty());
+}
+
+#[test]
+fn loop_wrapping() {
+    let beats = |x| Beats(R64::new_or(R64::ZERO, x));
+    let wrap = |position, playhead, region: [f64; 2]| {
+        *Sequencer::wrap_position(beats(position), beats(playhead), region.map(beats))
+    };
+    let region = [4.0, 8.0];
+    assert_eq!(wrap(6.0, 0.0, region), 6.0);
+    assert_eq!(wrap(8.0, 0.0, region), 4.0);
+    assert_eq!(wrap(13.5, 2.0, region), 5.5);
+    // the playback started after the loop region
+    assert_eq!(wrap(13.5, 9.0, region), 13.5);
+    // the loop region is disabled
+    assert_eq!(wrap(13.5, 0.0, [4.0; 2]), 13.5);
+}
+
+#[test]
+fn count_in_delay() {
+    let bps = r64!(2);
+    let tempo = TempoMap::new(bps);
+    let now = Secs(r64!(10));
+    let block = Beats(r64!(1));
+    let block_start = |playhead, count_in| {
+        Sequencer::playback_start(now, playhead, count_in, &tempo) + tempo.to_secs(block)
+    };
+    let delay = block_start(Beats::ZERO, Beats(r64!(4))) - block_start(Beats::ZERO, Beats::ZERO);
+    assert_eq!(delay.to_beats(bps), Beats(r64!(4)));
+    // the count-in is counted from the playhead, not from the start of the composition
+    assert_eq!(block_start(block, Beats(r64!(4))), now + Beats(r64!(4)).to_secs(bps));
+    assert_eq!(block_start(block, Beats::ZERO), now);
+}
+
+#[test]
+fn looped_tempo_timing() {
+    let beats = |x| Beats(R64::new_or(R64::ZERO, x));
+    let secs = |x| Secs(R64::new_or(R64::ZERO, x));
+    // 120 BPM until beat 2, then 60 BPM, so the loop over the first 4 beats lasts 3 seconds
+    let tempo = TempoMap::new(r64!(2)).set(beats(2.0), Some(R64::ONE));
+    let region = [Beats::ZERO, beats(4.0)];
+    let time = |position| Sequencer::position_time(beats(position), Beats::ZERO, region, &tempo);
+    let position = |time| Sequencer::time_position(secs(time), Beats::ZERO, region, &tempo);
+    assert_eq!(time(3.0), secs(2.0));
+    // the 2nd pass of the loop
+    assert_eq!(time(6.0), secs(4.0));
+    assert_eq!(position(4.0), beats(6.0));
+    // the 3rd pass
+    assert_eq!(time(9.0), secs(6.5));
+    assert_eq!(position(6.5), beats(9.0));
+    // the count-in is played in the tempo at the playhead
+    let time = |position| Sequencer::position_time(beats(position), beats(2.0), region, &tempo);
+    assert_eq!(time(0.0), secs(-1.0));
+    assert_eq!(Sequencer::time_position(secs(-1.0), beats(2.0), region, &tempo), Beats::ZERO);
+}
+
 #[derive(Debug, Clone)]
 pub enum PlaybackContext {
     None,
@@ -246,15 +456,307 @@ impl PlaybackContext {
     }
 }
 
+/// parameters of the echo on the master bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelayParams {
+    /// 0 disables the echo
+    pub time: Beats,
+    pub feedback: R32,
+    /// volume of the echoes relative to the dry signal
+    pub wet: R32,
+}
+
+impl Default for DelayParams {
+    fn default() -> Self {
+        Self { time: Beats::ZERO, feedback: r32!(0.4), wet: r32!(0.5) }
+    }
+}
+
+/// a feedback delay, i.e. an echo, on an audio bus
+#[derive(Debug, Clone)]
+pub struct Delay {
+    node: DelayNode,
+    feedback: GainNode,
+    wet: GainNode,
+}
+
+impl Delay {
+    /// the longest delay time supported
+    pub const MAX_TIME: Secs = Secs(r64!(5));
+    /// the echoes fade out only if the feedback's below 1, this leaves some headroom
+    pub const MAX_FEEDBACK: R32 = r32!(0.95);
+
+    /// Creates the delay's nodes in `ctx`, mixing the echoes of `input` into `output`, & registers
+    /// them in `nodes`. The dry signal isn't routed by the delay.
+    /// The delay is configured with `params` in tempo `bps`.
+    #[apply(fallible!)]
+    pub fn new(
+        ctx: &BaseAudioContext,
+        input: &AudioNode,
+        output: &AudioNode,
+        nodes: &mut NodeChain,
+        params: DelayParams,
+        bps: R64,
+    ) -> Self {
+        let node = nodes.add(ctx.create_delay_with_max_delay_time(*Self::MAX_TIME)?);
+        let feedback = nodes.add(ctx.create_gain()?);
+        let wet = nodes.add(ctx.create_gain()?);
+        input.connect_with_audio_node(&node)?;
+        node.connect_with_audio_node(&feedback)?.connect_with_audio_node(&node)?;
+        node.connect_with_audio_node(&wet)?.connect_with_audio_node(output)?;
+        let res = Self { node, feedback, wet };
+        res.set_params(params, bps);
+        res
+    }
+
+    /// Converts the delay time from beats to seconds in tempo `bps`, clamping it to `MAX_TIME`.
+    pub fn time_secs(time: Beats, bps: R64) -> Secs {
+        time.to_secs(bps).min(Self::MAX_TIME)
+    }
+
+    /// A delay time of 0 disables the delay.
+    pub fn set_params(&self, params: DelayParams, bps: R64) {
+        self.node.delay_time().set_value(*Self::time_secs(params.time, bps) as f32);
+        self.feedback.gain().set_value(*params.feedback.min(Self::MAX_FEEDBACK));
+        self.wet.gain().set_value((params.time > Beats::ZERO).choose(*params.wet, 0.0));
+    }
+}
+
+#[test]
+fn delay_time_conversion() {
+    assert_eq!(Delay::time_secs(Beats(r64!(1)), r64!(2)), Secs(r64!(0.5)));
+    assert_eq!(Delay::time_secs(Beats(r64!(0.75)), r64!(3)), Secs(r64!(0.25)));
+    assert_eq!(Delay::time_secs(Beats(r64!(0)), r64!(2)), Secs(r64!(0)));
+    assert_eq!(Delay::time_secs(Beats(r64!(100)), r64!(2)), Delay::MAX_TIME);
+}
+
+/// a parameter of the limiter on the master bus along with its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressorSetting {
+    /// in dB
+    Threshold(R32),
+    /// in dB
+    Knee(R32),
+    Ratio(R32),
+    Attack(Secs),
+    Release(Secs),
+}
+
+/// parameters of the `DynamicsCompressorNode` that limits the output of the master bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressorParams {
+    pub threshold: R32,
+    pub knee: R32,
+    pub ratio: R32,
+    pub attack: Secs,
+    pub release: Secs,
+}
+
+impl Default for CompressorParams {
+    fn default() -> Self {
+        Self {
+            threshold: r32!(0),
+            knee: r32!(0),
+            ratio: r32!(20),
+            attack: Secs(r64!(0.003)),
+            release: Secs::ONE,
+        }
+    }
+}
+
+impl CompressorParams {
+    /// Sets 1 of the parameters, clamping it to the range accepted by the node,
+    /// & returns the parameter's previous value.
+    pub fn set(&mut self, setting: CompressorSetting) -> CompressorSetting {
+        match setting {
+            CompressorSetting::Threshold(x) => CompressorSetting::Threshold(replace(
+                &mut self.threshold,
+                x.clamp(r32!(-100), R32::ZERO),
+            )),
+            CompressorSetting::Knee(x) => {
+                CompressorSetting::Knee(replace(&mut self.knee, x.clamp(R32::ZERO, r32!(40))))
+            }
+            CompressorSetting::Ratio(x) => {
+                CompressorSetting::Ratio(replace(&mut self.ratio, x.clamp(R32::ONE, r32!(20))))
+            }
+            CompressorSetting::Attack(x) => {
+                CompressorSetting::Attack(replace(&mut self.attack, x.clamp(Secs::ZERO, Secs::ONE)))
+            }
+            CompressorSetting::Release(x) => CompressorSetting::Release(replace(
+                &mut self.release,
+                x.clamp(Secs::ZERO, Secs::ONE),
+            )),
+        }
+    }
+
+    /// Creates the limiter in `ctx`, configured with these parameters.
+    pub fn create(&self, ctx: &BaseAudioContext) -> Result<DynamicsCompressorNode> {
+        self.create_with(|| Ok(ctx.create_dynamics_compressor()?))
+    }
+
+    /// Creates a limiter with `new`, configured with these parameters.
+    pub fn create_with<N: Limiter>(&self, new: impl FnOnce() -> Result<N>) -> Result<N> {
+        let res = new()?;
+        self.apply(&res);
+        Ok(res)
+    }
+
+    pub fn apply(&self, node: &impl Limiter) {
+        node.configure(
+            *self.threshold,
+            *self.knee,
+            *self.ratio,
+            *self.attack as f32,
+            *self.release as f32,
+        );
+    }
+}
+
+/// the node that limits the output of the master bus
+pub trait Limiter {
+    /// sets the threshold & the knee in dB, the ratio, & the attack & the release in seconds
+    fn configure(&self, threshold: f32, knee: f32, ratio: f32, attack: f32, release: f32);
+}
+
+impl Limiter for DynamicsCompressorNode {
+    fn configure(&self, threshold: f32, knee: f32, ratio: f32, attack: f32, release: f32) {
+        self.threshold().set_value(threshold);
+        self.knee().set_value(knee);
+        self.ratio().set_value(ratio);
+        self.attack().set_value(attack);
+        self.release().set_value(release);
+    }
+}
+
+/// a limiter that stores the parameters it's configured with
+#[cfg(test)]
+#[derive(Default)]
+struct ProbeLimiter(std::cell::Cell<[f32; 5]>);
+
+#[cfg(test)]
+impl Limiter for ProbeLimiter {
+    fn configure(&self, threshold: f32, knee: f32, ratio: f32, attack: f32, release: f32) {
+        self.0.set([threshold, knee, ratio, attack, release])
+    }
+}
+
+#[test]
+fn compressor_params() {
+    let mut params = CompressorParams::default();
+    assert_eq!(params.set(CompressorSetting::Ratio(r32!(4))), CompressorSetting::Ratio(r32!(20)));
+    let prev_threshold = params.set(CompressorSetting::Threshold(r32!(6)));
+    assert_eq!(prev_threshold, CompressorSetting::Threshold(R32::ZERO));
+    // the node that replaces the current one on the next playback is created from these
+    assert_eq!(params.ratio, r32!(4));
+    assert_eq!(params.threshold, R32::ZERO);
+    assert_eq!(CompressorParams { ratio: r32!(20), ..params }, CompressorParams::default());
+}
+
+#[test]
+fn limiter_recreation() {
+    let mut params = CompressorParams::default();
+    let old = params.create_with(|| Ok(ProbeLimiter::default())).unwrap_or_default();
+    assert_eq!(old.0.get(), [0.0, 0.0, 20.0, 0.003, 1.0]);
+    params.set(CompressorSetting::Ratio(r32!(4)));
+    params.set(CompressorSetting::Threshold(r32!(-12)));
+    params.apply(&old);
+    // on the next playback, the limiter's replaced by a new node, which must keep the parameters
+    let new = params.create_with(|| Ok(ProbeLimiter::default())).unwrap_or_default();
+    assert_eq!(new.0.get(), [-12.0, 0.0, 4.0, 0.003, 1.0]);
+    assert_eq!(new.0.get(), old.0.get());
+}
+
+/// volume & mute state of a layer of the composition, set in the mixer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerMix {
+    pub volume: R32,
+    pub muted: bool,
+}
+
+impl Default for LayerMix {
+    fn default() -> Self {
+        Self { volume: R32::ONE, muted: false }
+    }
+}
+
+impl LayerMix {
+    /// The gain applied to the blocks on the layer.
+    pub fn gain(&self) -> R32 {
+        self.muted.choose(R32::ZERO, self.volume)
+    }
+}
+
+/// Per-layer gain stage between the sound blocks & the master bus.
+#[derive(Debug, Default)]
+pub struct Mixer {
+    layers: HashMap<u32, LayerMix>,
+    /// created on demand when a block on the layer is first played through the current bus
+    layer_gains: HashMap<u32, GainNode>,
+}
+
+impl Mixer {
+    pub fn layer(&self, layer: u32) -> LayerMix {
+        self.layers.get(&layer).copied().unwrap_or_default()
+    }
+
+    /// Sets the state of `layer`, applying it to the playback in progress, & returns the previous
+    /// state.
+    pub fn set_layer(&mut self, layer: u32, mix: LayerMix) -> LayerMix {
+        if let Some(node) = self.layer_gains.get(&layer) {
+            node.gain().set_value(*mix.gain());
+        }
+        self.layers.insert(layer, mix).unwrap_or_default()
+    }
+
+    /// A mixer with the same settings but without any nodes, to be used with another audio
+    /// context.
+    pub fn settings(&self) -> Self {
+        Self { layers: self.layers.clone(), layer_gains: default() }
+    }
+
+    /// Returns the node through which the blocks on `layer` are to be played, connected to `bus`.
+    pub fn plug(&mut self, layer: u32, bus: &AudioNode) -> Result<GainNode> {
+        if let Some(node) = self.layer_gains.get(&layer) {
+            return Ok(node.clone());
+        }
+        let node = bus.context().create_gain()?;
+        node.gain().set_value(*self.layer(layer).gain());
+        node.connect_with_audio_node(bus)?;
+        self.layer_gains.insert(layer, node.clone());
+        Ok(node)
+    }
+
+    /// Disconnects & drops all the layer nodes, which are recreated with the next bus.
+    pub fn disconnect(&mut self) -> Result {
+        for (_, node) in self.layer_gains.drain() {
+            node.disconnect()?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn mixer_layers() {
+    let mut mixer = Mixer::default();
+    let prev = mixer.set_layer(2, LayerMix { volume: r32!(0.5), muted: false });
+    assert_eq!(prev, LayerMix::default());
+    let gains = [0, 1, 2, 2, 3].map(|layer| mixer.layer(layer).gain());
+    assert_eq!(gains, [R32::ONE, R32::ONE, r32!(0.5), r32!(0.5), R32::ONE]);
+    mixer.set_layer(2, LayerMix { volume: r32!(0.5), muted: true });
+    assert_eq!(mixer.layer(2).gain(), R32::ZERO);
+    assert_eq!(mixer.settings().layer(2), LayerMix { volume: r32!(0.5), muted: true });
+}
+
 pub struct Composition {
     pub pattern: Shared<GraphEditor<SoundBlock>>,
     pub inputs: Vec<Shared<AudioInput>>,
-    pub bps: Beats,
+    pub tempo: TempoMap,
+    pub delay: DelayParams,
 }
 
 impl Default for Composition {
     fn default() -> Self {
-        Composition { bps: r64!(2), pattern: default(), inputs: vec![] }
+        Composition { tempo: default(), pattern: default(), inputs: vec![], delay: default() }
     }
 }
 
@@ -265,14 +767,57 @@ pub struct Sequencer {
     gain: GainNode,
     ctx_created_at: Secs,
     playback_ctx: PlaybackContext,
+    metronome_enabled: bool,
+    beats_per_bar: u32,
+    /// How many beats of metronome clicks precede the playback of the whole composition.
+    count_in: Beats,
+    /// Separate from `gain` to keep the metronome's volume independent of the master volume.
+    metronome_gain: GainNode,
+    /// the nodes of the master bus, disconnected all at once when playback stops
+    bus: NodeChain,
+    /// the nodes applying the volume automation of the sound blocks, disconnected when playback
+    /// stops
+    automation: NodeChain,
+    /// The beat from which the metronome's clicks are yet to be scheduled.
+    next_click: Beats,
+    /// The beat from which the whole composition starts being played.
+    playhead: Beats,
+    /// The region of the composition that's played repeatedly, disabled if empty.
+    loop_start: Beats,
+    loop_end: Beats,
+    /// The beat, counted from the start of the composition without wrapping around the loop
+    /// region, until which the playback has been scheduled.
+    scheduled_until: Beats,
+    /// Limits the output of `gain` & `delay`, recreated along with `gain`.
+    compressor: DynamicsCompressorNode,
+    compressor_params: CompressorParams,
+    /// Echo on the master bus, fed by `gain`.
+    delay: Delay,
+    /// Whether the master bus is connected to the output.
+    bus_connected: bool,
+    /// Whether `audio_ctx` is being resumed before starting the playback.
+    resuming: bool,
+    /// Notes being played with the computer keyboard.
+    auditions: Vec<(Note, Audition)>,
+    mixer: Mixer,
+    /// How much the off-beat 8th notes are delayed, from 0 to 1, see `sound::swing_offset`.
+    swing: R32,
+    /// Sample rate of `audio_ctx`.
+    sample_rate: u32,
 }
 
 impl Sequencer {
+    /// Sample rate used until the live audio context is created.
     pub const SAMPLE_RATE: u32 = 44100;
     pub const CHANNEL_COUNT: u32 = 2;
+    const METRONOME_VOLUME: f32 = 0.3;
+    /// How far ahead of the playback the metronome's clicks & the repetitions of the loop region
+    /// are scheduled.
+    const LOOKAHEAD: Secs = Secs(r64!(0.1));
+    const CLICK_LEN: Secs = Secs(r64!(0.05));
 
     #[apply(fallible!)]
-    pub fn new() -> Self {
+    pub fn new(comp: Composition) -> Self {
         let audio_ctx =
             OfflineAudioContext::new_with_number_of_channels_and_length_and_sample_rate(
                 Self::CHANNEL_COUNT,
@@ -281,18 +826,101 @@ impl Sequencer {
             )?;
         let gain = audio_ctx.create_gain()?;
         gain.gain().set_value(0.2);
+        let compressor_params = CompressorParams::default();
+        let compressor = compressor_params.create(&audio_ctx)?;
+        gain.connect_with_audio_node(&compressor)?;
+        let mut bus = NodeChain::new();
+        let delay =
+            Delay::new(&audio_ctx, &gain, &compressor, &mut bus, comp.delay, comp.tempo.bps())?;
         Self {
-            comp: default(),
+            comp,
             analyser: audio_ctx.create_analyser()?,
+            metronome_gain: audio_ctx.create_gain()?,
+            bus,
+            automation: NodeChain::new(),
             gain,
+            compressor,
+            compressor_params,
+            delay,
+            bus_connected: false,
+            resuming: false,
+            auditions: vec![],
+            mixer: default(),
+            swing: R32::ZERO,
+            sample_rate: audio_ctx.sample_rate() as u32,
             audio_ctx: audio_ctx.into(),
             ctx_created_at: now()? / 1000,
             playback_ctx: PlaybackContext::None,
+            metronome_enabled: false,
+            beats_per_bar: 4,
+            count_in: Beats::ZERO,
+            next_click: Beats::ZERO,
+            playhead: Beats::ZERO,
+            loop_start: Beats::ZERO,
+            loop_end: Beats::ZERO,
+            scheduled_until: Beats::ZERO,
+        }
+    }
+
+    /// a sequencer that's usable outside of a browser, as long as no audio is played through it
+    #[cfg(test)]
+    pub fn test(comp: Composition) -> Self {
+        /// a handle to `undefined` in place of an object from the audio graph
+        fn stub<T: JsCast>() -> T {
+            wasm_bindgen::JsValue::UNDEFINED.unchecked_into()
+        }
+        Self {
+            comp,
+            audio_ctx: stub(),
+            analyser: stub(),
+            gain: stub(),
+            ctx_created_at: Secs::ZERO,
+            playback_ctx: PlaybackContext::None,
+            metronome_enabled: false,
+            beats_per_bar: 4,
+            count_in: Beats::ZERO,
+            metronome_gain: stub(),
+            bus: NodeChain::new(),
+            automation: NodeChain::new(),
+            next_click: Beats::ZERO,
+            playhead: Beats::ZERO,
+            loop_start: Beats::ZERO,
+            loop_end: Beats::ZERO,
+            scheduled_until: Beats::ZERO,
+            compressor: stub(),
+            compressor_params: default(),
+            delay: Delay { node: stub(), feedback: stub(), wet: stub() },
+            bus_connected: false,
+            resuming: false,
+            auditions: vec![],
+            mixer: default(),
+            swing: R32::ZERO,
+            sample_rate: Self::SAMPLE_RATE,
         }
     }
 
-    pub const fn bps(&self) -> Beats {
-        self.comp.bps
+    /// Sample rate of the audio context, to which all the imported audio is resampled.
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Initial tempo of the composition in beats per second.
+    pub const fn bps(&self) -> R64 {
+        self.comp.tempo.bps()
+    }
+    /// Initial tempo of the composition in beats per minute.
+    pub fn bpm(&self) -> R64 {
+        self.bps() * 60
+    }
+    /// Tempo of the composition at beat `beat` in beats per second.
+    pub fn bps_at(&self, beat: Beats) -> R64 {
+        self.comp.tempo.bps_at(beat)
+    }
+    pub const fn beats_per_bar(&self) -> u32 {
+        self.beats_per_bar
+    }
+    pub const fn composition(&self) -> &Composition {
+        &self.comp
     }
     pub const fn pattern(&self) -> &Shared<GraphEditor<SoundBlock>> {
         &self.comp.pattern
@@ -306,6 +934,122 @@ impl Sequencer {
     pub const fn playback_ctx(&self) -> &PlaybackContext {
         &self.playback_ctx
     }
+    pub const fn playhead(&self) -> Beats {
+        self.playhead
+    }
+    pub const fn loop_region(&self) -> [Beats; 2] {
+        [self.loop_start, self.loop_end]
+    }
+
+    /// Maps `position`, the number of beats since the start of the composition, onto the beat
+    /// being played when the playback started at beat `from` & loops over `[start, end]`.
+    /// The loop region is disabled if it's empty or if the playback started after its end.
+    pub fn wrap_position(position: Beats, from: Beats, [start, end]: [Beats; 2]) -> Beats {
+        if end <= start || from >= end || position < end {
+            return position;
+        }
+        start + (position - start) % (end - start)
+    }
+
+    /// The point in time at which the composition starts, if it's played from beat `playhead`
+    /// after a count-in of `count_in` beats starting at `now`.
+    pub fn playback_start(now: Secs, playhead: Beats, count_in: Beats, tempo: &TempoMap) -> Secs {
+        now + count_in.to_secs(tempo.bps_at(playhead)) - tempo.to_secs(playhead)
+    }
+
+    /// Whether the playback started at beat `from` loops over `[start, end]`, as per
+    /// `wrap_position`.
+    pub fn loops(from: Beats, [start, end]: [Beats; 2]) -> bool {
+        start < end && from < end
+    }
+
+    /// Point in time, relative to the start of the composition, at which beat `position` is
+    /// played when the playback started at beat `from` & loops over `region`, `position` being
+    /// counted without wrapping around the loop region.
+    /// The beats before `from` make up the count-in & are played in the tempo at beat `from`.
+    pub fn position_time(
+        position: Beats,
+        from: Beats,
+        region: [Beats; 2],
+        tempo: &TempoMap,
+    ) -> Secs {
+        let [loop_start, loop_end] = region;
+        if position < from {
+            tempo.to_secs(from) - (from - position).to_secs(tempo.bps_at(from))
+        } else if !Self::loops(from, region) || position < loop_end {
+            tempo.to_secs(position)
+        } else {
+            let loop_len = loop_end - loop_start;
+            let wrapped = Self::wrap_position(position, from, region);
+            let n_passes = (position - wrapped) / loop_len;
+            tempo.to_secs(wrapped) + tempo.duration(loop_start, loop_len) * n_passes
+        }
+    }
+
+    /// The inverse of `position_time`.
+    pub fn time_position(time: Secs, from: Beats, region: [Beats; 2], tempo: &TempoMap) -> Beats {
+        let [loop_start, loop_end] = region;
+        let from_time = tempo.to_secs(from);
+        if time < from_time {
+            from - (from_time - time).to_beats(tempo.bps_at(from))
+        } else if !Self::loops(from, region) || time < tempo.to_secs(loop_end) {
+            tempo.to_beats(time)
+        } else {
+            let loop_len = loop_end - loop_start;
+            let loop_duration = tempo.duration(loop_start, loop_len);
+            let n_passes = ((time - tempo.to_secs(loop_start)) / loop_duration).floor();
+            tempo.to_beats(time - loop_duration * n_passes) + loop_len * n_passes
+        }
+    }
+
+    /// The beat being played at `now`, a point in time of the page, if the whole composition is
+    /// being played; negative during the count-in.
+    pub fn playback_position(&self, now: Secs) -> Option<Beats> {
+        let PlaybackContext::All(start) = self.playback_ctx else { return None };
+        let region = self.loop_region();
+        let position = Self::time_position(now - start, self.playhead, region, &self.comp.tempo);
+        start.is_finite().then(|| Self::wrap_position(position, self.playhead, region))
+    }
+
+    /// Returns the beats within `range` on which the metronome clicks, i.e. all the whole ones.
+    pub fn metronome_clicks(range: Range<Beats>) -> impl Iterator<Item = Beats> {
+        (0..)
+            .map(move |i| range.start.ceil() + Beats(i.into()))
+            .take_while(move |beat| *beat < range.end)
+    }
+
+    /// Schedules a single click of the metronome at `at`, a point in time of the audio context.
+    /// Accented clicks, meant for the starts of bars, are pitched higher.
+    #[apply(fallible!)]
+    fn schedule_click(&self, at: Secs, accented: bool) {
+        let mut nodes = NodeChain::new();
+        let click = nodes.add(self.audio_ctx.create_gain()?);
+        let gain = click.gain();
+        gain.set_value_at_time(1.0, *at)?;
+        gain.exponential_ramp_to_value_at_time(0.001, *(at + Self::CLICK_LEN))?;
+
+        let click_core = nodes.add(self.audio_ctx.create_oscillator()?);
+        click_core.frequency().set_value(accented.choose(1760.0, 880.0));
+        click_core.connect_with_audio_node(&click)?.connect_with_audio_node(&self.metronome_gain)?;
+        click_core.start_with_when(*at)?;
+        click_core.stop_with_when(*(at + Self::CLICK_LEN))?;
+        click_core.set_onended(Some(&js_function!(|| nodes.teardown())));
+    }
+
+    /// Schedules the parts of the audible sound blocks that start within `span`, a range of beats,
+    /// `start` being the point in time of the audio context at which the composition starts.
+    #[apply(fallible!)]
+    fn schedule_blocks(&mut self, start: Secs, span: Range<Beats>) {
+        let pattern = self.comp.pattern.get()?;
+        for block in SoundBlock::audible_blocks(pattern.points()) {
+            let (offset, tempo) = (block.offset, &self.comp.tempo);
+            let plug = self.mixer.plug(block.layer, &self.gain)?;
+            let automated =
+                block.sound.automate_volume(&plug, start, offset, span.clone(), tempo)?;
+            let plug = self.automation.add_or(automated, plug);
+            block.sound.play(&plug, start, offset, span.clone(), tempo, self.swing)?;
+        }
+    }
 
     pub fn volume(&self) -> R32 {
         unsafe { R32::new_unchecked(self.gain.gain().value()) }
@@ -315,6 +1059,11 @@ impl Sequencer {
         &self.comp.inputs
     }
 
+    /// The layers with at least 1 sound block on them, in ascending order.
+    pub fn occupied_layers(&self) -> Result<BTreeSet<u32>> {
+        Ok(self.comp.pattern.get()?.points().iter().map(|x| x.layer).collect())
+    }
+
     pub fn tabs(&self, ctx: ContextRef) -> Html {
         let id = ctx.selected_tab();
         let setter = ctx.event_emitter();
@@ -332,6 +1081,18 @@ impl Sequencer {
                     setter={setter.reform(|_| AppEvent::SetTab(1))}
                     selected={id == 1}
                 />
+                <Tab
+                    name="Limiter"
+                    desc="Settings of the limiter on the master output"
+                    setter={setter.reform(|_| AppEvent::SetTab(2))}
+                    selected={id == 2}
+                />
+                <Tab
+                    name="Mixer"
+                    desc="Volumes of the layers of the composition"
+                    setter={setter.reform(|_| AppEvent::SetTab(3))}
+                    selected={id == 3}
+                />
             </>
         }
     }
@@ -341,21 +1102,94 @@ impl Sequencer {
         html! {
             match ctx.selected_tab() {
                 0 /* General */=> <div id="inputs">
-                    <Slider
+                    <Counter
                         key="tmp"
                         name="Tempo"
-                        setter={emitter.reform(AppEvent::Bpm)}
+                        default={r64!(120)}
+                        setter={emitter.reform(AppEvent::SetBpm)}
+                        fmt={|x: R64| format!("{x:.0}")}
                         min=30
-                        max=240
                         postfix="BPM"
-                        initial={self.comp.bps * 60}
+                        initial={self.bpm()}
                     />
+                    for &(at, bps) in self.comp.tempo.changes() {
+                        <div class="export-options">
+                            <Counter
+                                key={format!("tmp-{at}")}
+                                name={format!("Tempo from beat {at}")}
+                                setter={emitter.reform(move |x| {
+                                    AppEvent::SetTempoChange(at, Some(x))
+                                })}
+                                fmt={|x: R64| format!("{x:.0}")}
+                                min=30
+                                postfix="BPM"
+                                initial={bps * 60}
+                            />
+                            <Button
+                                name="Remove the tempo change"
+                                onclick={emitter.reform(move |_| {
+                                    AppEvent::SetTempoChange(at, None)
+                                })}
+                            >
+                                <img::Cross />
+                            </Button>
+                        </div>
+                    }
+                    <Button
+                        name="Add a tempo change"
+                        help="The tempo will change at the playhead"
+                        onclick={emitter.reform(|_| AppEvent::AddTempoChange)}
+                    >
+                        <img::Plus />
+                    </Button>
                     <Slider
                         key="gain"
                         name="Master volume"
                         setter={emitter.reform(|x| AppEvent::MasterVolume(R32::from(x)))}
                         initial={self.volume()}
                     />
+                    <Slider
+                        key="swing"
+                        name="Swing"
+                        default={R64::ZERO}
+                        setter={emitter.reform(|x| AppEvent::Swing(R32::from(x)))}
+                        initial={self.swing}
+                    />
+                    <Switch
+                        key="metronome"
+                        name="Metronome"
+                        setter={emitter.reform(|_| AppEvent::ToggleMetronome)}
+                        options={vec!["Off", "On"]}
+                        initial={self.metronome_enabled as usize}
+                    />
+                    <Counter
+                        key="count-in"
+                        name="Count-in"
+                        setter={emitter.reform(|x| AppEvent::SetCountIn(Beats(x)))}
+                        fmt={|x: R64| format!("{x:.0}")}
+                        postfix="Beats"
+                        initial={self.count_in}
+                    />
+                    <Counter
+                        key="delay-time"
+                        name="Echo Delay Time"
+                        setter={emitter.reform(|x| AppEvent::DelayTime(Beats(x)))}
+                        postfix="Beats"
+                        initial={self.comp.delay.time}
+                    />
+                    <Slider
+                        key="delay-feedback"
+                        name="Echo Feedback"
+                        setter={emitter.reform(|x| AppEvent::DelayFeedback(R32::from(x)))}
+                        max={R64::from(Delay::MAX_FEEDBACK)}
+                        initial={self.comp.delay.feedback}
+                    />
+                    <Slider
+                        key="delay-wet"
+                        name="Echo Level"
+                        setter={emitter.reform(|x| AppEvent::DelayWet(R32::from(x)))}
+                        initial={self.comp.delay.wet}
+                    />
                     <div class="export-options">
                         <Button
                             name="Export the project"
@@ -373,6 +1207,13 @@ impl Sequencer {
                         >
                             <span>{ "Export the project" }</span>
                         </Button>
+                        <Button
+                            name="Import a MIDI file"
+                            help="The notes of the file will be added as a new sound block"
+                            onclick={emitter.reform(|_| AppEvent::StartMidiImport)}
+                        >
+                            <span>{ "MIDI" }</span>
+                        </Button>
                         <Button
                             name="Save the project"
                             help="All the patterns & inputs will be saved as they are"
@@ -388,6 +1229,13 @@ impl Sequencer {
                         >
                             <img::FloppyDisk />
                         </Button>
+                        <Button
+                            name="Load a project"
+                            help="The current project will be replaced with one from a file"
+                            onclick={emitter.reform(|_| AppEvent::StartProjectLoad)}
+                        >
+                            <span>{ "Load" }</span>
+                        </Button>
                     </div>
                 </div>,
 
@@ -398,7 +1246,7 @@ impl Sequencer {
                             name={input.get().map_or_default(|x| AttrValue::from(x.name().clone()))}
                             {input}
                             {emitter}
-                            bps={self.comp.bps}
+                            bps={self.bps()}
                             class="extend-inner-button-panel"
                         />
                     }
@@ -410,39 +1258,235 @@ impl Sequencer {
                     </Button>
                 </div>,
 
+                2 /* Limiter */ => <div id="inputs">
+                    <Counter
+                        key="limiter-threshold"
+                        name="Limiter Threshold"
+                        setter={emitter.reform(|x| {
+                            AppEvent::SetCompressor(CompressorSetting::Threshold(R32::from(x)))
+                        })}
+                        coef={r64!(10)}
+                        min={r64!(-100)}
+                        postfix="dB"
+                        initial={self.compressor_params.threshold}
+                    />
+                    <Counter
+                        key="limiter-knee"
+                        name="Limiter Knee"
+                        setter={emitter.reform(|x| {
+                            AppEvent::SetCompressor(CompressorSetting::Knee(R32::from(x)))
+                        })}
+                        coef={r64!(10)}
+                        postfix="dB"
+                        initial={self.compressor_params.knee}
+                    />
+                    <Counter
+                        key="limiter-ratio"
+                        name="Limiter Ratio"
+                        setter={emitter.reform(|x| {
+                            AppEvent::SetCompressor(CompressorSetting::Ratio(R32::from(x)))
+                        })}
+                        coef={r64!(10)}
+                        min={R64::ONE}
+                        initial={self.compressor_params.ratio}
+                    />
+                    <Counter
+                        key="limiter-attack"
+                        name="Limiter Attack Time"
+                        setter={emitter.reform(|x| {
+                            AppEvent::SetCompressor(CompressorSetting::Attack(Secs(x)))
+                        })}
+                        fmt={|x| format!("{x:.3}")}
+                        logarithmic=true
+                        postfix="Secs"
+                        initial={self.compressor_params.attack}
+                    />
+                    <Counter
+                        key="limiter-release"
+                        name="Limiter Release Time"
+                        setter={emitter.reform(|x| {
+                            AppEvent::SetCompressor(CompressorSetting::Release(Secs(x)))
+                        })}
+                        fmt={|x| format!("{x:.3}")}
+                        logarithmic=true
+                        postfix="Secs"
+                        initial={self.compressor_params.release}
+                    />
+                </div>,
+
+                3 /* Mixer */ => <div id="inputs">
+                    for layer in self.occupied_layers().unwrap_or_default() {
+                        <Slider
+                            key={format!("layer-{layer}-vol")}
+                            name={format!("Layer {layer} Volume")}
+                            setter={emitter.reform(move |x| {
+                                AppEvent::LayerVolume(layer, R32::from(x))
+                            })}
+                            initial={self.mixer.layer(layer).volume}
+                        />
+                        <Button
+                            key={format!("layer-{layer}-mute")}
+                            name={format!("Mute layer {layer}")}
+                            class={self.mixer.layer(layer).muted.choose("selected", "")}
+                            help="Click to toggle whether the blocks on the layer are heard"
+                            onclick={emitter.reform(move |_| AppEvent::ToggleLayerMute(layer))}
+                        >
+                            <span>{ "M" }</span>
+                        </Button>
+                    }
+                </div>,
+
                 tab_id => <p style="color:red">{ format!("Invalid tab ID: {tab_id}") }</p>,
             }
         }
     }
 
+    /// Recreates the master bus, dropping everything scheduled to be played through the previous
+    /// one, & creates the live audio context if it hasn't been created yet.
+    #[apply(fallible!)]
+    fn reset_bus(&mut self) {
+        if self.audio_ctx.is_instance_of::<AudioContext>() {
+            self.disconnect_bus()?;
+        } else {
+            self.audio_ctx = AudioContext::new()?.into();
+            self.sample_rate = self.audio_ctx.sample_rate() as u32;
+            self.analyser = self.audio_ctx.create_analyser()?;
+            self.analyser.connect_with_audio_node(&self.audio_ctx.destination())?;
+            self.ctx_created_at = now()?;
+        }
+        let volume = self.volume();
+        self.gain = self.bus.add(self.audio_ctx.create_gain()?);
+        self.gain.gain().set_value(*volume);
+        self.compressor = self.bus.add(self.compressor_params.create(&self.audio_ctx)?);
+        self.compressor.connect_with_audio_node(&self.analyser)?;
+        self.gain.connect_with_audio_node(&self.compressor)?;
+        let bps = self.bps();
+        self.delay = Delay::new(
+            &self.audio_ctx,
+            &self.gain,
+            &self.compressor,
+            &mut self.bus,
+            self.comp.delay,
+            bps,
+        )?;
+        self.metronome_gain = self.bus.add(self.audio_ctx.create_gain()?);
+        self.metronome_gain.gain().set_value(Self::METRONOME_VOLUME);
+        self.metronome_gain.connect_with_audio_node(&self.analyser)?;
+        self.bus_connected = true;
+    }
+
+    #[apply(fallible!)]
+    fn disconnect_bus(&mut self) {
+        self.mixer.disconnect()?;
+        self.bus.teardown();
+        self.automation.teardown();
+        for (_, audition) in self.auditions.drain(..) {
+            audition.release()?;
+        }
+        self.bus_connected = false;
+    }
+
+    /// Whether the computer keyboard can play the selected sound block like a piano once the
+    /// piano is toggled on, which it can when the block has a Note sound.
+    #[apply(fallible!)]
+    pub fn piano_enabled(&self) -> bool {
+        let pattern = self.comp.pattern.get()?;
+        let Some(&id) = pattern.selection().first() else { return Ok(false) };
+        matches!(pattern.points().get(id)?.sound, Sound::Note(_))
+    }
+
+    /// Replaces the composition with `comp`, stopping the playback if there's any.
+    pub fn set_composition(&mut self, comp: Composition, ctx: ContextMut) {
+        self.comp = comp;
+        self.delay.set_params(self.comp.delay, self.bps());
+        if self.playback_ctx.playing() {
+            ctx.emit_event(AppEvent::StopPlay)
+        }
+        ctx.emit_event(AppEvent::RedrawEditorPlane);
+    }
+
+    /// Sets the tempo of the composition & redraws the editor planes, whose contents' lengths might
+    /// be tempo-dependent.
+    /// If the whole composition is being played, its playback is restarted, since the already
+    /// scheduled sounds can't be retimed.
+    fn set_tempo(&mut self, tempo: TempoMap, ctx: ContextMut) {
+        self.comp.tempo = tempo;
+        self.delay.set_params(self.comp.delay, self.bps());
+        ctx.emit_event(AppEvent::RedrawEditorPlane);
+        if self.playback_ctx.all_playing() {
+            ctx.emit_event(AppEvent::PreparePlay(None))
+        }
+    }
+
+    /// Sets the swing of the playback, restarting it if the whole composition is being played,
+    /// since the already scheduled sounds can't be retimed.
+    fn set_swing(&mut self, swing: R32, ctx: ContextMut) {
+        self.swing = swing;
+        if self.playback_ctx.all_playing() {
+            ctx.emit_event(AppEvent::PreparePlay(None))
+        }
+    }
+
+    /// Moves the playhead to `to`.
+    /// If the whole composition is being played, its playback is restarted from there.
+    #[apply(fallible!)]
+    pub fn seek(&mut self, to: Beats, ctx: ContextMut) {
+        self.playhead = to.max(Beats::ZERO);
+        self.comp.pattern.get_mut()?.force_redraw();
+        if self.playback_ctx.all_playing() {
+            ctx.emit_event(AppEvent::PreparePlay(None))
+        }
+    }
+
     #[apply(fallible!)]
     pub fn handle_event(&mut self, event: &AppEvent, mut ctx: ContextMut) {
         match *event {
             AppEvent::PreparePlay(ref input) => {
-                if self.audio_ctx.is_instance_of::<AudioContext>() {
-                    self.playback_ctx = PlaybackContext::None;
-                    self.gain.disconnect()?;
-                } else {
-                    self.audio_ctx = AudioContext::new()?.into();
-                    self.analyser = self.audio_ctx.create_analyser()?;
-                    self.analyser.connect_with_audio_node(&self.audio_ctx.destination())?;
-                    self.ctx_created_at = now()?;
-                }
-                if let Some(input) = input {
-                    input.get_mut()?.bake(self.comp.bps)?;
+                self.playback_ctx = PlaybackContext::None;
+                self.reset_bus()?;
+                let resized = if let Some(input) = input {
+                    input.get_mut()?.bake(self.bps())?
                 } else {
-                    for mut block in self.comp.pattern.get_mut()?.iter_data_mut() {
-                        block.inner().prepare(self.comp.bps)?;
+                    let mut resized = false;
+                    for mut block in self.comp.pattern.get_mut()?.points_mut().iter_mut() {
+                        resized |= block.inner().prepare(self.bps())?;
                     }
+                    resized
+                };
+                if resized {
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
                 }
-                let volume = self.volume();
-                self.gain = self.audio_ctx.create_gain()?;
-                self.gain.gain().set_value(*volume);
-                self.gain.connect_with_audio_node(&self.analyser)?;
                 ctx.emit_event(AppEvent::StartPlay(input.clone()))
             }
 
             AppEvent::StartPlay(ref input) => {
+                let resume_attempted = replace(&mut self.resuming, false);
+                match PlaybackStart::new(self.audio_ctx.state(), resume_attempted) {
+                    PlaybackStart::Schedule => (),
+                    PlaybackStart::Resume => {
+                        self.resuming = true;
+                        let resumed = JsFuture::from(self.audio_ctx.resume()?);
+                        let emitter = ctx.event_emitter().clone();
+                        let input = input.clone();
+                        // if the context is still suspended, the next `StartPlay` shows the hint
+                        spawn_local(async move {
+                            _ = resumed.await;
+                            emitter.emit(AppEvent::StartPlay(input))
+                        });
+                        return Ok(());
+                    }
+                    PlaybackStart::Abort => {
+                        ctx.emit_event(AppEvent::SetHint(
+                            "Click to enable audio".into(),
+                            "The browser blocks audio playback until the page is interacted with"
+                                .into(),
+                        ));
+                        ctx.emit_event(AppEvent::StopPlay);
+                        return Ok(());
+                    }
+                }
+                // the context's clock was paused while it was suspended
+                self.ctx_created_at = now()? - self.audio_ctx.current_time().try_into()?;
                 let now = now()? - self.ctx_created_at;
                 if let Some(input) = input {
                     let player = self.audio_ctx.create_buffer_source()?;
@@ -457,18 +1501,89 @@ impl Sequencer {
                     player.set_onended(Some(&js_function!(|| emitter.emit(AppEvent::StopPlay))));
                     player.start()?;
                 } else {
-                    self.playback_ctx = PlaybackContext::All(now + self.ctx_created_at);
-                    let mut pattern = self.comp.pattern.get_mut()?;
-                    for mut block in pattern.iter_data_mut() {
-                        let offset = block.offset.to_secs(self.comp.bps);
-                        block.inner().play(&self.gain, now, offset, self.comp.bps)?;
-                    }
+                    let tempo = &self.comp.tempo;
+                    let start = Self::playback_start(now, self.playhead, self.count_in, tempo);
+                    self.playback_ctx = PlaybackContext::All(start + self.ctx_created_at);
+                    self.next_click = self.playhead - self.count_in;
+                    let region = self.loop_region();
+                    self.scheduled_until = if Self::loops(self.playhead, region) {
+                        region[1]
+                    } else {
+                        Beats::INFINITY
+                    };
+                    self.schedule_blocks(start, self.playhead..self.scheduled_until)?;
                 }
             }
 
             AppEvent::StopPlay => {
                 self.playback_ctx = PlaybackContext::None;
-                self.gain.disconnect()?;
+                self.disconnect_bus()?;
+            }
+
+            AppEvent::PianoKeyDown(note) => {
+                if self.auditions.iter().any(|x| x.0 == note) {
+                    return Ok(());
+                }
+                if !self.bus_connected {
+                    self.reset_bus()?;
+                }
+                let pattern = self.comp.pattern.get()?;
+                let Some(&id) = pattern.selection().first() else { return Ok(()) };
+                if let Sound::Note(sound) = &pattern.points().get(id)?.sound {
+                    let audition = sound.audition(note, &self.gain, self.bps())?;
+                    self.auditions.push((note, audition));
+                }
+            }
+
+            AppEvent::PianoKeyUp(note) => {
+                if let Some(i) = self.auditions.iter().position(|x| x.0 == note) {
+                    self.auditions.swap_remove(i).1.release()?
+                }
+            }
+
+            AppEvent::Frame(_) => {
+                if let PlaybackContext::All(start) = self.playback_ctx && start.is_finite() {
+                    let (tempo, playhead) = (self.comp.tempo.clone(), self.playhead);
+                    let region = self.loop_region();
+                    let [loop_start, loop_end] = region;
+                    let time = |position| Self::position_time(position, playhead, region, &tempo);
+                    let until = ctx.frame() - start + Self::LOOKAHEAD;
+                    let until = Self::time_position(until, playhead, region, &tempo);
+                    let start = start - self.ctx_created_at;
+                    while self.scheduled_until <= until {
+                        // the loop region is scheduled again, as if it started at `scheduled_until`
+                        let pass_start =
+                            start + time(self.scheduled_until) - tempo.to_secs(loop_start);
+                        self.schedule_blocks(pass_start, loop_start..loop_end)?;
+                        self.scheduled_until += loop_end - loop_start;
+                    }
+                    // during the count-in, the metronome clicks even if it's disabled
+                    let clicks_until =
+                        if self.metronome_enabled { until } else { until.min(self.playhead) };
+                    for beat in Self::metronome_clicks(self.next_click..clicks_until) {
+                        let at = start + time(beat);
+                        let beat_played = Self::wrap_position(beat, self.playhead, region);
+                        let accented = beat_played.rem_euclid(self.beats_per_bar as f64) == 0.0;
+                        self.schedule_click(at, accented)?;
+                    }
+                    self.next_click = self.next_click.max(clicks_until);
+                }
+                self.comp.pattern.get_mut()?.handle_event(event, ctx, self, || ())?
+            }
+
+            AppEvent::ToggleMetronome => self.metronome_enabled.flip(),
+
+            AppEvent::SetCountIn(to) => self.count_in = to.max(Beats::ZERO).round(),
+
+            AppEvent::Seek(to) => self.seek(to, ctx)?,
+
+            AppEvent::SetLoop([start, end]) => {
+                self.loop_start = start.max(Beats::ZERO);
+                self.loop_end = end.max(self.loop_start);
+                ctx.emit_event(AppEvent::RedrawEditorPlane);
+                if self.playback_ctx.all_playing() {
+                    ctx.emit_event(AppEvent::PreparePlay(None))
+                }
             }
 
             AppEvent::StartInputAdd => {
@@ -481,9 +1596,75 @@ impl Sequencer {
                 temp.click();
             }
 
+            AppEvent::StartMidiImport => {
+                let temp = document().create_element("input")?.unchecked_into::<HtmlInputElement>();
+                temp.set_type("file");
+                temp.set_accept(".mid,.midi");
+                let emitter = ctx.event_emitter().clone();
+                temp.set_onchange(Some(&js_function!(|e| emitter.emit(AppEvent::MidiUploaded(e)))));
+                temp.click();
+            }
+
+            AppEvent::StartProjectLoad => {
+                let temp = document().create_element("input")?.unchecked_into::<HtmlInputElement>();
+                temp.set_type("file");
+                temp.set_accept(".wavexp");
+                let emitter = ctx.event_emitter().clone();
+                temp.set_onchange(Some(
+                    &js_function!(|e| emitter.emit(AppEvent::ProjectUploaded(e))),
+                ));
+                temp.click();
+            }
+
+            AppEvent::ProjectUploaded(ref e) => {
+                let target: HtmlInputElement = e.target_dyn_into()?;
+                let emitter = ctx.event_emitter().clone();
+
+                let file = target.files().and_then(|x| x.get(0))?;
+                spawn_local(async move {
+                    let buffer = JsFuture::from(file.array_buffer()).await.map_err(AppError::from);
+                    let Some(buffer) = buffer.report() else {
+                        return;
+                    };
+                    let bytes = Uint8Array::new(&buffer).to_vec();
+                    emitter.emit(AppEvent::LoadProject(bytes.into()))
+                })
+            }
+
+            AppEvent::MidiUploaded(ref e) => {
+                let target: HtmlInputElement = e.target_dyn_into()?;
+                let emitter = ctx.event_emitter().clone();
+
+                let file = target.files().and_then(|x| x.get(0))?;
+                spawn_local(async move {
+                    let buffer = JsFuture::from(file.array_buffer()).await.map_err(AppError::from);
+                    let Some(buffer) = buffer.report() else {
+                        return;
+                    };
+                    let bytes = Uint8Array::new(&buffer).to_vec();
+                    emitter.emit(AppEvent::ImportMidi(bytes.into()))
+                })
+            }
+
+            AppEvent::ImportMidi(ref bytes) => {
+                let sound = NoteSound::from_midi(bytes, self.bps())?;
+                let mut pattern = self.comp.pattern.get_mut()?;
+                let layer = pattern.points().iter().map(|x| x.layer + 1).max().unwrap_or(0);
+                let block = SoundBlock {
+                    sound: Sound::Note(sound),
+                    layer,
+                    offset: Beats::ZERO,
+                    muted: false,
+                    soloed: false,
+                };
+                let action = pattern.insert_points([block]);
+                ctx.register_action(action)?
+            }
+
             AppEvent::Export(ref filename) => {
                 let filename = filename.clone();
-                let wav = self.comp.export(self.volume())?;
+                let mixer = self.mixer.settings();
+                let wav = self.comp.export(self.volume(), mixer, self.swing, self.sample_rate)?;
                 spawn_local(async move {
                     let Some(wav) = wav.await.report() else {
                         return;
@@ -521,10 +1702,102 @@ impl Sequencer {
                 gain.set_value(*to);
             }
 
-            AppEvent::Bpm(mut to) => {
-                to /= 60;
-                ctx.register_action(EditorAction::SetTempo { from: self.comp.bps, to })?;
-                self.comp.bps = to
+            AppEvent::LayerVolume(layer, volume) => {
+                let to = LayerMix { volume, ..self.mixer.layer(layer) };
+                let from = self.mixer.set_layer(layer, to);
+                ctx.register_action(EditorAction::SetLayerMix { layer, from, to })?
+            }
+
+            AppEvent::ToggleLayerMute(layer) => {
+                let from = self.mixer.layer(layer);
+                let to = LayerMix { muted: !from.muted, ..from };
+                self.mixer.set_layer(layer, to);
+                ctx.register_action(EditorAction::SetLayerMix { layer, from, to })?
+            }
+
+            AppEvent::Swing(to) => {
+                ctx.register_action(EditorAction::SetSwing {
+                    from: replace(&mut self.swing, to),
+                    to,
+                })?;
+                self.set_swing(to, ctx)
+            }
+
+            AppEvent::DelayTime(to) => {
+                ctx.register_action(EditorAction::SetDelayTime {
+                    from: replace(&mut self.comp.delay.time, to),
+                    to,
+                })?;
+                self.delay.set_params(self.comp.delay, self.bps())
+            }
+
+            AppEvent::DelayFeedback(to) => {
+                let to = to.min(Delay::MAX_FEEDBACK);
+                ctx.register_action(EditorAction::SetDelayFeedback {
+                    from: replace(&mut self.comp.delay.feedback, to),
+                    to,
+                })?;
+                self.delay.set_params(self.comp.delay, self.bps())
+            }
+
+            AppEvent::DelayWet(to) => {
+                let to = to.clamp(R32::ZERO, R32::ONE);
+                ctx.register_action(EditorAction::SetDelayWet {
+                    from: replace(&mut self.comp.delay.wet, to),
+                    to,
+                })?;
+                self.delay.set_params(self.comp.delay, self.bps())
+            }
+
+            AppEvent::SetCompressor(to) => {
+                let from = self.compressor_params.set(to);
+                ctx.register_action(EditorAction::SetCompressor { from, to })?;
+                self.compressor_params.apply(&self.compressor)
+            }
+
+            AppEvent::SetBpm(to) => {
+                let to = to / 60;
+                ctx.register_action(EditorAction::SetTempo { from: self.bps(), to })?;
+                self.set_tempo(self.comp.tempo.set(Beats::ZERO, Some(to)), ctx)
+            }
+
+            AppEvent::AddTempoChange => {
+                let from = self.comp.tempo.clone();
+                let after_last = from.changes().last().map_or(Beats::ZERO, |x| x.0)
+                    + Beats(self.beats_per_bar.into());
+                let at = if self.playhead > Beats::ZERO
+                    && from.changes().iter().all(|x| x.0 != self.playhead)
+                {
+                    self.playhead
+                } else {
+                    after_last
+                };
+                let to = from.set(at, Some(self.bps_at(at)));
+                ctx.register_action(EditorAction::SetTempoMap { from, to: to.clone() })?;
+                self.set_tempo(to, ctx)
+            }
+
+            AppEvent::SetTempoChange(at, bpm) => {
+                let from = self.comp.tempo.clone();
+                let to = from.set(at, bpm.map(|x| x / 60));
+                ctx.register_action(EditorAction::SetTempoMap { from, to: to.clone() })?;
+                self.set_tempo(to, ctx)
+            }
+
+            AppEvent::ToggleMute(block_id) => {
+                let mut pattern = self.comp.pattern.get_mut()?;
+                let mut blocks = pattern.points_mut();
+                // Safety: whether a block is muted doesn't affect its placement
+                unsafe { blocks.get_mut(block_id)?.unlock() }.muted.flip();
+                ctx.register_action(EditorAction::ToggleMute { block_id })?
+            }
+
+            AppEvent::ToggleSolo(block_id) => {
+                let mut pattern = self.comp.pattern.get_mut()?;
+                let mut blocks = pattern.points_mut();
+                // Safety: whether a block is soloed doesn't affect its placement
+                unsafe { blocks.get_mut(block_id)?.unlock() }.soloed.flip();
+                ctx.register_action(EditorAction::ToggleSolo { block_id })?
             }
 
             AppEvent::RedrawEditorPlane => self.comp.pattern.get_mut()?.force_redraw(),
@@ -532,14 +1805,59 @@ impl Sequencer {
             AppEvent::Undo(ref actions) => {
                 for action in actions.iter() {
                     match *action {
-                        EditorAction::SetTempo { from, .. } => self.comp.bps = from,
+                        EditorAction::SetTempo { from, .. } => {
+                            let tempo = self.comp.tempo.set(Beats::ZERO, Some(from));
+                            self.set_tempo(tempo, ctx.as_mut())
+                        }
+
+                        EditorAction::SetTempoMap { ref from, .. } => {
+                            self.set_tempo(from.clone(), ctx.as_mut())
+                        }
 
                         EditorAction::SetMasterVolume { from, .. } => {
                             self.gain.gain().set_value(*from)
                         }
 
+                        EditorAction::SetLayerMix { layer, from, .. } => {
+                            self.mixer.set_layer(layer, from);
+                        }
+
+                        EditorAction::SetSwing { from, .. } => self.set_swing(from, ctx.as_mut()),
+
+                        EditorAction::SetDelayTime { from, .. } => {
+                            self.comp.delay.time = from;
+                            self.delay.set_params(self.comp.delay, self.bps())
+                        }
+
+                        EditorAction::SetDelayFeedback { from, .. } => {
+                            self.comp.delay.feedback = from;
+                            self.delay.set_params(self.comp.delay, self.bps())
+                        }
+
+                        EditorAction::SetDelayWet { from, .. } => {
+                            self.comp.delay.wet = from;
+                            self.delay.set_params(self.comp.delay, self.bps())
+                        }
+
+                        EditorAction::SetCompressor { from, .. } => {
+                            self.compressor_params.set(from);
+                            self.compressor_params.apply(&self.compressor)
+                        }
+
                         EditorAction::AddInput(_) => _ = self.comp.inputs.pop(),
 
+                        EditorAction::ToggleMute { block_id } => {
+                            let mut pattern = self.comp.pattern.get_mut()?;
+                            let mut blocks = pattern.points_mut();
+                            unsafe { blocks.get_mut(block_id)?.unlock() }.muted.flip()
+                        }
+
+                        EditorAction::ToggleSolo { block_id } => {
+                            let mut pattern = self.comp.pattern.get_mut()?;
+                            let mut blocks = pattern.points_mut();
+                            unsafe { blocks.get_mut(block_id)?.unlock() }.soloed.flip()
+                        }
+
                         _ => (),
                     }
                 }
@@ -549,12 +1867,57 @@ impl Sequencer {
             AppEvent::Redo(ref actions) => {
                 for action in actions.iter() {
                     match *action {
-                        EditorAction::SetTempo { to, .. } => self.comp.bps = to,
+                        EditorAction::SetTempo { to, .. } => {
+                            let tempo = self.comp.tempo.set(Beats::ZERO, Some(to));
+                            self.set_tempo(tempo, ctx.as_mut())
+                        }
+
+                        EditorAction::SetTempoMap { ref to, .. } => {
+                            self.set_tempo(to.clone(), ctx.as_mut())
+                        }
 
                         EditorAction::SetMasterVolume { to, .. } => self.gain.gain().set_value(*to),
 
+                        EditorAction::SetLayerMix { layer, to, .. } => {
+                            self.mixer.set_layer(layer, to);
+                        }
+
+                        EditorAction::SetSwing { to, .. } => self.set_swing(to, ctx.as_mut()),
+
+                        EditorAction::SetDelayTime { to, .. } => {
+                            self.comp.delay.time = to;
+                            self.delay.set_params(self.comp.delay, self.bps())
+                        }
+
+                        EditorAction::SetDelayFeedback { to, .. } => {
+                            self.comp.delay.feedback = to;
+                            self.delay.set_params(self.comp.delay, self.bps())
+                        }
+
+                        EditorAction::SetDelayWet { to, .. } => {
+                            self.comp.delay.wet = to;
+                            self.delay.set_params(self.comp.delay, self.bps())
+                        }
+
+                        EditorAction::SetCompressor { to, .. } => {
+                            self.compressor_params.set(to);
+                            self.compressor_params.apply(&self.compressor)
+                        }
+
                         EditorAction::AddInput(ref input) => self.comp.inputs.push(input.clone()),
 
+                        EditorAction::ToggleMute { block_id } => {
+                            let mut pattern = self.comp.pattern.get_mut()?;
+                            let mut blocks = pattern.points_mut();
+                            unsafe { blocks.get_mut(block_id)?.unlock() }.muted.flip()
+                        }
+
+                        EditorAction::ToggleSolo { block_id } => {
+                            let mut pattern = self.comp.pattern.get_mut()?;
+                            let mut blocks = pattern.points_mut();
+                            unsafe { blocks.get_mut(block_id)?.unlock() }.soloed.flip()
+                        }
+
                         _ => (),
                     }
                 }
@@ -0,0 +1,228 @@
+use crate::{
+    ctx::{AppEvent, ContextMut, ContextRef},
+    input::Cursor,
+    sequencer::Sequencer,
+    sound::Beats,
+    visual::{GraphEditor, GraphPoint},
+};
+use macro_rules_attribute::apply;
+use std::{mem::transmute, ops::Range};
+use wavexp_utils::{
+    diff::Diff,
+    error::Result,
+    ext::ArrayExt,
+    fallible, r32, r64,
+    range::{RangeBoundsExt, RangeInclusiveV2, RangeV2},
+    real::R32,
+    real::R64,
+    ArrayFrom,
+};
+use web_sys::Path2d;
+
+/// A breakpoint of an automation curve, the curve being linearly interpolated between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AutomationPoint {
+    /// the beat at which the value is reached, relative to the start of the sound block
+    pub time: Beats,
+    /// value of the automated parameter, from 0 to 1
+    pub value: R32,
+}
+
+impl Diff for AutomationPoint {
+    type Delta = (<Beats as Diff>::Delta, <R32 as Diff>::Delta);
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        (self.time.delta(&to.time), self.value.delta(&to.value))
+    }
+
+    fn patch(&mut self, (time, value): Self::Delta) {
+        self.time.patch(time);
+        self.value.patch(value);
+    }
+}
+
+impl GraphPoint for AutomationPoint {
+    const EDITOR_NAME: &'static str = "Volume Automation Editor";
+    const Y_BOUND: RangeV2<R64> = RangeV2 { start: r64!(0), end: r64!(1) };
+    const SCALE_Y_BOUND: RangeV2<R64> = RangeV2::unit(r64!(1.2));
+    const OFFSET_Y_BOUND: RangeV2<R64> = RangeV2::unit(r64!(-0.1));
+    const Y_SNAP: R64 = r64!(0.05);
+
+    type Inner = ();
+    type Y = R32;
+    /// sound block offset
+    type VisualContext = Beats;
+
+    fn create(_: &GraphEditor<Self>, [time, y]: [R64; 2]) -> Self {
+        Self {
+            time: Beats(time.max(R64::ZERO)),
+            value: R32::from(R64::ONE - y).clamp(r32!(0), r32!(1)),
+        }
+    }
+
+    fn inner(&self) -> &Self::Inner {
+        &()
+    }
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        unsafe { transmute(self) }
+    }
+
+    fn y(&self) -> &Self::Y {
+        &self.value
+    }
+    fn y_mut(&mut self) -> &mut Self::Y {
+        &mut self.value
+    }
+
+    /// The Y axis is flipped so that louder points are drawn higher.
+    fn loc(&self) -> [R64; 2] {
+        [self.time.0, R64::ONE - R64::from(self.value)]
+    }
+
+    fn móve(&mut self, delta: [R64; 2], _: bool) -> Result {
+        self.time = Beats::ZERO.max(self.time + Beats(delta[0]));
+        self.value = (self.value - R32::from(delta[1])).clamp(r32!(0), r32!(1));
+        Ok(())
+    }
+
+    fn move_point(point: &mut [R64; 2], delta: [R64; 2], _: bool) {
+        point[0] = R64::ZERO.max(point[0] + delta[0]);
+        point[1] = (point[1] + delta[1]).clamp(R64::ZERO, R64::ONE)
+    }
+
+    #[apply(fallible!)]
+    fn in_hitbox(
+        &self,
+        area: &[RangeInclusiveV2<R64>; 2],
+        _: ContextRef,
+        _: &Sequencer,
+        _: Self::VisualContext,
+    ) -> bool {
+        let [x, y] = self.loc();
+        let [w, h] = Self::HITBOX_SIZE.map(|x| x / 2u8);
+        (x - w..=x + w).overlap(&area[0]) && (y - h..=y + h).overlap(&area[1])
+    }
+
+    fn fmt_loc(loc: [R64; 2]) -> String {
+        format!("{:.3}, {:.0}%", loc[0], (R64::ONE - loc[1]) * 100u8)
+    }
+
+    #[apply(fallible!)]
+    fn on_redraw(
+        editor: &mut GraphEditor<Self>,
+        _: ContextRef,
+        _: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        dotted: &Path2d,
+        _: Self::VisualContext,
+    ) {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        let [w, h] = Self::HITBOX_SIZE.mul(step);
+        for (i, point) in editor.points().iter().enumerate() {
+            let [x, y] = point.loc().mul(step).sub(offset);
+            solid.rect(*x - *w / 2.0, *y - *h / 2.0, *w, *h);
+            if i == 0 {
+                dotted.move_to(*x, *y);
+            } else {
+                dotted.line_to(*x, *y);
+            }
+        }
+    }
+
+    #[apply(fallible!)]
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        _: &Path2d,
+        sb_offset: Self::VisualContext,
+    ) {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        if let Some(position) = sequencer.playback_position(ctx.frame())
+            && position >= sb_offset
+        {
+            editor.force_overlay_redraw();
+            let x = (position - sb_offset).0 * step[0] - offset[0];
+            solid.move_to(*x, 0.0);
+            solid.line_to(*x, *canvas_size[1]);
+        }
+    }
+
+    #[apply(fallible!)]
+    fn on_move(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextMut,
+        _: Cursor,
+        _: [R64; 2],
+        _: Option<usize>,
+    ) {
+        editor.force_redraw();
+        ctx.emit_event(AppEvent::RedrawEditorPlane)
+    }
+}
+
+impl AutomationPoint {
+    /// size of the square drawn for the point in user coordinates, which is also its hitbox
+    const HITBOX_SIZE: [R64; 2] = [r64!(0.25), r64!(0.05)];
+}
+
+/// Value of the automation curve going through `points` at `at`, a beat relative to the start of
+/// the sound block, or `None` if there are no points. The value is held constant before the 1st
+/// point & after the last one.
+pub fn automation_value(points: &[AutomationPoint], at: Beats) -> Option<R32> {
+    let i = points.partition_point(|x| x.time <= at);
+    match (i.checked_sub(1).and_then(|i| points.get(i)), points.get(i)) {
+        (None, None) => None,
+        (Some(x), None) | (None, Some(x)) => Some(x.value),
+        (Some(prev), Some(next)) => {
+            let progress = R32::from((at - prev.time) / (next.time - prev.time));
+            Some(prev.value + (next.value - prev.value) * progress)
+        }
+    }
+}
+
+/// Computes the beats, relative to the start of the sound block, at which the parameter
+/// automated by the curve going through `points` must be at certain values for the curve to be
+/// followed within `span`, also relative to the start of the sound block. The 1st of the returned
+/// values is to be set right away, the parameter being linearly ramped to the rest.
+pub fn automation_ramps(points: &[AutomationPoint], span: Range<Beats>) -> Vec<(Beats, R32)> {
+    let start = span.start.max(Beats::ZERO);
+    let Some(start_value) = automation_value(points, start) else {
+        return vec![];
+    };
+    let inner = points.iter().filter(|x| x.time > start && x.time < span.end);
+    let end = points
+        .last()
+        .filter(|x| x.time > span.end)
+        .and_then(|_| Some((span.end, automation_value(points, span.end)?)));
+    [(start, start_value)].into_iter().chain(inner.map(|x| (x.time, x.value))).chain(end).collect()
+}
+
+#[test]
+fn automation_ramp() {
+    let beats = |x| Beats(R64::new_or(R64::ZERO, x));
+    let points = [
+        AutomationPoint { time: beats(1.0), value: R32::ZERO },
+        AutomationPoint { time: beats(3.0), value: R32::ONE },
+    ];
+    // the volume is held before the 1st point, then ramped up to the 2nd one
+    assert_eq!(
+        automation_ramps(&points, Beats::ZERO..Beats::INFINITY),
+        [(Beats::ZERO, R32::ZERO), (beats(1.0), R32::ZERO), (beats(3.0), R32::ONE)]
+    );
+    // a span starting or ending between the points starts or ends halfway through the ramp
+    assert_eq!(
+        automation_ramps(&points, beats(2.0)..Beats::INFINITY),
+        [(beats(2.0), r32!(0.5)), (beats(3.0), R32::ONE)]
+    );
+    assert_eq!(
+        automation_ramps(&points, Beats::ZERO..beats(2.0)),
+        [(Beats::ZERO, R32::ZERO), (beats(1.0), R32::ZERO), (beats(2.0), r32!(0.5))]
+    );
+    assert_eq!(automation_ramps(&[], Beats::ZERO..Beats::INFINITY), []);
+}
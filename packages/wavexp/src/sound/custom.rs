@@ -1,10 +1,13 @@
 use super::NoteBlock;
 use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
-    input::{AudioInputButton, Counter, Cursor, GraphEditorCanvas, Slider},
+    input::{AudioInputButton, Counter, Cursor, GraphEditorCanvas},
     popup::Popup,
-    sequencer::{PlaybackContext, Sequencer},
-    sound::{AudioInput, Beats, FromBeats, Note, Secs},
+    sequencer::Sequencer,
+    sound::{
+        rep_progress, schedule, swing_time, AudioInput, AutomationPoint, Beats, FromSecs, Note,
+        Patterns, SampleParams, Secs, TempoMap,
+    },
     visual::{GraphEditor, GraphPoint},
 };
 use macro_rules_attribute::apply;
@@ -12,14 +15,14 @@ use std::{
     cmp::Ordering,
     mem::{replace, transmute},
     num::NonZeroU32,
-    ops::RangeBounds,
+    ops::{Range, RangeBounds},
 };
-use wasm_bindgen::JsCast;
 use wavexp_utils::{
     cell::Shared,
-    error::{AppError, Result},
-    ext::{ArrayExt, OptionExt, ResultExt},
-    fallible, js_function, r32, r64,
+    diff::Diff,
+    error::Result,
+    ext::{default, ArrayExt, OptionExt},
+    fallible, r32,
     range::{RangeBoundsExt, RangeInclusiveV2, RangeV2},
     real::R32,
     real::R64,
@@ -30,7 +33,7 @@ use yew::{html, Html};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CustomBlock {
-    pub offset: R64,
+    pub offset: Beats,
     pub pitch: Note,
 }
 
@@ -46,6 +49,19 @@ impl Ord for CustomBlock {
     }
 }
 
+impl Diff for CustomBlock {
+    type Delta = (<Beats as Diff>::Delta, u8);
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        (self.offset.delta(&to.offset), self.pitch.delta(&to.pitch))
+    }
+
+    fn patch(&mut self, (offset, pitch): Self::Delta) {
+        self.offset.patch(offset);
+        self.pitch.patch(pitch);
+    }
+}
+
 impl GraphPoint for CustomBlock {
     const EDITOR_NAME: &'static str = NoteBlock::EDITOR_NAME;
     const Y_BOUND: RangeV2<R64> = NoteBlock::Y_BOUND;
@@ -55,11 +71,12 @@ impl GraphPoint for CustomBlock {
 
     type Inner = ();
     type Y = Note;
-    /// (sound block offset, number of repetitions of the pattern, audio duration)
-    type VisualContext = (Beats, NonZeroU32, Beats);
+    /// (sound block offset, number of repetitions of the pattern, gap between them,
+    /// audio duration)
+    type VisualContext = (Beats, NonZeroU32, Beats, Secs);
 
     fn create(_: &GraphEditor<Self>, [offset, y]: [R64; 2]) -> Self {
-        Self { offset, pitch: Note::saturated(y.into()).recip() }
+        Self { offset: Beats(offset), pitch: Note::saturated(y.into()).recip() }
     }
 
     fn inner(&self) -> &Self::Inner {
@@ -77,12 +94,12 @@ impl GraphPoint for CustomBlock {
     }
 
     fn loc(&self) -> [R64; 2] {
-        [self.offset, self.pitch.recip().index().into()]
+        [self.offset.0, self.pitch.recip().index().into()]
     }
 
     #[apply(fallible!)]
     fn móve(&mut self, delta: [R64; 2], _: bool) {
-        self.offset += delta[0];
+        self.offset += Beats(delta[0]);
         self.pitch = (self.pitch - isize::from(delta[1]))?;
     }
 
@@ -95,11 +112,12 @@ impl GraphPoint for CustomBlock {
         &self,
         area: &[RangeInclusiveV2<R64>; 2],
         _: ContextRef,
-        _: &Sequencer,
+        sequencer: &Sequencer,
         (.., len): Self::VisualContext,
     ) -> Result<bool> {
+        let end = self.offset + len.to_beats(sequencer.bps()) / self.pitch.pitch_coef();
         Ok(area[1].map_bounds(usize::from).contains(&self.pitch.recip().index())
-            && (self.offset..=self.offset + len / self.pitch.pitch_coef()).overlap(&area[0]))
+            && (self.offset.0..=end.0).overlap(&area[0]))
     }
 
     fn fmt_loc(loc: [R64; 2]) -> String {
@@ -113,7 +131,7 @@ impl GraphPoint for CustomBlock {
         _: [R64; 2],
         point: Option<usize>,
     ) -> Result {
-        let Some(last) = editor.data().len().checked_sub(1) else {
+        let Some(last) = editor.points().len().checked_sub(1) else {
             return Ok(());
         };
         if point.map_or_else(|| editor.selection().contains(&last), |x| x == last) {
@@ -124,15 +142,14 @@ impl GraphPoint for CustomBlock {
 
     fn on_redraw(
         editor: &mut GraphEditor<Self>,
-        ctx: ContextRef,
+        _: ContextRef,
         sequencer: &Sequencer,
         canvas_size: &[R64; 2],
         solid: &Path2d,
         dotted: &Path2d,
-        (sb_offset, n_reps, len): Self::VisualContext,
+        (.., len): Self::VisualContext,
     ) -> Result {
-        let bps = sequencer.bps();
-        let len = len.secs_to_beats(bps);
+        let len = len.to_beats(sequencer.bps());
         let step = canvas_size.div(editor.scale());
         let offset = R64::array_from(editor.offset());
         dotted.rect(
@@ -141,18 +158,34 @@ impl GraphPoint for CustomBlock {
             *canvas_size[0] * 2.0,
             *step[1],
         );
-        for block in editor.data() {
+        for block in editor.points() {
             let [x, y] = block.loc().mul(step).sub(offset);
             solid.rect(*x, *y, *len / *block.pitch.pitch_coef() * *step[0], *step[1]);
         }
+        Ok(())
+    }
 
-        let total_len =
-            editor.data().last().map_or_default(|last| last.offset + len / last.pitch.pitch_coef());
-        if let PlaybackContext::All(start) = sequencer.playback_ctx() && start.is_finite() {
-            let progress = (ctx.frame() - start).secs_to_beats(bps) - sb_offset;
-            if progress < total_len * n_reps {
-                editor.force_redraw();
-                let x = R64::new_or(progress, *progress % *total_len) * step[0] - offset[0];
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        _: &Path2d,
+        (sb_offset, n_reps, rep_gap, len): Self::VisualContext,
+    ) -> Result {
+        let len = len.to_beats(sequencer.bps());
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        let total_len = editor
+            .points()
+            .last()
+            .map_or_default(|last| last.offset + len / last.pitch.pitch_coef());
+        if let Some(position) = sequencer.playback_position(ctx.frame()) {
+            let progress = rep_progress(position - sb_offset, total_len, n_reps, rep_gap);
+            if let Some(progress) = progress {
+                editor.force_overlay_redraw();
+                let x = progress.0 * step[0] - offset[0];
                 solid.move_to(*x, 0.0);
                 solid.line_to(*x, *canvas_size[1]);
             }
@@ -163,29 +196,22 @@ impl GraphPoint for CustomBlock {
 
 #[derive(Debug, Clone)]
 pub struct CustomSound {
-    pub pattern: Shared<GraphEditor<CustomBlock>>,
+    pub patterns: Patterns<CustomBlock>,
     pub src: Option<Shared<AudioInput>>,
-    pub volume: R32,
-    pub attack: Beats,
-    pub decay: Beats,
-    pub sustain: R32,
-    pub release: Beats,
-    pub rep_count: NonZeroU32,
+    pub params: SampleParams,
+    /// volume of the sound over the course of the sound block, on top of `params.volume`
+    pub volume_automation: Shared<GraphEditor<AutomationPoint>>,
     pub speed: R32,
 }
 
 impl Default for CustomSound {
     fn default() -> Self {
         Self {
-            pattern: GraphEditor::new(vec![CustomBlock { offset: r64!(0), pitch: Note::MID }])
+            patterns: GraphEditor::new(vec![CustomBlock { offset: Beats::ZERO, pitch: Note::MID }])
                 .into(),
             src: None,
-            volume: r32!(1),
-            attack: r64!(0),
-            decay: r64!(0),
-            sustain: r32!(1),
-            release: r64!(0),
-            rep_count: NonZeroU32::MIN,
+            params: default(),
+            volume_automation: default(),
             speed: r32!(1),
         }
     }
@@ -194,67 +220,75 @@ impl Default for CustomSound {
 impl CustomSound {
     pub const NAME: &'static str = "Custom Audio";
 
-    pub fn prepare(&mut self, bps: Beats) -> Result {
-        if let Some(src) = &self.src {
-            src.get_mut()?.bake(bps)?
+    pub fn prepare(&mut self, bps: R64) -> Result<bool> {
+        match &self.src {
+            Some(src) => src.get_mut()?.bake(bps),
+            None => Ok(false),
         }
-        Ok(())
     }
 
     #[apply(fallible!)]
-    pub fn play(&self, plug: &AudioNode, now: Secs, self_offset: Secs, bps: Beats) {
+    pub fn play(
+        &self,
+        plug: &AudioNode,
+        now: Secs,
+        self_offset: Beats,
+        span: Range<Beats>,
+        tempo: &TempoMap,
+        swing: R32,
+    ) {
         let Some(src) = &self.src else { return Ok(()) };
         let src = src.get()?;
-        let pat = self.pattern.get()?;
-        let Some(last) = pat.data().last() else {
+        let pat = self.patterns.active().get()?;
+        let Some(last) = pat.points().last() else {
             return Ok(());
         };
         let len = src.baked_duration() / self.speed;
-        let pat_len = last.offset.to_secs(bps) + len / last.pitch.pitch_coef();
-        let ctx = plug.context();
-
-        for rep in 0..self.rep_count.get() {
-            for CustomBlock { offset, pitch } in pat.data() {
-                let coef = pitch.pitch_coef();
-                let block = ctx.create_gain()?;
-                let gain = block.gain();
-                let start = now + self_offset + pat_len * rep + offset.to_secs(bps);
-                let mut at = start;
-                gain.set_value_at_time(0.0, *at)?;
-                at += self.attack.to_secs(bps);
-                gain.linear_ramp_to_value_at_time(*self.volume, *at)?;
-                at += self.decay.to_secs(bps);
-                let sus = self.sustain * self.volume;
-                gain.linear_ramp_to_value_at_time(*sus, *at)?;
-                at = start + len / coef;
-                gain.set_value_at_time(*sus, *at - *self.release.to_secs(bps))?;
-                gain.linear_ramp_to_value_at_time(0.0, *at)?;
-
-                let block_core = ctx.create_buffer_source()?;
-                block_core.set_buffer(Some(src.baked()?));
-                block_core.playback_rate().set_value(*(self.speed * coef));
-                block_core.connect_with_audio_node(&block)?.connect_with_audio_node(plug)?;
-                block_core.start_with_when(*start)?;
-                block_core.clone().set_onended(Some(&js_function!(|| {
-                    block.disconnect().map_err(AppError::from).report();
-                    block_core.disconnect().map_err(AppError::from).report();
-                })));
-            }
+        let last_len = (len / last.pitch.pitch_coef()).to_beats(tempo.bps_at(self_offset));
+        let period = last.offset + last_len + self.params.rep_gap;
+
+        let span = span.start - self_offset..span.end - self_offset;
+        let starts = schedule(pat.points(), self.params.rep_count, period, span, |x| x.offset);
+        for (start, CustomBlock { pitch, .. }) in starts {
+            let beat = self_offset + start;
+            let start = now + swing_time(beat, swing, tempo);
+            let rate = self.speed * pitch.pitch_coef();
+            self.params.play(&src, plug, start, tempo.bps_at(beat), rate)?;
         }
     }
 
-    pub fn len(&self, bps: Beats) -> Result<Beats> {
-        Ok(if let Some(block) = self.pattern.get()?.data().last() && let Some(src) = &self.src {
-            src.get()?.baked_duration().secs_to_beats(bps)
-                / self.speed / block.pitch.pitch_coef()
-                + block.offset
-        } else {
-            R64::ZERO
-        })
+    pub fn len(&self, bps: R64) -> Result<Beats> {
+        let pat = self.patterns.active().get()?;
+        Ok(
+            if let Some(block) = pat.points().last()
+                && let Some(src) = &self.src
+            {
+                src.get()?.baked_duration().to_beats(bps) / self.speed / block.pitch.pitch_coef()
+                    + block.offset
+            } else {
+                Beats::ZERO
+            },
+        )
     }
 
     pub const fn rep_count(&self) -> NonZeroU32 {
-        self.rep_count
+        self.params.rep_count
+    }
+
+    pub const fn rep_gap(&self) -> Beats {
+        self.params.rep_gap
+    }
+
+    fn visual_ctx(&self, offset: Beats) -> <CustomBlock as GraphPoint>::VisualContext {
+        (
+            offset,
+            self.params.rep_count,
+            self.params.rep_gap,
+            self.src
+                .as_ref()
+                .and_then(|x| x.get().ok())
+                .map_or_default(|x| x.baked_duration() / self.speed),
+        )
     }
 
     pub fn params(&self, ctx: ContextRef, sequencer: &Sequencer) -> Html {
@@ -262,25 +296,13 @@ impl CustomSound {
         match ctx.selected_tab() {
             0 /* General */ => html!{
                 <div id="inputs">
-                    <Slider
-                        key="custom-vol"
-                        setter={emitter.reform(|x| AppEvent::Volume(R32::from(x)))}
-                        name="Custom Audio Volume"
-                        initial={self.volume}
-                    />
-                    <Counter
-                        key="custom-repcnt"
-                        setter={emitter.reform(|x| AppEvent::RepCount(NonZeroU32::from(x)))}
-                        fmt={|x| format!("{x:.0}")}
-                        name="Number Of Pattern Repetitions"
-                        min=1
-                        initial={self.rep_count}
-                    />
+                    { self.params.general_inputs(Self::NAME, emitter) }
                     <Counter
                         key="note-speed"
                         setter={emitter.reform(|x| AppEvent::Speed(R32::from(x)))}
                         fmt={|x| format!("{x:.2}x")}
                         name="Playback speed"
+                        default={R64::ONE}
                         initial={self.speed}
                     />
                     <AudioInputButton
@@ -292,45 +314,22 @@ impl CustomSound {
                         {emitter}
                         input={&self.src}
                     />
+                    { self.patterns.render(emitter) }
                 </div>
             },
 
-            1 /* Envelope */ => html!{
-                <div id="inputs">
-                    <Counter
-                        key="custom-att"
-                        setter={emitter.reform(AppEvent::Attack)}
-                        name="Audio Attack Time"
-                        postfix="Beats"
-                        initial={self.attack}
-                    />
-                    <Counter
-                        key="custom-dec"
-                        setter={emitter.reform(AppEvent::Decay)}
-                        name="Audio Decay Time"
-                        postfix="Beats"
-                        initial={self.decay}
-                    />
-                    <Slider
-                        key="custom-sus"
-                        setter={emitter.reform(|x| AppEvent::Sustain(R32::from(x)))}
-                        name="Audio Sustain Level"
-                        initial={self.sustain}
-                    />
-                    <Counter
-                        key="custom-rel"
-                        setter={emitter.reform(AppEvent::Release)}
-                        name="Audio Release Time"
-                        postfix="Beats"
-                        initial={self.release}
-                    />
-                </div>
-            },
+            1 /* Envelope */ => self.params.envelope_tab(Self::NAME, emitter),
 
             2 /* Pattern */ => html! {
-                <GraphEditorCanvas<CustomBlock> editor={&self.pattern} {emitter} />
+                <GraphEditorCanvas<CustomBlock>
+                    key={self.patterns.active_index()}
+                    editor={self.patterns.active()}
+                    {emitter}
+                />
             },
 
+            3 /* Filter */ => self.params.filter_tab(Self::NAME, emitter),
+
             tab_id => html!{ <p style="color:red">{ format!("Invalid tab ID: {tab_id}") }</p> }
         }
     }
@@ -345,38 +344,12 @@ impl CustomSound {
         reset_sound: &mut bool,
         offset: Beats,
     ) -> Result {
+        if self.params.handle_event(event, ctx.as_mut())? {
+            return Ok(());
+        }
         match *event {
-            AppEvent::Volume(to) => ctx.register_action(EditorAction::SetVolume {
-                from: replace(&mut self.volume, to),
-                to,
-            })?,
-
-            AppEvent::Attack(to) => ctx.register_action(EditorAction::SetAttack {
-                from: replace(&mut self.attack, to),
-                to,
-            })?,
-
-            AppEvent::Decay(to) => ctx.register_action(EditorAction::SetDecay {
-                from: replace(&mut self.decay, to),
-                to,
-            })?,
-
-            AppEvent::Sustain(to) => ctx.register_action(EditorAction::SetSustain {
-                from: replace(&mut self.sustain, to),
-                to,
-            })?,
-
-            AppEvent::Release(to) => ctx.register_action(EditorAction::SetRelease {
-                from: replace(&mut self.release, to),
-                to,
-            })?,
-
-            AppEvent::RepCount(to) => {
-                ctx.register_action(EditorAction::SetRepCount {
-                    from: replace(&mut self.rep_count, to),
-                    to,
-                })?;
-                ctx.emit_event(AppEvent::RedrawEditorPlane);
+            AppEvent::AddPattern | AppEvent::SelectPattern(_) | AppEvent::RenamePattern(_) => {
+                self.patterns.handle_event(event, ctx)?
             }
 
             AppEvent::Speed(to) => {
@@ -397,7 +370,8 @@ impl CustomSound {
             }
 
             AppEvent::Undo(ref actions) => {
-                let mut pat = self.pattern.get_mut()?;
+                self.patterns.handle_event(event, ctx.as_mut())?;
+                let mut pat = self.patterns.active().get_mut()?;
                 for action in actions.iter() {
                     match *action {
                         EditorAction::SetBlockType(_) => {
@@ -405,21 +379,6 @@ impl CustomSound {
                             break;
                         }
 
-                        EditorAction::SetVolume { from, .. } => self.volume = from,
-
-                        EditorAction::SetAttack { from, .. } => self.attack = from,
-
-                        EditorAction::SetDecay { from, .. } => self.decay = from,
-
-                        EditorAction::SetSustain { from, .. } => self.sustain = from,
-
-                        EditorAction::SetRelease { from, .. } => self.release = from,
-
-                        EditorAction::SetRepCount { from, .. } => {
-                            self.rep_count = from;
-                            ctx.emit_event(AppEvent::RedrawEditorPlane)
-                        }
-
                         EditorAction::SetSpeed { from, .. } => {
                             self.speed = from;
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
@@ -430,42 +389,19 @@ impl CustomSound {
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
                         }
 
-                        _ => (),
+                        ref action => self.params.undo(action, &ctx),
                     }
                 }
                 if ctx.selected_tab() == 2 {
-                    pat.handle_event(event, ctx, sequencer, || {
-                        (
-                            offset,
-                            self.rep_count,
-                            self.src
-                                .as_ref()
-                                .and_then(|x| x.get().ok())
-                                .map_or_default(|x| x.baked_duration() / self.speed),
-                        )
-                    })?;
+                    pat.handle_event(event, ctx, sequencer, || self.visual_ctx(offset))?;
                 }
             }
 
             AppEvent::Redo(ref actions) => {
-                let mut pat = self.pattern.get_mut()?;
+                self.patterns.handle_event(event, ctx.as_mut())?;
+                let mut pat = self.patterns.active().get_mut()?;
                 for action in actions.iter() {
                     match *action {
-                        EditorAction::SetVolume { to, .. } => self.volume = to,
-
-                        EditorAction::SetAttack { to, .. } => self.attack = to,
-
-                        EditorAction::SetDecay { to, .. } => self.decay = to,
-
-                        EditorAction::SetSustain { to, .. } => self.sustain = to,
-
-                        EditorAction::SetRelease { to, .. } => self.release = to,
-
-                        EditorAction::SetRepCount { to, .. } => {
-                            self.rep_count = to;
-                            ctx.emit_event(AppEvent::RedrawEditorPlane)
-                        }
-
                         EditorAction::SetSpeed { to, .. } => {
                             self.speed = to;
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
@@ -476,35 +412,23 @@ impl CustomSound {
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
                         }
 
-                        _ => (),
+                        ref action => self.params.redo(action, &ctx),
                     }
                 }
                 if ctx.selected_tab() == 2 {
-                    pat.handle_event(event, ctx, sequencer, || {
-                        (
-                            offset,
-                            self.rep_count,
-                            self.src
-                                .as_ref()
-                                .and_then(|x| x.get().ok())
-                                .map_or_default(|x| x.baked_duration() / self.speed),
-                        )
-                    })?;
+                    pat.handle_event(event, ctx, sequencer, || self.visual_ctx(offset))?;
                 }
             }
 
             _ => {
                 if ctx.selected_tab() == 2 {
-                    self.pattern.get_mut()?.handle_event(event, ctx, sequencer, || {
-                        (
-                            offset,
-                            self.rep_count,
-                            self.src
-                                .as_ref()
-                                .and_then(|x| x.get().ok())
-                                .map_or_default(|x| x.baked_duration() / self.speed),
-                        )
-                    })?;
+                    let visual_ctx = self.visual_ctx(offset);
+                    self.patterns.active().get_mut()?.handle_event(
+                        event,
+                        ctx,
+                        sequencer,
+                        || visual_ctx,
+                    )?;
                 }
             }
         }
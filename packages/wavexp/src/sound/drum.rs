@@ -0,0 +1,446 @@
+use crate::{
+    ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
+    img,
+    input::{AudioInputButton, Button, Cursor, GraphEditorCanvas},
+    popup::Popup,
+    sequencer::Sequencer,
+    sound::{
+        rep_progress, schedule, swing_time, AudioInput, AutomationPoint, Beats, SampleParams, Secs,
+        TempoMap,
+    },
+    visual::{GraphEditor, GraphPoint},
+};
+use macro_rules_attribute::apply;
+use std::{
+    cmp::Ordering,
+    mem::transmute,
+    num::NonZeroU32,
+    ops::{Range, RangeBounds},
+};
+use wavexp_utils::{
+    cell::Shared,
+    diff::Diff,
+    error::Result,
+    ext::{default, ArrayExt, OptionExt},
+    fallible, r64,
+    range::{RangeBoundsExt, RangeInclusiveV2, RangeV2},
+    real::R32,
+    real::R64,
+    ArrayFrom,
+};
+use web_sys::{AudioNode, Path2d};
+use yew::Html;
+use yew_html_ext::html;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrumBlock {
+    pub offset: Beats,
+    /// index of the sample triggered by the block
+    pub row: u32,
+}
+
+impl PartialOrd for DrumBlock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.offset.cmp(&other.offset))
+    }
+}
+
+impl Ord for DrumBlock {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.offset.cmp(&other.offset)
+    }
+}
+
+impl Diff for DrumBlock {
+    type Delta = (<Beats as Diff>::Delta, u32);
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        (self.offset.delta(&to.offset), self.row.delta(&to.row))
+    }
+
+    fn patch(&mut self, (offset, row): Self::Delta) {
+        self.offset.patch(offset);
+        self.row.patch(row);
+    }
+}
+
+impl GraphPoint for DrumBlock {
+    const EDITOR_NAME: &'static str = "Drum Pattern Editor";
+    const Y_BOUND: RangeV2<R64> = RangeV2 { start: r64!(0), end: r64!(DrumSound::MAX_SAMPLES) };
+    const SCALE_Y_BOUND: RangeV2<R64> = RangeV2 { start: r64!(4), end: r64!(16) };
+    const OFFSET_Y_BOUND: RangeV2<R64> = RangeV2 { start: r64!(-1), end: r64!(12) };
+    const Y_SNAP: R64 = r64!(1);
+
+    type Inner = ();
+    type Y = u32;
+    /// (sound block offset, number of repetitions of the pattern, gap between them,
+    /// number of samples)
+    type VisualContext = (Beats, NonZeroU32, Beats, usize);
+
+    fn create(_: &GraphEditor<Self>, [offset, y]: [R64; 2]) -> Self {
+        Self { offset: Beats(offset), row: y.into() }
+    }
+
+    fn inner(&self) -> &Self::Inner {
+        &()
+    }
+    fn inner_mut(&mut self) -> &mut Self::Inner {
+        unsafe { transmute(self) }
+    }
+
+    fn y(&self) -> &Self::Y {
+        &self.row
+    }
+    fn y_mut(&mut self) -> &mut Self::Y {
+        &mut self.row
+    }
+
+    fn loc(&self) -> [R64; 2] {
+        [self.offset.0, self.row.into()]
+    }
+    fn span(&self, _: R64) -> Result<[R64; 2]> {
+        Ok([self.offset.0, (self.offset + DrumSound::HIT_LEN).0])
+    }
+
+    fn móve(&mut self, delta: [R64; 2], _: bool) -> Result {
+        self.offset = Beats::ZERO.max(self.offset + Beats(delta[0]));
+        self.row = u32::from(R64::from(self.row) + delta[1]).min(DrumSound::MAX_SAMPLES - 1);
+        Ok(())
+    }
+
+    fn move_point(point: &mut [R64; 2], delta: [R64; 2], _: bool) {
+        point[0] = R64::ZERO.max(point[0] + delta[0]);
+        point[1] += delta[1]
+    }
+
+    fn in_hitbox(
+        &self,
+        area: &[RangeInclusiveV2<R64>; 2],
+        _: ContextRef,
+        _: &Sequencer,
+        _: Self::VisualContext,
+    ) -> Result<bool> {
+        Ok(area[1].map_bounds(u32::from).contains(&self.row)
+            && (self.offset.0..=(self.offset + DrumSound::HIT_LEN).0).overlap(&area[0]))
+    }
+
+    fn fmt_loc(loc: [R64; 2]) -> String {
+        format!("{:.3}, sample #{}", loc[0], u32::from(loc[1]) + 1)
+    }
+
+    fn on_move(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextMut,
+        _: Cursor,
+        _: [R64; 2],
+        point: Option<usize>,
+    ) -> Result {
+        let Some(last) = editor.points().len().checked_sub(1) else {
+            return Ok(());
+        };
+        if point.map_or_else(|| editor.selection().contains(&last), |x| x == last) {
+            ctx.emit_event(AppEvent::RedrawEditorPlane)
+        }
+        Ok(())
+    }
+
+    fn on_redraw(
+        editor: &mut GraphEditor<Self>,
+        _: ContextRef,
+        _: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        dotted: &Path2d,
+        (.., n_samples): Self::VisualContext,
+    ) -> Result {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        // the rows that have samples
+        dotted.rect(-10.0, -*offset[1], *canvas_size[0] * 2.0, n_samples as f64 * *step[1]);
+        for block in editor.points() {
+            let [x, y] = block.loc().mul(step).sub(offset);
+            solid.rect(*x, *y, *DrumSound::HIT_LEN * *step[0], *step[1]);
+        }
+        Ok(())
+    }
+
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        _: &Path2d,
+        (sb_offset, n_reps, rep_gap, _): Self::VisualContext,
+    ) -> Result {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        let total_len = DrumSound::pattern_len(editor.points());
+        if let Some(position) = sequencer.playback_position(ctx.frame()) {
+            let progress = rep_progress(position - sb_offset, total_len, n_reps, rep_gap);
+            if let Some(progress) = progress {
+                editor.force_overlay_redraw();
+                let x = progress.0 * step[0] - offset[0];
+                solid.move_to(*x, 0.0);
+                solid.line_to(*x, *canvas_size[1]);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DrumSound {
+    pub pattern: Shared<GraphEditor<DrumBlock>>,
+    /// the samples triggered by the blocks on the rows of the pattern, 1 sample per row
+    pub samples: Vec<Shared<AudioInput>>,
+    pub params: SampleParams,
+    /// volume of the sound over the course of the sound block, on top of `params.volume`
+    pub volume_automation: Shared<GraphEditor<AutomationPoint>>,
+}
+
+impl Default for DrumSound {
+    fn default() -> Self {
+        Self {
+            pattern: GraphEditor::new(vec![DrumBlock { offset: Beats::ZERO, row: 0 }]).into(),
+            samples: vec![],
+            params: default(),
+            volume_automation: default(),
+        }
+    }
+}
+
+impl DrumSound {
+    pub const NAME: &'static str = "Drum Pads";
+    /// maximum number of the samples, i.e. of the rows of the pattern
+    pub const MAX_SAMPLES: u32 = 16;
+    /// length with which the blocks of the pattern are displayed, regardless of their samples
+    pub const HIT_LEN: Beats = Beats(r64!(0.25));
+
+    /// Length of a pattern, rounded up to a whole number of beats for its repetitions to start on
+    /// the beat.
+    pub fn pattern_len(pattern: &[DrumBlock]) -> Beats {
+        pattern.last().map_or_default(|last| (last.offset + Self::HIT_LEN).ceil())
+    }
+
+    /// Computes at which beats the samples are triggered, relative to the start of the sound
+    /// block, as `(start, row)`. Only the hits that start within `span` & are on 1 of the first
+    /// `n_samples` rows, i.e. have samples, are yielded.
+    pub fn hits(
+        pattern: &[DrumBlock],
+        n_samples: usize,
+        rep_count: NonZeroU32,
+        rep_gap: Beats,
+        span: Range<Beats>,
+    ) -> impl Iterator<Item = (Beats, usize)> + '_ {
+        let period = Self::pattern_len(pattern) + rep_gap;
+        schedule(pattern, rep_count, period, span, |x| x.offset)
+            .map(|(start, block)| (start, block.row as usize))
+            .filter(move |(_, row)| *row < n_samples)
+    }
+
+    /// Prepares the samples for playback.
+    /// Returns whether the length of any of them might've changed in the process.
+    pub fn prepare(&mut self, bps: R64) -> Result<bool> {
+        let mut res = false;
+        for sample in &self.samples {
+            res |= sample.get_mut()?.bake(bps)?;
+        }
+        Ok(res)
+    }
+
+    #[apply(fallible!)]
+    pub fn play(
+        &self,
+        plug: &AudioNode,
+        now: Secs,
+        self_offset: Beats,
+        span: Range<Beats>,
+        tempo: &TempoMap,
+        swing: R32,
+    ) {
+        let pat = self.pattern.get()?;
+        let samples = self.samples.iter().map(Shared::get).collect::<Result<Vec<_>>>()?;
+        let (rep_count, rep_gap) = (self.params.rep_count, self.params.rep_gap);
+
+        let span = span.start - self_offset..span.end - self_offset;
+        for (start, row) in Self::hits(pat.points(), samples.len(), rep_count, rep_gap, span) {
+            let beat = self_offset + start;
+            let start = now + swing_time(beat, swing, tempo);
+            self.params.play(samples.get(row)?, plug, start, tempo.bps_at(beat), R32::ONE)?;
+        }
+    }
+
+    pub fn len(&self) -> Result<Beats> {
+        Ok(Self::pattern_len(self.pattern.get()?.points()))
+    }
+
+    pub const fn rep_count(&self) -> NonZeroU32 {
+        self.params.rep_count
+    }
+
+    pub const fn rep_gap(&self) -> Beats {
+        self.params.rep_gap
+    }
+
+    fn visual_ctx(&self, offset: Beats) -> <DrumBlock as GraphPoint>::VisualContext {
+        (offset, self.params.rep_count, self.params.rep_gap, self.samples.len())
+    }
+
+    pub fn params(&self, ctx: ContextRef, sequencer: &Sequencer) -> Html {
+        let emitter = ctx.event_emitter();
+        let played = sequencer.playback_ctx().played_input();
+        match ctx.selected_tab() {
+            0 /* General */ => html!{
+                <div id="inputs">
+                    { self.params.general_inputs(Self::NAME, emitter) }
+                    for (row, sample) in self.samples.iter().enumerate() {
+                        <AudioInputButton
+                            name={format!("Drum sample #{}", row + 1)}
+                            help="Click to remove the sample, shifting the rows below it up"
+                            onclick={emitter.reform(move |_| AppEvent::RemoveDrumSample(row))}
+                            playing={played == Some(sample)}
+                            bps={sequencer.bps()}
+                            {emitter}
+                            input={Some(sample.clone())}
+                        />
+                    }
+                    if self.samples.len() < Self::MAX_SAMPLES as usize {
+                        <Button
+                            name="Add drum sample"
+                            help="Click to choose the sample for the next row of the pattern"
+                            onclick={emitter.reform(|_| AppEvent::OpenPopup(Popup::ChooseInput))}
+                        >
+                            <img::Plus />
+                        </Button>
+                    }
+                </div>
+            },
+
+            1 /* Envelope */ => self.params.envelope_tab(Self::NAME, emitter),
+
+            2 /* Pattern */ => html! {
+                <GraphEditorCanvas<DrumBlock> editor={&self.pattern} {emitter} />
+            },
+
+            3 /* Filter */ => self.params.filter_tab(Self::NAME, emitter),
+
+            tab_id => html!{ <p style="color:red">{ format!("Invalid tab ID: {tab_id}") }</p> }
+        }
+    }
+
+    /// `reset_sound` is set to `false` initially,
+    /// if set to true, resets the sound block to an `Undefined` type
+    pub fn handle_event(
+        &mut self,
+        event: &AppEvent,
+        mut ctx: ContextMut,
+        sequencer: &Sequencer,
+        reset_sound: &mut bool,
+        offset: Beats,
+    ) -> Result {
+        if self.params.handle_event(event, ctx.as_mut())? {
+            return Ok(());
+        }
+        match *event {
+            AppEvent::AddInput(ref sample) | AppEvent::SelectInput(ref sample) => {
+                if self.samples.len() < Self::MAX_SAMPLES as usize {
+                    ctx.register_action(EditorAction::AddDrumSample(sample.clone()))?;
+                    self.samples.push(sample.clone());
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
+                }
+            }
+
+            AppEvent::RemoveDrumSample(index) => {
+                if index < self.samples.len() {
+                    let sample = self.samples.remove(index);
+                    ctx.register_action(EditorAction::RemoveDrumSample { index, sample })?;
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
+                }
+            }
+
+            AppEvent::Undo(ref actions) => {
+                let mut pat = self.pattern.get_mut()?;
+                for action in actions.iter() {
+                    match *action {
+                        EditorAction::SetBlockType(_) => {
+                            *reset_sound = true;
+                            break;
+                        }
+
+                        EditorAction::AddDrumSample(_) => {
+                            self.samples.pop();
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::RemoveDrumSample { index, ref sample } => {
+                            self.samples.insert(index, sample.clone());
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        ref action => self.params.undo(action, &ctx),
+                    }
+                }
+                if ctx.selected_tab() == 2 {
+                    pat.handle_event(event, ctx, sequencer, || self.visual_ctx(offset))?;
+                }
+            }
+
+            AppEvent::Redo(ref actions) => {
+                let mut pat = self.pattern.get_mut()?;
+                for action in actions.iter() {
+                    match *action {
+                        EditorAction::AddDrumSample(ref sample) => {
+                            self.samples.push(sample.clone());
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::RemoveDrumSample { index, .. } => {
+                            self.samples.remove(index);
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        ref action => self.params.redo(action, &ctx),
+                    }
+                }
+                if ctx.selected_tab() == 2 {
+                    pat.handle_event(event, ctx, sequencer, || self.visual_ctx(offset))?;
+                }
+            }
+
+            _ => {
+                if ctx.selected_tab() == 2 {
+                    let visual_ctx = self.visual_ctx(offset);
+                    self.pattern.get_mut()?.handle_event(event, ctx, sequencer, || visual_ctx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn drum_hits() {
+    let [b0, b1] = [r64!(0), r64!(1.5)].map(Beats);
+    let pattern = [DrumBlock { offset: b0, row: 0 }, DrumBlock { offset: b1, row: 1 }];
+    let hits = |n_samples, rep_count, rep_gap, span| {
+        DrumSound::hits(&pattern, n_samples, rep_count, rep_gap, span).collect::<Vec<_>>()
+    };
+    let span = Beats::ZERO..Beats::INFINITY;
+    let hits_once = hits(2, NonZeroU32::MIN, Beats::ZERO, span.clone());
+    assert_eq!(hits_once, [(b0, 0), (b1, 1)]);
+    // the 2nd row has no sample
+    assert_eq!(hits(1, NonZeroU32::MIN, Beats::ZERO, span), [(b0, 0)]);
+    // the pattern is 2 beats long, so its repetitions start every 2 beats
+    let rep_count = NonZeroU32::MIN.saturating_add(1);
+    let span = Beats::ONE..Beats::INFINITY;
+    assert_eq!(
+        hits(2, rep_count, Beats::ZERO, span.clone()),
+        [(r64!(1.5), 1), (r64!(2), 0), (r64!(3.5), 1)].map(|(x, i)| (Beats(x), i))
+    );
+    // a gap of 2 beats between the repetitions delays the 2nd one by 2 beats
+    assert_eq!(
+        hits(2, rep_count, Beats(r64!(2)), span),
+        [(r64!(1.5), 1), (r64!(4), 0), (r64!(5.5), 1)].map(|(x, i)| (Beats(x), i))
+    );
+}
@@ -1,50 +1,321 @@
+mod automation;
 mod custom;
+mod drum;
 mod noise;
 mod note;
+mod sample;
 
 use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
-    input::Button,
+    img,
+    input::{Button, GraphEditorCanvas},
     sequencer::Sequencer,
+    visual::{GraphEditor, GraphPoint},
 };
+pub use automation::*;
 pub use custom::*;
+pub use drum::*;
+use js_sys::{ArrayBuffer, Uint8Array};
+use macro_rules_attribute::apply;
 pub use noise::*;
 pub use note::*;
+pub use sample::*;
 use std::{
     fmt::{self, Display, Formatter},
     future::Future,
     mem::{replace, variant_count},
     num::NonZeroU32,
-    ops::{Add, Deref, Div, Sub},
+    ops::{Add, Deref, Div, Range, Sub},
     rc::Rc,
 };
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
-use wavexp_utils::{error::Result, ext::default, r32, r64, real::R32, real::R64};
-use web_sys::{AudioBuffer, AudioBufferOptions, AudioNode, BaseAudioContext, File};
-use yew::Html;
+use wavexp_utils::{
+    cell::Shared,
+    diff::Diff,
+    ensure,
+    error::Result,
+    ext::{default, ResultExt, SliceExt},
+    fallible, r32, r64,
+    real::R32,
+    real::R64,
+};
+use web_sys::{
+    AudioBuffer, AudioBufferOptions, AudioNode, AudioParam, BaseAudioContext, BiquadFilterNode,
+    BiquadFilterType, File, GainNode, HtmlInputElement, OscillatorNode, OscillatorType,
+    StereoPannerNode,
+};
+use yew::{classes, AttrValue, Callback, Html, TargetCast};
 use yew_html_ext::html;
 
-pub type MSecs = R64;
-pub type Secs = R64;
-pub type Beats = R64;
+pub use wavexp_utils::time::{Beats, MSecs, Secs};
 
+/// Conversions of a duration in beats to real time, given the tempo in beats per second.
 pub trait FromBeats {
-    fn to_msecs(self, bps: Self) -> MSecs;
-    fn to_secs(self, bps: Self) -> Secs;
-    fn secs_to_beats(self, bps: Self) -> Beats;
+    fn to_msecs(self, bps: R64) -> MSecs;
+    fn to_secs(self, bps: R64) -> Secs;
 }
 
 impl FromBeats for Beats {
-    fn to_secs(self, bps: Self) -> Secs {
-        self / bps
+    fn to_secs(self, bps: R64) -> Secs {
+        Secs(self.0 / bps)
+    }
+    fn to_msecs(self, bps: R64) -> MSecs {
+        MSecs(self.0 * 1000u16 / bps)
+    }
+}
+
+/// Conversion of a duration in real time to beats, given the tempo in beats per second.
+pub trait FromSecs {
+    fn to_beats(self, bps: R64) -> Beats;
+}
+
+impl FromSecs for Secs {
+    fn to_beats(self, bps: R64) -> Beats {
+        Beats(self.0 * bps)
+    }
+}
+
+#[test]
+fn tempo_to_secs() {
+    let beats = Beats(r64!(3));
+    let bpm = r64!(90);
+    assert_eq!(beats.to_secs(bpm * 2u8 / 60u8), beats.to_secs(bpm / 60u8) / 2u8);
+    assert_eq!(beats.to_secs(bpm / 60u8).to_beats(bpm / 60u8), beats);
+    assert_eq!(beats.to_msecs(bpm / 60u8).0, beats.to_secs(bpm / 60u8).0 * 1000u16);
+}
+
+/// Tempo of a composition, in beats per second, that may change at certain beats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TempoMap {
+    /// tempo at beat 0
+    bps: R64,
+    /// the beats after beat 0 at which the tempo changes, paired with the tempo from then on,
+    /// sorted by the beat
+    changes: Rc<[(Beats, R64)]>,
+}
+
+impl Default for TempoMap {
+    fn default() -> Self {
+        Self::new(r64!(2))
     }
-    fn to_msecs(self, bps: Self) -> MSecs {
-        self * 1000u16 / bps
+}
+
+impl TempoMap {
+    /// Creates a tempo map with a constant tempo.
+    pub fn new(bps: R64) -> Self {
+        Self { bps, changes: Rc::new([]) }
     }
-    fn secs_to_beats(self, bps: Self) -> Beats {
-        self * bps
+
+    /// Creates a tempo map with the tempo `bps` at beat 0, changing at the given beats.
+    /// The changes not after beat 0 are ignored, as are all but the 1st of the ones at the same
+    /// beat.
+    pub fn with_changes(bps: R64, changes: impl IntoIterator<Item = (Beats, R64)>) -> Self {
+        let mut changes: Vec<_> = changes.into_iter().filter(|x| x.0 > Beats::ZERO).collect();
+        changes.sort_by_key(|x| x.0);
+        changes.dedup_by_key(|x| x.0);
+        Self { bps, changes: changes.into() }
+    }
+
+    /// Tempo at beat 0.
+    pub const fn bps(&self) -> R64 {
+        self.bps
+    }
+
+    /// The beats after beat 0 at which the tempo changes, paired with the tempo from then on.
+    pub fn changes(&self) -> &[(Beats, R64)] {
+        &self.changes
+    }
+
+    /// Sets the tempo from beat `at` on to `bps`, or removes the change of tempo at beat `at` if
+    /// `bps` is `None`. Setting the tempo at beat 0 or before it sets the initial tempo.
+    pub fn set(&self, at: Beats, bps: Option<R64>) -> Self {
+        if at <= Beats::ZERO {
+            return Self { bps: bps.unwrap_or(self.bps), changes: self.changes.clone() };
+        }
+        let mut changes = self.changes.to_vec();
+        match (changes.binary_search_by_key(&at, |x| x.0), bps) {
+            (Ok(i), Some(bps)) => changes.get_mut(i).iter_mut().for_each(|x| x.1 = bps),
+            (Err(i), Some(bps)) => changes.insert(i, (at, bps)),
+            (Ok(i), None) => _ = changes.remove(i),
+            (Err(_), None) => (),
+        }
+        Self { bps: self.bps, changes: changes.into() }
+    }
+
+    /// Tempo at beat `beat`.
+    pub fn bps_at(&self, beat: Beats) -> R64 {
+        let n_passed = self.changes.partition_point(|x| x.0 <= beat);
+        n_passed.checked_sub(1).and_then(|i| self.changes.get(i)).map_or(self.bps, |x| x.1)
+    }
+
+    /// Time it takes to get from beat 0 to beat `beat`, taking into account all the changes of
+    /// tempo in between.
+    pub fn to_secs(&self, beat: Beats) -> Secs {
+        let (mut res, mut at, mut bps) = (Secs::ZERO, Beats::ZERO, self.bps);
+        for &(next_at, next_bps) in self.changes.iter().take_while(|x| x.0 < beat) {
+            res += (next_at - at).to_secs(bps);
+            (at, bps) = (next_at, next_bps);
+        }
+        res + (beat - at).to_secs(bps)
+    }
+
+    /// The beat reached `secs` after beat 0, the inverse of `to_secs`.
+    pub fn to_beats(&self, secs: Secs) -> Beats {
+        let (mut elapsed, mut at, mut bps) = (Secs::ZERO, Beats::ZERO, self.bps);
+        for &(next_at, next_bps) in self.changes.iter() {
+            let next_elapsed = elapsed + (next_at - at).to_secs(bps);
+            if next_elapsed > secs {
+                break;
+            }
+            (elapsed, at, bps) = (next_elapsed, next_at, next_bps);
+        }
+        at + (secs - elapsed).to_beats(bps)
+    }
+
+    /// Time it takes to play `len` beats starting from beat `from`.
+    pub fn duration(&self, from: Beats, len: Beats) -> Secs {
+        self.to_secs(from + len) - self.to_secs(from)
+    }
+}
+
+#[test]
+fn tempo_map() {
+    let beats = |x: f64| Beats(R64::new_or(R64::ZERO, x));
+    let secs = |x: f64| Secs(R64::new_or(R64::ZERO, x));
+    // 120 BPM for 4 beats, then 60 BPM until beat 6, then 240 BPM
+    let tempo = TempoMap::with_changes(r64!(2), [(beats(6.0), r64!(4)), (beats(4.0), R64::ONE)]);
+    assert_eq!(tempo.bps_at(beats(-1.0)), r64!(2));
+    assert_eq!(tempo.bps_at(beats(4.0)), R64::ONE);
+    assert_eq!(tempo.bps_at(beats(100.0)), r64!(4));
+    // the analytic result of integrating the tempo piece by piece
+    let piecewise = |beat: f64| {
+        let first = beat.min(4.0) / 2.0;
+        let second = (beat.min(6.0) - 4.0).max(0.0);
+        let third = (beat - 6.0).max(0.0) / 4.0;
+        secs(first + second + third)
+    };
+    for beat in [0.0, 1.0, 4.0, 5.5, 6.0, 10.0] {
+        assert_eq!(tempo.to_secs(beats(beat)), piecewise(beat));
+        assert_eq!(tempo.to_beats(piecewise(beat)), beats(beat));
+    }
+    assert_eq!(tempo.duration(beats(3.0), beats(2.0)), secs(1.5));
+    // with no changes, the tempo is constant
+    assert_eq!(TempoMap::new(r64!(2)).to_secs(beats(-3.0)), secs(-1.5));
+
+    let tempo = tempo.set(beats(4.0), None).set(Beats::ZERO, Some(R64::ONE));
+    assert_eq!(tempo.changes(), [(beats(6.0), r64!(4))]);
+    assert_eq!(tempo.to_secs(beats(7.0)), secs(6.25));
+}
+
+/// Computes when the blocks of a pattern, repeated `rep_count` times every `period` beats,
+/// start relative to the start of the pattern, given when each of them starts within it.
+/// Only the blocks that start within `span` are yielded.
+pub fn schedule<'pat, T>(
+    blocks: &'pat [T],
+    rep_count: NonZeroU32,
+    period: Beats,
+    span: Range<Beats>,
+    start: impl Fn(&T) -> Beats + Copy + 'pat,
+) -> impl Iterator<Item = (Beats, &'pat T)> + 'pat {
+    (0..rep_count.get())
+        .flat_map(move |rep| blocks.iter().map(move |block| (period * rep + start(block), block)))
+        .filter(move |(at, _)| span.contains(at))
+}
+
+/// Length of a pattern of length `pat_len` repeated `rep_count` times, `rep_gap` apart.
+pub fn repeated_len(pat_len: Beats, rep_count: NonZeroU32, rep_gap: Beats) -> Beats {
+    (pat_len + rep_gap) * rep_count.get() - rep_gap
+}
+
+/// Maps `progress`, the number of beats since the start of a pattern of length `pat_len` repeated
+/// `rep_count` times `rep_gap` apart, onto the beat of the pattern being played, if any.
+pub fn rep_progress(
+    progress: Beats,
+    pat_len: Beats,
+    rep_count: NonZeroU32,
+    rep_gap: Beats,
+) -> Option<Beats> {
+    if progress < Beats::ZERO || progress >= repeated_len(pat_len, rep_count, rep_gap) {
+        return None;
+    }
+    let res = progress % (pat_len + rep_gap);
+    (res < pat_len).then_some(res)
+}
+
+#[test]
+fn pattern_repetition() {
+    let beats = |x: f64| Beats(R64::new_or(R64::ZERO, x));
+    let note = NoteBlock { value: Note::MID, ..NoteBlock::test(r64!(1), 0, r64!(3)) };
+    let rep_count = NonZeroU32::MIN.saturating_add(2);
+    let gap = beats(1.5);
+    let sound = |rep_gap| {
+        Sound::Note(NoteSound {
+            patterns: GraphEditor::new(vec![note]).into(),
+            rep_count,
+            rep_gap,
+            ..default()
+        })
+    };
+    let pat_len = beats(4.0);
+    assert_eq!(sound(Beats::ZERO).total_len(R64::ONE).ok(), Some(pat_len * 3u8));
+    assert_eq!(sound(gap).total_len(R64::ONE).ok(), Some(pat_len * 3u8 + gap * 2u8));
+
+    let at = |x| rep_progress(beats(x), pat_len, rep_count, gap);
+    assert_eq!(at(-1.0), None);
+    assert_eq!(at(1.0), Some(beats(1.0)));
+    assert_eq!(at(4.5), None);
+    assert_eq!(at(6.5), Some(beats(1.0)));
+    assert_eq!(at(16.0), None);
+    // with no gap, the repetitions follow each other
+    assert_eq!(rep_progress(beats(6.5), pat_len, rep_count, Beats::ZERO), Some(beats(2.5)));
+}
+
+/// Returns by how much a sound starting at `beat` is delayed by `swing`, 0 meaning no delay & 1
+/// meaning that the off-beat 8th notes are delayed by half their length. The on-beat 8th notes
+/// stay in place & everything in between is delayed proportionally.
+pub fn swing_offset(beat: Beats, swing: R32) -> Beats {
+    let max_delay = R64::from(swing.clamp(R32::ZERO, R32::ONE)) / 4;
+    let pos = beat - beat.floor();
+    (pos.min(Beats::ONE - pos) * 2) * max_delay
+}
+
+/// Delays beat `at` of the composition by `swing` & returns the point in time at which it's then
+/// played, relative to the start of the composition.
+pub fn swing_time(at: Beats, swing: R32, tempo: &TempoMap) -> Secs {
+    tempo.to_secs(at + swing_offset(at, swing))
+}
+
+#[test]
+fn schedule_from() {
+    let beats = |x: u8| Beats(x.into());
+    let blocks = [0, 1, 2, 3, 4, 5, 6, 7].map(beats);
+    let rep_count = NonZeroU32::MIN.saturating_add(1);
+    let starts = |span| {
+        schedule(&blocks, rep_count, beats(8), span, |x| *x).map(|x| x.0).collect::<Vec<_>>()
+    };
+    assert_eq!(starts(Beats::ZERO..Beats::INFINITY).len(), 16);
+    assert_eq!(starts(beats(4)..beats(10)), [4, 5, 6, 7, 8, 9].map(beats));
+    let starts = starts(beats(4)..Beats::INFINITY);
+    assert_eq!(starts.len(), 12);
+    assert_eq!(starts.first(), Some(&beats(4)));
+    assert!(starts.windows(2).all(|x| x[0] < x[1]));
+}
+
+#[test]
+fn swing_offsets() {
+    let beats = |x: f64| Beats(R64::new_or(R64::ZERO, x));
+    for swing in [R32::ZERO, r32!(0.5), R32::ONE] {
+        assert_eq!(swing_offset(beats(0.0), swing), Beats::ZERO);
+        assert_eq!(swing_offset(beats(3.0), swing), Beats::ZERO);
     }
+    assert_eq!(swing_offset(beats(1.5), R32::ZERO), Beats::ZERO);
+    assert_eq!(swing_offset(beats(1.5), r32!(0.5)), beats(0.125));
+    assert_eq!(swing_offset(beats(2.5), R32::ONE), beats(0.25));
+    assert_eq!(swing_offset(beats(2.25), R32::ONE), beats(0.125));
+    // swing never reorders the notes
+    let swung = [0, 1, 2, 3, 4].map(|x| beats(x.into()) / 4).map(|x| x + swing_offset(x, R32::ONE));
+    assert!(swung.windows(2).all(|x| x[0] < x[1]));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, PartialOrd, Ord)]
@@ -61,7 +332,19 @@ impl Deref for Note {
 
 impl Display for Note {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.name().fmt(f)
+        write!(f, "{}{}", self.semitone_name(), self.octave())
+    }
+}
+
+impl Diff for Note {
+    type Delta = u8;
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        self.0.delta(&to.0)
+    }
+
+    fn patch(&mut self, delta: Self::Delta) {
+        *self = Self::new(self.0 ^ delta).unwrap_or(*self)
     }
 }
 
@@ -115,50 +398,16 @@ impl Note {
     pub const N_NOTES: usize = 36;
     pub const MAX: Note = Note(Self::N_NOTES as u8 - 1);
     pub const MID: Note = Note(Self::N_NOTES as u8 / 2);
-    pub const FREQS: [R32; Self::N_NOTES] = [
-        r32!(65.410), // C2
-        r32!(69.300), // C#2
-        r32!(73.420), // D2
-        r32!(77.780), // D#2
-        r32!(82.410), // E2
-        r32!(87.310), // F2
-        r32!(92.500), // F#2
-        r32!(98.000), // G2
-        r32!(103.83), // G#2
-        r32!(110.00), // A2
-        r32!(116.54), // A#2
-        r32!(123.47), // B2
-        r32!(130.81), // C3
-        r32!(138.59), // C#3
-        r32!(146.83), // D3
-        r32!(155.56), // D#3
-        r32!(164.81), // E3
-        r32!(174.61), // F3
-        r32!(185.00), // F#3
-        r32!(196.00), // G3
-        r32!(207.65), // G#3
-        r32!(220.00), // A3
-        r32!(233.08), // A#3
-        r32!(246.94), // B3
-        r32!(261.63), // C4
-        r32!(277.18), // C#4
-        r32!(293.66), // D4
-        r32!(311.13), // D#4
-        r32!(329.63), // E4
-        r32!(349.23), // F4
-        r32!(369.99), // F#4
-        r32!(392.00), // G4
-        r32!(415.30), // G#4
-        r32!(440.00), // A4
-        r32!(466.16), // A#4
-        r32!(493.88), // B4
-    ];
-
-    pub const NAMES: [&'static str; Self::N_NOTES] = [
-        "C2", "C#2", "D2", "D#2", "E2", "F2", "F#2", "G2", "G#2", "A2", "A#2", "B2", "C3", "C#3",
-        "D3", "D#3", "E3", "F3", "F#3", "G3", "G#3", "A3", "A#3", "B3", "C4", "C#4", "D4", "D#4",
-        "E4", "F4", "F#4", "G4", "G#4", "A4", "A#4", "B4",
-    ];
+    /// octave of the lowest note
+    pub const FIRST_OCTAVE: i32 = 2;
+    /// frequency of A4, from which the frequencies of all the notes are derived
+    pub const A4_FREQ: R32 = r32!(440);
+    /// index A4 would have, even if it's out of the supported range
+    const A4_INDEX: i32 = (4 - Self::FIRST_OCTAVE) * 12 + 9;
+    /// MIDI key number of the lowest note
+    const MIDI_KEY_OFFSET: u8 = (Self::FIRST_OCTAVE as u8 + 1) * 12;
+    pub const SEMITONE_NAMES: [&'static str; 12] =
+        ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
 
     pub const fn new(index: u8) -> Option<Self> {
         if index <= Self::MAX.0 {
@@ -175,16 +424,50 @@ impl Note {
         }
     }
 
+    /// Converts a MIDI key number, 60 being C4, into a note, clamping it to the supported range.
+    pub const fn from_midi_key(key: u8) -> Self {
+        Self::saturated(key.saturating_sub(Self::MIDI_KEY_OFFSET))
+    }
+
+    /// Converts the note into a MIDI key number, 60 being C4.
+    pub const fn to_midi_key(self) -> u8 {
+        self.0 + Self::MIDI_KEY_OFFSET
+    }
+
     pub const fn index(&self) -> usize {
         self.0 as usize
     }
 
+    /// frequency of the note in 12-tone equal temperament
     pub fn freq(&self) -> R32 {
-        unsafe { *Self::FREQS.get_unchecked(self.0 as usize) }
+        (R32::from(self.0 as i32 - Self::A4_INDEX) / 12u8).exp2() * Self::A4_FREQ
     }
 
-    pub fn name(&self) -> &'static str {
-        unsafe { Self::NAMES.get_unchecked(self.0 as usize) }
+    /// The note whose frequency is the closest to `freq` on a logarithmic scale, i.e. in
+    /// semitones, clamped to the supported range.
+    pub fn from_freq(freq: R32) -> Self {
+        let Some(octaves) = (freq / Self::A4_FREQ).log2() else {
+            return Self(0);
+        };
+        Self::saturated((octaves * 12u8 + R32::from(Self::A4_INDEX)).round().into())
+    }
+
+    /// Deviation of `freq` from the frequency of the note closest to it in cents, i.e. hundredths
+    /// of a semitone. Beyond the supported range, the deviation is from the closest supported note.
+    pub fn cents_off(freq: R32) -> R32 {
+        (freq / Self::from_freq(freq).freq()).log2_or(-R32::INFINITY) * 1200u16
+    }
+
+    pub fn semitone_name(&self) -> &'static str {
+        unsafe { Self::SEMITONE_NAMES.get_unchecked(self.0 as usize % 12) }
+    }
+
+    pub const fn octave(&self) -> i32 {
+        Self::FIRST_OCTAVE + self.0 as i32 / 12
+    }
+
+    pub fn name(&self) -> String {
+        self.to_string()
     }
 
     pub const fn recip(self) -> Self {
@@ -194,9 +477,904 @@ impl Note {
     pub fn pitch_coef(&self) -> R64 {
         r64!(self.0 as i8 - Self::MID.0 as i8).div(12u8).exp2()
     }
+
+    /// semitone offsets of all the recognised note names from C of the same octave;
+    /// `Cb` & `B#` cross into the neighbouring octaves
+    const PITCH_CLASSES: [(&'static str, i32); 21] = [
+        ("C", 0),
+        ("C#", 1),
+        ("Db", 1),
+        ("D", 2),
+        ("D#", 3),
+        ("Eb", 3),
+        ("E", 4),
+        ("Fb", 4),
+        ("E#", 5),
+        ("F", 5),
+        ("F#", 6),
+        ("Gb", 6),
+        ("G", 7),
+        ("G#", 8),
+        ("Ab", 8),
+        ("A", 9),
+        ("A#", 10),
+        ("Bb", 10),
+        ("B", 11),
+        ("Cb", -1),
+        ("B#", 12),
+    ];
+
+    /// codes of the keys of a computer keyboard that play the notes from `PIANO_FIRST` upwards:
+    /// the home row plays the white keys, the row above it plays the black ones
+    const PIANO_KEYS: [&'static str; 18] = [
+        "KeyA",
+        "KeyW",
+        "KeyS",
+        "KeyE",
+        "KeyD",
+        "KeyF",
+        "KeyT",
+        "KeyG",
+        "KeyY",
+        "KeyH",
+        "KeyU",
+        "KeyJ",
+        "KeyK",
+        "KeyO",
+        "KeyL",
+        "KeyP",
+        "Semicolon",
+        "Quote",
+    ];
+    /// the note played by the 1st of `PIANO_KEYS`, C3
+    const PIANO_FIRST: u8 = 12;
+
+    /// Maps a key code, as in `KeyboardEvent.code`, to the note it plays when the computer
+    /// keyboard is used as a piano.
+    pub fn from_key_code(code: &str) -> Option<Self> {
+        let offset = Self::PIANO_KEYS.iter().position(|x| *x == code)?;
+        Self::new(Self::PIANO_FIRST + offset as u8)
+    }
+
+    /// Parses a note name like `C#4` or `Db4`.
+    /// Returns `None` if the name is malformed or the note is out of the supported range.
+    pub fn from_name(s: &str) -> Option<Self> {
+        let octave_start = s.find(|c: char| c.is_ascii_digit() || c == '-')?;
+        let (class, octave) = s.split_at(octave_start);
+        let (_, semitone) = Self::PITCH_CLASSES.iter().find(|(name, _)| *name == class)?;
+        let octave: i32 = octave.parse().ok()?;
+        let index =
+            octave.checked_sub(Self::FIRST_OCTAVE)?.checked_mul(12)?.checked_add(*semitone)?;
+        Self::new(index.try_into().ok()?)
+    }
+}
+
+#[test]
+fn note_from_key_code() {
+    assert_eq!(Note::from_key_code("KeyA"), Note::from_name("C3"));
+    assert_eq!(Note::from_key_code("KeyW"), Note::from_name("C#3"));
+    assert_eq!(Note::from_key_code("KeyJ"), Note::from_name("B3"));
+    assert_eq!(Note::from_key_code("KeyK"), Note::from_name("C4"));
+    assert_eq!(Note::from_key_code("Quote"), Note::from_name("F4"));
+    assert_eq!(Note::from_key_code("KeyR"), None);
+    assert_eq!(Note::from_key_code("Digit1"), None);
+}
+
+#[test]
+fn note_from_name() {
+    assert_eq!(Note::from_name("C#4"), Note::from_name("Db4"));
+    assert_eq!(Note::from_name("C#4").map(|x| x.name()).as_deref(), Some("C#4"));
+    assert_eq!(Note::from_name("B4"), Some(Note::MAX));
+    assert_eq!(Note::from_name("C2"), Some(Note(0)));
+    assert_eq!(Note::from_name("Cb3"), Note::from_name("B2"));
+    assert_eq!(Note::from_name("C1"), None);
+    assert_eq!(Note::from_name("C9"), None);
+    assert_eq!(Note::from_name("Cb2"), None);
+    assert_eq!(Note::from_name("H4"), None);
+    assert_eq!(Note::from_name("A#2").map(|x| x.to_string()).as_deref(), Some("A#2"));
+    assert_eq!(Note::from_name("C#"), None);
+    assert_eq!(Note::from_name(""), None);
+}
+
+#[test]
+fn note_arith_bounds() {
+    // arithmetic that would leave the supported range fails instead of wrapping or overflowing
+    assert_eq!(Note::MAX + 1u8, None);
+    assert_eq!(Note::MAX + isize::MAX, None);
+    assert_eq!(Note::MAX - -1i8, None);
+    assert_eq!(Note(0) - 1u8, None);
+    assert_eq!(Note(30) + 5u8, Some(Note::MAX));
+    // clamping stops at the highest supported note, B4, not one past it
+    let clamped = [Note::saturated(Note::N_NOTES as u8), Note::saturated(u8::MAX)];
+    for note in clamped.into_iter().chain([Note::from_midi_key(u8::MAX)]) {
+        assert_eq!(note, Note::MAX);
+        assert_eq!(note.name(), "B4");
+        assert!(note.freq() > Note::A4_FREQ && note.freq() < r32!(500));
+    }
+}
+
+#[test]
+fn note_from_freq() {
+    let a4 = Note::from_name("A4");
+    assert_eq!(Some(Note::from_freq(Note::A4_FREQ)), a4);
+    assert!(Note::cents_off(Note::A4_FREQ).abs() < r32!(0.01));
+    // 445 Hz is about 20 cents sharp of A4
+    assert_eq!(Some(Note::from_freq(r32!(445))), a4);
+    let cents = Note::cents_off(r32!(445));
+    assert!(cents > r32!(19) && cents < r32!(20));
+    // the frequencies of the notes map back onto them
+    for i in 0..Note::N_NOTES as u8 {
+        assert_eq!(Note::from_freq(Note(i).freq()), Note(i));
+    }
+    // frequencies out of the supported range are clamped to it
+    assert_eq!(Note::from_freq(r32!(20)), Note(0));
+    assert_eq!(Note::from_freq(R32::ZERO), Note(0));
+    assert_eq!(Note::from_freq(r32!(20000)), Note::MAX);
+    assert!(Note::cents_off(r32!(20000)) > r32!(1200));
+}
+
+#[test]
+fn scale_snap() {
+    // C4, C#4, D4 & D#4 respectively
+    let [c4, cs4, d4, ds4] = [24, 25, 26, 27].map(Note);
+    assert_eq!(Scale::Chromatic.snap(0, cs4), cs4);
+    assert_eq!(Scale::Major.snap(0, cs4), c4);
+    // D# in A minor lies between D & E, the lower one is preferred
+    assert_eq!(Scale::Minor.snap(9, ds4), d4);
+    assert_eq!(Scale::PentatonicMinor.snap(0, d4), ds4);
+    for i in 0..Note::N_NOTES as u8 {
+        let note = Scale::PentatonicMajor.snap(2, Note(i));
+        let degree = (note.index() as i32 - 2).rem_euclid(12);
+        assert!(Scale::PentatonicMajor.degrees().contains(&degree));
+    }
+}
+
+#[test]
+fn audio_input_cut_reversed() {
+    let mut data: Vec<f32> = (0..100).map(|x| x as f32).collect();
+    let baked = cut_channel(&mut data, 10, 20, true);
+    assert_eq!(baked.len(), 70);
+    assert_eq!(baked.first(), Some(&79.0));
+    assert_eq!(baked.last(), Some(&10.0));
+
+    let mut data: Vec<f32> = (0..100).map(|x| x as f32).collect();
+    let baked = cut_channel(&mut data, 10, 20, false);
+    assert_eq!(baked.first(), Some(&10.0));
+    assert_eq!(baked.last(), Some(&79.0));
+
+    assert!(cut_channel(&mut [0.0; 10], 6, 6, true).is_empty());
+}
+
+#[test]
+fn audio_input_remix() {
+    let left = vec![1.0f32, 0.5, 0.0];
+    let right = vec![0.0f32, -0.5, -1.0];
+    let stereo = remix_channels(&[left.clone(), right.clone()], 2);
+    assert_ne!(stereo[0], stereo[1]);
+    assert_eq!(stereo, [left.clone(), right.clone()]);
+
+    assert_eq!(remix_channels(&[left.clone()], 2), [left.clone(), left.clone()]);
+
+    let quad = remix_channels(&[left.clone(), right.clone(), right.clone(), left.clone()], 2);
+    assert_eq!(quad, [vec![0.5, 0.0, -0.5], vec![0.5, 0.0, -0.5]]);
+    // every source channel contributes to every output channel equally
+    let centre = vec![0.0f32, 0.0, 3.0];
+    let surround = remix_channels(&[left, right, centre], 2);
+    assert_eq!(surround, [vec![1.0 / 3.0, 0.0, 2.0 / 3.0], vec![1.0 / 3.0, 0.0, 2.0 / 3.0]]);
+}
+
+#[test]
+fn audio_input_baked_len() {
+    let bps = r64!(2);
+    let raw_len = Sequencer::SAMPLE_RATE * 2;
+    let raw_duration = Secs(R64::from(raw_len) / Sequencer::SAMPLE_RATE);
+    let rate = Sequencer::SAMPLE_RATE as f32;
+    let mut changes = AudioInputChanges::default();
+    assert_eq!(changes.baked_len(raw_len, bps, rate), raw_len);
+
+    changes.cut_start = raw_duration.to_beats(bps) / 2u8;
+    let baked_duration =
+        Secs(R64::from(changes.baked_len(raw_len, bps, rate)) / Sequencer::SAMPLE_RATE);
+    assert_eq!(baked_duration, raw_duration / 2u8);
+
+    changes.cut_end = raw_duration.to_beats(bps);
+    assert_eq!(changes.baked_len(raw_len, bps, rate), 0);
+
+    // the cuts are measured at the buffer's own sample rate
+    let changes = AudioInputChanges { cut_start: Beats::ONE, ..default() };
+    assert_eq!(changes.baked_len(48000, bps, 48000.0), 24000);
+    assert_eq!(changes.baked_len(48000, bps, 22050.0), 48000 - 11025);
+}
+
+#[test]
+fn audio_input_resample() {
+    let ramp: Vec<f32> = (0..100).map(|x| x as f32).collect();
+    let up = resample(&ramp, 22050.0, 44100.0);
+    assert_eq!(up.len(), ramp.len() * 2);
+    assert_eq!([up[0], up[up.len() - 1]], [0.0, 99.0]);
+    assert!(up.windows(2).all(|x| x[0] < x[1]));
+
+    let down = resample(&ramp, 48000.0, 44100.0);
+    assert_eq!(down.len(), 92);
+    assert_eq!([down[0], down[91]], [0.0, 99.0]);
+    assert_eq!(resample(&ramp, 44100.0, 44100.0), ramp);
+    assert!(resample(&[], 22050.0, 44100.0).is_empty());
+    assert_eq!(resample(&[1.0], 22050.0, 44100.0), [1.0, 1.0]);
+}
+
+#[test]
+fn audio_input_file_resample() {
+    // a WAV file at 22.05KHz with a chunk before the format one, which has to be skipped
+    let mut wav = b"RIFF\0\0\0\0WAVELIST\x03\0\0\0abc\0fmt \x10\0\0\0\x03\0\x01\0".to_vec();
+    wav.extend(22050u32.to_le_bytes());
+    assert_eq!(header_sample_rate(&wav), Some(22050));
+    assert_eq!(header_sample_rate(&wav[..wav.len() - 1]), None);
+
+    let src: Vec<f32> = (0..2205).map(|i| (i as f32 / 10.0).sin()).collect();
+    let res = resample(&src, 22050.0, 44100.0);
+    assert_eq!(res.len(), src.len() * 2);
+    assert_eq!([res[0], res[res.len() - 1]], [src[0], src[src.len() - 1]]);
+    // the resampled signal still passes through every sample of the source
+    for (i, x) in src.iter().enumerate() {
+        let at = i * (res.len() - 1) / (src.len() - 1);
+        assert!((res[at] - x).abs() < 0.1, "{i}: {} vs {x}", res[at]);
+    }
+
+    let mut flac = b"fLaC\0\0\0\x22".to_vec();
+    flac.extend([0; 10]);
+    flac.extend([0x0B, 0xB8, 0x01]);
+    assert_eq!(header_sample_rate(&flac), Some(48000));
+    assert_eq!(header_sample_rate(b"ID3\x04"), None);
+}
+
+#[test]
+fn audio_input_peaks() {
+    let data = [0.5, -0.5, 1.0, 0.0, -1.0, 0.25];
+    assert_eq!(peaks(&data, 3), [[-0.5, 0.5], [0.0, 1.0], [-1.0, 0.25]]);
+    assert_eq!(peaks(&data, 1), [[-1.0, 1.0]]);
+    // shorter than the width
+    assert_eq!(peaks(&data[..2], 4), [[0.5, 0.5], [0.5, 0.5], [-0.5, -0.5], [-0.5, -0.5]]);
+    assert_eq!(peaks(&[], 2), [[0.0; 2]; 2]);
+}
+
+#[test]
+fn audio_input_gain() {
+    let mut channels = vec![vec![0.5, -0.25, 0.0], vec![0.1, -0.5, 0.25]];
+    apply_gain(&mut channels, true, 1.0);
+    assert_eq!(channels, [[1.0, -0.5, 0.0], [0.2, -1.0, 0.5]]);
+    apply_gain(&mut channels, false, 0.5);
+    assert_eq!(channels, [[0.5, -0.25, 0.0], [0.1, -0.5, 0.25]]);
+
+    let mut silent = vec![vec![0.0; 4]; 2];
+    apply_gain(&mut silent, true, 1.0);
+    assert_eq!(silent, [[0.0; 4]; 2]);
+}
+
+#[test]
+fn audio_input_time_stretch() {
+    let data: Vec<f32> = (0..10000).map(|i| (i as f32 / 10.0).sin() * 0.5 + 0.25).collect();
+    let stretched = time_stretch(&data, 2.0);
+    assert_eq!(stretched.len(), 20000);
+    assert_eq!(time_stretch(&data, 0.5).len(), 5000);
+    assert_eq!(time_stretch(&data, 1.0), data);
+    // the level of the signal is kept
+    let mean = |x: &[f32]| x.iter().sum::<f32>() / x.len() as f32;
+    assert!((mean(&stretched) - mean(&data)).abs() < 0.01);
+    let constant = time_stretch(&[0.5; 3000], 1.5);
+    assert!(constant.iter().all(|x| (x - 0.5).abs() < 1e-4));
+
+    let changes = AudioInputChanges { stretch: r32!(2), ..default() };
+    assert_eq!(changes.baked_len(10000, r64!(1), 44100.0), 20000);
+}
+
+#[test]
+fn audio_input_reverse() {
+    let raw = vec![vec![0.0, 0.25, 0.5, 0.75, 1.0], vec![1.0, 0.5, 0.0, -0.5, -1.0]];
+    let mut changes = AudioInputChanges { cut_start: Beats::ONE, ..default() };
+    let apply = |changes: AudioInputChanges| changes.apply(raw.clone(), r64!(1), 1.0);
+    let baked = apply(changes);
+    assert_eq!(baked, [&raw[0][1..], &raw[1][1..]]);
+
+    changes.reversed = !changes.reversed;
+    // the cut stays at the start of the original audio
+    assert_eq!(apply(changes), [[1.0, 0.75, 0.5, 0.25], [-1.0, -0.5, 0.0, 0.5]]);
+    changes.reversed = !changes.reversed;
+    assert_eq!(apply(changes), baked);
+}
+
+#[test]
+fn audio_input_fades() {
+    let mut data = [1.0f32; 100];
+    apply_fades(&mut data, 0, 0);
+    assert_eq!(data, [1.0; 100]);
+
+    apply_fades(&mut data, 10, 0);
+    assert!(data[0].abs() < 1e-6);
+    assert!((data[10] - 1.0).abs() < 1e-6);
+    assert!((data[99] - 1.0).abs() < 1e-6);
+
+    let mut data = [1.0f32; 8];
+    apply_fades(&mut data, 0, 100);
+    assert!(data[7].abs() < 1e-6);
+    assert!(data.windows(2).all(|w| w[0] > w[1]));
+}
+
+#[test]
+fn note_freq() {
+    let a4 = Note::from_name("A4").map(|x| x.freq());
+    assert!(a4.is_some_and(|x| (*x - 440.0).abs() < 0.01), "{a4:?}");
+    let c2 = Note(0).freq();
+    assert!((*c2 - 65.406).abs() < 0.01, "{c2}");
+    let a3 = Note::from_name("A3").map(|x| x.freq());
+    assert!(a3.is_some_and(|x| (*x - 220.0).abs() < 0.01), "{a3:?}");
+}
+
+/// a set of notes, relative to a root, to which the notes of a pattern are snapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scale {
+    #[default]
+    Chromatic,
+    Major,
+    Minor,
+    PentatonicMajor,
+    PentatonicMinor,
+}
+
+impl Scale {
+    pub const ALL: [Self; 5] =
+        [Self::Chromatic, Self::Major, Self::Minor, Self::PentatonicMajor, Self::PentatonicMinor];
+    pub const NAMES: [&'static str; 5] =
+        ["Chromatic", "Major", "Minor", "Pentatonic Major", "Pentatonic Minor"];
+
+    /// semitones from the root that belong to the scale
+    pub const fn degrees(self) -> &'static [i32] {
+        match self {
+            Self::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            Self::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Self::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            Self::PentatonicMajor => &[0, 2, 4, 7, 9],
+            Self::PentatonicMinor => &[0, 3, 5, 7, 10],
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the in-scale note nearest to `note`, preferring the lower one of 2 equally near.
+    /// `root` is the semitone of the scale's root, counting from C.
+    pub fn snap(self, root: u8, note: Note) -> Note {
+        let rel = (note.index() as i32 - root as i32).rem_euclid(12);
+        let mut shifts: Vec<i32> =
+            self.degrees().iter().flat_map(|d| [d - 12, *d, d + 12]).map(|d| d - rel).collect();
+        shifts.sort_by_key(|x| (x.abs(), *x));
+        shifts
+            .into_iter()
+            .find_map(|shift| Note::new((note.index() as i32 + shift).try_into().ok()?))
+            .unwrap_or(note)
+    }
+}
+
+/// a set of notes placed at once in the note editor, relative to the clicked note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Chord {
+    #[default]
+    Single,
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Major7,
+    Minor7,
+    Dominant7,
+}
+
+impl Chord {
+    pub const ALL: [Self; 10] = [
+        Self::Single,
+        Self::Major,
+        Self::Minor,
+        Self::Diminished,
+        Self::Augmented,
+        Self::Sus2,
+        Self::Sus4,
+        Self::Major7,
+        Self::Minor7,
+        Self::Dominant7,
+    ];
+    pub const NAMES: [&'static str; 10] = [
+        "Single Note",
+        "Major",
+        "Minor",
+        "Diminished",
+        "Augmented",
+        "Sus2",
+        "Sus4",
+        "Major 7th",
+        "Minor 7th",
+        "Dominant 7th",
+    ];
+
+    /// semitones from the root that make up the chord
+    pub const fn intervals(self) -> &'static [isize] {
+        match self {
+            Self::Single => &[0],
+            Self::Major => &[0, 4, 7],
+            Self::Minor => &[0, 3, 7],
+            Self::Diminished => &[0, 3, 6],
+            Self::Augmented => &[0, 4, 8],
+            Self::Sus2 => &[0, 2, 7],
+            Self::Sus4 => &[0, 5, 7],
+            Self::Major7 => &[0, 4, 7, 11],
+            Self::Minor7 => &[0, 3, 7, 10],
+            Self::Dominant7 => &[0, 4, 7, 10],
+        }
+    }
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
 }
 
+/// a filter through which every note of a sound is passed
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterType {
+    #[default]
+    None,
+    LowPass,
+    HighPass,
+}
+
+impl FilterType {
+    pub const ALL: [Self; 3] = [Self::None, Self::LowPass, Self::HighPass];
+    pub const NAMES: [&'static str; 3] = ["None", "Low-pass", "High-pass"];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the configuration of the filter node with cutoff frequency `freq` in Hz
+    /// and resonance `q`, or `None` if no filtering is to be done.
+    pub fn params(self, freq: R64, q: R64) -> Option<FilterParams> {
+        let kind = match self {
+            Self::None => return None,
+            Self::LowPass => BiquadFilterType::Lowpass,
+            Self::HighPass => BiquadFilterType::Highpass,
+        };
+        Some(FilterParams { kind, freq: *freq as f32, q: *q as f32 })
+    }
+}
+
+/// configuration of a `BiquadFilterNode`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterParams {
+    pub kind: BiquadFilterType,
+    pub freq: f32,
+    pub q: f32,
+}
+
+impl FilterParams {
+    pub fn create(self, ctx: &BaseAudioContext) -> Result<BiquadFilterNode> {
+        let res = ctx.create_biquad_filter()?;
+        res.set_type(self.kind);
+        res.frequency().set_value(self.freq);
+        res.q().set_value(self.q);
+        Ok(res)
+    }
+}
+
+/// configuration of a `StereoPannerNode`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanParams {
+    /// -1 is fully left, 1 is fully right
+    pub pan: f32,
+}
+
+impl PanParams {
+    /// Clamps `pan` into the range accepted by `StereoPannerNode`.
+    pub fn new(pan: R32) -> Self {
+        Self { pan: *pan.clamp(r32!(-1), r32!(1)) }
+    }
+
+    pub fn create(self, ctx: &BaseAudioContext) -> Result<StereoPannerNode> {
+        let res = ctx.create_stereo_panner()?;
+        res.pan().set_value(self.pan);
+        Ok(res)
+    }
+}
+
+/// waveform of an LFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    Square,
+    Sawtooth,
+}
+
+impl LfoShape {
+    pub const ALL: [Self; 4] = [Self::Sine, Self::Triangle, Self::Square, Self::Sawtooth];
+    pub const NAMES: [&'static str; 4] = ["Sine", "Triangle", "Square", "Sawtooth"];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn oscillator_type(self) -> OscillatorType {
+        match self {
+            Self::Sine => OscillatorType::Sine,
+            Self::Triangle => OscillatorType::Triangle,
+            Self::Square => OscillatorType::Square,
+            Self::Sawtooth => OscillatorType::Sawtooth,
+        }
+    }
+}
+
+/// Returns the frequency in Hz of an LFO whose cycle lasts `rate` beats in tempo `bps`.
+pub fn lfo_freq(rate: Beats, bps: R64) -> R64 {
+    bps / rate.0
+}
+
+/// configuration of a low-frequency oscillator modulating an `AudioParam`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LfoParams {
+    pub shape: OscillatorType,
+    /// in Hz
+    pub freq: f32,
+    /// how far the modulated parameter deviates from its value in either direction
+    pub depth: f32,
+}
+
+impl LfoParams {
+    /// Creates an LFO of `shape` with a cycle of `rate` beats in tempo `bps`, or returns `None`
+    /// if the LFO would have no effect.
+    pub fn new(shape: LfoShape, rate: Beats, depth: R64, bps: R64) -> Option<Self> {
+        if rate <= Beats::ZERO || depth == R64::ZERO {
+            return None;
+        }
+        let freq = *lfo_freq(rate, bps) as f32;
+        Some(Self { shape: shape.oscillator_type(), freq, depth: *depth as f32 })
+    }
+
+    /// Connects the LFO to `target`, registering its nodes in `nodes`.
+    /// The returned oscillator is yet to be started.
+    pub fn create(
+        self,
+        ctx: &BaseAudioContext,
+        target: &AudioParam,
+        nodes: &mut NodeChain,
+    ) -> Result<OscillatorNode> {
+        let res = nodes.add(ctx.create_oscillator()?);
+        res.set_type(self.shape);
+        res.frequency().set_value(self.freq);
+        let depth = nodes.add(ctx.create_gain()?);
+        depth.gain().set_value(self.depth);
+        res.connect_with_audio_node(&depth)?.connect_with_audio_param(target)?;
+        Ok(res)
+    }
+}
+
+#[test]
+fn lfo_rate_to_freq() {
+    // 120 BPM
+    let bps = r64!(2);
+    assert_eq!(lfo_freq(Beats::ONE, bps), r64!(2));
+    assert_eq!(lfo_freq(Beats(r64!(4)), bps), r64!(0.5));
+    assert_eq!(lfo_freq(Beats(r64!(0.25)), r64!(1.5)), r64!(6));
+    let params = LfoParams::new(LfoShape::Square, Beats(r64!(2)), r64!(300), bps);
+    let expected = LfoParams { shape: OscillatorType::Square, freq: 1.0, depth: 300.0 };
+    assert_eq!(params, Some(expected));
+    assert_eq!(LfoParams::new(LfoShape::Sine, Beats(r64!(2)), R64::ZERO, bps), None);
+}
+
+#[test]
+fn sound_filter_params() {
+    let sound = NoteSound {
+        filter_type: FilterType::LowPass,
+        filter_freq: r64!(440),
+        filter_q: r64!(2.5),
+        ..default()
+    };
+    let expected = FilterParams { kind: BiquadFilterType::Lowpass, freq: 440.0, q: 2.5 };
+    assert_eq!(sound.filter(), Some(expected));
+    assert_eq!(NoteSound { filter_type: FilterType::None, ..sound }.filter(), None);
+}
+
+#[test]
+fn sound_pan_params() {
+    let sound = NoiseSound { pan: r32!(-1), ..default() };
+    assert_eq!(sound.panner(), PanParams { pan: -1.0 });
+    assert_eq!(SampleParams { pan: r32!(3), ..default() }.panner(), PanParams { pan: 1.0 });
+}
+
+/// an audio node that can be disconnected from all of its outputs
+pub trait Detach {
+    fn detach(&self) -> Result;
+}
+
+impl Detach for AudioNode {
+    fn detach(&self) -> Result {
+        Ok(self.disconnect()?)
+    }
+}
+
+/// Audio nodes created together, e.g. to play a single note, disconnected all at once on
+/// `teardown` or when the chain is dropped, so that an error in the middle of connecting the nodes
+/// doesn't leave the already connected ones behind.
+#[derive(Debug)]
+pub struct NodeChain<N: Detach = AudioNode>(Vec<N>);
+
+impl<N: Detach> Default for NodeChain<N> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl NodeChain {
+    pub const fn new() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<N: Detach> Drop for NodeChain<N> {
+    fn drop(&mut self) {
+        self.teardown()
+    }
+}
+
+impl<N: Detach> NodeChain<N> {
+    /// Registers `node` to be disconnected on teardown, returning it back.
+    pub fn add<T: Clone + Into<N>>(&mut self, node: T) -> T {
+        self.0.push(node.clone().into());
+        node
+    }
+
+    /// Registers `node` to be disconnected on teardown & returns it if there's one, otherwise
+    /// returns `plug`, for a sound to be played through whichever is returned.
+    pub fn add_or<T: Clone + Into<N>>(&mut self, node: Option<T>, plug: T) -> T {
+        node.map_or(plug, |node| self.add(node))
+    }
+
+    /// Disconnects every registered node, reporting the errors, & forgets about the nodes.
+    pub fn teardown(&mut self) {
+        for node in self.0.drain(..) {
+            node.detach().report();
+        }
+    }
+}
+
+/// a node that counts how many times it's been detached
+#[cfg(test)]
+#[derive(Clone)]
+struct Probe(Rc<std::cell::Cell<u32>>);
+
+#[cfg(test)]
+impl Detach for Probe {
+    fn detach(&self) -> Result {
+        Ok(self.0.set(self.0.get() + 1))
+    }
+}
+
+#[test]
+fn node_chain_teardown() {
+    use std::{array::from_fn, cell::Cell};
+    let counts: [_; 3] = from_fn(|_| Rc::new(Cell::new(0)));
+    let mut chain = NodeChain::<Probe>::default();
+    for count in &counts {
+        chain.add(Probe(count.clone()));
+    }
+    chain.teardown();
+    chain.teardown();
+    drop(chain);
+    assert!(counts.iter().all(|x| x.get() == 1));
+    // a chain that's dropped without being torn down, e.g. on an error, is torn down too
+    NodeChain::<Probe>::default().add(Probe(counts[0].clone()));
+    assert_eq!(counts[0].get(), 2);
+}
+
+#[test]
+fn automation_teardown() {
+    use std::{array::from_fn, cell::Cell};
+    // playing routes the automated blocks through their gain nodes & the rest straight to the plug
+    let counts: [_; 3] = from_fn(|_| Rc::new(Cell::new(0)));
+    let mut automation = NodeChain::<Probe>::default();
+    let plug = Probe(counts[0].clone());
+    for automated in [Some(&counts[1]), None, Some(&counts[2])] {
+        let routed = automation.add_or(automated.map(|x| Probe(x.clone())), plug.clone());
+        let expected = automated.unwrap_or(&counts[0]);
+        assert!(Rc::ptr_eq(&routed.0, expected));
+    }
+    assert_eq!(automation.0.len(), 2);
+    // stopping disconnects every gain node & forgets about them, leaving the plug connected
+    automation.teardown();
+    assert!(automation.0.is_empty());
+    assert_eq!(counts.map(|x| x.get()), [0, 1, 1]);
+}
+
+/// Named patterns of a sound block, only the active one of which is played & edited.
+#[derive(Debug, Clone)]
+pub struct Patterns<T: GraphPoint> {
+    patterns: Vec<(Rc<str>, Shared<GraphEditor<T>>)>,
+    active: usize,
+}
+
+impl<T: GraphPoint> Default for Patterns<T> {
+    fn default() -> Self {
+        GraphEditor::default().into()
+    }
+}
+
+impl<T: GraphPoint> From<GraphEditor<T>> for Patterns<T> {
+    fn from(value: GraphEditor<T>) -> Self {
+        Self { patterns: vec![(Self::default_name(0), value.into())], active: 0 }
+    }
+}
+
+impl<T: GraphPoint> Patterns<T> {
+    /// `active` must be a valid index into `patterns`
+    pub fn new(patterns: Vec<(Rc<str>, Shared<GraphEditor<T>>)>, active: usize) -> Result<Self> {
+        ensure!(active < patterns.len(), "no pattern #{active} among {} patterns", patterns.len());
+        Ok(Self { patterns, active })
+    }
+
+    fn default_name(index: usize) -> Rc<str> {
+        format!("Pattern {}", index + 1).into()
+    }
+
+    /// the pattern that's played & edited
+    pub fn active(&self) -> &Shared<GraphEditor<T>> {
+        &self.patterns[self.active].1
+    }
+
+    pub const fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// all the patterns with their names, in the order of their addition
+    pub fn all(&self) -> &[(Rc<str>, Shared<GraphEditor<T>>)] {
+        &self.patterns
+    }
+
+    /// Adds a copy of the active pattern & makes it the active one.
+    pub fn add(&mut self) -> Result<EditorAction> {
+        let copy = GraphEditor::new(self.active().get()?.snapshot().into_vec());
+        let pattern = (Self::default_name(self.patterns.len()), Shared::from(copy));
+        self.patterns.push(pattern.clone());
+        let prev_active = replace(&mut self.active, self.patterns.len() - 1);
+        Ok(EditorAction::AddPattern { pattern: Rc::new(pattern), prev_active })
+    }
+
+    /// Makes the pattern at `index` the active one, returning the index of the previously
+    /// active one.
+    pub fn select(&mut self, index: usize) -> Result<usize> {
+        ensure!(index < self.patterns.len(), "no pattern #{index} among {}", self.patterns.len());
+        Ok(replace(&mut self.active, index))
+    }
+
+    /// Renames the active pattern, returning its previous name.
+    pub fn rename(&mut self, name: Rc<str>) -> Rc<str> {
+        replace(&mut self.patterns[self.active].0, name)
+    }
+
+    #[apply(fallible!)]
+    pub fn handle_event(&mut self, event: &AppEvent, mut ctx: ContextMut) {
+        match event {
+            AppEvent::AddPattern => {
+                let action = self.add()?;
+                ctx.register_action(action)?
+            }
+
+            &AppEvent::SelectPattern(to) => {
+                if to != self.active {
+                    let from = self.select(to)?;
+                    ctx.register_action(EditorAction::SelectPattern { from, to })?;
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
+                }
+            }
+
+            AppEvent::RenamePattern(e) => {
+                let to: Rc<str> = e.target_dyn_into::<HtmlInputElement>()?.value().into();
+                if !to.is_empty() {
+                    let from = self.rename(to.clone());
+                    ctx.register_action(EditorAction::RenamePattern {
+                        index: self.active,
+                        from,
+                        to,
+                    })?
+                }
+            }
+
+            AppEvent::Undo(actions) => {
+                for action in actions.iter() {
+                    match action {
+                        &EditorAction::AddPattern { prev_active, .. } => {
+                            self.patterns.pop();
+                            self.select(prev_active)?;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        &EditorAction::SelectPattern { from, .. } => {
+                            self.select(from)?;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::RenamePattern { index, from, .. } => {
+                            self.patterns.get_mut(*index)?.0 = from.clone()
+                        }
+
+                        _ => (),
+                    }
+                }
+            }
+
+            AppEvent::Redo(actions) => {
+                for action in actions.iter() {
+                    match action {
+                        EditorAction::AddPattern { pattern, .. } => {
+                            let pattern =
+                                pattern.downcast_ref::<(Rc<str>, Shared<GraphEditor<T>>)>();
+                            self.patterns.push(pattern?.clone());
+                            self.active = self.patterns.len() - 1;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        &EditorAction::SelectPattern { to, .. } => {
+                            self.select(to)?;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::RenamePattern { index, to, .. } => {
+                            self.patterns.get_mut(*index)?.0 = to.clone()
+                        }
+
+                        _ => (),
+                    }
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    /// Renders the controls for switching between, adding & renaming the patterns.
+    pub fn render(&self, emitter: &Callback<AppEvent>) -> Html {
+        html! {
+            <div class="pattern-list">
+                for (i, (name, _)) in self.patterns.iter().enumerate() {
+                    <Button
+                        name={format!("Select pattern \"{name}\"")}
+                        class={classes!((i == self.active).then_some("selected"))}
+                        onclick={emitter.reform(move |_| AppEvent::SelectPattern(i))}
+                    >
+                        <span>{ name.clone() }</span>
+                    </Button>
+                }
+                <Button
+                    name="Add a pattern"
+                    class="small"
+                    help="Add a copy of the active pattern"
+                    onclick={emitter.reform(|_| AppEvent::AddPattern)}
+                >
+                    <img::Plus />
+                </Button>
+                <input
+                    type="text"
+                    value={self.patterns[self.active].0.clone()}
+                    placeholder="Enter name..."
+                    required=true
+                    class="dark-bg blue-border"
+                    data-main-hint="Active pattern name"
+                    onchange={emitter.reform(AppEvent::RenamePattern)}
+                />
+            </div>
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct AudioInputChanges {
     /// Make the input play backwards.
     pub reversed: bool,
@@ -204,53 +1382,360 @@ pub struct AudioInputChanges {
     pub cut_start: Beats,
     /// cut the input from the end.
     pub cut_end: Beats,
+    /// length of the linear fade-in at the start of the input, applied after the cuts.
+    pub fade_in: Beats,
+    /// length of the linear fade-out at the end of the input, applied after the cuts.
+    pub fade_out: Beats,
+    /// Scale the input so that its peak is at 1, applied after the fades.
+    pub normalize: bool,
+    /// multiplier of the input's amplitude, applied after the normalization.
+    pub gain: R32,
+    /// multiplier of the input's duration that keeps its pitch, applied after the cuts.
+    pub stretch: R32,
+}
+
+impl Default for AudioInputChanges {
+    fn default() -> Self {
+        Self {
+            reversed: false,
+            cut_start: Beats::ZERO,
+            cut_end: Beats::ZERO,
+            fade_in: Beats::ZERO,
+            fade_out: Beats::ZERO,
+            normalize: false,
+            gain: R32::ONE,
+            stretch: R32::ONE,
+        }
+    }
+}
+
+/// Remixes the channels of `src` into `n_out` channels.
+/// Channels are copied 1:1 where possible; if there are less source channels than `n_out`, the
+/// source channels are repeated, e.g. a mono source is copied into every output channel.
+/// If there are more source channels than `n_out`, they're down-mixed: every output channel is
+/// the average of all the source channels.
+fn remix_channels(src: &[Vec<f32>], n_out: usize) -> Vec<Vec<f32>> {
+    if src.len() <= n_out {
+        return (0..n_out).map(|i| src[i % src.len()].clone()).collect();
+    }
+    let len = src.iter().map(Vec::len).min().unwrap_or_default();
+    let mixed: Vec<f32> =
+        (0..len).map(|j| src.iter().map(|ch| ch[j]).sum::<f32>() / src.len() as f32).collect();
+    vec![mixed; n_out]
+}
+
+/// The sample rate that audio file `file` is encoded at, if it's a WAV or a FLAC file.
+pub fn encoded_sample_rate(file: &ArrayBuffer) -> Option<u32> {
+    /// number of bytes at the start of the file searched for the sample rate
+    const PROBE_LEN: u32 = 4096;
+    let len = file.byte_length().min(PROBE_LEN);
+    header_sample_rate(&Uint8Array::new_with_byte_offset_and_length(file, 0, len).to_vec())
+}
+
+/// The sample rate that the audio file starting with `header` is encoded at,
+/// if it's a WAV or a FLAC file.
+fn header_sample_rate(header: &[u8]) -> Option<u32> {
+    let read_u32 = |at: usize| Some(u32::from_le_bytes(header.get(at..at + 4)?.try_into().ok()?));
+    match header.get(..4)? {
+        b"RIFF" => {
+            if header.get(8..12)? != b"WAVE" {
+                return None;
+            }
+            let mut chunk = 12;
+            loop {
+                let size = read_u32(chunk + 4)? as usize;
+                if header.get(chunk..chunk + 4)? == b"fmt " {
+                    return read_u32(chunk + 12);
+                }
+                // chunks are padded to an even length
+                chunk += 8 + size + size % 2;
+            }
+        }
+        // the rate is the 20 bits after the 10 bytes of block & frame sizes in STREAMINFO
+        b"fLaC" => {
+            let &[a, b, c] = header.get(18..21)? else { return None };
+            Some(u32::from(a) << 12 | u32::from(b) << 4 | u32::from(c) >> 4)
+        }
+        _ => None,
+    }
+}
+
+/// Linearly resamples `data` from the sample rate `from` to `to`, keeping its duration within
+/// 1 sample and its first & last samples intact.
+fn resample(data: &[f32], from: f32, to: f32) -> Vec<f32> {
+    let len = (data.len() as f64 * to as f64 / from as f64).round() as usize;
+    let Some(&first) = data.first() else { return vec![] };
+    if len < 2 || data.len() < 2 {
+        return vec![first; len];
+    }
+    let step = (data.len() - 1) as f64 / (len - 1) as f64;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            let lower = data.get_saturating(pos.floor() as usize);
+            let upper = data.get_saturating(pos.ceil() as usize);
+            let weight = pos.fract() as f32;
+            lower * (1.0 - weight) + upper * weight
+        })
+        .collect()
+}
+
+/// Reduces `data` to `width` columns, each being the minimum & maximum of the samples it covers.
+/// If `data` is shorter than `width`, the samples are stretched over several columns.
+fn peaks(data: &[f32], width: usize) -> Vec<[f32; 2]> {
+    if data.is_empty() {
+        return vec![[0.0; 2]; width];
+    }
+    (0..width)
+        .map(|i| {
+            let start = i * data.len() / width;
+            let end = ((i + 1) * data.len() / width).max(start + 1);
+            let column = data.get(start..end).unwrap_or_default();
+            column.iter().fold([f32::INFINITY, f32::NEG_INFINITY], |[min, max], &x| {
+                [min.min(x), max.max(x)]
+            })
+        })
+        .collect()
+}
+
+/// Scales `channels` so that their common peak is at 1 if `normalize` is true, then multiplies
+/// them by `gain`. Silent channels aren't normalized.
+fn apply_gain(channels: &mut [Vec<f32>], normalize: bool, gain: f32) {
+    let peak = channels.iter().flatten().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    let factor = if normalize && peak > 0.0 { gain / peak } else { gain };
+    for x in channels.iter_mut().flatten() {
+        *x *= factor;
+    }
+}
+
+/// Number of samples `len` samples are turned into by `time_stretch` with the factor `factor`.
+fn stretched_len(len: usize, factor: f32) -> usize {
+    (len as f64 * factor as f64).round() as usize
+}
+
+/// Changes the duration of `data` by `factor` while keeping its pitch, by overlap-adding
+/// Hann-windowed frames of it taken at a different pace than they're put at.
+/// Being the simplest of such methods, it smears transients & may make tonal sounds phasey,
+/// the more so the further `factor` is from 1.
+fn time_stretch(data: &[f32], factor: f32) -> Vec<f32> {
+    const FRAME: usize = 1024;
+    const OUT_HOP: usize = FRAME / 4;
+    let len = stretched_len(data.len(), factor);
+    if factor == 1.0 || data.is_empty() {
+        return data.to_vec();
+    }
+    let in_hop = OUT_HOP as f64 / factor as f64;
+    let window: Vec<f32> = (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME as f32).cos())
+        .collect();
+    let mut res = vec![0.0f32; len];
+    let mut weights = vec![0.0f32; len];
+    // the frames are centred on the points they're taken from & put at,
+    // so the edges of the data get the full window weight too
+    for k in 0..=len / OUT_HOP + 1 {
+        let out_centre = (k * OUT_HOP) as isize;
+        let in_centre = (k as f64 * in_hop).round() as isize;
+        for (i, w) in window.iter().enumerate() {
+            let i = i as isize - (FRAME / 2) as isize;
+            let [Ok(out), Ok(src)] = [out_centre + i, in_centre + i].map(usize::try_from) else {
+                continue;
+            };
+            let (Some(dst), Some(weight), Some(x)) =
+                (res.get_mut(out), weights.get_mut(out), data.get(src))
+            else {
+                continue;
+            };
+            *dst += x * w;
+            *weight += w;
+        }
+    }
+    for (x, w) in res.iter_mut().zip(weights) {
+        if w > f32::EPSILON {
+            *x /= w;
+        }
+    }
+    res
+}
+
+/// See `AudioInput::waveform_path`
+fn buffer_waveform_path(buffer: &AudioBuffer, width: u32, height: u32) -> Result<String> {
+    let peaks = peaks(&buffer.get_channel_data(0)?, width as usize);
+    let half = height as f32 / 2.0;
+    let to_y = |x: f32| (1.0 - x.clamp(-1.0, 1.0)) * half;
+    let mut res = format!("M 0 {half}");
+    for (x, [_, max]) in peaks.iter().enumerate() {
+        res += &format!(" L {x} {} L {} {}", to_y(*max), x + 1, to_y(*max));
+    }
+    for (x, [min, _]) in peaks.iter().enumerate().rev() {
+        res += &format!(" L {} {} L {x} {}", x + 1, to_y(*min), to_y(*min));
+    }
+    res += " Z";
+    Ok(res)
+}
+
+/// Converts a duration in beats to a number of samples at the sample rate `rate`.
+fn beats_to_samples(x: Beats, bps: R64, rate: f32) -> usize {
+    (*x.to_secs(bps) * rate as f64) as usize
+}
+
+impl AudioInputChanges {
+    /// Number of samples left of a buffer of `raw_len` samples at the sample rate `rate` after the
+    /// changes are baked into it.
+    pub fn baked_len(&self, raw_len: u32, bps: R64, rate: f32) -> u32 {
+        let cuts =
+            beats_to_samples(self.cut_start, bps, rate) + beats_to_samples(self.cut_end, bps, rate);
+        stretched_len(raw_len.saturating_sub(cuts as u32) as usize, *self.stretch) as u32
+    }
+
+    /// Applies the changes to the channels of a buffer at the sample rate `rate`.
+    fn apply(&self, channels: Vec<Vec<f32>>, bps: R64, rate: f32) -> Vec<Vec<f32>> {
+        let cut_start = beats_to_samples(self.cut_start, bps, rate);
+        let cut_end = beats_to_samples(self.cut_end, bps, rate);
+        let fade_in = beats_to_samples(self.fade_in, bps, rate);
+        let fade_out = beats_to_samples(self.fade_out, bps, rate);
+        let mut res: Vec<_> = channels
+            .into_iter()
+            .map(|mut data| {
+                let data = cut_channel(&mut data, cut_start, cut_end, self.reversed);
+                let mut data = time_stretch(data, *self.stretch);
+                apply_fades(&mut data, fade_in, fade_out);
+                data
+            })
+            .collect();
+        apply_gain(&mut res, self.normalize, *self.gain);
+        res
+    }
+}
+
+/// Returns the part of `data` left after cutting `cut_start` samples off its start and `cut_end`
+/// samples off its end, reversed if `reversed` is true.
+/// The cuts are applied to the original orientation of `data`.
+fn cut_channel(data: &mut [f32], cut_start: usize, cut_end: usize, reversed: bool) -> &mut [f32] {
+    let start = cut_start.min(data.len());
+    let end = data.len().saturating_sub(cut_end).max(start);
+    let data = &mut data[start..end];
+    if reversed {
+        data.reverse();
+    }
+    data
+}
+
+/// Applies a linear fade-in over the first `fade_in` samples of `data` and a linear fade-out over
+/// its last `fade_out` samples. Both lengths are clamped to the length of `data`.
+fn apply_fades(data: &mut [f32], fade_in: usize, fade_out: usize) {
+    let fade_in = fade_in.min(data.len());
+    for (i, x) in data[..fade_in].iter_mut().enumerate() {
+        *x *= i as f32 / fade_in as f32;
+    }
+    let fade_out = fade_out.min(data.len());
+    for (i, x) in data.iter_mut().rev().take(fade_out).enumerate() {
+        *x *= i as f32 / fade_out as f32;
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AudioInput {
     name: Rc<str>,
+    /// sample rate of the audio before it was resampled to the project's one
+    original_rate: u32,
     duration: Secs,
     raw: AudioBuffer,
     raw_duration: Secs,
     pending_changes: AudioInputChanges,
     baked_changes: AudioInputChanges,
     baked: AudioBuffer,
+    /// SVG path of the baked buffer's waveform, see `AudioInput::THUMBNAIL_SIZE`
+    thumbnail: AttrValue,
+    /// SVG path of the raw buffer's waveform, see `AudioInput::THUMBNAIL_SIZE`
+    raw_thumbnail: AttrValue,
 }
 
 impl AudioInput {
+    /// size of the waveform's thumbnail in SVG user units
+    pub const THUMBNAIL_SIZE: [u32; 2] = [200, 40];
+
     pub fn new(name: Rc<str>, mut buffer: AudioBuffer) -> Result<Self> {
         if buffer.number_of_channels() != Sequencer::CHANNEL_COUNT {
             let new_buffer = AudioBuffer::new(
-                AudioBufferOptions::new(buffer.length(), Sequencer::SAMPLE_RATE as f32)
+                AudioBufferOptions::new(buffer.length(), buffer.sample_rate())
                     .number_of_channels(Sequencer::CHANNEL_COUNT),
             )?;
-            let main_ch = buffer.get_channel_data(0)?;
-            for ch_id in 0..Sequencer::CHANNEL_COUNT as i32 {
-                new_buffer.copy_to_channel(&main_ch, ch_id)?;
+            let src = (0..buffer.number_of_channels())
+                .map(|ch_id| buffer.get_channel_data(ch_id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let remixed = remix_channels(&src, Sequencer::CHANNEL_COUNT as usize);
+            for (ch_id, ch) in remixed.iter().enumerate() {
+                new_buffer.copy_to_channel(ch, ch_id as i32)?;
             }
             buffer = new_buffer;
         }
         let duration = buffer.duration().try_into()?;
-        Ok(Self {
+        let mut res = Self {
             name,
+            original_rate: buffer.sample_rate() as u32,
             duration,
             baked: buffer.clone(),
             raw: buffer,
             raw_duration: duration,
             pending_changes: default(),
             baked_changes: default(),
-        })
+            thumbnail: default(),
+            raw_thumbnail: default(),
+        };
+        let [w, h] = Self::THUMBNAIL_SIZE;
+        res.thumbnail = res.waveform_path(w, h)?.into();
+        res.raw_thumbnail = buffer_waveform_path(&res.raw, w, h)?.into();
+        Ok(res)
     }
 
     pub fn from_file(file: File, sequencer: &Sequencer) -> impl Future<Output = Result<Self>> {
-        Self::from_file_base(file, sequencer.audio_ctx().clone())
+        Self::from_file_base(file, sequencer.audio_ctx().clone(), sequencer.sample_rate())
     }
 
-    async fn from_file_base(file: File, audio_ctx: BaseAudioContext) -> Result<Self> {
-        let raw = JsFuture::from(file.array_buffer()).await?.dyn_into()?;
-        let buffer: AudioBuffer =
+    async fn from_file_base(
+        file: File,
+        audio_ctx: BaseAudioContext,
+        sample_rate: u32,
+    ) -> Result<Self> {
+        let raw: ArrayBuffer = JsFuture::from(file.array_buffer()).await?.dyn_into()?;
+        // decoding detaches the buffer, so the rate has to be read beforehand
+        let encoded_rate = encoded_sample_rate(&raw);
+        let mut buffer: AudioBuffer =
             JsFuture::from(audio_ctx.decode_audio_data(&raw)?).await?.dyn_into()?;
-        Self::new(format!("File {:?}", file.name()).into(), buffer)
+        // the browser decodes the file at the rate of the audio context, not at its own
+        let decoded_rate = buffer.sample_rate();
+        let original_rate = encoded_rate.unwrap_or(decoded_rate as u32);
+        if decoded_rate != sample_rate as f32 {
+            let channels = (0..buffer.number_of_channels())
+                .map(|ch_id| {
+                    let data = buffer.get_channel_data(ch_id)?;
+                    Ok(resample(&data, decoded_rate, sample_rate as f32))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            buffer = AudioBuffer::new(
+                AudioBufferOptions::new(
+                    channels.first().map_or(0, Vec::len) as u32,
+                    sample_rate as f32,
+                )
+                .number_of_channels(buffer.number_of_channels()),
+            )?;
+            for (ch_id, ch) in channels.iter().enumerate() {
+                buffer.copy_to_channel(ch, ch_id as i32)?;
+            }
+        }
+        Ok(Self::new(format!("File {:?}", file.name()).into(), buffer)?
+            .with_original_rate(original_rate))
+    }
+
+    /// Sets the sample rate the audio had before being resampled to the project's one.
+    pub const fn with_original_rate(mut self, rate: u32) -> Self {
+        self.original_rate = rate;
+        self
+    }
+
+    /// Sample rate of the audio before it was resampled to the project's one.
+    pub const fn original_rate(&self) -> u32 {
+        self.original_rate
     }
 
     /// Name of the input, exists solely for the user's convenience.
@@ -285,32 +1770,47 @@ impl AudioInput {
     }
 
     /// Bake all of the changes into a buffer that will be accessible through `.baked()` method.
+    /// Returns whether there were any changes to bake, i.e. whether the baked duration might've
+    /// changed.
     /// If an error occurs, the input will appear unbaked.
-    pub fn bake(&mut self, bps: Beats) -> Result {
+    pub fn bake(&mut self, bps: R64) -> Result<bool> {
         if self.pending_changes == self.baked_changes {
-            return Ok(());
+            return Ok(false);
         };
-        let cut_start =
-            (*self.pending_changes.cut_start.to_secs(bps) * Sequencer::SAMPLE_RATE as f64) as usize;
-        let cut_end =
-            (*self.pending_changes.cut_end.to_secs(bps) * Sequencer::SAMPLE_RATE as f64) as usize;
-        let length = self.raw.length() - cut_start as u32 - cut_end as u32;
+        let rate = self.raw.sample_rate();
+        let length = self.pending_changes.baked_len(self.raw.length(), bps, rate);
         self.baked = AudioBuffer::new(
-            AudioBufferOptions::new(length, Sequencer::SAMPLE_RATE as f32)
-                .number_of_channels(Sequencer::CHANNEL_COUNT),
+            AudioBufferOptions::new(length, rate).number_of_channels(Sequencer::CHANNEL_COUNT),
         )?;
 
-        // TODO: this doesn't affect anything for some reason.
-        self.duration = R64::from(length) / Sequencer::SAMPLE_RATE;
-        for i in 0..Sequencer::CHANNEL_COUNT {
-            let mut data = self.raw.get_channel_data(i)?;
-            if self.pending_changes.reversed {
-                data.reverse();
-            }
-            self.baked.copy_to_channel(&data[cut_start..], i as i32)?;
+        self.duration = Secs(R64::from(self.baked.length()) / rate);
+        let channels = (0..Sequencer::CHANNEL_COUNT)
+            .map(|i| self.raw.get_channel_data(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, data) in self.pending_changes.apply(channels, bps, rate).iter().enumerate() {
+            self.baked.copy_to_channel(data, i as i32)?;
         }
 
-        Ok(self.baked_changes = self.pending_changes)
+        self.baked_changes = self.pending_changes;
+        let [w, h] = Self::THUMBNAIL_SIZE;
+        self.thumbnail = self.waveform_path(w, h)?.into();
+        Ok(true)
+    }
+
+    /// Builds an SVG path, filling the area between the minimum & maximum peaks of every column
+    /// of the 1st channel of the baked buffer when drawn in a `width` by `height` box.
+    pub fn waveform_path(&self, width: u32, height: u32) -> Result<String> {
+        buffer_waveform_path(&self.baked, width, height)
+    }
+
+    /// SVG path of the waveform of the baked buffer, as of the last bake.
+    pub const fn thumbnail(&self) -> &AttrValue {
+        &self.thumbnail
+    }
+
+    /// SVG path of the waveform of the raw buffer.
+    pub const fn raw_thumbnail(&self) -> &AttrValue {
+        &self.raw_thumbnail
     }
 
     /// Buffer with all the requested changes baked in.
@@ -319,8 +1819,12 @@ impl AudioInput {
         (self.pending_changes == self.baked_changes).then_some(&self.baked)
     }
 
-    pub fn desc(&self, bps: Beats) -> String {
-        format!("{}, {:.2} beats", self.name, self.duration.secs_to_beats(bps))
+    pub fn desc(&self, bps: R64) -> String {
+        let res = format!("{}, {:.2} beats", self.name, self.duration.to_beats(bps));
+        if self.original_rate as f32 == self.raw.sample_rate() {
+            return res;
+        }
+        format!("{res}, resampled from {} Hz", self.original_rate)
     }
 }
 
@@ -329,6 +1833,7 @@ pub enum SoundType {
     Note,
     Noise,
     Custom,
+    Drum,
 }
 
 impl SoundType {
@@ -337,6 +1842,7 @@ impl SoundType {
             Self::Note => NoteSound::NAME,
             Self::Noise => NoiseSound::NAME,
             Self::Custom => CustomSound::NAME,
+            Self::Drum => DrumSound::NAME,
         }
     }
 }
@@ -348,13 +1854,15 @@ pub enum Sound {
     Note(NoteSound),
     Noise(NoiseSound),
     Custom(CustomSound),
+    Drum(DrumSound),
 }
 
 impl Sound {
     pub const TYPES: [SoundType; variant_count::<Self>() - 1 /* None */] = [
         SoundType::Note,
         SoundType::Noise,
-        SoundType::Custom
+        SoundType::Custom,
+        SoundType::Drum,
     ];
 
     pub fn new(sound_type: SoundType) -> Self {
@@ -362,6 +1870,7 @@ impl Sound {
             SoundType::Note => Self::Note(default()),
             SoundType::Noise => Self::Noise(default()),
             SoundType::Custom => Self::Custom(default()),
+            SoundType::Drum => Self::Drum(default()),
         }
     }
 
@@ -371,31 +1880,76 @@ impl Sound {
             Self::Note(_) => NoteSound::NAME,
             Self::Noise(_) => NoiseSound::NAME,
             Self::Custom(_) => CustomSound::NAME,
+            Self::Drum(_) => DrumSound::NAME,
         }
     }
 
-    pub fn prepare(&mut self, bps: Beats) -> Result {
+    /// Prepares the sound for playback.
+    /// Returns whether the length of the sound might've changed in the process.
+    pub fn prepare(&mut self, bps: R64) -> Result<bool> {
         match self {
             Sound::Custom(inner) => inner.prepare(bps),
-            _ => Ok(()),
+            Sound::Drum(inner) => inner.prepare(bps),
+            _ => Ok(false),
+        }
+    }
+
+    /// Creates a gain node connected to `plug` that follows the volume automation of the sound
+    /// within `span`, for the sound to be played through it, or returns `None` if the sound has no
+    /// volume automation. The rest of the parameters are the same as those of `play`.
+    pub fn automate_volume(
+        &self,
+        plug: &AudioNode,
+        now: Secs,
+        self_offset: Beats,
+        span: Range<Beats>,
+        tempo: &TempoMap,
+    ) -> Result<Option<GainNode>> {
+        let Some(automation) = self.volume_automation() else { return Ok(None) };
+        let span = span.start - self_offset..span.end - self_offset;
+        let ramps = automation_ramps(automation.get()?.points(), span);
+        let Some((&(at, value), rest)) = ramps.split_first() else { return Ok(None) };
+        let res = plug.context().create_gain()?;
+        let gain = res.gain();
+        let time = |at| now + tempo.to_secs(self_offset + at);
+        gain.set_value_at_time(*value, *time(at))?;
+        for &(at, value) in rest {
+            gain.linear_ramp_to_value_at_time(*value, *time(at))?;
         }
+        res.connect_with_audio_node(plug)?;
+        Ok(Some(res))
     }
 
-    pub fn play(&self, plug: &AudioNode, now: Secs, self_offset: Secs, bps: Beats) -> Result {
+    /// Schedules the sound to be played, `now` being the time at which the composition starts &
+    /// `self_offset` being the beat at which the sound block starts.
+    /// Only the parts of the sound that start within `span`, a range of beats of the composition,
+    /// are scheduled, the starts of the parts being then delayed according to `swing`, as per
+    /// `swing_offset`.
+    pub fn play(
+        &self,
+        plug: &AudioNode,
+        now: Secs,
+        self_offset: Beats,
+        span: Range<Beats>,
+        tempo: &TempoMap,
+        swing: R32,
+    ) -> Result {
         match self {
             Self::None => Ok(()),
-            Self::Note(inner) => inner.play(plug, now, self_offset, bps),
-            Self::Noise(inner) => inner.play(plug, now, self_offset, bps),
-            Self::Custom(inner) => inner.play(plug, now, self_offset, bps),
+            Self::Note(inner) => inner.play(plug, now, self_offset, span, tempo, swing),
+            Self::Noise(inner) => inner.play(plug, now, self_offset, span, tempo, swing),
+            Self::Custom(inner) => inner.play(plug, now, self_offset, span, tempo, swing),
+            Self::Drum(inner) => inner.play(plug, now, self_offset, span, tempo, swing),
         }
     }
 
-    pub fn len(&self, bps: Beats) -> Result<Beats> {
+    pub fn len(&self, bps: R64) -> Result<Beats> {
         match self {
-            Self::None => Ok(r64!(1)),
+            Self::None => Ok(Beats::ONE),
             Self::Note(inner) => inner.len(),
             Self::Noise(inner) => inner.len(),
             Self::Custom(inner) => inner.len(bps),
+            Self::Drum(inner) => inner.len(),
         }
     }
 
@@ -405,10 +1959,47 @@ impl Sound {
             Self::Note(inner) => inner.rep_count(),
             Self::Noise(inner) => inner.rep_count(),
             Self::Custom(inner) => inner.rep_count(),
+            Self::Drum(inner) => inner.rep_count(),
         }
     }
 
+    pub const fn rep_gap(&self) -> Beats {
+        match self {
+            Self::None => Beats::ZERO,
+            Self::Note(inner) => inner.rep_gap(),
+            Self::Noise(inner) => inner.rep_gap(),
+            Self::Custom(inner) => inner.rep_gap(),
+            Self::Drum(inner) => inner.rep_gap(),
+        }
+    }
+
+    /// The curve of the volume of the sound over the course of the sound block.
+    pub const fn volume_automation(&self) -> Option<&Shared<GraphEditor<AutomationPoint>>> {
+        match self {
+            Self::None => None,
+            Self::Note(inner) => Some(&inner.volume_automation),
+            Self::Noise(inner) => Some(&inner.volume_automation),
+            Self::Custom(inner) => Some(&inner.volume_automation),
+            Self::Drum(inner) => Some(&inner.volume_automation),
+        }
+    }
+
+    /// Length of the sound with all the repetitions of its pattern.
+    pub fn total_len(&self, bps: R64) -> Result<Beats> {
+        Ok(repeated_len(self.len(bps)?, self.rep_count(), self.rep_gap()))
+    }
+
     pub fn params(&self, ctx: ContextRef, sequencer: &Sequencer) -> Html {
+        if let Some(automation) = self.volume_automation()
+            && ctx.selected_tab() == 4
+        {
+            return html! {
+                <GraphEditorCanvas<AutomationPoint>
+                    editor={automation}
+                    emitter={ctx.event_emitter()}
+                />
+            };
+        }
         match self {
             Self::None => {
                 let emitter = ctx.event_emitter();
@@ -429,6 +2020,7 @@ impl Sound {
             Self::Note(inner) => inner.params(ctx),
             Self::Noise(inner) => inner.params(ctx),
             Self::Custom(inner) => inner.params(ctx, sequencer),
+            Self::Drum(inner) => inner.params(ctx, sequencer),
         }
     }
 
@@ -439,6 +2031,11 @@ impl Sound {
         sequencer: &Sequencer,
         offset: Beats,
     ) -> Result {
+        if let Some(automation) = self.volume_automation()
+            && ctx.selected_tab() == 4
+        {
+            automation.get_mut()?.handle_event(event, ctx.as_mut(), sequencer, || offset)?;
+        }
         let r = &mut false;
         match self {
             Sound::None => match event {
@@ -463,6 +2060,7 @@ impl Sound {
             Sound::Note(inner) => inner.handle_event(event, ctx, sequencer, r, offset)?,
             Sound::Noise(inner) => inner.handle_event(event, ctx, sequencer, r, offset)?,
             Sound::Custom(inner) => inner.handle_event(event, ctx, sequencer, r, offset)?,
+            Sound::Drum(inner) => inner.handle_event(event, ctx, sequencer, r, offset)?,
         };
         if *r {
             *self = Self::None
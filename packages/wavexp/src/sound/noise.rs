@@ -1,24 +1,34 @@
 use super::CustomBlock;
 use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
-    input::{Counter, GraphEditorCanvas, Slider},
-    sequencer::{PlaybackContext, Sequencer},
-    sound::{Beats, FromBeats, Note, Secs},
+    input::{Counter, EnvelopeGraph, GraphEditorCanvas, Slider, Switch},
+    sequencer::Sequencer,
+    sound::{
+        rep_progress, schedule, swing_time, AutomationPoint, Beats, FilterParams, FilterType,
+        FromBeats, NodeChain, Note, PanParams, Secs, TempoMap,
+    },
     visual::{GraphEditor, GraphPoint},
 };
-use js_sys::Math::random;
 use macro_rules_attribute::apply;
-use std::{array::from_fn, cell::LazyCell, mem::replace, num::NonZeroU32, ops::RangeBounds};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    mem::replace,
+    num::NonZeroU32,
+    ops::{Range, RangeBounds},
+};
 use wasm_bindgen::JsCast;
 use wavexp_utils::{
     cell::{Shared, WasmCell},
-    error::{AppError, Result},
+    diff::Diff,
+    error::Result,
     ext::default,
-    ext::{ArrayExt, OptionExt, ResultExt},
+    ext::{ArrayExt, OptionExt, SliceExt},
     fallible, js_function, r32, r64,
     range::{RangeBoundsExt, RangeInclusiveV2, RangeV2},
     real::R32,
     real::R64,
+    rng::Rng,
     ArrayFrom,
 };
 use web_sys::{AudioBuffer, AudioBufferOptions, AudioNode, Path2d};
@@ -31,6 +41,20 @@ pub struct NoiseBlock {
     pub len: Beats,
 }
 
+impl Diff for NoiseBlock {
+    type Delta = (<Beats as Diff>::Delta, u8, <Beats as Diff>::Delta);
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        (self.offset.delta(&to.offset), self.pitch.delta(&to.pitch), self.len.delta(&to.len))
+    }
+
+    fn patch(&mut self, (offset, pitch, len): Self::Delta) {
+        self.offset.patch(offset);
+        self.pitch.patch(pitch);
+        self.len.patch(len);
+    }
+}
+
 impl GraphPoint for NoiseBlock {
     const EDITOR_NAME: &'static str = CustomBlock::EDITOR_NAME;
     const Y_BOUND: RangeV2<R64> = CustomBlock::Y_BOUND;
@@ -40,11 +64,11 @@ impl GraphPoint for NoiseBlock {
 
     type Inner = Beats;
     type Y = Note;
-    /// (sound block offset, number of repetitions of the pattern)
-    type VisualContext = (Beats, NonZeroU32);
+    /// (sound block offset, number of repetitions of the pattern, gap between them)
+    type VisualContext = (Beats, NonZeroU32, Beats);
 
     fn create(_: &GraphEditor<Self>, [offset, y]: [R64; 2]) -> Self {
-        Self { offset, pitch: Note::saturated(y.into()).recip(), len: r64!(1) }
+        Self { offset: Beats(offset), pitch: Note::saturated(y.into()).recip(), len: Beats::ONE }
     }
 
     fn inner(&self) -> &Self::Inner {
@@ -60,16 +84,19 @@ impl GraphPoint for NoiseBlock {
         &mut self.pitch
     }
     fn loc(&self) -> [R64; 2] {
-        [self.offset, self.pitch.recip().index().into()]
+        [self.offset.0, self.pitch.recip().index().into()]
+    }
+    fn span(&self, _: R64) -> Result<[R64; 2]> {
+        Ok([self.offset.0, (self.offset + self.len).0])
     }
 
     #[apply(fallible!)]
     fn móve(&mut self, delta: [R64; 2], meta: bool) {
         if meta {
-            self.len += delta[0];
+            self.len += Beats(delta[0]);
         } else {
-            self.offset += delta[0];
-            self.offset = R64::ZERO.max(self.offset);
+            self.offset += Beats(delta[0]);
+            self.offset = Beats::ZERO.max(self.offset);
         }
         self.pitch = (self.pitch - isize::from(delta[1]))?;
     }
@@ -90,33 +117,46 @@ impl GraphPoint for NoiseBlock {
         _: Self::VisualContext,
     ) -> bool {
         area[1].map_bounds(usize::from).contains(&self.pitch.recip().index())
-            && (self.offset..=self.offset + self.len).overlap(&area[0])
+            && (self.offset.0..=(self.offset + self.len).0).overlap(&area[0])
     }
 
     #[apply(fallible!)]
     fn on_redraw(
         editor: &mut GraphEditor<Self>,
-        ctx: ContextRef,
-        sequencer: &Sequencer,
+        _: ContextRef,
+        _: &Sequencer,
         canvas_size: &[R64; 2],
         solid: &Path2d,
         _: &Path2d,
-        (sb_offset, n_reps): Self::VisualContext,
+        _: Self::VisualContext,
     ) {
-        let bps = sequencer.bps();
         let step = canvas_size.div(editor.scale());
         let offset = R64::array_from(editor.offset());
-        for block in editor.data() {
+        for block in editor.points() {
             let [x, y] = block.loc().mul(step).sub(offset);
             solid.rect(*x, *y, *block.len * *step[0], *step[1]);
         }
+    }
+
+    #[apply(fallible!)]
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        _: &Path2d,
+        (sb_offset, n_reps, rep_gap): Self::VisualContext,
+    ) {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
 
-        let total_len = editor.data().last().map_or_default(|x| x.offset + x.len);
-        if let PlaybackContext::All(start) = sequencer.playback_ctx() && start.is_finite() {
-            let progress = (ctx.frame() - start).secs_to_beats(bps) - sb_offset;
-            if progress < total_len * n_reps {
-                editor.force_redraw();
-                let x = R64::new_or(progress, *progress % *total_len) * step[0] - offset[0];
+        let total_len = editor.points().last().map_or_default(|x| x.offset + x.len);
+        if let Some(position) = sequencer.playback_position(ctx.frame()) {
+            let progress = rep_progress(position - sb_offset, total_len, n_reps, rep_gap);
+            if let Some(progress) = progress {
+                editor.force_overlay_redraw();
+                let x = progress.0 * step[0] - offset[0];
                 solid.move_to(*x, 0.0);
                 solid.line_to(*x, *canvas_size[1]);
             }
@@ -128,15 +168,78 @@ impl GraphPoint for NoiseBlock {
     }
 }
 
+/// the way the energy of the noise is distributed across frequencies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseColor {
+    #[default]
+    White,
+    Pink,
+    Brown,
+}
+
+impl NoiseColor {
+    pub const ALL: [Self; 3] = [Self::White, Self::Pink, Self::Brown];
+    pub const NAMES: [&'static str; 3] = ["White", "Pink", "Brown"];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Turns white noise into noise of this color in-place,
+    /// keeping the RMS of the signal the same so that all the colors are equally loud.
+    pub fn shape(self, samples: &mut [f32]) {
+        let rms = |x: &[f32]| (x.iter().map(|x| x * x).sum::<f32>() / x.len() as f32).sqrt();
+        let target_rms = rms(samples);
+        match self {
+            Self::White => return,
+            // Paul Kellet's economy filter, accurate to within 0.05dB above 9.2Hz
+            Self::Pink => {
+                let mut acc = [0.0f32; 3];
+                for x in samples.iter_mut() {
+                    acc[0] = 0.99765 * acc[0] + *x * 0.099_046;
+                    acc[1] = 0.963 * acc[1] + *x * 0.296_516_4;
+                    acc[2] = 0.57 * acc[2] + *x * 1.052_691_3;
+                    *x = acc.iter().sum::<f32>() + *x * 0.1848;
+                }
+            }
+            // leaky integration, so that the signal doesn't drift away from 0
+            Self::Brown => {
+                let mut acc = 0.0f32;
+                for x in samples.iter_mut() {
+                    acc = (acc + *x * 0.02) / 1.02;
+                    *x = acc;
+                }
+            }
+        }
+        let gain = target_rms / rms(samples);
+        if gain.is_finite() {
+            samples.iter_mut().for_each(|x| *x *= gain);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NoiseSound {
     pub pattern: Shared<GraphEditor<NoiseBlock>>,
     pub volume: R32,
+    /// stereo position of the sound, from -1 (fully left) to 1 (fully right)
+    pub pan: R32,
     pub attack: Beats,
     pub decay: Beats,
     pub sustain: R32,
     pub release: Beats,
     pub rep_count: NonZeroU32,
+    /// the gap between the repetitions of the pattern
+    pub rep_gap: Beats,
+    /// volume of the sound over the course of the sound block, on top of `volume`
+    pub volume_automation: Shared<GraphEditor<AutomationPoint>>,
+    pub filter_type: FilterType,
+    /// cutoff frequency of the filter, in Hz
+    pub filter_freq: R64,
+    pub filter_q: R64,
+    pub color: NoiseColor,
+    /// seed of the generated noise, the same seed always giving the same noise
+    pub seed: u64,
 }
 
 impl Default for NoiseSound {
@@ -144,81 +247,130 @@ impl Default for NoiseSound {
         Self {
             pattern: default(),
             volume: r32!(0.2),
-            attack: r64!(0),
-            decay: r64!(0),
+            pan: r32!(0),
+            attack: Beats::ZERO,
+            decay: Beats::ZERO,
             sustain: r32!(1),
-            release: r64!(0.2),
+            release: Beats(r64!(0.2)),
             rep_count: NonZeroU32::MIN,
+            rep_gap: Beats::ZERO,
+            volume_automation: default(),
+            filter_type: FilterType::None,
+            filter_freq: r64!(1000),
+            filter_q: r64!(1),
+            color: NoiseColor::White,
+            seed: Rng::DEFAULT_SEED,
         }
     }
 }
 
-static NOISE: WasmCell<LazyCell<Option<AudioBuffer>>> = WasmCell(LazyCell::new(|| {
-    let res: Result<AudioBuffer> = try {
-        let buf: [f32; Sequencer::SAMPLE_RATE as usize] = from_fn(|_| random() as f32 * 2.0 - 1.0);
-        let res = AudioBuffer::new(
-            AudioBufferOptions::new(Sequencer::SAMPLE_RATE, Sequencer::SAMPLE_RATE as f32)
-                .number_of_channels(Sequencer::CHANNEL_COUNT),
-        )?;
-        for i in 0..Sequencer::CHANNEL_COUNT as i32 {
-            res.copy_to_channel(&buf, i)?;
-        }
-        res
-    };
-    res.report()
-}));
+/// 1 second of noise for every color & seed played so far, keyed by `NoiseColor::index` & the seed
+static NOISE: WasmCell<RefCell<BTreeMap<(usize, u64), AudioBuffer>>> =
+    WasmCell(RefCell::new(BTreeMap::new()));
+
+/// 1 second of noise of the given color, the same `seed` always giving the same samples
+fn noise_samples(color: NoiseColor, seed: u64) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    let mut res: Vec<f32> = (0..Sequencer::SAMPLE_RATE).map(|_| rng.next_signed() as f32).collect();
+    color.shape(&mut res);
+    res
+}
+
+/// `noise_samples` as an audio buffer, created once for every color & seed
+fn noise_buffer(color: NoiseColor, seed: u64) -> Result<AudioBuffer> {
+    let mut cache = NOISE.borrow_mut();
+    if let Some(res) = cache.get(&(color.index(), seed)) {
+        return Ok(res.clone());
+    }
+    let buf = noise_samples(color, seed);
+    let res = AudioBuffer::new(
+        AudioBufferOptions::new(Sequencer::SAMPLE_RATE, Sequencer::SAMPLE_RATE as f32)
+            .number_of_channels(Sequencer::CHANNEL_COUNT),
+    )?;
+    for i in 0..Sequencer::CHANNEL_COUNT as i32 {
+        res.copy_to_channel(&buf, i)?;
+    }
+    cache.insert((color.index(), seed), res.clone());
+    Ok(res)
+}
 
 impl NoiseSound {
     pub const NAME: &'static str = "White Noise";
 
-    pub fn play(&self, plug: &AudioNode, now: Secs, self_offset: Secs, bps: Beats) -> Result {
+    pub fn play(
+        &self,
+        plug: &AudioNode,
+        now: Secs,
+        self_offset: Beats,
+        span: Range<Beats>,
+        tempo: &TempoMap,
+        swing: R32,
+    ) -> Result {
         let pat = self.pattern.get()?;
-        let Some(last) = pat.data().last() else {
+        let Some(last) = pat.points().last() else {
             return Ok(());
         };
-        let pat_len = (last.offset + last.len).to_secs(bps);
+        let period = last.offset + last.len + self.rep_gap;
         let ctx = plug.context();
 
-        for rep in 0..self.rep_count.get() {
-            for NoiseBlock { offset, len, pitch } in pat.data() {
-                let block = ctx.create_gain()?;
-                let gain = block.gain();
-                let start = now + self_offset + pat_len * rep + offset.to_secs(bps);
-                let mut at = start;
-                gain.set_value_at_time(0.0, *at)?;
-                at += self.attack.to_secs(bps);
-                gain.linear_ramp_to_value_at_time(*self.volume, *at)?;
-                at += self.decay.to_secs(bps);
-                let sus = self.sustain * self.volume;
-                gain.linear_ramp_to_value_at_time(*sus, *at)?;
-                at = start + len.to_secs(bps);
-                gain.set_value_at_time(*sus, *at - *self.release.to_secs(bps))?;
-                gain.linear_ramp_to_value_at_time(0.0, *at)?;
-
-                let block_core = ctx.create_buffer_source()?;
-                block_core.set_buffer(NOISE.as_ref());
-                block_core.playback_rate().set_value(*pitch.pitch_coef() as f32);
-                block_core.set_loop(true);
-                block_core.connect_with_audio_node(&block)?.connect_with_audio_node(plug)?;
-                block_core.start_with_when(*start)?;
-                block_core.stop_with_when(*at)?;
-                block_core.clone().set_onended(Some(&js_function!(|| {
-                    block.disconnect().map_err(AppError::from).report();
-                    block_core.disconnect().map_err(AppError::from).report();
-                })));
+        let span = span.start - self_offset..span.end - self_offset;
+        let starts = schedule(pat.points(), self.rep_count, period, span, |x| x.offset);
+        for (start, NoiseBlock { len, pitch, .. }) in starts {
+            let mut nodes = NodeChain::new();
+            let block = nodes.add(ctx.create_gain()?);
+            let gain = block.gain();
+            let beat = self_offset + start;
+            let bps = tempo.bps_at(beat);
+            let start = now + swing_time(beat, swing, tempo);
+            let mut at = start;
+            gain.set_value_at_time(0.0, *at)?;
+            at += self.attack.to_secs(bps);
+            gain.linear_ramp_to_value_at_time(*self.volume, *at)?;
+            at += self.decay.to_secs(bps);
+            let sus = self.sustain * self.volume;
+            gain.linear_ramp_to_value_at_time(*sus, *at)?;
+            at = start + tempo.duration(beat, *len);
+            gain.set_value_at_time(*sus, *at - *self.release.to_secs(bps))?;
+            gain.linear_ramp_to_value_at_time(0.0, *at)?;
+
+            let block_core = nodes.add(ctx.create_buffer_source()?);
+            block_core.set_buffer(Some(&noise_buffer(self.color, self.seed)?));
+            block_core.playback_rate().set_value(*pitch.pitch_coef() as f32);
+            block_core.set_loop(true);
+            let filter = self.filter().map(|x| x.create(&ctx)).transpose()?.map(|x| nodes.add(x));
+            let mut output = block_core.connect_with_audio_node(&block)?;
+            if let Some(filter) = &filter {
+                output = output.connect_with_audio_node(filter)?;
             }
+            let panner = nodes.add(self.panner().create(&ctx)?);
+            output.connect_with_audio_node(&panner)?.connect_with_audio_node(plug)?;
+            block_core.start_with_when(*start)?;
+            block_core.stop_with_when(*at)?;
+            block_core.set_onended(Some(&js_function!(|| nodes.teardown())));
         }
         Ok(())
     }
 
     pub fn len(&self) -> Result<Beats> {
-        Ok(self.pattern.get()?.data().last().map_or_default(|x| x.offset + x.len))
+        Ok(self.pattern.get()?.points().last().map_or_default(|x| x.offset + x.len))
     }
 
     pub const fn rep_count(&self) -> NonZeroU32 {
         self.rep_count
     }
 
+    pub const fn rep_gap(&self) -> Beats {
+        self.rep_gap
+    }
+
+    pub fn filter(&self) -> Option<FilterParams> {
+        self.filter_type.params(self.filter_freq, self.filter_q)
+    }
+
+    pub fn panner(&self) -> PanParams {
+        PanParams::new(self.pan)
+    }
+
     pub fn params(&self, ctx: ContextRef) -> Html {
         let emitter = ctx.event_emitter();
         match ctx.selected_tab() {
@@ -228,8 +380,17 @@ impl NoiseSound {
                         key="noise-vol"
                         setter={emitter.reform(|x| AppEvent::Volume(R32::from(x)))}
                         name="Noise Volume"
+                        default={r64!(0.2)}
                         initial={self.volume}
                     />
+                    <Slider
+                        key="noise-pan"
+                        setter={emitter.reform(|x| AppEvent::Pan(R32::from(x)))}
+                        name="Noise Pan"
+                        default={R64::ZERO}
+                        bipolar=true
+                        initial={self.pan}
+                    />
                     <Counter
                         key="noise-repcnt"
                         setter={emitter.reform(|x| AppEvent::RepCount(NonZeroU32::from(x)))}
@@ -238,6 +399,22 @@ impl NoiseSound {
                         min=1
                         initial={self.rep_count}
                     />
+                    <Counter
+                        key="noise-repgap"
+                        setter={emitter.reform(|x| AppEvent::RepGap(Beats(x)))}
+                        name="Gap Between Pattern Repetitions"
+                        postfix="Beats"
+                        initial={self.rep_gap}
+                    />
+                    <Switch
+                        key="noise-color"
+                        name="Noise Color"
+                        options={NoiseColor::NAMES.to_vec()}
+                        setter={emitter.reform(|x| {
+                            AppEvent::NoiseColor(*NoiseColor::ALL.get_wrapping(x))
+                        })}
+                        initial={self.color.index()}
+                    />
                 </div>
             },
 
@@ -245,14 +422,14 @@ impl NoiseSound {
                 <div id="inputs">
                     <Counter
                         key="noise-att"
-                        setter={emitter.reform(AppEvent::Attack)}
+                        setter={emitter.reform(|x| AppEvent::Attack(Beats(x)))}
                         name="Noise Attack Time"
                         postfix="Beats"
                         initial={self.attack}
                     />
                     <Counter
                         key="noise-dec"
-                        setter={emitter.reform(AppEvent::Decay)}
+                        setter={emitter.reform(|x| AppEvent::Decay(Beats(x)))}
                         name="Noise Decay Time"
                         postfix="Beats"
                         initial={self.decay}
@@ -265,11 +442,18 @@ impl NoiseSound {
                     />
                     <Counter
                         key="noise-rel"
-                        setter={emitter.reform(AppEvent::Release)}
+                        setter={emitter.reform(|x| AppEvent::Release(Beats(x)))}
                         name="Noise Release Time"
                         postfix="Beats"
                         initial={self.release}
                     />
+                    <EnvelopeGraph
+                        attack={self.attack}
+                        decay={self.decay}
+                        sustain={self.sustain}
+                        release={self.release}
+                        emitter={emitter.clone()}
+                    />
                 </div>
             },
 
@@ -277,6 +461,38 @@ impl NoiseSound {
                 <GraphEditorCanvas<NoiseBlock> editor={&self.pattern} {emitter} />
             },
 
+            3 /* Filter */ => html!{
+                <div id="inputs">
+                    <Switch
+                        key="noise-filter-type"
+                        name="Noise Filter Type"
+                        options={FilterType::NAMES.to_vec()}
+                        setter={emitter.reform(|x| {
+                            AppEvent::FilterType(*FilterType::ALL.get_wrapping(x))
+                        })}
+                        initial={self.filter_type.index()}
+                    />
+                    <Counter
+                        key="noise-filter-freq"
+                        setter={emitter.reform(AppEvent::FilterFreq)}
+                        fmt={|x| format!("{x:.0}")}
+                        name="Noise Filter Cutoff Frequency"
+                        postfix="Hz"
+                        coef={r64!(1000)}
+                        min={r64!(10)}
+                        initial={self.filter_freq}
+                    />
+                    <Counter
+                        key="noise-filter-q"
+                        setter={emitter.reform(AppEvent::FilterQ)}
+                        name="Noise Filter Resonance"
+                        coef={r64!(10)}
+                        min={r64!(0.0001)}
+                        initial={self.filter_q}
+                    />
+                </div>
+            },
+
             tab_id => html!{ <p style="color:red">{ format!("Invalid tab ID: {tab_id}") }</p> }
         }
     }
@@ -298,6 +514,10 @@ impl NoiseSound {
                 to,
             })?,
 
+            AppEvent::Pan(to) => {
+                ctx.register_action(EditorAction::SetPan { from: replace(&mut self.pan, to), to })?
+            }
+
             AppEvent::Attack(to) => ctx.register_action(EditorAction::SetAttack {
                 from: replace(&mut self.attack, to),
                 to,
@@ -318,6 +538,21 @@ impl NoiseSound {
                 to,
             })?,
 
+            AppEvent::FilterType(to) => ctx.register_action(EditorAction::SetFilterType {
+                from: replace(&mut self.filter_type, to),
+                to,
+            })?,
+
+            AppEvent::FilterFreq(to) => ctx.register_action(EditorAction::SetFilterFreq {
+                from: replace(&mut self.filter_freq, to),
+                to,
+            })?,
+
+            AppEvent::FilterQ(to) => ctx.register_action(EditorAction::SetFilterQ {
+                from: replace(&mut self.filter_q, to),
+                to,
+            })?,
+
             AppEvent::RepCount(to) => {
                 ctx.register_action(EditorAction::SetRepCount {
                     from: replace(&mut self.rep_count, to),
@@ -326,6 +561,19 @@ impl NoiseSound {
                 ctx.emit_event(AppEvent::RedrawEditorPlane);
             }
 
+            AppEvent::RepGap(to) => {
+                ctx.register_action(EditorAction::SetRepGap {
+                    from: replace(&mut self.rep_gap, to),
+                    to,
+                })?;
+                ctx.emit_event(AppEvent::RedrawEditorPlane);
+            }
+
+            AppEvent::NoiseColor(to) => ctx.register_action(EditorAction::SetNoiseColor {
+                from: replace(&mut self.color, to),
+                to,
+            })?,
+
             AppEvent::Undo(ref actions) => {
                 let mut pat = self.pattern.get_mut()?;
                 for action in actions.iter() {
@@ -337,6 +585,8 @@ impl NoiseSound {
 
                         EditorAction::SetVolume { from, .. } => self.volume = from,
 
+                        EditorAction::SetPan { from, .. } => self.pan = from,
+
                         EditorAction::SetAttack { from, .. } => self.attack = from,
 
                         EditorAction::SetDecay { from, .. } => self.decay = from,
@@ -345,17 +595,32 @@ impl NoiseSound {
 
                         EditorAction::SetRelease { from, .. } => self.release = from,
 
+                        EditorAction::SetFilterType { from, .. } => self.filter_type = from,
+
+                        EditorAction::SetFilterFreq { from, .. } => self.filter_freq = from,
+
+                        EditorAction::SetFilterQ { from, .. } => self.filter_q = from,
+
                         EditorAction::SetRepCount { from, .. } => {
                             self.rep_count = from;
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
                         }
 
+                        EditorAction::SetRepGap { from, .. } => {
+                            self.rep_gap = from;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::SetNoiseColor { from, .. } => self.color = from,
+
                         _ => (),
                     }
                 }
 
                 if ctx.selected_tab() == 2 {
-                    pat.handle_event(event, ctx, sequencer, || (offset, self.rep_count))?;
+                    pat.handle_event(event, ctx, sequencer, || {
+                        (offset, self.rep_count, self.rep_gap)
+                    })?;
                 }
             }
 
@@ -365,6 +630,8 @@ impl NoiseSound {
                     match *action {
                         EditorAction::SetVolume { to, .. } => self.volume = to,
 
+                        EditorAction::SetPan { to, .. } => self.pan = to,
+
                         EditorAction::SetAttack { to, .. } => self.attack = to,
 
                         EditorAction::SetDecay { to, .. } => self.decay = to,
@@ -373,27 +640,92 @@ impl NoiseSound {
 
                         EditorAction::SetRelease { to, .. } => self.release = to,
 
+                        EditorAction::SetFilterType { to, .. } => self.filter_type = to,
+
+                        EditorAction::SetFilterFreq { to, .. } => self.filter_freq = to,
+
+                        EditorAction::SetFilterQ { to, .. } => self.filter_q = to,
+
                         EditorAction::SetRepCount { to, .. } => {
                             self.rep_count = to;
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
                         }
 
+                        EditorAction::SetRepGap { to, .. } => {
+                            self.rep_gap = to;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::SetNoiseColor { to, .. } => self.color = to,
+
                         _ => (),
                     }
                 }
 
                 if ctx.selected_tab() == 2 {
-                    pat.handle_event(event, ctx, sequencer, || (offset, self.rep_count))?;
+                    pat.handle_event(event, ctx, sequencer, || {
+                        (offset, self.rep_count, self.rep_gap)
+                    })?;
                 }
             }
 
             _ => {
                 if ctx.selected_tab() == 2 {
-                    self.pattern
-                        .get_mut()?
-                        .handle_event(event, ctx, sequencer, || (offset, self.rep_count))?;
+                    self.pattern.get_mut()?.handle_event(event, ctx, sequencer, || {
+                        (offset, self.rep_count, self.rep_gap)
+                    })?;
                 }
             }
         }
     }
 }
+
+#[test]
+fn brown_noise_is_lower_than_white() {
+    let white = noise_samples(NoiseColor::White, Rng::DEFAULT_SEED);
+    let mut brown = white.clone();
+    NoiseColor::Brown.shape(&mut brown);
+
+    let zero_crossings = |x: &[f32]| x.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+    assert!(zero_crossings(&brown) * 4 < zero_crossings(&white));
+    let rms = |x: &[f32]| x.iter().map(|x| x * x).sum::<f32>() / x.len() as f32;
+    assert!((rms(&brown) / rms(&white) - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn noise_samples_seeded() {
+    for color in NoiseColor::ALL {
+        assert_eq!(noise_samples(color, 42), noise_samples(color, 42));
+        assert_ne!(noise_samples(color, 42), noise_samples(color, 43));
+    }
+}
+
+#[test]
+fn muted_noise_is_silent() {
+    use crate::sequencer::SoundBlock;
+    use crate::sound::Sound;
+    let block = |seed, muted, soloed| SoundBlock {
+        sound: Sound::Noise(NoiseSound { seed, ..default() }),
+        layer: 0,
+        offset: Beats::ZERO,
+        muted,
+        soloed,
+    };
+    // mixes the noise of every block that gets played, the way the layers are mixed on playback
+    let render = |blocks: &[SoundBlock]| {
+        let mut res = vec![0.0f32; Sequencer::SAMPLE_RATE as usize];
+        for block in SoundBlock::audible_blocks(blocks) {
+            let Sound::Noise(sound) = &block.sound else { continue };
+            let samples = noise_samples(sound.color, sound.seed);
+            res.iter_mut().zip(samples).for_each(|(dst, src)| *dst += src);
+        }
+        res
+    };
+    let loud = render(&[block(1, false, false)]);
+    assert!(loud.iter().any(|x| *x != 0.0));
+    assert_eq!(render(&[block(1, false, false), block(2, true, false)]), loud);
+    assert!(render(&[block(1, true, false), block(2, true, false)]).iter().all(|x| *x == 0.0));
+    // a soloed block silences the rest, unless it's muted itself
+    assert_eq!(render(&[block(1, false, true), block(2, false, false)]), loud);
+    assert_eq!(render(&[block(1, true, true), block(2, false, false)]), render(&[]));
+}
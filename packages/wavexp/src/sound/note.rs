@@ -1,25 +1,39 @@
 use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction},
-    input::{Counter, Cursor, GraphEditorCanvas, Slider},
-    sequencer::{PlaybackContext, Sequencer},
-    sound::{Beats, FromBeats, Note, Secs},
+    input::{Button, Counter, Cursor, EnvelopeGraph, GraphEditorCanvas, Slider, Switch},
+    midi,
+    sequencer::Sequencer,
+    sound::{
+        rep_progress, schedule, swing_time, AutomationPoint, Beats, Chord, FilterParams,
+        FilterType, FromBeats, FromSecs, LfoParams, LfoShape, NodeChain, Note, PanParams, Patterns,
+        Scale, Secs, TempoMap,
+    },
     visual::{GraphEditor, GraphPoint},
 };
 use macro_rules_attribute::apply;
-use std::{cmp::Ordering, mem::replace, num::NonZeroU32, ops::RangeBounds};
+use std::{
+    cmp::Ordering,
+    mem::replace,
+    num::NonZeroU32,
+    ops::{Range, RangeBounds},
+};
 use wasm_bindgen::JsCast;
 use wavexp_utils::{
+    error::Result,
     cell::Shared,
-    error::{AppError, Result},
+    diff::{Diff, PatternDiff},
     ext::default,
-    ext::{ArrayExt, OptionExt, ResultExt},
-    fallible, js_function, r32, r64,
+    ext::{ArrayExt, BoolExt, OptionExt, SliceExt},
+    fallible,
+    js::save_file,
+    js_function, r32, r64,
     range::{RangeBoundsExt, RangeInclusiveV2, RangeV2},
     real::R32,
     real::R64,
-    ArrayFrom,
+    rng::Rng,
+    ArrayFrom, RoundTo,
 };
-use web_sys::{AudioNode, Path2d};
+use web_sys::{AudioNode, GainNode, OscillatorNode, Path2d};
 use yew::{html, Html};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,6 +41,8 @@ pub struct NoteBlock {
     pub offset: Beats,
     pub value: Note,
     pub len: Beats,
+    /// from 0 to 1, scales the volume of the note
+    pub velocity: R32,
 }
 
 impl PartialOrd for NoteBlock {
@@ -41,8 +57,48 @@ impl Ord for NoteBlock {
     }
 }
 
+impl Diff for NoteBlock {
+    type Delta = (<Beats as Diff>::Delta, u8, <Beats as Diff>::Delta, <R32 as Diff>::Delta);
+
+    fn delta(&self, to: &Self) -> Self::Delta {
+        (
+            self.offset.delta(&to.offset),
+            self.value.delta(&to.value),
+            self.len.delta(&to.len),
+            self.velocity.delta(&to.velocity),
+        )
+    }
+
+    fn patch(&mut self, (offset, value, len, velocity): Self::Delta) {
+        self.offset.patch(offset);
+        self.value.patch(value);
+        self.len.patch(len);
+        self.velocity.patch(velocity);
+    }
+}
+
+impl NoteBlock {
+    /// returns `true` if the given `area` in user coordinates overlaps with the note.
+    pub fn in_area(&self, area: &[RangeInclusiveV2<R64>; 2]) -> bool {
+        area[1].map_bounds(usize::from).contains(&self.value.recip().index())
+            && (self.offset.0..=(self.offset + self.len).0).overlap(&area[0])
+    }
+
+    /// a note at full velocity, to shorten the construction of patterns in tests
+    #[cfg(test)]
+    pub const fn test(offset: R64, value: u8, len: R64) -> Self {
+        Self {
+            offset: Beats(offset),
+            value: Note::saturated(value),
+            len: Beats(len),
+            velocity: R32::ONE,
+        }
+    }
+}
+
 impl GraphPoint for NoteBlock {
     const EDITOR_NAME: &'static str = "Note Editor";
+    const PITCHED: bool = true;
     const Y_BOUND: RangeV2<R64> = RangeV2 { start: r64!(0), end: r64!(Note::N_NOTES) };
     // // I wish...
     // const SCALE_Y_BOUND: RangeV2<R64> = RangeV2::unit(Note::N_NOTES.ceil_to(10).into());
@@ -53,11 +109,28 @@ impl GraphPoint for NoteBlock {
 
     type Inner = Beats;
     type Y = Note;
-    /// (sound block offset, number of repetitions of the pattern)
-    type VisualContext = (Beats, NonZeroU32);
+    /// (sound block offset, number of repetitions of the pattern, gap between them,
+    /// scale & its root, chord placed by a click)
+    type VisualContext = (Beats, NonZeroU32, Beats, (Scale, u8), Chord);
 
     fn create(_: &GraphEditor<Self>, [offset, y]: [R64; 2]) -> Self {
-        Self { offset, value: Note::saturated(y.into()).recip(), len: r64!(1) }
+        let value = Note::saturated(y.into()).recip();
+        Self { offset: Beats(offset), value, len: Beats::ONE, velocity: R32::ONE }
+    }
+
+    /// The clicked note is the root of the chord, the notes of the chord that would be out of
+    /// the supported range are skipped.
+    fn stamp(
+        editor: &GraphEditor<Self>,
+        at: [R64; 2],
+        (.., chord): Self::VisualContext,
+    ) -> Vec<Self> {
+        let root = Self::create(editor, at);
+        chord
+            .intervals()
+            .iter()
+            .filter_map(|&x| Some(Self { value: (root.value + x)?, ..root }))
+            .collect()
     }
 
     fn inner(&self) -> &Self::Inner {
@@ -75,16 +148,19 @@ impl GraphPoint for NoteBlock {
     }
 
     fn loc(&self) -> [R64; 2] {
-        [self.offset, self.value.recip().index().into()]
+        [self.offset.0, self.value.recip().index().into()]
+    }
+    fn span(&self, _: R64) -> Result<[R64; 2]> {
+        Ok([self.offset.0, (self.offset + self.len).0])
     }
 
     #[apply(fallible!)]
     fn móve(&mut self, delta: [R64; 2], meta: bool) {
         if meta {
-            self.len += delta[0];
+            self.len += Beats(delta[0]);
         } else {
-            self.offset += delta[0];
-            self.offset = R64::ZERO.max(self.offset);
+            self.offset += Beats(delta[0]);
+            self.offset = Beats::ZERO.max(self.offset);
         }
         self.value = (self.value - isize::from(delta[1]))?;
     }
@@ -96,6 +172,11 @@ impl GraphPoint for NoteBlock {
         point[1] += delta[1]
     }
 
+    fn snap([x, y]: [R64; 2], (.., (scale, root), _): Self::VisualContext) -> [R64; 2] {
+        let note = scale.snap(root, Note::saturated(y.into()).recip());
+        [x, note.recip().index().into()]
+    }
+
     #[apply(fallible!)]
     fn in_hitbox(
         &self,
@@ -104,8 +185,7 @@ impl GraphPoint for NoteBlock {
         _: &Sequencer,
         _: Self::VisualContext,
     ) -> bool {
-        area[1].map_bounds(usize::from).contains(&self.value.recip().index())
-            && (self.offset..=self.offset + self.len).overlap(&area[0])
+        self.in_area(area)
     }
 
     fn fmt_loc(loc: [R64; 2]) -> String {
@@ -120,7 +200,7 @@ impl GraphPoint for NoteBlock {
         _: [R64; 2],
         point: Option<usize>,
     ) {
-        let Some(last) = editor.data().len().checked_sub(1) else {
+        let Some(last) = editor.points().len().checked_sub(1) else {
             return Ok(());
         };
 
@@ -132,26 +212,40 @@ impl GraphPoint for NoteBlock {
     #[apply(fallible!)]
     fn on_redraw(
         editor: &mut GraphEditor<Self>,
-        ctx: ContextRef,
-        sequencer: &Sequencer,
+        _: ContextRef,
+        _: &Sequencer,
         canvas_size: &[R64; 2],
         solid: &Path2d,
         _: &Path2d,
-        (sb_offset, n_reps): Self::VisualContext,
+        _: Self::VisualContext,
     ) {
         let step = canvas_size.div(editor.scale());
         let offset = R64::array_from(editor.offset());
-        for block in editor.data() {
+        for block in editor.points() {
             let [x, y] = block.loc().mul(step).sub(offset);
             solid.rect(*x, *y, *block.len * *step[0], *step[1]);
         }
-        let total_len = editor.data().last().map_or_default(|x| x.offset + x.len);
+    }
 
-        if let PlaybackContext::All(start) = sequencer.playback_ctx() && start.is_finite() {
-            let progress = (ctx.frame() - start).secs_to_beats(sequencer.bps()) - sb_offset;
-            if progress < total_len * n_reps {
-                editor.force_redraw();
-                let x = R64::new_or(progress, *progress % *total_len) * step[0] - offset[0];
+    #[apply(fallible!)]
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        _: &Path2d,
+        (sb_offset, n_reps, rep_gap, ..): Self::VisualContext,
+    ) {
+        let step = canvas_size.div(editor.scale());
+        let offset = R64::array_from(editor.offset());
+        let total_len = editor.points().last().map_or_default(|x| x.offset + x.len);
+
+        if let Some(position) = sequencer.playback_position(ctx.frame()) {
+            let progress = rep_progress(position - sb_offset, total_len, n_reps, rep_gap);
+            if let Some(progress) = progress {
+                editor.force_overlay_redraw();
+                let x = progress.0 * step[0] - offset[0];
                 solid.move_to(*x, 0.0);
                 solid.line_to(*x, *canvas_size[1]);
             }
@@ -159,27 +253,372 @@ impl GraphPoint for NoteBlock {
     }
 }
 
+#[test]
+fn chord_stamp() {
+    let mut editor = GraphEditor::default();
+    let c4 = Note::saturated(24);
+    let at = [r64!(2), c4.recip().index().into()];
+    let visual_ctx =
+        (Beats::ZERO, NonZeroU32::MIN, Beats::ZERO, (Scale::Chromatic, 0), Chord::Major);
+    _ = editor.insert_points(NoteBlock::stamp(&editor, at, visual_ctx));
+    let mut values: Vec<_> = editor.points().iter().map(|x| x.value.index()).collect();
+    values.sort_unstable();
+    assert_eq!(values, [24, 28, 31]);
+    assert!(editor.points().iter().all(|x| x.offset == Beats(r64!(2))));
+    // the notes above the highest supported one are skipped
+    let top = [R64::ZERO, Note::MAX.recip().index().into()];
+    assert_eq!(NoteBlock::stamp(&editor, top, visual_ctx).len(), 1);
+}
+
+#[test]
+fn note_sound_from_midi() {
+    #[rustfmt::skip]
+    let smf = [
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+        b'M', b'T', b'r', b'k', 0, 0, 0, 26,
+        // 120 BPM
+        0, 0xFF, 0x51, 3, 0x07, 0xA1, 0x20,
+        // C4 for 1 quarter note
+        0, 0x90, 60, 64,
+        96, 0x80, 60, 64,
+        // D4 for half a quarter note, turned off with a 0-velocity note-on under running status
+        0, 0x90, 62, 64,
+        48, 62, 0,
+        0, 0xFF, 0x2F, 0,
+    ];
+    let sound = NoteSound::from_midi(&smf, r64!(2)).unwrap_or_default();
+    let pattern = sound.patterns.active().get().ok();
+    let data = pattern.as_ref().map_or(&[][..], |x| x.points());
+    assert_eq!(
+        data,
+        [NoteBlock::test(r64!(0), 24, r64!(1)), NoteBlock::test(r64!(1), 26, r64!(0.5))]
+    );
+}
+
+#[test]
+fn note_sound_quantize() {
+    let notes = vec![
+        NoteBlock::test(r64!(0.03), 24, r64!(0.1)),
+        NoteBlock::test(r64!(0.48), 24, r64!(0.6)),
+        NoteBlock::test(r64!(1.02), 24, r64!(1)),
+    ];
+    let mut sound = NoteSound { patterns: GraphEditor::new(notes).into(), ..default() };
+    let offsets = |sound: &NoteSound| {
+        sound
+            .patterns
+            .active()
+            .get()
+            .map(|x| x.points().iter().map(|x| x.offset.0).collect::<Vec<_>>())
+    };
+    sound.patterns.active().get_mut().map(|mut x| x.select_all(R64::ONE)).ok();
+    assert!(sound.quantize(Beats(r64!(0.25)), R32::ONE, false).is_ok());
+    assert_eq!(offsets(&sound).ok(), Some(vec![r64!(0), r64!(0.5), r64!(1)]));
+    // lengths aren't snapped below the grid step
+    assert!(sound.quantize(Beats(r64!(0.25)), R32::ONE, true).is_ok());
+    let lens = sound
+        .patterns
+        .active()
+        .get()
+        .map(|x| x.points().iter().map(|x| x.len.0).collect::<Vec<_>>());
+    assert_eq!(lens.ok(), Some(vec![r64!(0.25), r64!(0.5), r64!(1)]));
+}
+
+#[test]
+fn note_sound_legato() {
+    let notes = vec![
+        NoteBlock::test(r64!(0), 24, r64!(0.5)),
+        NoteBlock::test(r64!(1), 12, r64!(0.5)),
+        NoteBlock::test(r64!(2), 24, r64!(0.5)),
+        NoteBlock::test(r64!(5), 24, r64!(0.5)),
+    ];
+    let mut sound = NoteSound { patterns: GraphEditor::new(notes).into(), ..default() };
+    sound.patterns.active().get_mut().map(|mut x| x.select_all(R64::ONE)).ok();
+    assert!(sound.legato().is_ok());
+    let lens = sound
+        .patterns
+        .active()
+        .get()
+        .map(|x| x.points().iter().map(|x| x.len.0).collect::<Vec<_>>());
+    assert_eq!(lens.ok(), Some(vec![r64!(2), r64!(0.5), r64!(3), r64!(0.5)]));
+}
+
+#[test]
+fn note_sound_humanize() {
+    let sound = || {
+        let block =
+            |offset| NoteBlock { velocity: r32!(0.9), ..NoteBlock::test(offset, 24, r64!(1)) };
+        let notes = vec![block(r64!(0)), block(r64!(0.05)), block(r64!(1)), block(r64!(2))];
+        let sound = NoteSound { patterns: GraphEditor::new(notes).into(), ..default() };
+        sound.patterns.active().get_mut().map(|mut x| x.select_all(R64::ONE)).ok();
+        sound
+    };
+    let humanized = |seed| {
+        let mut sound = sound();
+        let Ok(EditorAction::Humanize { changes }) =
+            sound.humanize(seed, Beats(r64!(0.1)), r32!(0.2))
+        else {
+            panic!("humanization failed")
+        };
+        (changes, sound.patterns.active().get().map(|x| x.points().to_vec()).unwrap_or_default())
+    };
+    let (changes, notes) = humanized(42);
+    assert_eq!(humanized(42), (changes.clone(), notes.clone()));
+    assert_ne!(humanized(7).1, notes);
+    assert!(notes.windows(2).all(|x| x[0].offset <= x[1].offset));
+    assert!(notes.iter().all(|x| x.offset >= Beats::ZERO && x.velocity >= 0 && x.velocity <= 1));
+    // the action holds the exact change, reverting it restoring the original notes
+    let original = sound().patterns.active().get().map(|x| x.points().to_vec()).unwrap_or_default();
+    let mut reverted = notes.clone();
+    changes.unapply(&mut reverted);
+    assert_eq!(reverted, original);
+    changes.apply(&mut reverted);
+    assert_eq!(reverted, notes);
+}
+
+#[test]
+fn note_sound_transpose() {
+    let notes = vec![NoteBlock::test(r64!(0), 10, r64!(1)), NoteBlock::test(r64!(0), 30, r64!(1))];
+    let mut sound = NoteSound { patterns: GraphEditor::new(notes).into(), ..default() };
+    let values = |sound: &NoteSound| {
+        sound
+            .patterns
+            .active()
+            .get()
+            .map(|x| x.points().iter().map(|x| x.value).collect::<Vec<_>>())
+    };
+    sound.patterns.active().get_mut().map(|mut x| x.select_all(R64::ONE)).ok();
+    assert!(sound.transpose(5).is_ok_and(|x| x.is_some()));
+    assert_eq!(values(&sound).ok(), Some(vec![Note(15), Note(35)]));
+    // the 2nd note would go past `Note::MAX`, so neither is moved
+    assert!(sound.transpose(1).is_ok_and(|x| x.is_none()));
+    assert_eq!(values(&sound).ok(), Some(vec![Note(15), Note::MAX]));
+}
+
+#[test]
+fn note_sound_midi_round_trip() {
+    let notes = [
+        NoteBlock::test(r64!(0), 24, r64!(2)),
+        // overlaps the previous note at the same pitch
+        NoteBlock::test(r64!(1), 24, r64!(1.5)),
+        NoteBlock::test(r64!(1.25), 0, r64!(0.25)),
+        NoteBlock { value: Note::MAX, ..NoteBlock::test(r64!(3), 0, r64!(0.5)) },
+    ];
+    let bps = r64!(2);
+    let sound = NoteSound { patterns: GraphEditor::new(notes.to_vec()).into(), ..default() };
+    let decoded = sound.to_midi(bps).and_then(|x| NoteSound::from_midi(&x, bps));
+    let decoded = decoded.unwrap_or_default();
+    let pattern = decoded.patterns.active().get().ok();
+    assert_eq!(pattern.as_ref().map_or(&[][..], |x| x.points()), notes);
+}
+
+#[test]
+fn note_sound_active_pattern_len() {
+    let note = |offset, len| NoteBlock { value: Note::MID, ..NoteBlock::test(offset, 0, len) };
+    let short = GraphEditor::new(vec![note(r64!(0), r64!(2))]);
+    let long = GraphEditor::new(vec![note(r64!(0), r64!(1)), note(r64!(4), r64!(1.5))]);
+    let patterns = Patterns::new(vec![("A".into(), short.into()), ("B".into(), long.into())], 0);
+    let mut sound = NoteSound { patterns: patterns.unwrap_or_default(), ..default() };
+    assert_eq!(sound.len().ok(), Some(Beats(r64!(2))));
+    assert_eq!(sound.patterns.select(1).ok(), Some(0));
+    assert_eq!(sound.len().ok(), Some(Beats(r64!(5.5))));
+    // a new pattern starts out as a copy of the active one
+    assert!(sound.patterns.add().is_ok());
+    assert_eq!((sound.patterns.active_index(), sound.len().ok()), (2, Some(Beats(r64!(5.5)))));
+    assert_eq!(sound.patterns.select(0).ok(), Some(2));
+    assert_eq!(sound.len().ok(), Some(Beats(r64!(2))));
+}
+
+/// a note of a `NoteSound` that's being played live, until it's released
+#[derive(Debug)]
+pub struct Audition {
+    gain: GainNode,
+    core: OscillatorNode,
+    lfo: Option<OscillatorNode>,
+    nodes: NodeChain,
+    release: Secs,
+}
+
+impl Audition {
+    /// Starts the release phase of the note, after which it's stopped & disconnected.
+    #[apply(fallible!)]
+    pub fn release(self) {
+        let Self { gain, core, lfo, mut nodes, release } = self;
+        let now = gain.context().current_time();
+        let param = gain.gain();
+        param.cancel_scheduled_values(now)?;
+        param.set_value_at_time(param.value(), now)?;
+        param.linear_ramp_to_value_at_time(0.0, now + *release)?;
+        core.stop_with_when(now + *release)?;
+        if let Some(lfo) = lfo {
+            lfo.stop_with_when(now + *release)?;
+        }
+        core.set_onended(Some(&js_function!(|| nodes.teardown())));
+    }
+}
+
+/// order in which the arpeggiator plays the notes of a chord
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArpMode {
+    #[default]
+    Up,
+    Down,
+    UpDown,
+    Random,
+}
+
+impl ArpMode {
+    pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::UpDown, Self::Random];
+    pub const NAMES: [&'static str; 4] = ["Up", "Down", "Up & Down", "Random"];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the index of the note played at `step` among `n_notes` notes sorted by pitch.
+    fn note_index(self, step: usize, n_notes: usize, rng: &mut Rng) -> usize {
+        match self {
+            Self::Up => step % n_notes,
+            Self::Down => n_notes - 1 - step % n_notes,
+            Self::UpDown => {
+                // the highest & the lowest notes aren't repeated when the direction changes
+                let period = (n_notes * 2 - 2).max(1);
+                let pos = step % period;
+                pos.min(period - pos)
+            }
+            Self::Random => (rng.next_u64() % n_notes as u64) as usize,
+        }
+    }
+}
+
+/// Replaces every chord in `notes`, i.e. 2 or more notes starting at the same offset, with its
+/// notes played one after another, each lasting `rate`, for as long as the longest of them.
+/// `notes` must be sorted by offset, the returned notes are sorted the same way.
+pub fn arpeggiate(notes: &[NoteBlock], rate: Beats, mode: ArpMode) -> Vec<NoteBlock> {
+    let mut res = Vec::with_capacity(notes.len());
+    let mut rest = notes;
+    while let Some(first) = rest.first() {
+        let n_notes = rest.iter().take_while(|x| x.offset == first.offset).count();
+        let (chord, next) = rest.split_at(n_notes);
+        rest = next;
+        if n_notes == 1 || rate <= Beats::ZERO {
+            res.extend_from_slice(chord);
+            continue;
+        }
+        let mut chord = chord.to_vec();
+        chord.sort_unstable_by_key(|x| x.value);
+        let end = first.offset + chord.iter().map(|x| x.len).max().unwrap_or_default();
+        let mut rng = Rng::new(first.offset.to_bits());
+        let (mut offset, mut step) = (first.offset, 0);
+        while offset < end {
+            let Some(note) = chord.get(mode.note_index(step, n_notes, &mut rng)) else { break };
+            res.push(NoteBlock { offset, len: rate.min(end - offset), ..*note });
+            offset += rate;
+            step += 1;
+        }
+    }
+    res.sort();
+    res
+}
+
+#[test]
+fn arpeggiator_up() {
+    let chord = [
+        NoteBlock::test(r64!(0), 28, r64!(1)),
+        NoteBlock::test(r64!(0), 24, r64!(1)),
+        NoteBlock::test(r64!(0), 31, r64!(1)),
+    ];
+    let arp = arpeggiate(&chord, Beats(r64!(0.25)), ArpMode::Up);
+    let expected = [
+        NoteBlock::test(r64!(0), 24, r64!(0.25)),
+        NoteBlock::test(r64!(0.25), 28, r64!(0.25)),
+        NoteBlock::test(r64!(0.5), 31, r64!(0.25)),
+        NoteBlock::test(r64!(0.75), 24, r64!(0.25)),
+    ];
+    assert_eq!(arp, expected);
+    // single notes are left as they are
+    let single = [NoteBlock::test(r64!(2), 20, r64!(1.5))];
+    assert_eq!(arpeggiate(&single, Beats(r64!(0.25)), ArpMode::Up), single);
+    let pitches = |mode| {
+        let arp = arpeggiate(&chord, Beats(r64!(0.125)), mode);
+        arp.iter().map(|x| x.value.index()).collect::<Vec<_>>()
+    };
+    assert_eq!(pitches(ArpMode::Down), [31, 28, 24, 31, 28, 24, 31, 28]);
+    assert_eq!(pitches(ArpMode::UpDown), [24, 28, 31, 28, 24, 28, 31, 28]);
+    // random order is seeded with the chord's offset, so it's the same on every playback
+    let mut rng = Rng::new(Beats::ZERO.to_bits());
+    let expected: Vec<_> =
+        (0..8).map(|i| [24, 28, 31][ArpMode::Random.note_index(i, 3, &mut rng)]).collect();
+    assert_eq!(pitches(ArpMode::Random), expected);
+    assert_eq!(pitches(ArpMode::Random), pitches(ArpMode::Random));
+    let mut rng = Rng::new(42);
+    let picks: Vec<_> = (0..64).map(|i| ArpMode::Random.note_index(i, 3, &mut rng)).collect();
+    assert!((0..3).all(|x| picks.contains(&x)));
+    assert!(picks.iter().all(|&x| x < 3));
+}
+
 #[derive(Debug, Clone)]
 pub struct NoteSound {
-    pub pattern: Shared<GraphEditor<NoteBlock>>,
+    pub patterns: Patterns<NoteBlock>,
     pub volume: R32,
+    /// stereo position of the sound, from -1 (fully left) to 1 (fully right)
+    pub pan: R32,
     pub attack: Beats,
     pub decay: Beats,
     pub sustain: R32,
     pub release: Beats,
     pub rep_count: NonZeroU32,
+    /// the gap between the repetitions of the pattern
+    pub rep_gap: Beats,
+    /// volume of the sound over the course of the sound block, on top of `volume`
+    pub volume_automation: Shared<GraphEditor<AutomationPoint>>,
+    pub filter_type: FilterType,
+    /// cutoff frequency of the filter, in Hz
+    pub filter_freq: R64,
+    pub filter_q: R64,
+    /// waveform of the LFO modulating the filter cutoff
+    pub lfo_shape: LfoShape,
+    /// length of a cycle of the LFO
+    pub lfo_rate: Beats,
+    /// how far the LFO moves the filter cutoff in either direction, in Hz, 0 disables the LFO
+    pub lfo_depth: R64,
+    /// whether chords are played as arpeggios
+    pub arp_enabled: bool,
+    /// length of a note played by the arpeggiator
+    pub arp_rate: Beats,
+    /// whether the arpeggiator goes up, down or jumps around the chord
+    pub arp_mode: ArpMode,
+    pub scale: Scale,
+    /// root of `scale`, in semitones from C
+    pub scale_root: u8,
+    /// notes placed by a single click in the note editor
+    pub chord: Chord,
 }
 
 impl Default for NoteSound {
     fn default() -> Self {
         Self {
-            pattern: default(),
+            patterns: default(),
             volume: r32!(1),
-            attack: r64!(0),
-            decay: r64!(0),
+            pan: r32!(0),
+            attack: Beats::ZERO,
+            decay: Beats::ZERO,
             sustain: r32!(1),
-            release: r64!(0),
+            release: Beats::ZERO,
             rep_count: NonZeroU32::MIN,
+            rep_gap: Beats::ZERO,
+            volume_automation: default(),
+            filter_type: FilterType::None,
+            filter_freq: r64!(1000),
+            filter_q: r64!(1),
+            lfo_shape: LfoShape::Sine,
+            lfo_rate: Beats::ONE,
+            lfo_depth: r64!(0),
+            arp_enabled: false,
+            arp_rate: Beats(r64!(0.25)),
+            arp_mode: ArpMode::Up,
+            scale: Scale::Chromatic,
+            scale_root: 0,
+            chord: Chord::Single,
         }
     }
 }
@@ -187,53 +626,275 @@ impl Default for NoteSound {
 impl NoteSound {
     pub const NAME: &'static str = "Simple Wave";
 
-    pub fn play(&self, plug: &AudioNode, now: Secs, self_offset: Secs, bps: Beats) -> Result {
-        let pat = self.pattern.get()?;
-        let Some(last) = pat.data().last() else {
+    /// Creates a sound from the notes of a Standard MIDI File, placing them according to the
+    /// tempo `bps`. Notes outside of the supported range are clamped to it.
+    pub fn from_midi(bytes: &[u8], bps: R64) -> Result<Self> {
+        let pattern = midi::decode(bytes)?
+            .into_iter()
+            .filter(|note| note.len > Secs::ZERO)
+            .map(|note| NoteBlock {
+                offset: note.start.to_beats(bps),
+                value: Note::from_midi_key(note.key),
+                len: note.len.to_beats(bps),
+                velocity: R32::ONE,
+            })
+            .collect();
+        Ok(Self { patterns: GraphEditor::new(pattern).into(), ..default() })
+    }
+
+    /// Encodes the pattern, repeated `rep_count` times `rep_gap` apart, as a Standard MIDI File in
+    /// tempo `bps`.
+    pub fn to_midi(&self, bps: R64) -> Result<Box<[u8]>> {
+        let pat = self.patterns.active().get()?;
+        let period = pat.points().last().map_or_default(|x| x.offset + x.len) + self.rep_gap;
+        let notes: Vec<_> = (0..self.rep_count.get())
+            .flat_map(|rep| {
+                pat.points().iter().map(move |note| midi::MidiNote {
+                    key: note.value.to_midi_key(),
+                    start: (period * rep + note.offset).to_secs(bps),
+                    len: note.len.to_secs(bps),
+                })
+            })
+            .collect();
+        Ok(midi::encode(&notes, bps))
+    }
+
+    pub fn play(
+        &self,
+        plug: &AudioNode,
+        now: Secs,
+        self_offset: Beats,
+        span: Range<Beats>,
+        tempo: &TempoMap,
+        swing: R32,
+    ) -> Result {
+        let pat = self.patterns.active().get()?;
+        let Some(last) = pat.points().last() else {
             return Ok(());
         };
-        let pat_len = (last.offset + last.len).to_secs(bps);
+        let period = last.offset + last.len + self.rep_gap;
         let ctx = plug.context();
+        let arp;
+        let notes = if self.arp_enabled {
+            arp = arpeggiate(pat.points(), self.arp_rate, self.arp_mode);
+            &arp
+        } else {
+            pat.points()
+        };
+
+        let span = span.start - self_offset..span.end - self_offset;
+        let starts = schedule(notes, self.rep_count, period, span, |x| x.offset);
+        for (start, NoteBlock { value, len, velocity, .. }) in starts {
+            let mut nodes = NodeChain::new();
+            let block = nodes.add(ctx.create_gain()?);
+            let gain = block.gain();
+            let beat = self_offset + start;
+            let bps = tempo.bps_at(beat);
+            let start = now + swing_time(beat, swing, tempo);
+            let volume = self.volume * *velocity;
+            let mut at = start;
+            gain.set_value_at_time(0.0, *at)?;
+            at += self.attack.to_secs(bps);
+            gain.linear_ramp_to_value_at_time(*volume, *at)?;
+            at += self.decay.to_secs(bps);
+            let sus = self.sustain * volume;
+            gain.linear_ramp_to_value_at_time(*sus, *at)?;
+            at = start + tempo.duration(beat, *len);
+            gain.set_value_at_time(*sus, *at - *self.release.to_secs(bps))?;
+            gain.linear_ramp_to_value_at_time(0.0, *at)?;
 
-        for rep in 0..self.rep_count.get() {
-            for NoteBlock { offset, value, len } in pat.data() {
-                let block = ctx.create_gain()?;
-                let gain = block.gain();
-                let start = now + self_offset + pat_len * rep + offset.to_secs(bps);
-                let mut at = start;
-                gain.set_value_at_time(0.0, *at)?;
-                at += self.attack.to_secs(bps);
-                gain.linear_ramp_to_value_at_time(*self.volume, *at)?;
-                at += self.decay.to_secs(bps);
-                let sus = self.sustain * self.volume;
-                gain.linear_ramp_to_value_at_time(*sus, *at)?;
-                at = start + len.to_secs(bps);
-                gain.set_value_at_time(*sus, *at - *self.release.to_secs(bps))?;
-                gain.linear_ramp_to_value_at_time(0.0, *at)?;
-
-                let block_core = ctx.create_oscillator()?;
-                block_core.frequency().set_value(*value.freq());
-                block_core.connect_with_audio_node(&block)?.connect_with_audio_node(plug)?;
-                block_core.start_with_when(*start)?;
-                block_core.stop_with_when(*at)?;
-                block_core.clone().set_onended(Some(&js_function!(|| {
-                    block.disconnect().map_err(AppError::from).report();
-                    block_core.disconnect().map_err(AppError::from).report();
-                })));
+            let block_core = nodes.add(ctx.create_oscillator()?);
+            block_core.frequency().set_value(*value.freq());
+            let filter = self.filter().map(|x| x.create(&ctx)).transpose()?.map(|x| nodes.add(x));
+            let mut output = block_core.connect_with_audio_node(&block)?;
+            if let Some(filter) = &filter {
+                output = output.connect_with_audio_node(filter)?;
+                if let Some(lfo) = self.lfo(bps) {
+                    let lfo = lfo.create(&ctx, &filter.frequency(), &mut nodes)?;
+                    lfo.start_with_when(*start)?;
+                    lfo.stop_with_when(*at)?;
+                }
             }
+            let panner = nodes.add(self.panner().create(&ctx)?);
+            output.connect_with_audio_node(&panner)?.connect_with_audio_node(plug)?;
+            block_core.start_with_when(*start)?;
+            block_core.stop_with_when(*at)?;
+            block_core.set_onended(Some(&js_function!(|| nodes.teardown())));
         }
         Ok(())
     }
 
+    /// Starts playing `note` with the sound's envelope & filter right away.
+    /// The note is held at the sustain level until the returned `Audition` is released.
+    #[apply(fallible!)]
+    pub fn audition(&self, note: Note, plug: &AudioNode, bps: R64) -> Audition {
+        let ctx = plug.context();
+        let mut nodes = NodeChain::new();
+        let block = nodes.add(ctx.create_gain()?);
+        let gain = block.gain();
+        let mut at = Secs(R64::new_or(R64::ZERO, ctx.current_time()));
+        gain.set_value_at_time(0.0, *at)?;
+        at += self.attack.to_secs(bps);
+        gain.linear_ramp_to_value_at_time(*self.volume, *at)?;
+        at += self.decay.to_secs(bps);
+        gain.linear_ramp_to_value_at_time(*(self.sustain * self.volume), *at)?;
+
+        let core = nodes.add(ctx.create_oscillator()?);
+        core.frequency().set_value(*note.freq());
+        let filter = self.filter().map(|x| x.create(&ctx)).transpose()?.map(|x| nodes.add(x));
+        let mut output = core.connect_with_audio_node(&block)?;
+        let mut lfo = None;
+        if let Some(filter) = &filter {
+            output = output.connect_with_audio_node(filter)?;
+            if let Some(params) = self.lfo(bps) {
+                lfo = Some(params.create(&ctx, &filter.frequency(), &mut nodes)?);
+            }
+        }
+        let panner = nodes.add(self.panner().create(&ctx)?);
+        output.connect_with_audio_node(&panner)?.connect_with_audio_node(plug)?;
+        core.start()?;
+        if let Some(lfo) = &lfo {
+            lfo.start()?;
+        }
+        Audition { gain: block, core, lfo, nodes, release: self.release.to_secs(bps) }
+    }
+
     #[apply(fallible!)]
     pub fn len(&self) -> Beats {
-        self.pattern.get()?.data().last().map_or_default(|x| x.offset + x.len)
+        self.patterns.active().get()?.points().last().map_or_default(|x| x.offset + x.len)
+    }
+
+    /// Moves the offsets of the selected notes, & their lengths if `lengths` is set, towards the
+    /// nearest multiple of `step` by `strength`, 0 meaning no change & 1 meaning full snap.
+    /// Lengths are never snapped to less than `step`.
+    /// Returns the action that represents the quantization.
+    #[apply(fallible!)]
+    pub fn quantize(&mut self, step: Beats, strength: R32, lengths: bool) -> EditorAction {
+        let snap = |x: Beats, min: Beats| {
+            let snapped = (x + step / r64!(2)).floor_to(step).max(min);
+            x + (snapped - x) * R64::from(strength)
+        };
+        let mut pat = self.patterns.active().get_mut()?;
+        let mut ids = pat.selection().to_vec();
+        let mut changes = PatternDiff::default();
+        for i in 0..ids.len() {
+            let id = *ids.get(i)?;
+            let mut note = *pat.points().get(id)?;
+            note.offset = snap(note.offset, Beats::ZERO);
+            if lengths {
+                note.len = snap(note.len, step);
+            }
+            pat.set_point(id, note, &mut changes)?.apply(&mut ids);
+        }
+        EditorAction::QuantizeNotes { changes }
+    }
+
+    /// Extends every selected note so that it ends right where the next selected note of the same
+    /// pitch starts. The last selected note of every pitch keeps its length.
+    /// Returns the action that represents the change.
+    #[apply(fallible!)]
+    pub fn legato(&mut self) -> EditorAction {
+        let mut pat = self.patterns.active().get_mut()?;
+        let ids = pat.selection().to_box();
+        let mut lens = vec![];
+        for (i, &id) in ids.iter().enumerate() {
+            let note = pat.points().get(id)?;
+            let Some(next) = ids
+                .get(i + 1..)?
+                .iter()
+                .filter_map(|&id| pat.points().get(id))
+                .find(|x| x.value == note.value && x.offset > note.offset)
+            else {
+                continue;
+            };
+            lens.push((id, next.offset - note.offset));
+        }
+        let mut changes = PatternDiff::default();
+        for (id, len) in lens {
+            // the lengths of the notes don't affect their order, so the IDs stay valid
+            let note = NoteBlock { len, ..*pat.points().get(id)? };
+            pat.set_point(id, note, &mut changes)?;
+        }
+        EditorAction::Legato { changes }
+    }
+
+    /// Moves the pitches of the selected notes by `by` semitones. If that would move some of
+    /// them out of the supported range, none of them are moved.
+    /// Returns the action that represents the transposition, or `None` if nothing was moved.
+    #[apply(fallible!)]
+    pub fn transpose(&mut self, by: isize) -> Option<EditorAction> {
+        let mut pat = self.patterns.active().get_mut()?;
+        let ids = pat.selection().to_box();
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let Some(values) = ids
+            .iter()
+            .map(|&id| pat.points().get(id).and_then(|x| x.value + by))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(None);
+        };
+        let mut notes = pat.points_mut();
+        for (&id, value) in ids.iter().zip(values) {
+            *notes.get_mut(id)?.y() = value;
+        }
+        Some(EditorAction::Transpose { ids, by })
+    }
+
+    /// Moves the offsets of the selected notes by up to `time_amt` & their velocities by up to
+    /// `vel_amt` in either direction, pseudo-randomly, the same `seed` always giving the same
+    /// result. The offsets are kept non-negative & in the same order, the velocities are kept
+    /// within [0, 1].
+    /// Returns the action that represents the change.
+    #[apply(fallible!)]
+    pub fn humanize(&mut self, seed: u64, time_amt: Beats, vel_amt: R32) -> EditorAction {
+        let mut rng = Rng::new(seed);
+        let mut jitter = move || R64::new_or(R64::ZERO, rng.next_signed());
+        let mut pat = self.patterns.active().get_mut()?;
+        let mut ids = pat.selection().to_vec();
+        ids.sort_unstable();
+        let mut changes = PatternDiff::default();
+        for id in ids {
+            let prev = id.checked_sub(1).and_then(|x| pat.points().get(x));
+            let min = prev.map_or(Beats::ZERO, |x| x.offset);
+            let max = pat.points().get(id + 1).map_or(Beats::INFINITY, |x| x.offset);
+            // the offset is kept between those of the neighbouring notes, so the IDs stay valid
+            let mut note = *pat.points().get(id)?;
+            note.offset = (note.offset + time_amt * jitter()).clamp(min, max);
+            note.velocity =
+                (note.velocity + R32::from(jitter()) * vel_amt).clamp(R32::ZERO, R32::ONE);
+            pat.set_point(id, note, &mut changes)?;
+        }
+        EditorAction::Humanize { changes }
     }
 
     pub const fn rep_count(&self) -> NonZeroU32 {
         self.rep_count
     }
 
+    pub const fn rep_gap(&self) -> Beats {
+        self.rep_gap
+    }
+
+    pub fn filter(&self) -> Option<FilterParams> {
+        self.filter_type.params(self.filter_freq, self.filter_q)
+    }
+
+    pub fn panner(&self) -> PanParams {
+        PanParams::new(self.pan)
+    }
+
+    /// Returns the configuration of the LFO modulating the filter cutoff in tempo `bps`,
+    /// or `None` if the cutoff isn't modulated.
+    pub fn lfo(&self, bps: R64) -> Option<LfoParams> {
+        LfoParams::new(self.lfo_shape, self.lfo_rate, self.lfo_depth, bps)
+    }
+
+    const fn visual_ctx(&self, offset: Beats) -> <NoteBlock as GraphPoint>::VisualContext {
+        (offset, self.rep_count, self.rep_gap, (self.scale, self.scale_root), self.chord)
+    }
+
     pub fn params(&self, ctx: ContextRef) -> Html {
         let emitter = ctx.event_emitter();
         match ctx.selected_tab() {
@@ -243,8 +904,17 @@ impl NoteSound {
                         key="note-vol"
                         setter={emitter.reform(|x| AppEvent::Volume(R32::from(x)))}
                         name="Note Volume"
+                        default={R64::ONE}
                         initial={self.volume}
                     />
+                    <Slider
+                        key="note-pan"
+                        setter={emitter.reform(|x| AppEvent::Pan(R32::from(x)))}
+                        name="Note Pan"
+                        default={R64::ZERO}
+                        bipolar=true
+                        initial={self.pan}
+                    />
                     <Counter
                         key="note-repcnt"
                         setter={emitter.reform(|x| AppEvent::RepCount(NonZeroU32::from(x)))}
@@ -253,6 +923,93 @@ impl NoteSound {
                         min=1
                         initial={self.rep_count}
                     />
+                    <Counter
+                        key="note-repgap"
+                        setter={emitter.reform(|x| AppEvent::RepGap(Beats(x)))}
+                        name="Gap Between Pattern Repetitions"
+                        postfix="Beats"
+                        initial={self.rep_gap}
+                    />
+                    <Switch
+                        key="note-scale"
+                        name="Scale"
+                        options={Scale::NAMES.to_vec()}
+                        setter={emitter.reform(|x| AppEvent::Scale(*Scale::ALL.get_wrapping(x)))}
+                        initial={self.scale.index()}
+                    />
+                    <Switch
+                        key="note-scale-root"
+                        name="Scale Root"
+                        options={Note::SEMITONE_NAMES.to_vec()}
+                        setter={emitter.reform(|x| AppEvent::ScaleRoot(x as u8))}
+                        initial={self.scale_root as usize}
+                    />
+                    <Switch
+                        key="note-chord"
+                        name="Chord Placed By A Click"
+                        options={Chord::NAMES.to_vec()}
+                        setter={emitter.reform(|x| AppEvent::Chord(*Chord::ALL.get_wrapping(x)))}
+                        initial={self.chord.index()}
+                    />
+                    <Switch
+                        key="note-arp"
+                        name="Arpeggiator"
+                        options={vec!["Off", "On"]}
+                        setter={emitter.reform(|_| AppEvent::ToggleArp)}
+                        initial={self.arp_enabled as usize}
+                    />
+                    if self.arp_enabled {
+                        <Switch
+                            key="note-arp-mode"
+                            name="Arpeggiator Mode"
+                            options={ArpMode::NAMES.to_vec()}
+                            setter={emitter.reform(|x| {
+                                AppEvent::ArpMode(*ArpMode::ALL.get_wrapping(x))
+                            })}
+                            initial={self.arp_mode.index()}
+                        />
+                        <Counter
+                            key="note-arp-rate"
+                            setter={emitter.reform(|x| AppEvent::ArpRate(Beats(x)))}
+                            name="Arpeggiator Rate"
+                            postfix="Beats"
+                            min={r64!(0.0625)}
+                            initial={self.arp_rate}
+                        />
+                    }
+                    <Button
+                        name="Transpose the selected notes an octave down"
+                        onclick={emitter.reform(|_| AppEvent::Transpose(-12))}
+                    >
+                        <span>{ "-12" }</span>
+                    </Button>
+                    <Button
+                        name="Transpose the selected notes an octave up"
+                        onclick={emitter.reform(|_| AppEvent::Transpose(12))}
+                    >
+                        <span>{ "+12" }</span>
+                    </Button>
+                    <Button
+                        name="Transpose the selected notes a semitone down"
+                        onclick={emitter.reform(|_| AppEvent::Transpose(-1))}
+                    >
+                        <span>{ "-1" }</span>
+                    </Button>
+                    <Button
+                        name="Transpose the selected notes a semitone up"
+                        onclick={emitter.reform(|_| AppEvent::Transpose(1))}
+                    >
+                        <span>{ "+1" }</span>
+                    </Button>
+                    <Button
+                        name="Export the pattern as MIDI"
+                        class="wide"
+                        help="Save the pattern with all its repetitions as a MIDI file"
+                        onclick={emitter.reform(|_| AppEvent::ExportMidi)}
+                    >
+                        <span>{ "Export as MIDI" }</span>
+                    </Button>
+                    { self.patterns.render(emitter) }
                 </div>
             },
 
@@ -260,14 +1017,14 @@ impl NoteSound {
                 <div id="inputs">
                     <Counter
                         key="note-att"
-                        setter={emitter.reform(AppEvent::Attack)}
+                        setter={emitter.reform(|x| AppEvent::Attack(Beats(x)))}
                         name="Note Attack Time"
                         postfix="Beats"
                         initial={self.attack}
                     />
                     <Counter
                         key="note-dec"
-                        setter={emitter.reform(AppEvent::Decay)}
+                        setter={emitter.reform(|x| AppEvent::Decay(Beats(x)))}
                         name="Note Decay Time"
                         postfix="Beats"
                         initial={self.decay}
@@ -280,16 +1037,85 @@ impl NoteSound {
                     />
                     <Counter
                         key="note-rel"
-                        setter={emitter.reform(AppEvent::Release)}
+                        setter={emitter.reform(|x| AppEvent::Release(Beats(x)))}
                         name="Note Release Time"
                         postfix="Beats"
                         initial={self.release}
                     />
+                    <EnvelopeGraph
+                        attack={self.attack}
+                        decay={self.decay}
+                        sustain={self.sustain}
+                        release={self.release}
+                        emitter={emitter.clone()}
+                    />
                 </div>
             },
 
             2 /* Pattern */ => html!{
-                <GraphEditorCanvas<NoteBlock> editor={&self.pattern} {emitter} />
+                <GraphEditorCanvas<NoteBlock>
+                    key={self.patterns.active_index()}
+                    editor={self.patterns.active()}
+                    {emitter}
+                />
+            },
+
+            3 /* Filter */ => html!{
+                <div id="inputs">
+                    <Switch
+                        key="note-filter-type"
+                        name="Note Filter Type"
+                        options={FilterType::NAMES.to_vec()}
+                        setter={emitter.reform(|x| {
+                            AppEvent::FilterType(*FilterType::ALL.get_wrapping(x))
+                        })}
+                        initial={self.filter_type.index()}
+                    />
+                    <Counter
+                        key="note-filter-freq"
+                        setter={emitter.reform(AppEvent::FilterFreq)}
+                        fmt={|x| format!("{x:.0}")}
+                        name="Note Filter Cutoff Frequency"
+                        postfix="Hz"
+                        coef={r64!(1000)}
+                        min={r64!(10)}
+                        initial={self.filter_freq}
+                    />
+                    <Counter
+                        key="note-filter-q"
+                        setter={emitter.reform(AppEvent::FilterQ)}
+                        name="Note Filter Resonance"
+                        coef={r64!(10)}
+                        min={r64!(0.0001)}
+                        initial={self.filter_q}
+                    />
+                    <Switch
+                        key="note-lfo-shape"
+                        name="Note LFO Shape"
+                        options={LfoShape::NAMES.to_vec()}
+                        setter={emitter.reform(|x| {
+                            AppEvent::LfoShape(*LfoShape::ALL.get_wrapping(x))
+                        })}
+                        initial={self.lfo_shape.index()}
+                    />
+                    <Counter
+                        key="note-lfo-rate"
+                        setter={emitter.reform(|x| AppEvent::LfoRate(Beats(x)))}
+                        name="Note LFO Rate"
+                        postfix="Beats"
+                        min={r64!(0.0625)}
+                        initial={self.lfo_rate}
+                    />
+                    <Counter
+                        key="note-lfo-depth"
+                        setter={emitter.reform(AppEvent::LfoDepth)}
+                        fmt={|x| format!("{x:.0}")}
+                        name="Note LFO Depth"
+                        postfix="Hz"
+                        coef={r64!(1000)}
+                        initial={self.lfo_depth}
+                    />
+                </div>
             },
 
             tab_id => html!{ <p style="color:red">{ format!("Invalid tab ID: {tab_id}") }</p> }
@@ -313,6 +1139,10 @@ impl NoteSound {
                 to,
             })?,
 
+            AppEvent::Pan(to) => {
+                ctx.register_action(EditorAction::SetPan { from: replace(&mut self.pan, to), to })?
+            }
+
             AppEvent::Attack(to) => ctx.register_action(EditorAction::SetAttack {
                 from: replace(&mut self.attack, to),
                 to,
@@ -333,6 +1163,51 @@ impl NoteSound {
                 to,
             })?,
 
+            AppEvent::FilterType(to) => ctx.register_action(EditorAction::SetFilterType {
+                from: replace(&mut self.filter_type, to),
+                to,
+            })?,
+
+            AppEvent::FilterFreq(to) => ctx.register_action(EditorAction::SetFilterFreq {
+                from: replace(&mut self.filter_freq, to),
+                to,
+            })?,
+
+            AppEvent::FilterQ(to) => ctx.register_action(EditorAction::SetFilterQ {
+                from: replace(&mut self.filter_q, to),
+                to,
+            })?,
+
+            AppEvent::LfoShape(to) => ctx.register_action(EditorAction::SetLfoShape {
+                from: replace(&mut self.lfo_shape, to),
+                to,
+            })?,
+
+            AppEvent::LfoRate(to) => ctx.register_action(EditorAction::SetLfoRate {
+                from: replace(&mut self.lfo_rate, to),
+                to,
+            })?,
+
+            AppEvent::LfoDepth(to) => ctx.register_action(EditorAction::SetLfoDepth {
+                from: replace(&mut self.lfo_depth, to),
+                to,
+            })?,
+
+            AppEvent::ToggleArp => {
+                self.arp_enabled.flip();
+                ctx.register_action(EditorAction::ToggleArp)?
+            }
+
+            AppEvent::ArpMode(to) => ctx.register_action(EditorAction::SetArpMode {
+                from: replace(&mut self.arp_mode, to),
+                to,
+            })?,
+
+            AppEvent::ArpRate(to) => ctx.register_action(EditorAction::SetArpRate {
+                from: replace(&mut self.arp_rate, to),
+                to,
+            })?,
+
             AppEvent::RepCount(to) => {
                 ctx.register_action(EditorAction::SetRepCount {
                     from: replace(&mut self.rep_count, to),
@@ -341,8 +1216,72 @@ impl NoteSound {
                 ctx.emit_event(AppEvent::RedrawEditorPlane);
             }
 
+            AppEvent::RepGap(to) => {
+                ctx.register_action(EditorAction::SetRepGap {
+                    from: replace(&mut self.rep_gap, to),
+                    to,
+                })?;
+                ctx.emit_event(AppEvent::RedrawEditorPlane);
+            }
+
+            AppEvent::ExportMidi => save_file(&self.to_midi(sequencer.bps())?, "pattern.mid")?,
+
+            AppEvent::AddPattern | AppEvent::SelectPattern(_) | AppEvent::RenamePattern(_) => {
+                self.patterns.handle_event(event, ctx)?
+            }
+
+            AppEvent::Scale(to) => ctx.register_action(EditorAction::SetScale {
+                from: replace(&mut self.scale, to),
+                to,
+            })?,
+
+            AppEvent::ScaleRoot(to) => ctx.register_action(EditorAction::SetScaleRoot {
+                from: replace(&mut self.scale_root, to),
+                to,
+            })?,
+
+            AppEvent::Chord(to) => ctx.register_action(EditorAction::SetChord {
+                from: replace(&mut self.chord, to),
+                to,
+            })?,
+
+            AppEvent::Quantize { strength, lengths } => {
+                if ctx.selected_tab() == 2 && !self.patterns.active().get()?.selection().is_empty()
+                {
+                    let action = self.quantize(Beats(ctx.snap_step()), strength, lengths)?;
+                    ctx.register_action(action)?;
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
+                }
+            }
+
+            AppEvent::Legato => {
+                if ctx.selected_tab() == 2 && !self.patterns.active().get()?.selection().is_empty()
+                {
+                    let action = self.legato()?;
+                    ctx.register_action(action)?;
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
+                }
+            }
+
+            AppEvent::Humanize { time_amt, vel_amt } => {
+                if ctx.selected_tab() == 2 && !self.patterns.active().get()?.selection().is_empty()
+                {
+                    let seed = ctx.frame().to_bits();
+                    let action = self.humanize(seed, time_amt, vel_amt)?;
+                    ctx.register_action(action)?;
+                    ctx.emit_event(AppEvent::RedrawEditorPlane)
+                }
+            }
+
+            AppEvent::Transpose(by) => {
+                if let Some(action) = self.transpose(by)? {
+                    ctx.register_action(action)?;
+                }
+            }
+
             AppEvent::Undo(ref actions) => {
-                let mut pat = self.pattern.get_mut()?;
+                self.patterns.handle_event(event, ctx.as_mut())?;
+                let mut pat = self.patterns.active().get_mut()?;
                 for action in actions.iter() {
                     match *action {
                         EditorAction::SetBlockType(_) => {
@@ -352,6 +1291,8 @@ impl NoteSound {
 
                         EditorAction::SetVolume { from, .. } => self.volume = from,
 
+                        EditorAction::SetPan { from, .. } => self.pan = from,
+
                         EditorAction::SetAttack { from, .. } => self.attack = from,
 
                         EditorAction::SetDecay { from, .. } => self.decay = from,
@@ -360,26 +1301,74 @@ impl NoteSound {
 
                         EditorAction::SetRelease { from, .. } => self.release = from,
 
+                        EditorAction::SetFilterType { from, .. } => self.filter_type = from,
+
+                        EditorAction::SetFilterFreq { from, .. } => self.filter_freq = from,
+
+                        EditorAction::SetFilterQ { from, .. } => self.filter_q = from,
+
+                        EditorAction::SetLfoShape { from, .. } => self.lfo_shape = from,
+
+                        EditorAction::SetLfoRate { from, .. } => self.lfo_rate = from,
+
+                        EditorAction::SetLfoDepth { from, .. } => self.lfo_depth = from,
+
+                        EditorAction::ToggleArp => self.arp_enabled.flip(),
+
+                        EditorAction::SetArpMode { from, .. } => self.arp_mode = from,
+
+                        EditorAction::SetArpRate { from, .. } => self.arp_rate = from,
+
                         EditorAction::SetRepCount { from, .. } => {
                             self.rep_count = from;
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
                         }
 
+                        EditorAction::SetRepGap { from, .. } => {
+                            self.rep_gap = from;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::SetScale { from, .. } => self.scale = from,
+
+                        EditorAction::SetScaleRoot { from, .. } => self.scale_root = from,
+
+                        EditorAction::SetChord { from, .. } => self.chord = from,
+
+                        EditorAction::QuantizeNotes { ref changes }
+                        | EditorAction::Legato { ref changes }
+                        | EditorAction::Humanize { ref changes } => {
+                            pat.apply_diff(changes, true);
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::Transpose { ref ids, by } => {
+                            let mut notes = pat.points_mut();
+                            for &id in ids.iter() {
+                                let mut note = notes.get_mut(id)?;
+                                let value = note.y();
+                                *value = (*value - by)?;
+                            }
+                        }
+
                         _ => (),
                     }
                 }
 
                 if ctx.selected_tab() == 2 {
-                    pat.handle_event(event, ctx, sequencer, || (offset, self.rep_count))?;
+                    pat.handle_event(event, ctx, sequencer, || self.visual_ctx(offset))?;
                 }
             }
 
             AppEvent::Redo(ref actions) => {
-                let mut pat = self.pattern.get_mut()?;
+                self.patterns.handle_event(event, ctx.as_mut())?;
+                let mut pat = self.patterns.active().get_mut()?;
                 for action in actions.iter() {
                     match *action {
                         EditorAction::SetVolume { to, .. } => self.volume = to,
 
+                        EditorAction::SetPan { to, .. } => self.pan = to,
+
                         EditorAction::SetAttack { to, .. } => self.attack = to,
 
                         EditorAction::SetDecay { to, .. } => self.decay = to,
@@ -388,25 +1377,73 @@ impl NoteSound {
 
                         EditorAction::SetRelease { to, .. } => self.release = to,
 
+                        EditorAction::SetFilterType { to, .. } => self.filter_type = to,
+
+                        EditorAction::SetFilterFreq { to, .. } => self.filter_freq = to,
+
+                        EditorAction::SetFilterQ { to, .. } => self.filter_q = to,
+
+                        EditorAction::SetLfoShape { to, .. } => self.lfo_shape = to,
+
+                        EditorAction::SetLfoRate { to, .. } => self.lfo_rate = to,
+
+                        EditorAction::SetLfoDepth { to, .. } => self.lfo_depth = to,
+
+                        EditorAction::ToggleArp => self.arp_enabled.flip(),
+
+                        EditorAction::SetArpMode { to, .. } => self.arp_mode = to,
+
+                        EditorAction::SetArpRate { to, .. } => self.arp_rate = to,
+
                         EditorAction::SetRepCount { to, .. } => {
                             self.rep_count = to;
                             ctx.emit_event(AppEvent::RedrawEditorPlane)
                         }
 
+                        EditorAction::SetRepGap { to, .. } => {
+                            self.rep_gap = to;
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::SetScale { to, .. } => self.scale = to,
+
+                        EditorAction::SetScaleRoot { to, .. } => self.scale_root = to,
+
+                        EditorAction::SetChord { to, .. } => self.chord = to,
+
+                        EditorAction::QuantizeNotes { ref changes }
+                        | EditorAction::Legato { ref changes }
+                        | EditorAction::Humanize { ref changes } => {
+                            pat.apply_diff(changes, false);
+                            ctx.emit_event(AppEvent::RedrawEditorPlane)
+                        }
+
+                        EditorAction::Transpose { ref ids, by } => {
+                            let mut notes = pat.points_mut();
+                            for &id in ids.iter() {
+                                let mut note = notes.get_mut(id)?;
+                                let value = note.y();
+                                *value = (*value + by)?;
+                            }
+                        }
+
                         _ => (),
                     }
                 }
 
                 if ctx.selected_tab() == 2 {
-                    pat.handle_event(event, ctx, sequencer, || (offset, self.rep_count))?;
+                    pat.handle_event(event, ctx, sequencer, || self.visual_ctx(offset))?;
                 }
             }
 
             _ => {
                 if ctx.selected_tab() == 2 {
-                    self.pattern
-                        .get_mut()?
-                        .handle_event(event, ctx, sequencer, || (offset, self.rep_count))?;
+                    self.patterns.active().get_mut()?.handle_event(
+                        event,
+                        ctx,
+                        sequencer,
+                        || self.visual_ctx(offset),
+                    )?;
                 }
             }
         }
@@ -0,0 +1,347 @@
+use crate::{
+    ctx::{AppEvent, ContextMut, EditorAction},
+    input::{Counter, EnvelopeGraph, Slider, Switch},
+    sound::{AudioInput, Beats, FilterParams, FilterType, FromBeats, NodeChain, PanParams, Secs},
+};
+use macro_rules_attribute::apply;
+use std::{mem::replace, num::NonZeroU32};
+use wasm_bindgen::JsCast;
+use wavexp_utils::{
+    error::Result,
+    ext::SliceExt,
+    fallible, js_function, r32, r64,
+    real::{R32, R64},
+};
+use web_sys::AudioNode;
+use yew::{html, Callback, Html};
+
+/// parameters shared by the sounds that play audio samples, i.e. `CustomSound` & `DrumSound`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleParams {
+    pub volume: R32,
+    /// stereo position of the sound, from -1 (fully left) to 1 (fully right)
+    pub pan: R32,
+    pub attack: Beats,
+    pub decay: Beats,
+    pub sustain: R32,
+    pub release: Beats,
+    pub rep_count: NonZeroU32,
+    /// the gap between the repetitions of the pattern
+    pub rep_gap: Beats,
+    pub filter_type: FilterType,
+    /// cutoff frequency of the filter, in Hz
+    pub filter_freq: R64,
+    pub filter_q: R64,
+}
+
+impl Default for SampleParams {
+    fn default() -> Self {
+        Self {
+            volume: r32!(1),
+            pan: r32!(0),
+            attack: Beats::ZERO,
+            decay: Beats::ZERO,
+            sustain: r32!(1),
+            release: Beats::ZERO,
+            rep_count: NonZeroU32::MIN,
+            rep_gap: Beats::ZERO,
+            filter_type: FilterType::None,
+            filter_freq: r64!(1000),
+            filter_q: r64!(1),
+        }
+    }
+}
+
+impl SampleParams {
+    pub fn filter(&self) -> Option<FilterParams> {
+        self.filter_type.params(self.filter_freq, self.filter_q)
+    }
+
+    pub fn panner(&self) -> PanParams {
+        PanParams::new(self.pan)
+    }
+
+    /// Plays `sample` into `plug` from `start` at playback rate `rate`, shaping its volume with
+    /// the envelope in tempo `bps` & passing it through the filter & the panner.
+    #[apply(fallible!)]
+    pub fn play(&self, sample: &AudioInput, plug: &AudioNode, start: Secs, bps: R64, rate: R32) {
+        let ctx = plug.context();
+        let mut nodes = NodeChain::new();
+        let block = nodes.add(ctx.create_gain()?);
+        let gain = block.gain();
+        let mut at = start;
+        gain.set_value_at_time(0.0, *at)?;
+        at += self.attack.to_secs(bps);
+        gain.linear_ramp_to_value_at_time(*self.volume, *at)?;
+        at += self.decay.to_secs(bps);
+        let sus = self.sustain * self.volume;
+        gain.linear_ramp_to_value_at_time(*sus, *at)?;
+        at = start + sample.baked_duration() / rate;
+        gain.set_value_at_time(*sus, *at - *self.release.to_secs(bps))?;
+        gain.linear_ramp_to_value_at_time(0.0, *at)?;
+
+        let block_core = nodes.add(ctx.create_buffer_source()?);
+        block_core.set_buffer(Some(sample.baked()?));
+        block_core.playback_rate().set_value(*rate);
+        let filter = self.filter().map(|x| x.create(&ctx)).transpose()?.map(|x| nodes.add(x));
+        let mut output = block_core.connect_with_audio_node(&block)?;
+        if let Some(filter) = &filter {
+            output = output.connect_with_audio_node(filter)?;
+        }
+        let panner = nodes.add(self.panner().create(&ctx)?);
+        output.connect_with_audio_node(&panner)?.connect_with_audio_node(plug)?;
+        block_core.start_with_when(*start)?;
+        block_core.set_onended(Some(&js_function!(|| nodes.teardown())));
+    }
+
+    /// Inputs for the volume, the pan & the repetitions of the pattern of a sound named `name`.
+    pub fn general_inputs(&self, name: &str, emitter: &Callback<AppEvent>) -> Html {
+        html! {<>
+            <Slider
+                key={format!("{name}-vol")}
+                setter={emitter.reform(|x| AppEvent::Volume(R32::from(x)))}
+                name={format!("{name} Volume")}
+                default={R64::ONE}
+                initial={self.volume}
+            />
+            <Slider
+                key={format!("{name}-pan")}
+                setter={emitter.reform(|x| AppEvent::Pan(R32::from(x)))}
+                name={format!("{name} Pan")}
+                default={R64::ZERO}
+                bipolar=true
+                initial={self.pan}
+            />
+            <Counter
+                key={format!("{name}-repcnt")}
+                setter={emitter.reform(|x| AppEvent::RepCount(NonZeroU32::from(x)))}
+                fmt={|x| format!("{x:.0}")}
+                name="Number Of Pattern Repetitions"
+                min=1
+                initial={self.rep_count}
+            />
+            <Counter
+                key={format!("{name}-repgap")}
+                setter={emitter.reform(|x| AppEvent::RepGap(Beats(x)))}
+                name="Gap Between Pattern Repetitions"
+                postfix="Beats"
+                initial={self.rep_gap}
+            />
+        </>}
+    }
+
+    /// The tab with the envelope of a sound named `name`.
+    pub fn envelope_tab(&self, name: &str, emitter: &Callback<AppEvent>) -> Html {
+        html! {
+            <div id="inputs">
+                <Counter
+                    key={format!("{name}-att")}
+                    setter={emitter.reform(|x| AppEvent::Attack(Beats(x)))}
+                    name={format!("{name} Attack Time")}
+                    postfix="Beats"
+                    initial={self.attack}
+                />
+                <Counter
+                    key={format!("{name}-dec")}
+                    setter={emitter.reform(|x| AppEvent::Decay(Beats(x)))}
+                    name={format!("{name} Decay Time")}
+                    postfix="Beats"
+                    initial={self.decay}
+                />
+                <Slider
+                    key={format!("{name}-sus")}
+                    setter={emitter.reform(|x| AppEvent::Sustain(R32::from(x)))}
+                    name={format!("{name} Sustain Level")}
+                    initial={self.sustain}
+                />
+                <Counter
+                    key={format!("{name}-rel")}
+                    setter={emitter.reform(|x| AppEvent::Release(Beats(x)))}
+                    name={format!("{name} Release Time")}
+                    postfix="Beats"
+                    initial={self.release}
+                />
+                <EnvelopeGraph
+                    attack={self.attack}
+                    decay={self.decay}
+                    sustain={self.sustain}
+                    release={self.release}
+                    emitter={emitter.clone()}
+                />
+            </div>
+        }
+    }
+
+    /// The tab with the filter of a sound named `name`.
+    pub fn filter_tab(&self, name: &str, emitter: &Callback<AppEvent>) -> Html {
+        html! {
+            <div id="inputs">
+                <Switch
+                    key={format!("{name}-filter-type")}
+                    name={format!("{name} Filter Type")}
+                    options={FilterType::NAMES.to_vec()}
+                    setter={emitter.reform(|x| {
+                        AppEvent::FilterType(*FilterType::ALL.get_wrapping(x))
+                    })}
+                    initial={self.filter_type.index()}
+                />
+                <Counter
+                    key={format!("{name}-filter-freq")}
+                    setter={emitter.reform(AppEvent::FilterFreq)}
+                    fmt={|x| format!("{x:.0}")}
+                    name={format!("{name} Filter Cutoff Frequency")}
+                    postfix="Hz"
+                    coef={r64!(1000)}
+                    min={r64!(10)}
+                    initial={self.filter_freq}
+                />
+                <Counter
+                    key={format!("{name}-filter-q")}
+                    setter={emitter.reform(AppEvent::FilterQ)}
+                    name={format!("{name} Filter Resonance")}
+                    coef={r64!(10)}
+                    min={r64!(0.0001)}
+                    initial={self.filter_q}
+                />
+            </div>
+        }
+    }
+
+    /// Handles the change of 1 of the parameters, returning whether `event` was such a change.
+    pub fn handle_event(&mut self, event: &AppEvent, mut ctx: ContextMut) -> Result<bool> {
+        match *event {
+            AppEvent::Volume(to) => ctx.register_action(EditorAction::SetVolume {
+                from: replace(&mut self.volume, to),
+                to,
+            })?,
+
+            AppEvent::Pan(to) => {
+                ctx.register_action(EditorAction::SetPan { from: replace(&mut self.pan, to), to })?
+            }
+
+            AppEvent::Attack(to) => ctx.register_action(EditorAction::SetAttack {
+                from: replace(&mut self.attack, to),
+                to,
+            })?,
+
+            AppEvent::Decay(to) => ctx.register_action(EditorAction::SetDecay {
+                from: replace(&mut self.decay, to),
+                to,
+            })?,
+
+            AppEvent::Sustain(to) => ctx.register_action(EditorAction::SetSustain {
+                from: replace(&mut self.sustain, to),
+                to,
+            })?,
+
+            AppEvent::Release(to) => ctx.register_action(EditorAction::SetRelease {
+                from: replace(&mut self.release, to),
+                to,
+            })?,
+
+            AppEvent::FilterType(to) => ctx.register_action(EditorAction::SetFilterType {
+                from: replace(&mut self.filter_type, to),
+                to,
+            })?,
+
+            AppEvent::FilterFreq(to) => ctx.register_action(EditorAction::SetFilterFreq {
+                from: replace(&mut self.filter_freq, to),
+                to,
+            })?,
+
+            AppEvent::FilterQ(to) => ctx.register_action(EditorAction::SetFilterQ {
+                from: replace(&mut self.filter_q, to),
+                to,
+            })?,
+
+            AppEvent::RepCount(to) => {
+                ctx.register_action(EditorAction::SetRepCount {
+                    from: replace(&mut self.rep_count, to),
+                    to,
+                })?;
+                ctx.emit_event(AppEvent::RedrawEditorPlane);
+            }
+
+            AppEvent::RepGap(to) => {
+                ctx.register_action(EditorAction::SetRepGap {
+                    from: replace(&mut self.rep_gap, to),
+                    to,
+                })?;
+                ctx.emit_event(AppEvent::RedrawEditorPlane);
+            }
+
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Reverts `action` if it's a change of 1 of the parameters.
+    pub fn undo(&mut self, action: &EditorAction, ctx: &ContextMut) {
+        match *action {
+            EditorAction::SetVolume { from, .. } => self.volume = from,
+
+            EditorAction::SetPan { from, .. } => self.pan = from,
+
+            EditorAction::SetAttack { from, .. } => self.attack = from,
+
+            EditorAction::SetDecay { from, .. } => self.decay = from,
+
+            EditorAction::SetSustain { from, .. } => self.sustain = from,
+
+            EditorAction::SetRelease { from, .. } => self.release = from,
+
+            EditorAction::SetFilterType { from, .. } => self.filter_type = from,
+
+            EditorAction::SetFilterFreq { from, .. } => self.filter_freq = from,
+
+            EditorAction::SetFilterQ { from, .. } => self.filter_q = from,
+
+            EditorAction::SetRepCount { from, .. } => {
+                self.rep_count = from;
+                ctx.emit_event(AppEvent::RedrawEditorPlane)
+            }
+
+            EditorAction::SetRepGap { from, .. } => {
+                self.rep_gap = from;
+                ctx.emit_event(AppEvent::RedrawEditorPlane)
+            }
+
+            _ => (),
+        }
+    }
+
+    /// Reapplies `action` if it's a change of 1 of the parameters.
+    pub fn redo(&mut self, action: &EditorAction, ctx: &ContextMut) {
+        match *action {
+            EditorAction::SetVolume { to, .. } => self.volume = to,
+
+            EditorAction::SetPan { to, .. } => self.pan = to,
+
+            EditorAction::SetAttack { to, .. } => self.attack = to,
+
+            EditorAction::SetDecay { to, .. } => self.decay = to,
+
+            EditorAction::SetSustain { to, .. } => self.sustain = to,
+
+            EditorAction::SetRelease { to, .. } => self.release = to,
+
+            EditorAction::SetFilterType { to, .. } => self.filter_type = to,
+
+            EditorAction::SetFilterFreq { to, .. } => self.filter_freq = to,
+
+            EditorAction::SetFilterQ { to, .. } => self.filter_q = to,
+
+            EditorAction::SetRepCount { to, .. } => {
+                self.rep_count = to;
+                ctx.emit_event(AppEvent::RedrawEditorPlane)
+            }
+
+            EditorAction::SetRepGap { to, .. } => {
+                self.rep_gap = to;
+                ctx.emit_event(AppEvent::RedrawEditorPlane)
+            }
+
+            _ => (),
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{
     ctx::{AppEvent, ContextMut, ContextRef, EditorAction, RemovedPoint},
     input::{Buttons, Cursor},
+    popup::Popup,
     sequencer::Sequencer,
+    sound::{Beats, Secs},
 };
 use macro_rules_attribute::apply;
 use std::{
@@ -18,21 +20,26 @@ use std::{
 use wasm_bindgen::{Clamped, JsCast, JsValue};
 use wavexp_utils::{
     cell::WasmCell,
+    diff::{Diff, PatternDiff},
     ensure,
     error::Result,
     eval_once,
     ext::{
-        default, neg, ArrayExt, BoolExt, HtmlCanvasExt, HtmlElementExt, OptionExt, SliceExt,
-        TransposedArray, VecExt,
+        default, neg, ArrayExt, BoolExt, CanvasContextOptions, HtmlCanvasExt, HtmlElementExt,
+        OptionExt, SliceExt, SliceMove, TransposedArray, VecExt,
     },
     fallible,
     iter::ToEveryNth,
+    js::{document, now},
     js_array, r64,
     range::{IntoRange, RangeBoundsExt, RangeInclusiveV2, RangeV2},
     real::R64,
-    ArrayFrom, IntoArray, Point, RoundTo, SliceRef,
+    ArrayFrom, IntoArray, Point, Rect, RoundTo, SliceRef,
+};
+use web_sys::{
+    CanvasRenderingContext2d, Element, HtmlCanvasElement, HtmlElement, ImageData, MouseEvent,
+    Path2d, SvgElement,
 };
-use web_sys::{Element, HtmlCanvasElement, HtmlElement, ImageData, Path2d, SvgElement};
 use yew::{NodeRef, TargetCast};
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -60,33 +67,130 @@ impl From<Rgba> for u32 {
     }
 }
 
+impl Rgba {
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Linearly interpolates between the 2 colours, `t` being the weight of `other`.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let mix =
+            |x: u8, y: u8| (x as f32 * (1.0 - t) + y as f32 * t).round().clamp(0.0, 255.0) as u8;
+        Self {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+
+    /// Composites the colour over `bg`, both with straight, i.e. non-premultiplied, alpha.
+    pub fn over(self, bg: Self) -> Self {
+        let [src_a, bg_a] = [self.a, bg.a].map(|x| x as f32 / 255.0);
+        let bg_a = bg_a * (1.0 - src_a);
+        let a = src_a + bg_a;
+        if a == 0.0 {
+            return Self { r: 0, g: 0, b: 0, a: 0 };
+        }
+        let mix = |x: u8, y: u8| {
+            ((x as f32 * src_a + y as f32 * bg_a) / a).round().clamp(0.0, 255.0) as u8
+        };
+        Self {
+            r: mix(self.r, bg.r),
+            g: mix(self.g, bg.g),
+            b: mix(self.b, bg.b),
+            a: (a * 255.0).round().clamp(0.0, 255.0) as u8,
+        }
+    }
+}
+
+#[test]
+fn rgba_compositing() {
+    let [src, bg] = [Rgba::from(0x0069E1FF), Rgba::from(0x181818FF)];
+    assert_eq!(src.over(bg), src);
+    assert_eq!(src.with_alpha(0).over(bg), bg);
+    assert_eq!(src.with_alpha(0x80).over(bg), Rgba::from(0x0C417DFF));
+    assert_eq!(Rgba::from(0x000000FF).lerp(Rgba::from(0xFF804000), 0.5), Rgba::from(0x80402080));
+}
+
 impl Display for Rgba {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
     }
 }
 
-fn interp<const N: usize>(colours: &[Rgba; N], index: u8) -> Rgba {
-    let index = index as f32 / 255.0 * (N as f32 - 1.0);
+/// `colours` must not be empty
+fn interp(colours: &[Rgba], index: u8) -> Rgba {
+    let index = index as f32 / 255.0 * (colours.len() as f32 - 1.0);
     let lower = colours.get_saturating(index.floor() as usize);
     let upper = colours.get_saturating(index.ceil() as usize);
-    let weight = (index / (N as f32 - 1.0)).fract();
-    let weight_recip = 1.0 - weight;
-    Rgba {
-        r: (lower.r as f32 * weight_recip + upper.r as f32 * weight) as u8,
-        g: (lower.g as f32 * weight_recip + upper.g as f32 * weight) as u8,
-        b: (lower.b as f32 * weight_recip + upper.b as f32 * weight) as u8,
-        a: (lower.a as f32 * weight_recip + upper.a as f32 * weight) as u8,
+    lower.lerp(*upper, index.fract())
+}
+
+#[test]
+fn gradient_interp() {
+    let stops = [Rgba::from(0x000000FF), Rgba::from(0x808080FF), Rgba::from(0xFFFFFFFF)];
+    assert_eq!(interp(&stops, 0), stops[0]);
+    assert_eq!(interp(&stops, 128), stops[1]);
+    assert_eq!(interp(&stops, 255), stops[2]);
+    assert_eq!(interp(&[stops[0], stops[2]], 51), Rgba::from(0x333333FF));
+}
+
+/// colour scheme of the spectrogram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientPreset {
+    #[default]
+    Default,
+    Viridis,
+    Inferno,
+}
+
+impl GradientPreset {
+    pub const ALL: [Self; 3] = [Self::Default, Self::Viridis, Self::Inferno];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::Viridis => "Viridis",
+            Self::Inferno => "Inferno",
+        }
+    }
+
+    /// the colours of the gradient, from the quietest to the loudest
+    pub fn stops(self) -> Vec<Rgba> {
+        let stops: &[u32] = match self {
+            Self::Default => return vec![SoundVisualiser::FG.with_alpha(0), SoundVisualiser::FG],
+            Self::Viridis => &[0x440154FF, 0x3B528BFF, 0x21918CFF, 0x5EC962FF, 0xFDE725FF],
+            Self::Inferno => {
+                &[0x000004FF, 0x420A68FF, 0x932667FF, 0xDD513AFF, 0xFCA50AFF, 0xFCFFA4FF]
+            }
+        };
+        stops.iter().map(|&x| Rgba::from(x)).collect()
     }
 }
 
+/// what the sound visualiser displays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisualiserMode {
+    /// a scrolling graph of the frequencies of the played sound
+    #[default]
+    Spectrogram,
+    /// the waveform of the played sound
+    Oscilloscope,
+}
+
 pub struct SoundVisualiser {
     out_data: Vec<Rgba>,
     in_data: Vec<u8>,
+    /// colours the gradient is interpolated from
+    stops: Vec<Rgba>,
     gradient: Vec<Rgba>,
     canvas: NodeRef,
     width: u32,
     height: u32,
+    mode: VisualiserMode,
+    /// whether the spectrogram's magnitudes & frequencies are scaled logarithmically
+    log_scale: bool,
 }
 
 impl SoundVisualiser {
@@ -95,20 +199,78 @@ impl SoundVisualiser {
     pub const BG: Rgba = Rgba { r: 0x18, g: 0x18, b: 0x18, a: 0xFF };
 
     pub fn new() -> Self {
-        Self {
+        let mut res = Self {
             out_data: vec![],
             in_data: vec![],
-            gradient: (0..=u8::MAX).map(|i| interp(&[Self::BG, Self::FG], i)).collect(),
+            stops: vec![],
+            gradient: vec![],
             width: 0,
             height: 0,
             canvas: default(),
-        }
+            mode: default(),
+            log_scale: false,
+        };
+        res.set_gradient(default());
+        res
+    }
+
+    /// Rebuilds the lookup table of the spectrogram's colours from the preset's stops,
+    /// drawn over the background.
+    pub fn set_gradient(&mut self, preset: GradientPreset) {
+        self.stops = preset.stops();
+        self.gradient = (0..=u8::MAX).map(|i| interp(&self.stops, i).over(Self::BG)).collect();
+    }
+
+    /// the preset the current gradient was built from, if any
+    pub fn gradient_preset(&self) -> Option<GradientPreset> {
+        GradientPreset::ALL.into_iter().find(|x| x.stops() == self.stops)
     }
 
     pub const fn canvas(&self) -> &NodeRef {
         &self.canvas
     }
 
+    pub const fn log_scale(&self) -> bool {
+        self.log_scale
+    }
+
+    /// Maps time-domain samples, as given by `AnalyserNode::get_byte_time_domain_data`,
+    /// to Y coordinates from 0 at the top of the canvas to 1 at its bottom.
+    pub fn waveform(data: &[u8]) -> impl Iterator<Item = R64> + '_ {
+        data.iter().map(|&x| R64::ONE - R64::from(x) / u8::MAX as f64)
+    }
+
+    /// Maps a row of the spectrogram, counted from the bottom, to the frequency bin it displays.
+    /// When `log` is true, the lower frequencies are given more rows than the higher ones.
+    pub fn source_bin(row: usize, rows: usize, bins: usize, log: bool) -> usize {
+        if rows == 0 || bins == 0 {
+            return 0;
+        }
+        let pos = row as f64 / rows as f64;
+        let bin = if log { (bins as f64).powf(pos) - 1.0 } else { pos * bins as f64 };
+        (bin as usize).min(bins - 1)
+    }
+
+    /// Compresses the magnitude of a frequency logarithmically, making the quiet ones visible.
+    pub fn log_magnitude(x: u8) -> u8 {
+        ((x as f64).ln_1p() / (u8::MAX as f64).ln_1p() * u8::MAX as f64).round() as u8
+    }
+
+    /// Resizes the buffers to fit the canvas in the current mode, clearing the drawn image.
+    /// `bins` is the number of frequency bins the analyser provides.
+    #[apply(fallible!)]
+    fn resize_buffers(&mut self, bins: u32) {
+        self.in_data.clear();
+        self.out_data.clear();
+        match self.mode {
+            VisualiserMode::Spectrogram => {
+                self.in_data.resize(bins as usize, 0);
+                self.out_data.resize(self.width.checked_mul(self.height)? as usize, Self::BG);
+            }
+            VisualiserMode::Oscilloscope => self.in_data.resize(self.width as usize, 0),
+        }
+    }
+
     // TODO: correctly readjust the graph when shrinked in the UI
     #[apply(fallible!)]
     pub fn handle_event(&mut self, event: &AppEvent, sequencer: &Sequencer) {
@@ -120,37 +282,126 @@ impl SoundVisualiser {
                 canvas.set_height(h);
                 self.width = w;
                 self.height = h;
-                self.in_data.resize(w as usize, 0);
-                self.out_data.resize(w.checked_mul(w)? as usize, Self::BG);
+                self.resize_buffers(sequencer.analyser().frequency_bin_count())?;
+            }
+
+            &AppEvent::SetVisualiserGradient(preset) => self.set_gradient(preset),
+
+            AppEvent::ToggleSpectrogramLogScale => self.log_scale.flip(),
+
+            AppEvent::ToggleVisualiserMode => {
+                self.mode = match self.mode {
+                    VisualiserMode::Spectrogram => VisualiserMode::Oscilloscope,
+                    VisualiserMode::Oscilloscope => VisualiserMode::Spectrogram,
+                };
+                self.resize_buffers(sequencer.analyser().frequency_bin_count())?;
+                let canvas: HtmlCanvasElement = self.canvas.cast()?;
+                let canvas_ctx = canvas.get_2d_context()?;
+                canvas_ctx.set_fill_style(&Self::BG.to_string().into());
+                canvas_ctx.fill_rect(0.0, 0.0, self.width as f64, self.height as f64);
             }
 
             AppEvent::Frame(..) => {
                 if sequencer.playback_ctx().playing() {
-                    self.out_data.rotate_right(1);
-                    sequencer.analyser().get_byte_frequency_data(&mut self.in_data);
-                    for (&src, dst) in
-                        self.in_data.iter().zip(self.out_data.every_nth_mut(self.width as usize))
-                    {
-                        *dst = unsafe { *self.gradient.get_unchecked(src as usize) };
+                    match self.mode {
+                        VisualiserMode::Spectrogram => self.draw_spectrogram(sequencer)?,
+                        VisualiserMode::Oscilloscope => self.draw_waveform(sequencer)?,
                     }
-
-                    let out = unsafe {
-                        from_raw_parts(
-                            self.out_data.as_ptr().cast(),
-                            self.out_data.len().checked_mul(4)?,
-                        )
-                    };
-                    let out = ImageData::new_with_u8_clamped_array(Clamped(out), self.width)?;
-                    self.canvas
-                        .cast::<HtmlCanvasElement>()?
-                        .get_2d_context()?
-                        .put_image_data(&out, 0.0, 0.0)?;
                 }
             }
 
             _ => (),
         }
     }
+
+    /// Scrolls a row-major image of width `width` to the left by one column,
+    /// filling the rightmost column with `column`, from top to bottom.
+    fn push_column(out: &mut [Rgba], width: usize, column: impl Iterator<Item = Rgba>) {
+        // the 1st pixel of every row moves to the end of the previous row,
+        // right where the new column is written
+        out.copy_within(1.., 0);
+        let Some(last_column) = out.get_mut(width.saturating_sub(1)..) else { return };
+        for (src, dst) in column.zip(last_column.every_nth_mut(width)) {
+            *dst = src;
+        }
+    }
+
+    #[apply(fallible!)]
+    fn draw_spectrogram(&mut self, sequencer: &Sequencer) {
+        sequencer.analyser().get_byte_frequency_data(&mut self.in_data);
+        let [rows, bins] = [self.height as usize, self.in_data.len()];
+        // the lowest frequencies are at the bottom
+        let column = (0..rows).rev().map(|row| {
+            let x = self.in_data.get(Self::source_bin(row, rows, bins, self.log_scale));
+            let x = x.map_or(0, |&x| if self.log_scale { Self::log_magnitude(x) } else { x });
+            unsafe { *self.gradient.get_unchecked(x as usize) }
+        });
+        Self::push_column(&mut self.out_data, self.width as usize, column);
+
+        let out = unsafe {
+            from_raw_parts(self.out_data.as_ptr().cast(), self.out_data.len().checked_mul(4)?)
+        };
+        let out = ImageData::new_with_u8_clamped_array(Clamped(out), self.width)?;
+        self.canvas.cast::<HtmlCanvasElement>()?.get_2d_context()?.put_image_data(&out, 0.0, 0.0)?;
+    }
+
+    #[apply(fallible!)]
+    fn draw_waveform(&mut self, sequencer: &Sequencer) {
+        sequencer.analyser().get_byte_time_domain_data(&mut self.in_data);
+        let canvas_ctx = self.canvas.cast::<HtmlCanvasElement>()?.get_2d_context()?;
+        let height = R64::from(self.height);
+        canvas_ctx.set_fill_style(&Self::BG.to_string().into());
+        canvas_ctx.fill_rect(0.0, 0.0, self.width as f64, *height);
+        let line = Path2d::new()?;
+        for (x, y) in Self::waveform(&self.in_data).enumerate() {
+            line.line_to(x as f64, *(y * height));
+        }
+        canvas_ctx.set_stroke_style(&Self::FG.to_string().into());
+        canvas_ctx.stroke_with_path(&line);
+    }
+}
+
+#[test]
+fn sound_visualiser_scrolling() {
+    let [w, h] = [5, 3];
+    let mut out = vec![SoundVisualiser::BG; w * h];
+    let colour = |x: u32| Rgba::from(x);
+    SoundVisualiser::push_column(&mut out, w, (1..=3).map(colour));
+    for n in 1..4 {
+        SoundVisualiser::push_column(&mut out, w, [colour(0xFF); 3].into_iter());
+        // the oldest column has scrolled `n` positions to the left
+        let oldest: Vec<_> = out.every_nth(w).skip((w - 1 - n) * h).take(h).copied().collect();
+        assert_eq!(oldest, (1..=3).map(colour).collect::<Vec<_>>());
+    }
+    // nothing wraps around between the rows
+    assert!(out.every_nth(w).take(h).all(|&x| x == SoundVisualiser::BG));
+    assert!(out.every_nth(w).skip(2 * h).all(|&x| x == colour(0xFF)));
+}
+
+#[test]
+fn spectrogram_log_scale() {
+    let [rows, bins] = [100, 1024];
+    let lower_half = |log| {
+        let is_lower = |&row: &usize| SoundVisualiser::source_bin(row, rows, bins, log) < bins / 2;
+        (0..rows).filter(is_lower).count()
+    };
+    assert_eq!(lower_half(false), rows / 2);
+    assert!(lower_half(true) > rows / 2);
+    assert_eq!(SoundVisualiser::source_bin(0, rows, bins, true), 0);
+    assert!(SoundVisualiser::source_bin(rows - 1, rows, bins, true) > bins * 9 / 10);
+    // quiet frequencies are boosted, the loudest stay the same
+    assert_eq!(SoundVisualiser::log_magnitude(0), 0);
+    assert!(SoundVisualiser::log_magnitude(16) > 16 * 2);
+    assert_eq!(SoundVisualiser::log_magnitude(u8::MAX), u8::MAX);
+}
+
+#[test]
+fn sound_visualiser_waveform() {
+    let ys: Vec<_> = SoundVisualiser::waveform(&[0, 255, 51]).collect();
+    assert_eq!(ys, [r64!(1), r64!(0), r64!(0.8)]);
+    // silence is drawn in the middle
+    let mid = SoundVisualiser::waveform(&[128]).next().unwrap_or_default();
+    assert!((mid - r64!(0.5)).abs() < r64!(0.01));
 }
 
 #[derive(Debug, PartialEq, Default)]
@@ -209,10 +460,338 @@ impl HintHandler {
     }
 }
 
+/// an overview of the whole arrangement, with the visible area of the editor plane outlined
+#[derive(Debug, Default)]
+pub struct Minimap {
+    canvas: NodeRef,
+    /// size of the drawn part of the arrangement, in user coordinates
+    extent: [R64; 2],
+    /// the viewport followed by the sound blocks as `[x, y, w, h]`, as of the last redraw
+    drawn: Vec<[R64; 4]>,
+}
+
+impl Minimap {
+    const BG_STYLE: &'static str = "#181818";
+    const FG_STYLE: &'static str = "#0069E1";
+    const VIEWPORT_STYLE: &'static str = "#FFFFFF";
+
+    pub const fn canvas(&self) -> &NodeRef {
+        &self.canvas
+    }
+
+    /// Maps a point of the arrangement onto a minimap of size `size` showing `extent` of it.
+    pub fn to_minimap(point: [R64; 2], extent: [R64; 2], size: [R64; 2]) -> [R64; 2] {
+        point.mul(size).div(extent)
+    }
+
+    /// The inverse of `Minimap::to_minimap`
+    pub fn from_minimap(point: [R64; 2], extent: [R64; 2], size: [R64; 2]) -> [R64; 2] {
+        point.mul(extent).div(size)
+    }
+
+    #[apply(fallible!)]
+    pub fn handle_event(&mut self, event: &AppEvent, ctx: ContextMut, sequencer: &Sequencer) {
+        match event {
+            AppEvent::Resize => {
+                let canvas: HtmlCanvasElement = self.canvas.cast()?;
+                let [w, h] = canvas.client_size().map(|x| x as u32);
+                canvas.set_width(w);
+                canvas.set_height(h);
+                self.drawn.clear();
+            }
+
+            AppEvent::Frame(..) => {
+                let pattern = sequencer.pattern().get()?;
+                let bps = sequencer.bps();
+                let mut rects = vec![pattern.viewport()];
+                for block in pattern.points() {
+                    let [x, y] = block.loc();
+                    let w = block.total_len(bps)?.0;
+                    rects.push([x, y, w, r64!(1)]);
+                }
+
+                if rects != self.drawn {
+                    let canvas: HtmlCanvasElement = self.canvas.cast()?;
+                    let size = canvas.size().map(R64::from);
+                    self.extent = rects.iter().fold([R64::ZERO; 2], |[w, h], rect| {
+                        [w.max(rect[0] + rect[2]), h.max(rect[1] + rect[3])]
+                    });
+                    let canvas_ctx = canvas.get_2d_context()?;
+                    canvas_ctx.set_fill_style(&Self::BG_STYLE.into());
+                    canvas_ctx.fill_rect(0.0, 0.0, *size[0], *size[1]);
+                    let to_canvas = |[x, y, w, h]: [R64; 4]| {
+                        let [x, y] = Self::to_minimap([x, y], self.extent, size);
+                        let [w, h] = Self::to_minimap([w, h], self.extent, size);
+                        [*x, *y, *w, *h]
+                    };
+
+                    canvas_ctx.set_fill_style(&Self::FG_STYLE.into());
+                    for &block in rects.get(1..).unwrap_or_default() {
+                        let [x, y, w, h] = to_canvas(block);
+                        canvas_ctx.fill_rect(x, y, w, h);
+                    }
+                    let [x, y, w, h] = to_canvas(*rects.first()?);
+                    canvas_ctx.set_stroke_style(&Self::VIEWPORT_STYLE.into());
+                    canvas_ctx.stroke_rect(x, y, w, h);
+                    self.drawn = rects;
+                }
+            }
+
+            AppEvent::MinimapPointer(e) if e.buttons() & 1 != 0 => {
+                let canvas: Element = e.target_dyn_into()?;
+                canvas.set_pointer_capture(e.pointer_id())?;
+                let size = canvas.client_size().map(R64::from);
+                let point = [e.offset_x(), e.offset_y()].map(R64::from);
+                let centre = Self::from_minimap(point, self.extent, size);
+                ctx.emit_event(AppEvent::ScrollTo(sequencer.pattern().get()?.id(), centre))
+            }
+
+            AppEvent::MinimapPointer(e) if e.type_() == "pointerup" => {
+                ctx.emit_event(AppEvent::FinishScroll(sequencer.pattern().get()?.id()))
+            }
+
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn minimap_scaling() {
+    let extent = [r64!(40), r64!(8)];
+    let size = [r64!(200), r64!(100)];
+    assert_eq!(Minimap::to_minimap([r64!(10), r64!(2)], extent, size), [r64!(50), r64!(25)]);
+    assert_eq!(Minimap::to_minimap(extent, extent, size), size);
+    let point = [r64!(30), r64!(4)];
+    assert_eq!(
+        Minimap::from_minimap(Minimap::to_minimap(point, extent, size), extent, size),
+        point
+    );
+
+    type Pattern = GraphEditor<crate::sequencer::SoundBlock>;
+    let scale = [r64!(10), r64!(10)];
+    let centre = Minimap::from_minimap([r64!(100), r64!(50)], extent, size);
+    assert_eq!(Pattern::scroll_offset(centre, scale, size), Point { x: 300, y: -10 });
+    // confined to the offset bounds
+    assert_eq!(Pattern::scroll_offset([r64!(0); 2], scale, size), Point { x: -20, y: -10 });
+}
+
+#[test]
+fn fit_to_content() {
+    use crate::sequencer::SoundBlock;
+    let block = |offset, layer| SoundBlock {
+        sound: crate::sound::Sound::None,
+        layer,
+        offset: Beats(offset),
+        muted: false,
+        soloed: false,
+    };
+    let mut editor = GraphEditor::new(vec![block(r64!(2), 1), block(r64!(20), 2)]);
+    let size = [r64!(800), r64!(400)];
+    assert!(editor.fit_to_content(size, r64!(2)).is_ok());
+    for loc in editor.points().iter().map(SoundBlock::loc) {
+        let on_canvas = loc.mul(size.div(editor.scale)).sub(R64::array_from(editor.offset));
+        assert!(on_canvas.zip(size, |x, max| (R64::ZERO..=max).contains(&x)).iter().all(|x| *x));
+    }
+    // from the start of the 1st block to the end of the 2nd one, 1 beat after its start
+    assert_eq!(editor.scale[0], r64!(23.75));
+    // confined to the scale bounds
+    assert_eq!(editor.scale[1], SoundBlock::SCALE_Y_BOUND.start);
+
+    let mut editor = GraphEditor::<SoundBlock>::default();
+    editor.inner.offset = Point { x: 500, y: 500 };
+    assert!(editor.fit_to_content(size, r64!(2)).is_ok());
+    assert_eq!(editor.scale, GraphEditor::<SoundBlock>::DEFAULT_SCALE);
+    assert!(editor.offset.x <= 0 && editor.offset.y <= 0);
+
+    // the end of the last point is visible too
+    use crate::sound::NoteBlock;
+    let notes = vec![NoteBlock::test(r64!(2), 10, r64!(1)), NoteBlock::test(r64!(18), 12, r64!(4))];
+    let mut editor = GraphEditor::new(notes);
+    assert!(editor.fit_to_content(size, r64!(2)).is_ok());
+    assert_eq!(editor.scale[0], r64!(25));
+    let end = r64!(22) * size[0] / editor.scale[0] - R64::from(editor.offset.x);
+    assert!(end <= size[0]);
+}
+
+#[test]
+fn minimap_scroll() {
+    let mut editor = GraphEditor::<crate::sequencer::SoundBlock>::default();
+    let size = [r64!(800), r64!(400)];
+    let init_offset = editor.offset;
+    // dragging across the minimap moves the plane on every step, but is undone as a whole
+    for x in [r64!(10), r64!(20), r64!(30)] {
+        editor.scroll_to([x, r64!(5)], size);
+    }
+    let last_offset = editor.offset;
+    assert_ne!(last_offset, init_offset);
+    assert_eq!(editor.finish_scroll().ok(), last_offset - init_offset);
+    // nothing left to record once the scrolling is finished
+    assert_eq!(editor.finish_scroll().ok(), Some(Point::default()));
+}
+
+#[test]
+fn zoom_keeps_point_in_place() {
+    let mut editor = GraphEditor::<crate::sequencer::SoundBlock>::default();
+    editor.inner.offset = Point { x: 37, y: -12 };
+    let size = [r64!(800), r64!(400)];
+    let at = Point { x: 250, y: 130 };
+    let user_point = |editor: &GraphEditor<_>| {
+        R64::array_from((at + editor.offset).unwrap_or_default()).mul(editor.scale).div(size)
+    };
+    let init_scale = editor.scale;
+    let before = user_point(&editor);
+    assert!(editor.zoom(r64!(0.5), at, Axis::Both).is_ok());
+    assert!(editor.scale.zip(init_scale, |new, old| new < old).iter().all(|x| *x));
+    // the offset is rounded to the nearest pixel
+    let precision = editor.scale.div(size);
+    assert!(user_point(&editor).sub(before).zip(precision, |d, p| d.abs() <= p).iter().all(|x| *x));
+
+    assert!(editor.zoom(r64!(100), at, Axis::X).is_ok());
+    assert_eq!(editor.scale[0], crate::sequencer::SoundBlock::SCALE_X_BOUND.start);
+    assert!(editor.scale[1] < init_scale[1]);
+}
+
+/// a mark on a `Ruler`, with the number of the beat it's at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulerLabel {
+    /// the start of a bar
+    Bar(u32),
+    /// any other beat
+    Beat(u32),
+}
+
+/// a strip above the editor plane with the numbers of the beats visible on it
+#[derive(Debug, Default)]
+pub struct Ruler {
+    canvas: NodeRef,
+    /// the visible part of the editor plane's X axis as `[start, length]`, the number of beats
+    /// per bar & the loop region, as of the last redraw
+    drawn: Option<([Beats; 2], u32, [Beats; 2])>,
+    /// the beat at which the user started shift-dragging across the ruler to set the loop region
+    loop_anchor: Option<Beats>,
+}
+
+impl Ruler {
+    const BG_STYLE: &'static str = "#181818";
+    const FG_STYLE: &'static str = "#0069E1";
+    const LOOP_STYLE: &'static str = "#0069E140";
+    const FONT: &'static str = "14px consolas";
+    /// minimum distance between 2 beats, in pixels, for all of them to be labeled; otherwise, only
+    /// the starts of bars are
+    const MIN_LABEL_SPACING: f64 = 24.0;
+
+    pub const fn canvas(&self) -> &NodeRef {
+        &self.canvas
+    }
+
+    /// Computes the marks of a ruler `width` pixels wide that shows `scale` beats starting from
+    /// beat `offset`, as their X coordinates on the ruler paired with their labels.
+    pub fn ticks(
+        offset: Beats,
+        scale: Beats,
+        width: R64,
+        beats_per_bar: u32,
+    ) -> Vec<(f64, RulerLabel)> {
+        let mut res = vec![];
+        let mut beat = offset.max(Beats::ZERO).ceil();
+        while beat <= offset + scale {
+            let n = u32::from(beat.0);
+            let x = (beat - offset) * width / scale;
+            let label = if n % beats_per_bar.max(1) == 0 {
+                RulerLabel::Bar(n)
+            } else {
+                RulerLabel::Beat(n)
+            };
+            res.push((*x, label));
+            beat += Beats::ONE;
+        }
+        res
+    }
+
+    #[apply(fallible!)]
+    pub fn handle_event(&mut self, event: &AppEvent, ctx: ContextMut, sequencer: &Sequencer) {
+        match event {
+            AppEvent::Resize => {
+                self.canvas.cast::<HtmlCanvasElement>()?.sync_hidpi()?;
+                self.drawn = None;
+            }
+
+            AppEvent::RulerPointer(e) if e.buttons() & 1 != 0 => {
+                let canvas: Element = e.target_dyn_into()?;
+                canvas.set_pointer_capture(e.pointer_id())?;
+                let [start, _, len, _] = sequencer.pattern().get()?.viewport().map(Beats);
+                let x = R64::from(e.offset_x()) / R64::from(canvas.client_width());
+                let beat = start + len * x;
+                if e.shift_key() {
+                    let beat = beat.max(Beats::ZERO).round();
+                    let anchor = *self.loop_anchor.get_or_insert(beat);
+                    ctx.emit_event(AppEvent::SetLoop([anchor.min(beat), anchor.max(beat)]))
+                } else {
+                    ctx.emit_event(AppEvent::Seek(beat))
+                }
+            }
+
+            AppEvent::RulerPointer(_) => self.loop_anchor = None,
+
+            AppEvent::Frame(..) => {
+                let [start, _, len, _] = sequencer.pattern().get()?.viewport().map(Beats);
+                let loop_region = sequencer.loop_region();
+                let drawn = Some(([start, len], sequencer.beats_per_bar(), loop_region));
+                if drawn != self.drawn {
+                    let canvas: HtmlCanvasElement = self.canvas.cast()?;
+                    // the context is scaled to CSS pixels by `sync_hidpi`
+                    let [w, h] = canvas.client_size().map(R64::from);
+                    let canvas_ctx = canvas.get_2d_context()?;
+                    canvas_ctx.set_fill_style(&Self::BG_STYLE.into());
+                    canvas_ctx.fill_rect(0.0, 0.0, *w, *h);
+
+                    let [loop_start, loop_end] = loop_region.map(|x| *((x - start) * w / len));
+                    canvas_ctx.set_fill_style(&Self::LOOP_STYLE.into());
+                    canvas_ctx.fill_rect(loop_start, 0.0, loop_end - loop_start, *h);
+
+                    canvas_ctx.set_fill_style(&Self::FG_STYLE.into());
+                    canvas_ctx.set_font(Self::FONT);
+                    canvas_ctx.set_text_align("left");
+                    canvas_ctx.set_text_baseline("top");
+                    let label_all = *(w / len.0) >= Self::MIN_LABEL_SPACING;
+                    for (x, label) in Self::ticks(start, len, w, sequencer.beats_per_bar()) {
+                        let (beat, height, width) = match label {
+                            RulerLabel::Bar(beat) => (Some(beat), *h, AnyGraphEditor::LINE_WIDTH),
+                            RulerLabel::Beat(beat) => (label_all.then_some(beat), *h / 2.0, 1.0),
+                        };
+                        canvas_ctx.fill_rect(x, *h - height, width, height);
+                        if let Some(beat) = beat {
+                            canvas_ctx.fill_text(&beat.to_string(), x + 5.0, 2.0)?;
+                        }
+                    }
+                    self.drawn = drawn;
+                }
+            }
+
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn ruler_ticks() {
+    let ticks = Ruler::ticks(Beats(r64!(2.5)), Beats(r64!(10)), r64!(400), 4);
+    assert_eq!(ticks.len(), 10);
+    assert_eq!(ticks[0], (20.0, RulerLabel::Beat(3)));
+    assert_eq!(ticks[1], (60.0, RulerLabel::Bar(4)));
+    assert_eq!(ticks[9], (380.0, RulerLabel::Bar(12)));
+    // no ticks before the start of the plane
+    let ticks = Ruler::ticks(Beats(r64!(-1)), Beats(r64!(4)), r64!(400), 4);
+    assert_eq!(ticks.first(), Some(&(100.0, RulerLabel::Bar(0))));
+    assert_eq!(ticks.last(), Some(&(400.0, RulerLabel::Beat(3))));
+}
+
 /// data that can be edited with a generic graph editor defined below
-pub trait GraphPoint: Sized + Clone + Ord + 'static {
+pub trait GraphPoint: Sized + Clone + Ord + Diff + 'static {
     /// the name of the plane that will be displayed as a hint when hovered over it
     const EDITOR_NAME: &'static str;
+    /// whether the points can be transposed & quantized from their context menu
+    const PITCHED: bool = false;
     /// bounds for the points along the X axis
     const X_BOUND: RangeV2<R64> = RangeV2 { start: r64!(0), end: R64::INFINITY };
     /// bounds for the scale of the X axis of the graph
@@ -239,6 +818,11 @@ pub trait GraphPoint: Sized + Clone + Ord + 'static {
 
     /// Creates a new point from the given user coordinates.
     fn create(editor: &GraphEditor<Self>, at: [R64; 2]) -> Self;
+    /// Creates the points added at once by clicking at the given user coordinates,
+    /// by default only the one made by `create`.
+    fn stamp(editor: &GraphEditor<Self>, at: [R64; 2], _: Self::VisualContext) -> Vec<Self> {
+        vec![Self::create(editor, at)]
+    }
 
     /// inner data of the point
     fn inner(&self) -> &Self::Inner;
@@ -250,12 +834,24 @@ pub trait GraphPoint: Sized + Clone + Ord + 'static {
     fn y_mut(&mut self) -> &mut Self::Y;
     /// location of the point in user coordinates
     fn loc(&self) -> [R64; 2];
+    /// start & end of the point along the X axis in user coordinates in tempo `bps`, to which
+    /// other points are magnetically snapped; by default, the point is assumed to have no length
+    fn span(&self, _: R64) -> Result<[R64; 2]> {
+        let x = self.loc()[0];
+        Ok([x, x])
+    }
     /// change the location of `self` in user coordinates when moved in the UI
     /// `meta` signifies whether the meta key was held while moving the point
     fn móve(&mut self, delta: [R64; 2], meta: bool) -> Result<()>;
     /// change the location of the point in user coordinates when moved in the UI
     /// `meta` signifies whether the meta key was held while moving the point
     fn move_point(point: &mut [R64; 2], delta: [R64; 2], meta: bool);
+
+    /// Snaps a location the cursor points to, already aligned to the grid, to where a point could
+    /// be placed or moved. Returns the location as is by default.
+    fn snap(loc: [R64; 2], _: Self::VisualContext) -> [R64; 2] {
+        loc
+    }
     /// returns `true` if the given `area` in user coordinates overlaps with the hitbox of the point.
     fn in_hitbox(
         &self,
@@ -280,7 +876,9 @@ pub trait GraphPoint: Sized + Clone + Ord + 'static {
         Ok(())
     }
 
-    /// Handle request for a redraw.
+    /// Handle request for a redraw of the points.
+    /// What's drawn is cached along with the background of the plane & reused until a redraw is
+    /// requested with `GraphEditor::force_redraw` or the plane is resized, zoomed or scrolled.
     /// `editor` is the editor that needs redraw.
     /// `ctx` is the application context.
     /// `sequencer` is the the app's global sequencer.
@@ -300,6 +898,23 @@ pub trait GraphPoint: Sized + Clone + Ord + 'static {
         visual_ctx: Self::VisualContext,
     ) -> Result<()>;
 
+    /// Handle request for a redraw of what's drawn on top of the cached points, e.g. the playhead.
+    /// Called on every redraw, including the ones requested with
+    /// `GraphEditor::force_overlay_redraw`, which leave the cached points as they are.
+    /// The parameters are the same as those of `on_redraw`.
+    #[allow(unused_variables)]
+    fn on_overlay_redraw(
+        editor: &mut GraphEditor<Self>,
+        ctx: ContextRef,
+        sequencer: &Sequencer,
+        canvas_size: &[R64; 2],
+        solid: &Path2d,
+        dotted: &Path2d,
+        visual_ctx: Self::VisualContext,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Handle change of selection area.
     /// `editor` is the editor, the selection area of which was changed.
     /// `app` is the application context.
@@ -331,10 +946,126 @@ impl<'point, T: GraphPoint> GraphPointView<'point, T> {
     pub fn inner(&mut self) -> &mut T::Inner {
         self.0.inner_mut()
     }
-    //pub fn y(&mut self) -> &mut T::Y {self.0.y_mut()}
 
-    // /// the caller must ensure that the point retains its sorted placement
-    // pub unsafe fn unlock(self) -> &'a mut T {self.0}
+    pub fn y(&mut self) -> &mut T::Y {
+        self.0.y_mut()
+    }
+
+    /// # Safety
+    /// the caller must ensure that the point retains its sorted placement
+    pub unsafe fn unlock(self) -> &'point mut T {
+        self.0
+    }
+}
+
+/// Mutable access to the points of a graph editor, returned by `GraphEditor::points_mut`.
+/// `get_mut` & `iter_mut` give views of the points that leave their X axis as it is, while
+/// `DerefMut` gives full access, after which the points whose placement was changed are moved to
+/// where they belong when the guard is dropped, keeping the points sorted & the selection pointing
+/// to the same points. Either way, the editor is redrawn.
+pub struct PointsMut<'editor, T: GraphPoint> {
+    editor: &'editor mut GraphEditor<T>,
+    /// whether the points were accessed mutably
+    touched: bool,
+    /// whether the X axis of the points could've been changed
+    unlocked: bool,
+}
+
+impl<'editor, T: GraphPoint> Deref for PointsMut<'editor, T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.editor.data
+    }
+}
+
+impl<'editor, T: GraphPoint> DerefMut for PointsMut<'editor, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        (self.touched, self.unlocked) = (true, true);
+        &mut self.editor.data
+    }
+}
+
+impl<'editor, T: GraphPoint> Drop for PointsMut<'editor, T> {
+    fn drop(&mut self) {
+        let GraphEditor { inner, data, .. } = &mut *self.editor;
+        inner.redraw |= self.touched;
+        if !self.unlocked {
+            return;
+        }
+        let mut moved = false;
+        // insertion sort, cheap when only a few points were moved
+        for i in 1..data.len() {
+            let Some(Ok(m)) = data.get_mut(..=i).map(|x| x.reorder_by(i, Ord::cmp)) else {
+                continue;
+            };
+            if m.from != m.to {
+                m.apply(&mut inner.selection);
+                moved = true;
+            }
+        }
+        if moved {
+            inner.selection.sort_unstable()
+        }
+    }
+}
+
+impl<'editor, T: GraphPoint> PointsMut<'editor, T> {
+    /// A view of the point at `index` that can't change its placement, so it's never re-sorted.
+    pub fn get_mut(&mut self, index: usize) -> Option<GraphPointView<'_, T>> {
+        self.touched = true;
+        self.editor.data.get_mut(index).map(GraphPointView)
+    }
+
+    /// Views of all the points that can't change their placement, so they're never re-sorted.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = GraphPointView<'_, T>> {
+        self.touched = true;
+        self.editor.data.iter_mut().map(GraphPointView)
+    }
+}
+
+#[test]
+fn points_mut_resorting() {
+    use crate::sound::AutomationPoint;
+    use wavexp_utils::real::R32;
+    let point = |time: u8| AutomationPoint { time: Beats(time.into()), value: R32::ONE };
+    let mut editor = GraphEditor::new((0..4).map(point).collect());
+    editor.set_selection(vec![0, 3], r64!(2));
+    let snapshot = editor.snapshot();
+    if let Some(x) = editor.points_mut().first_mut() {
+        x.time = Beats(r64!(2.5))
+    }
+    // the moved point ends up between the points it was moved between, still selected
+    let times: Vec<_> = editor.points().iter().map(|x| x.time.0).collect();
+    assert_eq!(times, [r64!(1), r64!(2), r64!(2.5), r64!(3)]);
+    assert_eq!(editor.selection(), [2, 3]);
+    assert_eq!(snapshot.first().map(|x| x.time), Some(Beats::ZERO));
+    assert!(editor.take_redraw());
+}
+
+#[test]
+fn points_mut_view() {
+    use crate::sequencer::SoundBlock;
+    let block = |offset: u8| SoundBlock {
+        sound: crate::sound::Sound::None,
+        layer: 0,
+        offset: Beats(offset.into()),
+        muted: false,
+        soloed: false,
+    };
+    // out of order for any re-sorting to show
+    let mut editor = GraphEditor::new(vec![block(1), block(1), block(0)]);
+    editor.take_redraw();
+    // only reading the points doesn't redraw the editor
+    assert!(editor.points_mut().first().is_some());
+    assert!(!editor.take_redraw());
+    // flags are edited without re-sorting the blocks, but the editor is still redrawn
+    if let Some(x) = editor.points_mut().get_mut(1) {
+        // Safety: whether a block is muted doesn't affect its placement
+        unsafe { x.unlock() }.muted = true;
+    }
+    let flags: Vec<_> = editor.points().iter().map(|x| (x.offset.0, x.muted)).collect();
+    assert_eq!(flags, [(r64!(1), false), (r64!(1), true), (r64!(0), false)]);
+    assert!(editor.take_redraw());
 }
 
 // types as coordinate space hints:
@@ -355,6 +1086,14 @@ pub enum SpecialAction {
     Remove,
 }
 
+/// axes of a graph editor's plane affected by zooming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Both,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 enum Focus {
     #[default]
@@ -374,14 +1113,460 @@ enum Focus {
         last_loc: ConfinedAlignedUserPoint,
         origin: ConfinedAlignedUserPoint,
         meta: bool,
+        /// index of the axis the drag is constrained to while Shift is held
+        locked_axis: Option<usize>,
     },
     Selection {
         origin: ConfinedAlignedUserPoint,
         end: ConfinedAlignedUserPoint,
         meta: bool,
+        /// index of the axis the drag is constrained to while Shift is held
+        locked_axis: Option<usize>,
     },
 }
 
+/// Confines `new` to the axis along which it's the farthest from `origin`, in canvas coordinates.
+/// The axis is chosen on the first non-zero displacement and stays in `locked_axis` until reset.
+fn constrain_to_axis(
+    origin: ConfinedAlignedUserPoint,
+    new: ConfinedAlignedUserPoint,
+    step: [R64; 2],
+    locked_axis: &mut Option<usize>,
+) -> ConfinedAlignedUserPoint {
+    let axis = match *locked_axis {
+        Some(axis) => axis,
+        None => {
+            let [dx, dy] = new.sub(origin).mul(step).map(R64::abs);
+            if dx == 0 && dy == 0 {
+                return origin;
+            }
+            *locked_axis.insert((dy > dx) as usize)
+        }
+    };
+    let mut res = origin;
+    res[axis] = new[axis];
+    res
+}
+
+/// Returns the shift along the X axis that moves a point at `x` onto the nearest multiple of
+/// `step`, or 0 if `step` is 0, i.e. if snapping is disabled.
+/// Applied to points at the start of dragging them for them to stay on the grid afterwards.
+fn snap_correction(x: R64, step: R64) -> R64 {
+    if step == 0 {
+        return R64::ZERO;
+    }
+    R64::new_or(R64::ZERO, (*x / *step).round() * *step - *x)
+}
+
+/// Picks where to move the start of a point that spans `len` along the X axis, given `raw`, where
+/// the cursor would move it without any snapping, & `grid`, where it would move it when snapped to
+/// the grid. Positions at which the start or the end of the point touches one of `edges` compete
+/// with `grid` if they're within `threshold` of `raw`; the one nearest to `raw` wins.
+fn magnetic_snap(
+    raw: R64,
+    grid: R64,
+    len: R64,
+    edges: impl IntoIterator<Item = R64>,
+    threshold: R64,
+) -> R64 {
+    edges
+        .into_iter()
+        .flat_map(|edge| [edge, edge - len])
+        .filter(|x| (*x - raw).abs() <= threshold)
+        .chain([grid])
+        .min_by_key(|x| (*x - raw).abs())
+        .unwrap_or(grid)
+}
+
+/// Returns the index of the first of `points` whose hitbox, as reported by `in_hitbox`, contains
+/// `loc` in user coordinates.
+fn point_at<T>(
+    points: &[T],
+    loc: [R64; 2],
+    mut in_hitbox: impl FnMut(&T, &[RangeInclusiveV2<R64>; 2]) -> Result<bool>,
+) -> Result<Option<usize>> {
+    let area = loc.map(|x| x.incl_range_to(x));
+    Ok(points.iter().enumerate().try_find(|x| in_hitbox(x.1, &area))?.map(|x| x.0))
+}
+
+#[test]
+fn context_menu_target() {
+    use crate::sound::{Note, NoteBlock};
+    let notes = [
+        NoteBlock::test(r64!(0), 20, r64!(1)),
+        NoteBlock::test(r64!(0.5), 5, r64!(1)),
+        NoteBlock::test(r64!(2), 20, r64!(1)),
+    ];
+    let target = |loc| point_at(&notes, loc, |x, area| Ok(x.in_area(area))).ok().flatten();
+    let y = |value| R64::from(Note::saturated(value).recip().index());
+    assert_eq!(target([r64!(0.25), y(20)]), Some(0));
+    assert_eq!(target([r64!(1.25), y(5)]), Some(1));
+    assert_eq!(target([r64!(2.5), y(20)]), Some(2));
+    assert_eq!(target([r64!(1.5), y(20)]), None);
+    assert_eq!(target([r64!(0.25), y(5)]), None);
+}
+
+#[test]
+fn magnetic_snapping() {
+    let edges = [r64!(2), r64!(4.5), r64!(9)];
+    // the start snaps to an edge
+    assert_eq!(magnetic_snap(r64!(4.4), r64!(4), r64!(1), edges, r64!(0.25)), r64!(4.5));
+    // the end snaps to an edge
+    assert_eq!(magnetic_snap(r64!(7.9), r64!(7.75), r64!(1), edges, r64!(0.25)), r64!(8));
+    assert_eq!(magnetic_snap(r64!(7.85), r64!(7.5), r64!(1.2), edges, r64!(0.25)), r64!(7.8));
+    // the grid is nearer
+    assert_eq!(magnetic_snap(r64!(2.2), r64!(2.25), r64!(1), edges, r64!(0.25)), r64!(2.25));
+    // no edges within the threshold
+    assert_eq!(magnetic_snap(r64!(6), r64!(6), r64!(1), edges, r64!(0.25)), r64!(6));
+}
+
+#[test]
+fn drag_snapping() {
+    use crate::sound::{Note, NoteBlock};
+    let mut block = NoteBlock { value: Note::MID, ..NoteBlock::test(r64!(1.375), 0, r64!(1)) };
+    let step = r64!(0.5);
+    // the cursor only moves by multiples of the snap step
+    let delta = r64!(1) + snap_correction(block.offset.0, step);
+    assert!(block.móve([delta, r64!(0)], false).is_ok());
+    assert_eq!(block.offset, Beats(r64!(2.5)));
+    assert_eq!(*block.offset % *step, 0.0);
+    // snap step of 0 disables snapping
+    assert_eq!(snap_correction(r64!(1.375), r64!(0)), r64!(0));
+}
+
+#[test]
+fn frame_budget() {
+    let start = Secs(r64!(10));
+    assert!(!AnyGraphEditor::frame_budget_exceeded(start, start));
+    assert!(!AnyGraphEditor::frame_budget_exceeded(start, start + AnyGraphEditor::FRAME_BUDGET));
+    assert!(AnyGraphEditor::frame_budget_exceeded(start, start + Secs(r64!(0.1))));
+}
+
+#[test]
+fn drag_axis_constraint() {
+    let origin = [r64!(2), r64!(5)];
+    let mut locked_axis = None;
+    assert_eq!(constrain_to_axis(origin, origin, [r64!(1); 2], &mut locked_axis), origin);
+    assert_eq!(locked_axis, None);
+    let new = constrain_to_axis(origin, [r64!(5), r64!(4)], [r64!(1); 2], &mut locked_axis);
+    assert_eq!(new, [r64!(5), r64!(5)]);
+    assert_eq!(locked_axis, Some(0));
+    // the axis stays locked even if the other one becomes dominant
+    let new = constrain_to_axis(origin, [r64!(3), r64!(15)], [r64!(1); 2], &mut locked_axis);
+    assert_eq!(new, [r64!(3), r64!(5)]);
+    // canvas step is accounted for when choosing the axis
+    let mut locked_axis = None;
+    let new = constrain_to_axis(origin, [r64!(5), r64!(4)], [r64!(1), r64!(10)], &mut locked_axis);
+    assert_eq!(new, [r64!(2), r64!(4)]);
+    assert_eq!(locked_axis, Some(1));
+}
+
+#[test]
+fn copy_paste() {
+    use crate::sound::NoteBlock;
+    let mut editor = GraphEditor::new(vec![
+        NoteBlock::test(r64!(0), 20, r64!(1)),
+        NoteBlock::test(r64!(1.5), 23, r64!(1)),
+    ]);
+    editor.inner.selection = vec![0, 1];
+    let copied = editor.copy_selection();
+    assert!(editor.paste(copied.clone(), [r64!(4), r64!(2)]).is_ok());
+    let locs: Vec<_> = editor.points().iter().map(NoteBlock::loc).collect();
+    assert_eq!(
+        locs,
+        [
+            [r64!(0), r64!(15)],
+            [r64!(1.5), r64!(12)],
+            [r64!(4), r64!(5)],
+            [r64!(5.5), r64!(2)]
+        ]
+    );
+    // moved up to keep the lowest note within the supported range
+    assert!(editor.paste(copied, [r64!(8), r64!(35)]).is_ok());
+    let locs: Vec<_> = editor.points().iter().skip(4).map(NoteBlock::loc).collect();
+    assert_eq!(locs, [[r64!(8), r64!(35)], [r64!(9.5), r64!(32)]]);
+}
+
+#[test]
+fn duplicate_selection() {
+    use crate::sound::NoteBlock;
+    let mut editor = GraphEditor::new(vec![
+        NoteBlock::test(r64!(0), 20, r64!(2)),
+        NoteBlock::test(r64!(1), 5, r64!(2)),
+        NoteBlock::test(r64!(3), 10, r64!(2)),
+    ]);
+    editor.inner.selection = vec![0, 2];
+    assert!(editor.duplicate_selection(r64!(0.5)).is_ok());
+    assert_eq!(
+        editor.points(),
+        &[
+            NoteBlock::test(r64!(0), 20, r64!(2)),
+            NoteBlock::test(r64!(0.5), 20, r64!(2)),
+            NoteBlock::test(r64!(1), 5, r64!(2)),
+            NoteBlock::test(r64!(3), 10, r64!(2)),
+            NoteBlock::test(r64!(3.5), 10, r64!(2))
+        ]
+    );
+    assert_eq!(editor.selection(), [1, 4]);
+}
+
+#[test]
+fn select_all_and_invert() {
+    use crate::sound::NoteBlock;
+    let mut editor = GraphEditor::new(
+        [0, 1, 2, 3, 4].map(|x| NoteBlock::test(R64::from(x), 10, r64!(1))).to_vec(),
+    );
+    editor.select_all(R64::ONE);
+    assert_eq!(editor.selection(), [0, 1, 2, 3, 4]);
+    // the selection area covers the whole length of the last point
+    assert_eq!(editor.selection_src[0], R64::ZERO);
+    assert_eq!(editor.selection_size, [r64!(5), R64::ZERO]);
+    editor.invert_selection(R64::ONE);
+    assert!(editor.selection().is_empty());
+    editor.inner.selection = vec![1, 3];
+    editor.invert_selection(R64::ONE);
+    assert_eq!(editor.selection(), [0, 2, 4]);
+}
+
+#[test]
+fn nudge_selection() {
+    use crate::sound::NoteBlock;
+    let mut editor = GraphEditor::new(vec![
+        NoteBlock::test(r64!(1), 20, r64!(1)),
+        NoteBlock::test(r64!(2), 5, r64!(1)),
+        NoteBlock::test(r64!(3), 10, r64!(1)),
+    ]);
+    editor.inner.selection = vec![0, 2];
+    assert_eq!(editor.nudge_selection([r64!(-0.25), r64!(0)]).ok(), Some([r64!(-0.25), r64!(0)]));
+    assert_eq!(
+        editor.points(),
+        &[
+            NoteBlock::test(r64!(0.75), 20, r64!(1)),
+            NoteBlock::test(r64!(2), 5, r64!(1)),
+            NoteBlock::test(r64!(2.75), 10, r64!(1))
+        ]
+    );
+    // the leftmost selected block can't go before the start of the pattern
+    assert_eq!(editor.nudge_selection([r64!(-1), r64!(0)]).ok(), Some([r64!(-0.75), r64!(0)]));
+    assert_eq!(
+        editor.points(),
+        &[
+            NoteBlock::test(r64!(0), 20, r64!(1)),
+            NoteBlock::test(r64!(2), 5, r64!(1)),
+            NoteBlock::test(r64!(2), 10, r64!(1))
+        ]
+    );
+}
+
+#[test]
+fn set_point() {
+    use crate::sound::NoteBlock;
+    let notes = vec![
+        NoteBlock::test(r64!(0), 20, r64!(1)),
+        NoteBlock::test(r64!(1), 5, r64!(1)),
+        NoteBlock::test(r64!(2), 10, r64!(1)),
+    ];
+    let mut editor = GraphEditor::new(notes.clone());
+    editor.inner.selection = vec![0, 2];
+    // moving the point past an unselected one keeps the points sorted & the selection on them
+    let moved = NoteBlock::test(r64!(1.5), 20, r64!(1));
+    let mut diff = PatternDiff::default();
+    assert!(editor.set_point(0, moved, &mut diff).is_ok());
+    assert_eq!(editor.points(), &[notes[1], moved, notes[2]]);
+    assert_eq!(editor.selection(), [1, 2]);
+    // reverting the change restores the exact original state
+    editor.apply_diff(&diff, true);
+    assert_eq!(editor.points(), &notes);
+    assert_eq!(editor.selection(), [0, 2]);
+}
+
+#[test]
+fn drag_recording() {
+    use crate::sound::{CustomBlock, Note};
+    let block = |offset: f64, pitch: u8| CustomBlock {
+        offset: Beats(R64::new_or(R64::ZERO, offset)),
+        pitch: Note::new(pitch).unwrap_or_default(),
+    };
+    let blocks = vec![block(1.0, 20), block(2.0, 5), block(3.0, 10)];
+    let mut editor = GraphEditor::new(blocks.clone());
+    editor.inner.selection = vec![0, 2];
+    editor.drag_src = GraphEditor::ghost_of(&blocks, &editor.inner.selection);
+    // dragged by 1.5 beats, the 1st block past an unselected one, & by 2 semitones
+    for (&id, point) in editor.inner.selection.iter().zip([block(2.5, 18), block(4.5, 8)]) {
+        if let Some(x) = editor.data.get_mut(id) {
+            *x = point
+        }
+    }
+    let mut ids = editor.inner.selection.clone();
+    let Ok(changes) = editor.record_drag(&mut ids) else { panic!("points out of bounds") };
+    let dragged = [block(2.0, 5), block(2.5, 18), block(4.5, 8)];
+    assert_eq!(editor.points(), &dragged);
+    assert_eq!(editor.selection(), [1, 2]);
+    assert_eq!(ids, [1, 2]);
+    // undone & redone exactly, unlike when moving the blocks back by the inverse of the drag
+    editor.apply_diff(&changes, true);
+    assert_eq!(editor.points(), &blocks);
+    assert_eq!(editor.selection(), [0, 2]);
+    editor.apply_diff(&changes, false);
+    assert_eq!(editor.points(), &dragged);
+}
+
+#[test]
+fn remove_selection() {
+    use crate::sound::NoteBlock;
+    let notes = vec![
+        NoteBlock::test(r64!(0), 20, r64!(1)),
+        NoteBlock::test(r64!(1), 5, r64!(1)),
+        NoteBlock::test(r64!(2), 10, r64!(1)),
+        NoteBlock::test(r64!(3), 7, r64!(1)),
+    ];
+    let mut editor = GraphEditor::new(notes.clone());
+    editor.inner.selection = vec![3, 1];
+    let Ok(EditorAction::RemovePoint(_, removed)) = editor.remove_selection() else {
+        panic!("expected the points to be removed")
+    };
+    assert_eq!(
+        editor.points(),
+        &[NoteBlock::test(r64!(0), 20, r64!(1)), NoteBlock::test(r64!(2), 10, r64!(1))]
+    );
+    assert!(editor.selection().is_empty());
+    // undoing puts the notes back at their original offsets
+    assert!(editor.restore_points(&removed).is_ok());
+    assert_eq!(editor.points(), &notes);
+    assert_eq!(editor.selection(), [1, 3]);
+    // nothing is removed if nothing is selected
+    editor.inner.selection.clear();
+    let Ok(EditorAction::RemovePoint(_, removed)) = editor.remove_selection() else {
+        panic!("expected an empty removal")
+    };
+    assert!(removed.is_empty());
+    assert_eq!(editor.points(), &notes);
+}
+
+#[test]
+fn drag_ghost() {
+    use crate::{
+        app::AppContext,
+        editor::EditorContext,
+        sound::{Beats, Chord, NoteBlock, Scale},
+    };
+    use std::num::NonZeroU32;
+
+    let notes = [
+        NoteBlock::test(r64!(1), 20, r64!(1)),
+        NoteBlock::test(r64!(2), 5, r64!(1)),
+        NoteBlock::test(r64!(3), 10, r64!(1)),
+    ];
+    let mut editor = GraphEditor::new(notes.to_vec());
+    editor.set_selection(vec![2, 0], r64!(2));
+    // 10 pixels per unit on both axes
+    editor.inner.size = [100, 400];
+    editor.inner.scale = [r64!(10), r64!(40)];
+    let (sequencer, mut app) = (Sequencer::test(default()), AppContext::test());
+    let (mut editor_ctx, visual_ctx) = (
+        EditorContext::new(),
+        (Beats::ZERO, NonZeroU32::MIN, Beats::ZERO, (Scale::Chromatic, 0), Chord::Single),
+    );
+    let mut hover = |editor: &mut GraphEditor<NoteBlock>, cursor: Option<([i32; 2], bool)>| {
+        let ctx = ContextMut { editor: &mut editor_ctx, app: &mut app };
+        let cursor = cursor.map(|([x, y], left)| {
+            let mut cursor = Cursor::default();
+            (cursor.point, cursor.left) = (Point { x, y }, left);
+            cursor
+        });
+        assert!(editor.handle_hover(cursor, ctx, &sequencer, &visual_ctx).is_ok());
+    };
+
+    // focusing on the plane & then on the selection, under the cursor at (2, 20)
+    hover(&mut editor, Some(([20, 200], false)));
+    hover(&mut editor, Some(([20, 200], false)));
+    assert!(editor.ghost.is_empty());
+    // captured when the drag starts
+    hover(&mut editor, Some(([20, 200], true)));
+    hover(&mut editor, Some(([30, 200], true)));
+    assert_eq!(editor.ghost, [notes[2], notes[0]]);
+    assert_eq!(
+        editor.points(),
+        &[
+            NoteBlock::test(r64!(2), 20, r64!(1)),
+            NoteBlock::test(r64!(2), 5, r64!(1)),
+            NoteBlock::test(r64!(4), 10, r64!(1))
+        ]
+    );
+    // cleared once the selection is dropped
+    hover(&mut editor, Some(([30, 200], false)));
+    assert!(editor.ghost.is_empty());
+    // & once the cursor leaves the canvas mid-drag
+    hover(&mut editor, Some(([30, 200], true)));
+    assert_eq!(editor.ghost.len(), 2);
+    hover(&mut editor, None);
+    assert!(editor.ghost.is_empty());
+}
+
+#[test]
+fn redraw_coalescing() {
+    use crate::sound::NoteBlock;
+    let mut editor = GraphEditor::<NoteBlock>::default();
+    for _ in 0..5 {
+        editor.force_redraw();
+    }
+    let redraws = (0..3).filter(|_| editor.take_redraw()).count();
+    assert_eq!(redraws, 1);
+    editor.force_redraw();
+    assert!(editor.take_redraw());
+}
+
+/// parameters the cached background of a graph editor, with the points drawn over it, depends on
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BackgroundKey {
+    size: [R64; 2],
+    scale: [R64; 2],
+    offset: Point,
+    /// `AnyGraphEditor::points_version` at the time of rendering
+    points: u32,
+}
+
+impl BackgroundKey {
+    /// whether the background rendered with `cached` parameters must be rendered anew
+    fn invalidates(self, cached: Option<Self>) -> bool {
+        cached != Some(self)
+    }
+}
+
+#[test]
+fn background_invalidation() {
+    let key = BackgroundKey {
+        size: [r64!(800), r64!(600)],
+        scale: [r64!(8), r64!(5)],
+        offset: default(),
+        points: 0,
+    };
+    assert!(key.invalidates(None));
+    assert!(!key.invalidates(Some(key)));
+    assert!(key.invalidates(Some(BackgroundKey { size: [r64!(400), r64!(600)], ..key })));
+    assert!(key.invalidates(Some(BackgroundKey { scale: [r64!(4), r64!(5)], ..key })));
+    assert!(key.invalidates(Some(BackgroundKey { offset: Point { x: 1, y: 0 }, ..key })));
+    assert!(key.invalidates(Some(BackgroundKey { points: 1, ..key })));
+}
+
+#[test]
+fn overlay_redraw() {
+    use crate::sound::NoteBlock;
+    // moving a point outdates the cached points, redrawing just the playhead doesn't
+    let block = |offset| NoteBlock::test(offset, 10, r64!(1));
+    let mut editor = GraphEditor::new(vec![block(r64!(0)), block(r64!(2))]);
+    let size = [r64!(800), r64!(600)];
+    let key = editor.background_key(size);
+    editor.force_overlay_redraw();
+    assert!(editor.take_redraw());
+    assert!(!editor.background_key(size).invalidates(Some(key)));
+    let mut diff = PatternDiff::default();
+    assert!(editor.set_point(1, block(r64!(3)), &mut diff).is_ok());
+    editor.force_overlay_redraw();
+    assert!(editor.take_redraw());
+    assert!(editor.background_key(size).invalidates(Some(key)));
+}
+
 static GRAPH_EDITOR_COUNT: WasmCell<Cell<usize>> =
     WasmCell(Cell::new(AnyGraphEditor::INVALID_ID + 1));
 
@@ -390,6 +1575,9 @@ static GRAPH_EDITOR_COUNT: WasmCell<Cell<usize>> =
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct AnyGraphEditor {
     canvas: NodeRef,
+    /// size of the canvas in the units it's drawn on in, see `GraphPoint::canvas_coords`; its
+    /// backing store is `devicePixelRatio` times larger
+    size: [u32; 2],
     offset: Point,
     scale: [R64; 2],
     focus: Focus,
@@ -398,26 +1586,66 @@ pub struct AnyGraphEditor {
     selection_size: [R64; 2],
     last_cursor: Cursor,
     redraw: bool,
+    /// set when only what's drawn on top of the cached points needs to be redrawn
+    redraw_overlay: bool,
+    /// incremented every time the cached points are outdated
+    points_version: u32,
+    /// the offset of the plane before the ongoing scrolling through the minimap, if any
+    scroll_origin: Option<Point>,
     update_hint: bool,
     grid: Option<(Path2d, [R64; 2])>,
+    background: Option<(HtmlCanvasElement, BackgroundKey)>,
     id: usize,
 }
 
 impl AnyGraphEditor {
     const FONT: &'static str = "20px consolas";
-    const BG_STYLE: &'static str = "#232328";
+    pub const BG_STYLE: &'static str = "#232328";
     const MG_STYLE: &'static str = "#333338";
-    const FG_STYLE: &'static str = "#0069E1";
-    const LINE_WIDTH: f64 = 3.0;
+    pub const FG_STYLE: &'static str = "#0069E1";
+    /// colour of the original positions of a dragged selection
+    const GHOST: Rgba = SoundVisualiser::FG.with_alpha(0x40);
+    pub const LINE_WIDTH: f64 = 3.0;
+    /// distance in pixels within which a dragged point is magnetically snapped to other points
+    pub const MAGNETIC_SNAP_DISTANCE: R64 = r64!(8);
+    /// time a redraw can take before the non-essential parts of it, like guides, get skipped
+    pub const FRAME_BUDGET: Secs = Secs(r64!(0.008));
     /// an ID that's guaranteed to never be used by any graph editor
     pub const INVALID_ID: usize = 0;
 
     pub const fn id(&self) -> usize {
         self.id
     }
+    /// whether a redraw that started at `start` has exceeded `Self::FRAME_BUDGET` by `now`
+    pub fn frame_budget_exceeded(start: Secs, now: Secs) -> bool {
+        now - start > Self::FRAME_BUDGET
+    }
+    /// fills & strokes `solid`, strokes `dotted` with a dotted line
+    #[apply(fallible!)]
+    fn draw_paths(dst: &CanvasRenderingContext2d, solid: &Path2d, dotted: &Path2d) {
+        dst.set_fill_style(&Self::MG_STYLE.into());
+        dst.set_stroke_style(&Self::FG_STYLE.into());
+        dst.fill_with_path_2d(solid);
+        dst.stroke_with_path(solid);
+        dst.set_line_dash(eval_once!(JsValue: js_array![number 10.0, number 10.0]))?;
+        dst.stroke_with_path(dotted);
+        dst.set_line_dash(eval_once!(JsValue: js_array![]))?;
+    }
     pub const fn canvas(&self) -> &NodeRef {
         &self.canvas
     }
+    /// The canvas's area in the units it's drawn on in.
+    fn coords_rect(&self) -> Rect {
+        Rect::new(Point::ZERO, Point { x: self.size[0] as i32, y: self.size[1] as i32 })
+    }
+    /// The cursor of `e` in the units the canvas is drawn on in, rather than in device pixels.
+    #[apply(fallible!)]
+    fn cursor(&self, e: &MouseEvent) -> Cursor {
+        let canvas: HtmlCanvasElement = self.canvas.cast()?;
+        let mut res = Cursor::try_from(e)?;
+        res.point = res.point.normalise(canvas.rect(), self.coords_rect())?;
+        res
+    }
     pub fn selection(&self) -> &[usize] {
         &self.selection
     }
@@ -432,12 +1660,24 @@ impl AnyGraphEditor {
     pub const fn scale(&self) -> [R64; 2] {
         self.scale
     }
+    /// The area of the plane visible on the canvas, in user coordinates, as `[x, y, w, h]`.
+    pub fn viewport(&self) -> [R64; 4] {
+        let size = self.size.map(R64::from);
+        let [x, y] = R64::array_from(self.offset).div(size.div(self.scale));
+        [x, y, self.scale[0], self.scale[1]]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct GraphEditor<T: GraphPoint> {
     inner: AnyGraphEditor,
     data: Vec<T>,
+    /// the selected points as they were when the selection started being dragged,
+    /// empty if it isn't being dragged
+    ghost: Vec<T>,
+    /// the dragged points as they were before the drag, from which the drag is recorded once
+    /// it ends
+    drag_src: Vec<T>,
 }
 
 impl<T: GraphPoint> Deref for GraphEditor<T> {
@@ -460,22 +1700,27 @@ impl<T: GraphPoint> Default for GraphEditor<T> {
 }
 
 impl<T: GraphPoint> GraphEditor<T> {
+    /// scale of a newly created editor
+    const DEFAULT_SCALE: [R64; 2] = [
+        R64::new_or(
+            T::SCALE_X_BOUND.start,
+            T::SCALE_X_BOUND.start.get() * 0.75 + T::SCALE_X_BOUND.end.get() * 0.25,
+        ),
+        R64::new_or(
+            T::SCALE_Y_BOUND.start,
+            T::SCALE_Y_BOUND.start.get() * 0.75 + T::SCALE_Y_BOUND.end.get() * 0.25,
+        ),
+    ];
+    /// fraction of the visible area left empty on each side of the points by `fit_to_content`
+    const FIT_MARGIN: R64 = r64!(0.1);
+
     pub fn new(data: Vec<T>) -> Self {
         let res = Self {
             data,
+            ghost: vec![],
+            drag_src: vec![],
             inner: AnyGraphEditor {
-                scale: const {
-                    [
-                        R64::new_or(
-                            T::SCALE_X_BOUND.start,
-                            T::SCALE_X_BOUND.start.get() * 0.75 + T::SCALE_X_BOUND.end.get() * 0.25,
-                        ),
-                        R64::new_or(
-                            T::SCALE_Y_BOUND.start,
-                            T::SCALE_Y_BOUND.start.get() * 0.75 + T::SCALE_Y_BOUND.end.get() * 0.25,
-                        ),
-                    ]
-                },
+                scale: Self::DEFAULT_SCALE,
                 id: GRAPH_EDITOR_COUNT.get(),
                 ..default()
             },
@@ -484,16 +1729,23 @@ impl<T: GraphPoint> GraphEditor<T> {
         res
     }
 
-    pub const fn data(&self) -> &Vec<T> {
+    pub fn points(&self) -> &[T] {
         &self.data
     }
 
-    pub fn get_mut(&mut self, index: usize) -> Option<GraphPointView<'_, T>> {
-        self.data.get_mut(index).map(GraphPointView)
+    /// Gives mutable access to the points, which are sorted back into place once it's dropped.
+    pub fn points_mut(&mut self) -> PointsMut<'_, T> {
+        PointsMut { editor: self, touched: false, unlocked: false }
     }
 
-    pub fn iter_data_mut(&mut self) -> impl Iterator<Item = GraphPointView<'_, T>> {
-        self.data.iter_mut().map(GraphPointView)
+    /// Copies of the points as they are now, unaffected by any further changes to the editor.
+    pub fn snapshot(&self) -> Box<[T]> {
+        self.data.as_slice().into()
+    }
+
+    /// Copies of the selected points, drawn at their original positions while they're dragged.
+    fn ghost_of(data: &[T], selection: &[usize]) -> Vec<T> {
+        selection.iter().filter_map(|&id| data.get(id)).cloned().collect()
     }
 
     /// `to_remove` accepts an ID of the selected point and a reference to it,
@@ -527,7 +1779,7 @@ impl<T: GraphPoint> GraphEditor<T> {
         to_remove: impl Iterator<Item = usize> + DoubleEndedIterator,
     ) -> Result<EditorAction> {
         self.redraw = true;
-        let GraphEditor { inner, data } = self;
+        let GraphEditor { inner, data, .. } = self;
         let mut ids_iter = inner.selection.iter_mut().rev();
         let mut prev_id = data.len();
         let mut removed = vec![];
@@ -559,26 +1811,356 @@ impl<T: GraphPoint> GraphEditor<T> {
         Ok(EditorAction::RemovePoint(self.id, removed.into_boxed_slice()))
     }
 
+    /// Removes the selected points.
+    /// Returns the action that represents the removal of the points.
+    pub fn remove_selection(&mut self) -> Result<EditorAction> {
+        self.inner.selection.sort_unstable();
+        let ids = self.selection.clone();
+        self.remove_points(ids.into_iter())
+    }
+
+    /// Puts the points removed by `remove_points` or `filter_selected` back where they were,
+    /// reselecting the ones that were selected.
+    #[apply(fallible!)]
+    fn restore_points(&mut self, points: &[RemovedPoint]) {
+        // the points are listed from the last one to the first one
+        for &RemovedPoint { ref point, index, was_selected } in points.iter().rev() {
+            self.insert_at(index, point.downcast_ref::<T>()?.clone());
+            if was_selected {
+                self.inner.selection.push(index);
+            }
+        }
+        self.inner.selection.sort_unstable();
+    }
+
+    /// Inserts `point` at `index`, keeping the selection pointing to the same points.
+    fn insert_at(&mut self, index: usize, point: T) {
+        self.redraw = true;
+        self.data.insert(index, point);
+        for id in &mut self.inner.selection {
+            if *id >= index {
+                *id += 1;
+            }
+        }
+    }
+
+    /// Inserts `point` where it keeps the points sorted, returning the index it was inserted at.
+    fn insert_sorted(&mut self, point: T) -> usize {
+        let index = self.data.partition_point(|x| x <= &point);
+        self.insert_at(index, point);
+        index
+    }
+
+    /// Inserts the points into the editor, keeping the points sorted.
+    /// Returns the action that represents the insertion of the points.
+    pub fn insert_points(&mut self, points: impl IntoIterator<Item = T>) -> EditorAction {
+        let inserted = points
+            .into_iter()
+            .map(|point| {
+                let index = self.insert_sorted(point.clone());
+                RemovedPoint { point: Rc::new(point), index, was_selected: false }
+            })
+            .collect();
+        EditorAction::InsertPoint(self.id, inserted)
+    }
+
+    /// Inserts copies of the selected points, moved by `shift` along the X axis, & selects them
+    /// instead of the originals.
+    /// Returns the action that represents the duplication.
+    pub fn duplicate_selection(&mut self, shift: R64) -> Result<EditorAction> {
+        let delta = [shift, R64::ZERO];
+        let mut copies = self.copy_selection();
+        for point in &mut copies {
+            point.móve(delta, false)?;
+        }
+        let prev_ids = take(&mut self.inner.selection).into_boxed_slice();
+        let inserted = copies
+            .into_iter()
+            .map(|point| {
+                let index = self.insert_sorted(point.clone());
+                self.inner.selection.push(index);
+                RemovedPoint { point: Rc::new(point), index, was_selected: true }
+            })
+            .collect();
+        self.inner.selection.sort_unstable();
+        T::move_point(&mut self.inner.selection_src, delta, false);
+        Ok(EditorAction::DuplicateSelection { editor_id: self.id, inserted, prev_ids, delta })
+    }
+
+    /// Replaces the selection with `ids`, which must be sorted, making the selection area the
+    /// bounding box of the newly selected points, including their lengths in tempo `bps`.
+    /// Returns the action that represents the change of the selection.
+    fn set_selection(&mut self, ids: Vec<usize>, bps: R64) -> EditorAction {
+        self.redraw = true;
+        self.update_hint = true;
+        let points = ids.iter().filter_map(|&id| self.data.get(id));
+        let (min, max) = Self::bounding_box(points, bps).ok().flatten().unwrap_or_default();
+        EditorAction::SetSelection {
+            editor_id: self.id,
+            prev_ids: replace(&mut self.inner.selection, ids).into_boxed_slice(),
+            prev_src: replace(&mut self.inner.selection_src, min),
+            prev_size: replace(&mut self.inner.selection_size, max.sub(min)),
+            cur_ids: self.selection.to_box(),
+            cur_src: self.selection_src,
+            cur_size: self.selection_size,
+        }
+    }
+
+    /// Selects all the points.
+    /// Returns the action that represents the change of the selection.
+    pub fn select_all(&mut self, bps: R64) -> EditorAction {
+        self.set_selection((0..self.data.len()).collect(), bps)
+    }
+
+    /// Selects the points that aren't selected & unselects the ones that are.
+    /// Returns the action that represents the change of the selection.
+    pub fn invert_selection(&mut self, bps: R64) -> EditorAction {
+        let mut selected = self.selection.iter().copied().peekable();
+        let ids = (0..self.data.len()).filter(|&id| selected.next_if_eq(&id).is_none()).collect();
+        self.set_selection(ids, bps)
+    }
+
+    /// Moves the selected points by `delta`, which is reduced if needed for all of them to stay
+    /// within the editor's bounds, e.g. for the pitches of the notes to stay within the supported
+    /// range.
+    /// Returns the delta by which the points were actually moved.
+    #[apply(fallible!)]
+    pub fn nudge_selection(&mut self, delta: [R64; 2]) -> [R64; 2] {
+        let mut locs = self.selection.iter().filter_map(|&id| self.data.get(id).map(T::loc));
+        let Some(first) = locs.next() else {
+            return Ok(default());
+        };
+        let (min, max) = locs.fold((first, first), |(min, max), loc| {
+            (min.zip(loc, R64::min), max.zip(loc, R64::max))
+        });
+        let lower = [T::X_BOUND.start, T::Y_BOUND.start].sub(min);
+        let upper = [T::X_BOUND.end, T::Y_BOUND.end - T::Y_SNAP].sub(max);
+        let delta = delta.zip(upper, R64::min).zip(lower, R64::max);
+        if delta.any(|x| *x != 0) {
+            self.redraw = true;
+            for &id in &self.inner.selection {
+                T::móve(self.data.get_mut(id)?, delta, false)?;
+            }
+            T::move_point(&mut self.inner.selection_src, delta, false);
+        }
+        delta
+    }
+
+    /// Returns copies of the selected points.
+    pub fn copy_selection(&self) -> Vec<T> {
+        self.selection.iter().filter_map(|&id| self.data.get(id)).cloned().collect()
+    }
+
+    /// Inserts `points`, moved so that the top-left corner of their bounding box lands at `at`.
+    /// If that would move some of them out of the editor's bounds, the points are moved as a
+    /// whole to stay within them, e.g. the pitches of pasted notes are kept within the supported
+    /// range.
+    /// Returns the action that represents the insertion of the points.
+    pub fn paste(&mut self, mut points: Vec<T>, at: [R64; 2]) -> Result<EditorAction> {
+        let Some(first) = points.first().map(T::loc) else {
+            return Ok(EditorAction::InsertPoint(self.id, default()));
+        };
+        let (min, max) = points.iter().map(T::loc).fold((first, first), |(min, max), loc| {
+            (min.zip(loc, R64::min), max.zip(loc, R64::max))
+        });
+        let lower = [T::X_BOUND.start, T::Y_BOUND.start].sub(min);
+        let upper = [T::X_BOUND.end, T::Y_BOUND.end - T::Y_SNAP].sub(max);
+        let delta = at.sub(min).zip(upper, R64::min).zip(lower, R64::max);
+        for point in &mut points {
+            point.móve(delta, false)?;
+        }
+        Ok(self.insert_points(points))
+    }
+
+    /// Replaces the point at `index` with `value`, moving it to keep the points sorted, & records
+    /// the change in `diff`. The selection keeps pointing to the same points.
+    /// Returns the move of the point, with which other indices of the points must be adjusted.
+    pub fn set_point(
+        &mut self,
+        index: usize,
+        value: T,
+        diff: &mut PatternDiff<T>,
+    ) -> Result<SliceMove> {
+        let moved = diff.set(&mut self.data, index, value, Ord::cmp)?;
+        moved.apply(&mut self.inner.selection);
+        self.inner.selection.sort_unstable();
+        self.force_redraw();
+        Ok(moved)
+    }
+
+    /// Repeats or, if `rev` is set, reverts the change recorded in `diff`, keeping the selection
+    /// pointing to the same points.
+    pub fn apply_diff(&mut self, diff: &PatternDiff<T>, rev: bool) {
+        if rev {
+            diff.unapply(&mut self.data);
+            diff.unapply_to_indices(&mut self.inner.selection);
+        } else {
+            diff.apply(&mut self.data);
+            diff.apply_to_indices(&mut self.inner.selection);
+        }
+        self.inner.selection.sort_unstable();
+        self.force_redraw()
+    }
+
+    /// Records the drag of the points at `ids`, which were `drag_src` before it, by setting them
+    /// from where they were to where they were dragged one by one, which also moves them to keep
+    /// the points sorted. `ids` is adjusted to keep pointing to the dragged points.
+    #[apply(fallible!)]
+    fn record_drag(&mut self, ids: &mut [usize]) -> PatternDiff<T> {
+        let mut dst = Vec::with_capacity(ids.len());
+        for (&id, point) in ids.iter().zip(take(&mut self.drag_src)) {
+            dst.push(replace(self.data.get_mut(id)?, point));
+        }
+        let mut diff = PatternDiff::default();
+        for (i, point) in dst.into_iter().enumerate() {
+            self.set_point(*ids.get(i)?, point, &mut diff)?.apply(ids);
+        }
+        diff
+    }
+
+    /// Requests the editor to be redrawn on the next frame. Any number of requests made before
+    /// the frame result in a single redraw.
     pub fn force_redraw(&mut self) {
         self.redraw = true
     }
 
+    /// Like `force_redraw`, but the points are copied over from the cache instead of being
+    /// redrawn, only `GraphPoint::on_overlay_redraw` is called.
+    pub fn force_overlay_redraw(&mut self) {
+        self.redraw_overlay = true
+    }
+
+    /// Returns whether a redraw was requested since the last call, clearing the request.
+    /// Unless only the overlay was requested to be redrawn, the cached points are outdated.
+    fn take_redraw(&mut self) -> bool {
+        let overlay = take(&mut self.inner.redraw_overlay);
+        if take(&mut self.inner.redraw) {
+            self.inner.points_version = self.inner.points_version.wrapping_add(1);
+            return true;
+        }
+        overlay
+    }
+
+    /// parameters of the background rendered for a canvas of size `size`
+    fn background_key(&self, size: [R64; 2]) -> BackgroundKey {
+        BackgroundKey {
+            size,
+            scale: self.scale,
+            offset: self.offset,
+            points: self.points_version,
+        }
+    }
+
+    /// Offset of the plane at which the user point `centre` is in the middle of a canvas of size
+    /// `size`, confined to the offset bounds.
+    pub fn scroll_offset(centre: [R64; 2], scale: [R64; 2], size: [R64; 2]) -> Point {
+        let step = size.div(scale);
+        let [x, y] = centre.mul(step).sub(size.div([r64!(2); 2]));
+        Point {
+            x: T::OFFSET_X_BOUND.map_bounds(|x| x * step[0]).fit(x).into(),
+            y: T::OFFSET_Y_BOUND.map_bounds(|y| y * step[1]).fit(y).into(),
+        }
+    }
+
+    /// Scrolls the plane so that the user point `centre` is in the middle of a canvas of size
+    /// `size`, as part of a scrolling that's finished with `finish_scroll`.
+    pub fn scroll_to(&mut self, centre: [R64; 2], size: [R64; 2]) {
+        self.inner.scroll_origin.get_or_insert(self.inner.offset);
+        self.inner.offset = Self::scroll_offset(centre, self.scale, size);
+        self.inner.redraw = true;
+    }
+
+    /// Finishes the scrolling started by the first call to `scroll_to` since the last call.
+    /// Returns the change of the offset over the whole scrolling.
+    #[apply(fallible!)]
+    pub fn finish_scroll(&mut self) -> Point {
+        match self.inner.scroll_origin.take() {
+            Some(origin) => (self.offset - origin)?,
+            None => default(),
+        }
+    }
+
+    /// Multiplies the scale along `axis` by `2 ^ -delta`, so that a positive `delta` zooms in,
+    /// keeping the point `at` of the canvas in place.
+    /// Returns the change of the offset & the change of the scale.
+    #[apply(fallible!)]
+    pub fn zoom(&mut self, delta: R64, at: Point, axis: Axis) -> (Point, [R64; 2]) {
+        let factor = (-delta).exp2();
+        let at = R64::array_from(at);
+        let (init_offset, init_scale) = (self.offset, self.scale);
+        let mut offset = R64::array_from(init_offset);
+        let axes = [(axis != Axis::Y, T::SCALE_X_BOUND), (axis != Axis::X, T::SCALE_Y_BOUND)];
+        for (i, (zoomed, bound)) in axes.into_iter().enumerate() {
+            if !zoomed || bound.is_empty() {
+                continue;
+            }
+            self.inner.scale[i] = bound.fit(init_scale[i] * factor);
+            offset[i] = (at[i] + offset[i]) * init_scale[i] / self.scale[i] - at[i];
+        }
+        self.inner.offset = offset.into();
+        self.inner.redraw = true;
+        ((self.offset - init_offset)?, self.scale.sub(init_scale))
+    }
+
+    /// The minimum & maximum corners of the bounding box of `points` in user coordinates,
+    /// including the points' lengths in tempo `bps`, or `None` if there are no points.
+    fn bounding_box<'point>(
+        points: impl IntoIterator<Item = &'point T>,
+        bps: R64,
+    ) -> Result<Option<([R64; 2], [R64; 2])>> {
+        points.into_iter().try_fold(None, |bounds, point| {
+            let ([start, end], [_, y]) = (point.span(bps)?, point.loc());
+            let (start, end) = ([start, y], [end, y]);
+            Ok(Some(match bounds {
+                Some((min, max)) => (start.zip(min, R64::min), end.zip(max, R64::max)),
+                None => (start, end),
+            }))
+        })
+    }
+
+    /// Sets the scale & offset so that all the points, along with their lengths in tempo `bps`,
+    /// are visible on a canvas of size `size`, with a margin around them, or resets them to the
+    /// defaults if there are no points.
+    /// Returns the change of the offset & the change of the scale.
+    #[apply(fallible!)]
+    pub fn fit_to_content(&mut self, size: [R64; 2], bps: R64) -> (Point, [R64; 2]) {
+        let (init_offset, init_scale) = (self.offset, self.scale);
+        let bounds = Self::bounding_box(&self.data, bps)?;
+        self.inner.redraw = true;
+        let Some((min, max)) = bounds else {
+            self.inner.scale = Self::DEFAULT_SCALE;
+            let start = [T::OFFSET_X_BOUND.start, T::OFFSET_Y_BOUND.start];
+            self.inner.offset = start.mul(size.div(self.scale)).into();
+            return Ok(((self.offset - init_offset)?, self.scale.sub(init_scale)));
+        };
+        let visible = max.sub(min).div([R64::ONE - Self::FIT_MARGIN * r64!(2); 2]);
+        self.inner.scale = [T::SCALE_X_BOUND, T::SCALE_Y_BOUND].zip(visible, |b, x| b.fit(x));
+        let centre = min.add(max).div([r64!(2); 2]);
+        self.inner.offset = Self::scroll_offset(centre, self.scale, size);
+        ((self.offset - init_offset)?, self.scale.sub(init_scale))
+    }
+
     /// must be called when a canvas has just been bound or its dimensions have been changed
     #[apply(fallible!)]
     pub fn init(&mut self) {
         let canvas: HtmlCanvasElement = self.canvas.cast()?;
         let [w, h] = T::canvas_coords(&canvas)?;
-        canvas.set_width(w);
-        canvas.set_height(h);
+        // the plane is always filled with the background colour, & the playhead is redrawn every
+        // frame during playback; the attributes only apply to a newly created context, so it's
+        // created before being scaled for the device pixel ratio
+        let ctx = canvas
+            .get_2d_context_with(CanvasContextOptions { alpha: false, desynchronized: true })?;
+        canvas.sync_hidpi_as([w, h])?;
+        self.size = [w, h];
         self.scale = self.scale.ceil_to([r64!(2); 2]);
         self.grid = None;
+        self.background = None;
         if self.offset.x <= 0 {
             self.offset.x = (T::OFFSET_X_BOUND.start * (w as f32) / self.scale[0]).into()
         }
         if self.offset.y <= 0 {
             self.offset.y = (T::OFFSET_Y_BOUND.start * h / self.scale[1]).into()
         }
-        let ctx = canvas.get_2d_context()?;
         ctx.set_font(AnyGraphEditor::FONT);
         ctx.set_line_width(AnyGraphEditor::LINE_WIDTH);
         self.redraw = true
@@ -591,14 +2173,10 @@ impl<T: GraphPoint> GraphEditor<T> {
         sequencer: &Sequencer,
         visual_ctx: T::VisualContext,
     ) -> Result<Option<SliceRef<'_, T>>> {
-        Ok(self
-            .data
-            .iter()
-            .enumerate()
-            .try_find(|x| {
-                x.1.in_hitbox(&loc.map(|x| x.incl_range_to(x)), ctx.as_ref(), sequencer, visual_ctx)
-            })?
-            .map(|(id, x)| unsafe { SliceRef::raw(x, id) }))
+        let id = point_at(&self.data, loc, |x, area| {
+            x.in_hitbox(area, ctx.as_ref(), sequencer, visual_ctx)
+        })?;
+        Ok(id.map(|id| unsafe { SliceRef::raw(self.data.get_unchecked(id), id) }))
     }
 
     fn point_in_selection(&self, loc: ConfinedAlignedUserPoint) -> bool {
@@ -684,7 +2262,7 @@ impl<T: GraphPoint> GraphEditor<T> {
                     },
 
                     Buttons { left: true, meta: false, .. } => {
-                        (main() + ": moving", "Release to stop")
+                        (main() + ": moving", "Release to stop, Shift - move along 1 axis")
                     }
 
                     Buttons { left: true, meta: true, .. } => match ctx.special_action() {
@@ -733,7 +2311,7 @@ impl<T: GraphPoint> GraphEditor<T> {
                     },
 
                     Buttons { left: true, meta: false, .. } => {
-                        (main() + ": moving", "Release to stop")
+                        (main() + ": moving", "Release to stop, Shift - move along 1 axis")
                     }
 
                     Buttons { left: true, meta: true, .. } => match ctx.special_action() {
@@ -770,12 +2348,19 @@ impl<T: GraphPoint> GraphEditor<T> {
     }
 
     fn set_point_focus(&mut self, id: usize) {
-        self.focus = Focus::Point { id, last_loc: default(), origin: default(), meta: false };
+        self.focus = Focus::Point {
+            id,
+            last_loc: default(),
+            origin: default(),
+            meta: false,
+            locked_axis: None,
+        };
         self.update_hint = true;
     }
 
     fn set_selection_focus(&mut self) {
-        self.focus = Focus::Selection { origin: default(), end: default(), meta: false };
+        self.focus =
+            Focus::Selection { origin: default(), end: default(), meta: false, locked_axis: None };
         self.update_hint = true;
     }
 
@@ -834,7 +2419,11 @@ impl<T: GraphPoint> GraphEditor<T> {
 
             SpecialAction::Add => {
                 if !matches!(self.focus, Focus::Point { .. }) {
-                    let new = T::create(self, released_at);
+                    let mut new = T::stamp(self, released_at, *visual_ctx);
+                    if new.len() > 1 {
+                        return ctx.register_action(self.insert_points(new));
+                    }
+                    let Some(new) = new.pop() else { return Ok(()) };
                     let point_id = self.data.len();
                     self.data.push(new);
                     ctx.register_action(EditorAction::AddPoint {
@@ -864,10 +2453,12 @@ impl<T: GraphPoint> GraphEditor<T> {
     ) {
         let Some(cursor) = cursor else {
             self.focus = Focus::None;
+            self.ghost.clear();
+            self.drag_src.clear();
             return Ok(());
         };
 
-        let size = self.canvas.cast::<HtmlCanvasElement>()?.size();
+        let size = self.size;
         let snap_step = [ctx.snap_step(), T::Y_SNAP];
         let step = R64::array_from(size).div(self.scale);
 
@@ -876,7 +2467,8 @@ impl<T: GraphPoint> GraphEditor<T> {
             move || R64::array_from(cursor.point.add(off).unwrap_or_default()).div(step)
         });
         let cursor_point_user_aligned_confined = LazyCell::new(|| {
-            cursor_point_user.floor_to(snap_step).array_fit_into([T::X_BOUND, T::Y_BOUND])
+            T::snap(cursor_point_user.floor_to(snap_step), *visual_ctx)
+                .array_fit_into([T::X_BOUND, T::Y_BOUND])
         });
 
         match &mut self.inner.focus {
@@ -1003,7 +2595,7 @@ impl<T: GraphPoint> GraphEditor<T> {
                 }
             },
 
-            Focus::Point { id, last_loc, origin, meta } => {
+            Focus::Point { id, last_loc, origin, meta, locked_axis } => {
                 if cursor.left {
                     if *meta {
                         self.special_action_on_drag(
@@ -1014,11 +2606,37 @@ impl<T: GraphPoint> GraphEditor<T> {
                     } else {
                         let delta = if !self.inner.last_cursor.left {
                             *last_loc = *cursor_point_user_aligned_confined;
-                            *origin = *last_loc;
                             *meta = cursor.meta;
+                            if !*meta {
+                                let point = self.data.get(*id)?;
+                                last_loc[0] -= snap_correction(point.loc()[0], snap_step[0]);
+                                self.drag_src = vec![point.clone()];
+                            }
+                            *origin = *last_loc;
+                            *locked_axis = None;
                             default()
                         } else {
-                            let new = *cursor_point_user_aligned_confined;
+                            let mut new = *cursor_point_user_aligned_confined;
+                            if ctx.magnetic_snap() {
+                                let [start, end] = self.data.get(*id)?.span(sequencer.bps())?;
+                                let edges = self
+                                    .data
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(i, _)| i != id)
+                                    .map(|(_, point)| point.span(sequencer.bps()))
+                                    .collect::<Result<Vec<_>>>()?;
+                                let raw = start + cursor_point_user[0] - last_loc[0];
+                                let grid = start + new[0] - last_loc[0];
+                                let threshold = AnyGraphEditor::MAGNETIC_SNAP_DISTANCE / step[0];
+                                let (len, edges) = (end - start, edges.into_iter().flatten());
+                                new[0] += magnetic_snap(raw, grid, len, edges, threshold) - grid;
+                            }
+                            if cursor.shift {
+                                new = constrain_to_axis(*origin, new, step, locked_axis);
+                            } else {
+                                *locked_axis = None;
+                            }
                             new.sub(replace(last_loc, new))
                         };
                         if delta.any(|x| *x != 0) {
@@ -1040,12 +2658,15 @@ impl<T: GraphPoint> GraphEditor<T> {
                             visual_ctx,
                         )?;
                     } else {
-                        let delta = dst.sub(src);
-                        if delta.any(|x| *x != 0) {
+                        let mut ids = [point_id];
+                        let changes = self.record_drag(&mut ids)?;
+                        if let Focus::Point { id, .. } = &mut self.inner.focus {
+                            [*id] = ids;
+                        }
+                        if !changes.is_empty() {
                             ctx.register_action(EditorAction::DragPoint {
                                 editor_id: self.id,
-                                point_id,
-                                delta,
+                                changes: Rc::new(changes),
                             })?;
                         }
                     }
@@ -1061,15 +2682,36 @@ impl<T: GraphPoint> GraphEditor<T> {
                 }
             }
 
-            Focus::Selection { origin, end, meta } => {
+            Focus::Selection { origin, end, meta, locked_axis } => {
                 if cursor.left {
                     let delta = if !self.inner.last_cursor.left {
                         *end = *cursor_point_user_aligned_confined;
-                        *origin = *end;
                         *meta = cursor.meta;
+                        let leftmost = self
+                            .inner
+                            .selection
+                            .iter()
+                            .filter_map(|&id| Some(self.data.get(id)?.loc()[0]))
+                            .min();
+                        if !*meta && let Some(x) = leftmost {
+                            end[0] -= snap_correction(x, snap_step[0]);
+                        }
+                        *origin = *end;
+                        *locked_axis = None;
+                        if !*meta {
+                            self.drag_src = Self::ghost_of(&self.data, &self.inner.selection);
+                            if ctx.drag_ghost() {
+                                self.ghost.clone_from(&self.drag_src);
+                            }
+                        }
                         default()
                     } else {
-                        let new = *cursor_point_user_aligned_confined;
+                        let mut new = *cursor_point_user_aligned_confined;
+                        if cursor.shift && !*meta {
+                            new = constrain_to_axis(*origin, new, step, locked_axis);
+                        } else {
+                            *locked_axis = None;
+                        }
                         new.sub(replace(end, new))
                     };
                     if delta.any(|x| *x != 0) {
@@ -1083,7 +2725,10 @@ impl<T: GraphPoint> GraphEditor<T> {
                 } else if self.inner.last_cursor.left {
                     let (dst, src) = (*cursor_point_user_aligned_confined, take(origin));
                     let meta = *meta;
-                    *end = default();
+                    self.ghost.clear();
+                    // the selection is at `end`, which may differ from `dst` if the drag was
+                    // constrained to 1 axis
+                    let end = take(end);
                     if meta {
                         self.special_action_on_click(
                             ctx.as_mut(),
@@ -1093,9 +2738,12 @@ impl<T: GraphPoint> GraphEditor<T> {
                             visual_ctx,
                         )?;
                     } else {
+                        let mut ids = self.inner.selection.clone();
+                        let changes = self.record_drag(&mut ids)?;
                         ctx.register_action(EditorAction::DragSelection {
                             editor_id: self.id,
-                            delta: dst.sub(src),
+                            delta: end.sub(src),
+                            changes: Rc::new(changes),
                         })?;
                     }
                 } else if cursor.shift {
@@ -1121,6 +2769,49 @@ impl<T: GraphPoint> GraphEditor<T> {
         }
     }
 
+    /// renders the background fill & the grid of the plane, i.e. everything that's static
+    /// as long as the plane isn't resized, zoomed or scrolled
+    #[apply(fallible!)]
+    fn draw_background(&mut self, dst: &CanvasRenderingContext2d, size: [R64; 2], step: [R64; 2]) {
+        let offset_x = R64::from(-self.offset.x)
+            % (self.offset.x > (T::X_BOUND.start * step[0]).into()).choose(step[0], R64::INFINITY);
+        let offset_y = R64::from(-self.offset.y)
+            % if self.offset.y > (T::Y_BOUND.start * step[1]).into() {
+                step[1] * 2
+            } else {
+                R64::INFINITY
+            };
+
+        dst.set_fill_style(&AnyGraphEditor::BG_STYLE.into());
+        dst.fill_rect(0.0, 0.0, *size[0], *size[1]);
+
+        let (grid, original_scale) = self
+            .inner
+            .grid
+            .get_or_try_insert(|| Self::draw_grid(size, step, self.inner.scale))?;
+        let grid_scale = original_scale.div(self.inner.scale);
+        let reps = self
+            .inner
+            .scale
+            .sub([offset_x, offset_y].div(step))
+            .div(*original_scale)
+            .map(|x| usize::from(x.ceil()));
+
+        // the transform is restored afterwards rather than reset, keeping the device pixel ratio
+        dst.save();
+        dst.set_fill_style(&AnyGraphEditor::MG_STYLE.into());
+        dst.transform(*grid_scale[0], 0.0, 0.0, *grid_scale[1], *offset_x, *offset_y)?;
+
+        for _ in 0..reps[1] {
+            for _ in 0..reps[0] {
+                dst.fill_with_path_2d(grid);
+                dst.translate(*size[0], 0.0)?;
+            }
+            dst.translate(-*size[0] * reps[0] as f64, *size[1])?;
+        }
+        dst.restore();
+    }
+
     /// an offset of 0 is assumed
     /// the returned array are the actual bounds of the rendered grid in user coordinates
     fn draw_grid(
@@ -1162,12 +2853,14 @@ impl<T: GraphPoint> GraphEditor<T> {
     ) {
         match event {
             AppEvent::Enter(id, e) | AppEvent::Hover(id, e) if *id == self.id => {
-                self.handle_hover(Some(e.try_into()?), ctx, sequencer, LazyCell::new(visual_ctx))?
+                let cursor = self.cursor(e)?;
+                self.handle_hover(Some(cursor), ctx, sequencer, LazyCell::new(visual_ctx))?
             }
 
             AppEvent::Focus(id, e) if *id == self.id => {
                 e.target_dyn_into::<Element>()?.set_pointer_capture(e.pointer_id())?;
-                self.handle_hover(Some(e.try_into()?), ctx, sequencer, LazyCell::new(visual_ctx))?;
+                let cursor = self.cursor(e)?;
+                self.handle_hover(Some(cursor), ctx, sequencer, LazyCell::new(visual_ctx))?;
             }
 
             AppEvent::SetSpecialAction(_) => {
@@ -1193,10 +2886,135 @@ impl<T: GraphPoint> GraphEditor<T> {
                 self.handle_hover(None, ctx, sequencer, LazyCell::new(visual_ctx))?
             }
 
+            AppEvent::Copy(id) if *id == self.id => {
+                ctx.set_clipboard(Rc::new(self.copy_selection()))
+            }
+
+            AppEvent::Paste(id) if *id == self.id => {
+                let Some(points) = ctx.clipboard().and_then(|x| x.downcast_ref::<Vec<T>>()) else {
+                    return Ok(());
+                };
+                let points = points.clone();
+                let step = R64::array_from(self.size).div(self.scale);
+                let at = R64::array_from((self.last_cursor.point + self.offset)?)
+                    .div(step)
+                    .floor_to([ctx.snap_step(), T::Y_SNAP]);
+                let action = self.paste(points, at)?;
+                ctx.register_action(action)?
+            }
+
+            AppEvent::OpenContextMenu(id, e) if *id == self.id => {
+                // don't let the browser open its own context menu
+                e.prevent_default();
+                let cursor = self.cursor(e)?;
+                let step = R64::array_from(self.size).div(self.scale);
+                let loc = R64::array_from((cursor.point + self.offset)?).div(step);
+                let visual_ctx = visual_ctx();
+                let Some(target) = point_at(&self.data, loc, |x, area| {
+                    x.in_hitbox(area, ctx.as_ref(), sequencer, visual_ctx)
+                })?
+                else {
+                    return Ok(());
+                };
+                if !self.selection.contains(&target) {
+                    let action = self.set_selection(vec![target], sequencer.bps());
+                    ctx.register_action(action)?;
+                    T::on_selection_change(self, ctx.as_mut())?;
+                }
+                ctx.emit_event(AppEvent::OpenPopup(Popup::ContextMenu {
+                    editor_id: self.id,
+                    at: Point { x: e.client_x(), y: e.client_y() },
+                    pitched: T::PITCHED,
+                }))
+            }
+
+            AppEvent::RemoveSelection(id) if *id == self.id && !self.selection.is_empty() => {
+                let action = self.remove_selection()?;
+                ctx.register_action(action)?;
+                T::on_selection_change(self, ctx)?
+            }
+
+            AppEvent::KeyPress(id, e)
+                if *id == self.id && matches!(e.code().as_str(), "Delete" | "Backspace") =>
+            {
+                // don't let the browser navigate back
+                e.prevent_default();
+                if !self.selection.is_empty() {
+                    let action = self.remove_selection()?;
+                    ctx.register_action(action)?;
+                    T::on_selection_change(self, ctx)?
+                }
+            }
+
+            AppEvent::DuplicateSelection(id) if *id == self.id && !self.selection.is_empty() => {
+                let action = self.duplicate_selection(ctx.snap_step())?;
+                ctx.register_action(action)?
+            }
+
+            AppEvent::SelectAll(id) if *id == self.id => {
+                let action = self.select_all(sequencer.bps());
+                ctx.register_action(action)?;
+                T::on_selection_change(self, ctx)?
+            }
+
+            AppEvent::InvertSelection(id) if *id == self.id => {
+                let action = self.invert_selection(sequencer.bps());
+                ctx.register_action(action)?;
+                T::on_selection_change(self, ctx)?
+            }
+
+            AppEvent::NudgeSelection { editor_id, dx, dy } if *editor_id == self.id => {
+                let delta = self.nudge_selection([*dx, *dy])?;
+                if delta.any(|x| *x != 0) {
+                    T::on_move(self, ctx.as_mut(), self.inner.last_cursor, delta, None)?;
+                    ctx.register_action(EditorAction::NudgeSelection { editor_id: self.id, delta })?
+                }
+            }
+
             AppEvent::Resize => self.init()?,
 
             AppEvent::StartPlay(_) => self.redraw = true,
 
+            AppEvent::ScrollTo(id, centre) if *id == self.id => {
+                self.scroll_to(*centre, self.size.map(R64::from));
+            }
+
+            AppEvent::FinishScroll(id) if *id == self.id => {
+                let offset_delta = self.finish_scroll()?;
+                if !offset_delta.is_zero() {
+                    ctx.register_action(EditorAction::DragPlane {
+                        editor_id: self.id,
+                        offset_delta,
+                        scale_delta: default(),
+                    })?;
+                }
+            }
+
+            AppEvent::FitView(id) if *id == self.id => {
+                let size = self.size.map(R64::from);
+                let (offset_delta, scale_delta) = self.fit_to_content(size, sequencer.bps())?;
+                if !offset_delta.is_zero() || scale_delta.any(|x| **x != 0.0) {
+                    ctx.register_action(EditorAction::DragPlane {
+                        editor_id: self.id,
+                        offset_delta,
+                        scale_delta,
+                    })?;
+                }
+            }
+
+            AppEvent::Zoom { editor_id, delta, at, axis } if *editor_id == self.id => {
+                let canvas: HtmlCanvasElement = self.canvas.cast()?;
+                let at = at.normalise(canvas.client_rect(), self.coords_rect())?;
+                let (offset_delta, scale_delta) = self.zoom(*delta, at, *axis)?;
+                if !offset_delta.is_zero() || scale_delta.any(|x| **x != 0.0) {
+                    ctx.register_action(EditorAction::DragPlane {
+                        editor_id: self.id,
+                        offset_delta,
+                        scale_delta,
+                    })?;
+                }
+            }
+
             AppEvent::Undo(actions) => {
                 for action in actions.iter() {
                     match *action {
@@ -1208,15 +3026,20 @@ impl<T: GraphPoint> GraphEditor<T> {
                             }
                         }
 
-                        EditorAction::DragPoint { editor_id, point_id, mut delta } => {
-                            if editor_id == self.id {
-                                self.redraw = true;
-                                delta = delta.map(|x| -x);
-                                T::móve(self.data.get_mut(point_id)?, delta, false)?
-                            }
+                        EditorAction::DragPoint { editor_id, ref changes }
+                            if editor_id == self.id =>
+                        {
+                            self.apply_diff(changes.downcast_ref()?, true)
+                        }
+
+                        EditorAction::DragSelection { editor_id, delta, ref changes }
+                            if editor_id == self.id =>
+                        {
+                            self.apply_diff(changes.downcast_ref()?, true);
+                            T::move_point(&mut self.selection_src, delta.map(neg), false)
                         }
 
-                        EditorAction::DragSelection { editor_id, mut delta } => {
+                        EditorAction::NudgeSelection { editor_id, mut delta } => {
                             if editor_id == self.id {
                                 self.redraw = true;
                                 delta = delta.map(neg);
@@ -1248,15 +3071,31 @@ impl<T: GraphPoint> GraphEditor<T> {
                             _ = self.remove_points(once(point_id))?
                         }
 
-                        EditorAction::RemovePoint(editor_id, ref points)
+                        EditorAction::InsertPoint(editor_id, ref points)
                             if editor_id == self.id =>
                         {
-                            for &RemovedPoint { ref point, index, was_selected } in points.iter() {
-                                self.data.insert(index, point.downcast_ref::<T>()?.clone());
-                                if was_selected {
-                                    self.selection.push(index);
-                                }
+                            for point in points.iter().rev() {
+                                _ = self.remove_points(once(point.index))?
+                            }
+                        }
+
+                        EditorAction::DuplicateSelection {
+                            editor_id,
+                            ref inserted,
+                            ref prev_ids,
+                            delta,
+                        } if editor_id == self.id => {
+                            for point in inserted.iter().rev() {
+                                _ = self.remove_points(once(point.index))?
                             }
+                            self.inner.selection = prev_ids.to_vec();
+                            T::move_point(&mut self.inner.selection_src, delta.map(neg), false)
+                        }
+
+                        EditorAction::RemovePoint(editor_id, ref points)
+                            if editor_id == self.id =>
+                        {
+                            self.restore_points(points)?
                         }
 
                         _ => (),
@@ -1275,14 +3114,20 @@ impl<T: GraphPoint> GraphEditor<T> {
                             }
                         }
 
-                        EditorAction::DragPoint { editor_id, point_id, delta } => {
-                            if editor_id == self.id {
-                                self.redraw = true;
-                                T::móve(self.data.get_mut(point_id)?, delta, false)?
-                            }
+                        EditorAction::DragPoint { editor_id, ref changes }
+                            if editor_id == self.id =>
+                        {
+                            self.apply_diff(changes.downcast_ref()?, false)
+                        }
+
+                        EditorAction::DragSelection { editor_id, delta, ref changes }
+                            if editor_id == self.id =>
+                        {
+                            self.apply_diff(changes.downcast_ref()?, false);
+                            T::move_point(&mut self.selection_src, delta, false)
                         }
 
-                        EditorAction::DragSelection { editor_id, delta } => {
+                        EditorAction::NudgeSelection { editor_id, delta } => {
                             if editor_id == self.id {
                                 self.redraw = true;
                                 for &id in &self.inner.selection {
@@ -1317,7 +3162,27 @@ impl<T: GraphPoint> GraphEditor<T> {
                         EditorAction::RemovePoint(editor_id, ref points)
                             if editor_id == self.id =>
                         {
-                            _ = self.remove_points(points.iter().map(|x| x.index))?
+                            _ = self.remove_points(points.iter().rev().map(|x| x.index))?
+                        }
+
+                        EditorAction::InsertPoint(editor_id, ref points)
+                            if editor_id == self.id =>
+                        {
+                            for &RemovedPoint { ref point, index, .. } in points.iter() {
+                                self.insert_at(index, point.downcast_ref::<T>()?.clone())
+                            }
+                        }
+
+                        EditorAction::DuplicateSelection { editor_id, ref inserted, delta, .. }
+                            if editor_id == self.id =>
+                        {
+                            self.inner.selection.clear();
+                            for &RemovedPoint { ref point, index, .. } in inserted.iter() {
+                                self.insert_at(index, point.downcast_ref::<T>()?.clone());
+                                self.inner.selection.push(index);
+                            }
+                            self.inner.selection.sort_unstable();
+                            T::move_point(&mut self.inner.selection_src, delta, false)
                         }
 
                         _ => (),
@@ -1325,61 +3190,67 @@ impl<T: GraphPoint> GraphEditor<T> {
                 }
             }
 
-            AppEvent::Frame(_) if self.redraw => {
+            // cleared beforehand for `T::on_overlay_redraw` to be able to request the next frame be
+            // redrawn
+            AppEvent::Frame(_) if self.take_redraw() => {
+                let start = now()?;
                 let canvas: HtmlCanvasElement = self.canvas.cast()?;
-                let size = canvas.size().map(R64::from);
+                let size = self.size.map(R64::from);
                 let snap_step = [ctx.snap_step(), T::Y_SNAP];
                 let canvas_ctx = canvas.get_2d_context()?;
 
                 let step = size.div(self.scale);
-                let offset_x = R64::from(-self.offset.x)
-                    % (self.offset.x > (T::X_BOUND.start * step[0]).into())
-                        .choose(step[0], R64::INFINITY);
-                let offset_y = R64::from(-self.offset.y)
-                    % if self.offset.y > (T::Y_BOUND.start * step[1]).into() {
-                        step[1] * 2
-                    } else {
-                        R64::INFINITY
-                    };
-
                 let offset = R64::array_from(self.offset);
                 let to_user = |loc| Some(R64::array_from(loc).add(offset).div(step));
                 let to_aligned_canvas = |loc: Point| loc.floor_to(snap_step.mul(step).into());
                 let confine = |x: [R64; 2]| x.array_fit_into([T::X_BOUND, T::Y_BOUND]);
 
-                canvas_ctx.set_fill_style(&AnyGraphEditor::BG_STYLE.into());
-                canvas_ctx.fill_rect(0.0, 0.0, *size[0], *size[1]);
-
-                let (grid, original_scale) = self
-                    .inner
-                    .grid
-                    .get_or_try_insert(|| Self::draw_grid(size, step, self.inner.scale))?;
-                let grid_scale = original_scale.div(self.inner.scale);
-                let reps = self
-                    .inner
-                    .scale
-                    .sub([offset_x, offset_y].div(step))
-                    .div(*original_scale)
-                    .map(|x| usize::from(x.ceil()));
-
-                canvas_ctx.set_fill_style(&AnyGraphEditor::MG_STYLE.into());
-                canvas_ctx.transform(
-                    *grid_scale[0],
-                    0.0,
-                    0.0,
-                    *grid_scale[1],
-                    *offset_x,
-                    *offset_y,
-                )?;
-
-                for _ in 0..reps[1] {
-                    for _ in 0..reps[0] {
-                        canvas_ctx.fill_with_path_2d(grid);
-                        canvas_ctx.translate(*size[0], 0.0)?;
+                // the background with the points drawn over it only changes when the points are
+                // changed or the plane is resized, zoomed or scrolled, otherwise it's just copied
+                // over from an offscreen canvas, e.g. when only the playhead moves
+                let visual_ctx = visual_ctx();
+                let key = self.background_key(size);
+                if key.invalidates(self.background.as_ref().map(|x| x.1)) {
+                    let bg: HtmlCanvasElement = match self.inner.background.take() {
+                        Some((bg, _)) => bg,
+                        None => document().create_element("canvas")?.unchecked_into(),
+                    };
+                    let opts = CanvasContextOptions { alpha: false, ..default() };
+                    let bg_ctx = bg.get_2d_context_with(opts)?;
+                    bg.sync_hidpi_as(self.size)?;
+                    bg_ctx.set_line_width(AnyGraphEditor::LINE_WIDTH);
+                    self.draw_background(&bg_ctx, size, step)?;
+
+                    if !self.ghost.is_empty() {
+                        bg_ctx.set_fill_style(&AnyGraphEditor::GHOST.to_string().into());
+                        for point in &self.ghost {
+                            let [start, end] = point.span(sequencer.bps())?;
+                            let [x, y] = [start, point.loc()[1]].mul(step).sub(offset);
+                            let w = (*((end - start) * step[0])).max(AnyGraphEditor::LINE_WIDTH);
+                            bg_ctx.fill_rect(*x, *y, w, *step[1]);
+                        }
                     }
-                    canvas_ctx.translate(-*size[0] * reps[0] as f64, *size[1])?;
+
+                    let solid = Path2d::new()?;
+                    let dotted = Path2d::new()?;
+                    T::on_redraw(
+                        self,
+                        ctx.as_ref(),
+                        sequencer,
+                        &size,
+                        &solid,
+                        &dotted,
+                        visual_ctx,
+                    )?;
+                    AnyGraphEditor::draw_paths(&bg_ctx, &solid, &dotted)?;
+                    self.inner.background = Some((bg, key));
+                }
+                if let Some((bg, _)) = &self.background {
+                    // scaled back down to the size it was drawn at
+                    canvas_ctx.draw_image_with_html_canvas_element_and_dw_and_dh(
+                        bg, 0.0, 0.0, *size[0], *size[1],
+                    )?;
                 }
-                canvas_ctx.reset_transform()?;
 
                 let solid = Path2d::new()?;
                 let dotted = Path2d::new()?;
@@ -1387,57 +3258,62 @@ impl<T: GraphPoint> GraphEditor<T> {
                 let [w, h] = self.selection_size.mul(step);
                 dotted.rect(*x, *y, *w, *h);
 
-                match self.focus {
-                    Focus::Zoom { pivot, init_offset, .. } => {
-                        if self.last_cursor.left {
-                            let [x, y] = pivot.sub(init_offset)?.map(|x| x as f64);
-                            solid.move_to(x - 10.0, y);
-                            solid.line_to(x + 10.0, y);
-                            solid.move_to(x, y - 10.0);
-                            solid.line_to(x, y + 10.0);
-                        } else {
-                            canvas_ctx.set_text_align("left");
-                            canvas_ctx.set_text_baseline("bottom");
-                            canvas_ctx.set_fill_style(&AnyGraphEditor::FG_STYLE.into());
-                            canvas_ctx.fill_text(
-                                &T::fmt_loc(confine(to_user(pivot)?)),
-                                5.0,
-                                *size[1] - 5.0,
-                            )?;
+                T::on_overlay_redraw(
+                    self,
+                    ctx.as_ref(),
+                    sequencer,
+                    &size,
+                    &solid,
+                    &dotted,
+                    visual_ctx,
+                )?;
+
+                // the guides are the first to go when redrawing takes too long
+                if !AnyGraphEditor::frame_budget_exceeded(start, now()?) {
+                    match self.focus {
+                        Focus::Zoom { pivot, init_offset, .. } => {
+                            if self.last_cursor.left {
+                                let [x, y] = pivot.sub(init_offset)?.map(|x| x as f64);
+                                solid.move_to(x - 10.0, y);
+                                solid.line_to(x + 10.0, y);
+                                solid.move_to(x, y - 10.0);
+                                solid.line_to(x, y + 10.0);
+                            } else {
+                                canvas_ctx.set_text_align("left");
+                                canvas_ctx.set_text_baseline("bottom");
+                                canvas_ctx.set_fill_style(&AnyGraphEditor::FG_STYLE.into());
+                                canvas_ctx.fill_text(
+                                    &T::fmt_loc(confine(to_user(pivot)?)),
+                                    5.0,
+                                    *size[1] - 5.0,
+                                )?;
+                            }
                         }
-                    }
 
-                    Focus::Plane { origin, .. } if self.last_cursor.meta => {
-                        if self.last_cursor.left {
-                            let cur = to_aligned_canvas(self.last_cursor.point)?;
-                            let origin = origin.mul(step).sub(offset).map(|x| *x);
-                            dotted.rect(
-                                origin[0],
-                                origin[1],
-                                cur.x as f64 - origin[0],
-                                cur.y as f64 - origin[1],
-                            );
-                        } else {
-                            let [x, y] = self.last_cursor.point.map(|x| x as f64);
-                            dotted.move_to(-*size[0], y);
-                            dotted.line_to(*size[0], y);
-                            dotted.move_to(x, -*size[1]);
-                            dotted.line_to(x, *size[1]);
+                        Focus::Plane { origin, .. } if self.last_cursor.meta => {
+                            if self.last_cursor.left {
+                                let cur = to_aligned_canvas(self.last_cursor.point)?;
+                                let origin = origin.mul(step).sub(offset).map(|x| *x);
+                                dotted.rect(
+                                    origin[0],
+                                    origin[1],
+                                    cur.x as f64 - origin[0],
+                                    cur.y as f64 - origin[1],
+                                );
+                            } else {
+                                let [x, y] = self.last_cursor.point.map(|x| x as f64);
+                                dotted.move_to(-*size[0], y);
+                                dotted.line_to(*size[0], y);
+                                dotted.move_to(x, -*size[1]);
+                                dotted.line_to(x, *size[1]);
+                            }
                         }
-                    }
 
-                    _ => (),
+                        _ => (),
+                    }
                 }
 
-                T::on_redraw(self, ctx.as_ref(), sequencer, &size, &solid, &dotted, visual_ctx())?;
-
-                canvas_ctx.set_stroke_style(&AnyGraphEditor::FG_STYLE.into());
-                canvas_ctx.fill_with_path_2d(&solid);
-                canvas_ctx.stroke_with_path(&solid);
-                canvas_ctx
-                    .set_line_dash(eval_once!(JsValue: js_array![number 10.0, number 10.0]))?;
-                canvas_ctx.stroke_with_path(&dotted);
-                canvas_ctx.set_line_dash(eval_once!(JsValue: js_array![]))?;
+                AnyGraphEditor::draw_paths(&canvas_ctx, &solid, &dotted)?;
             }
 
             _ => (),